@@ -1,11 +1,17 @@
 use catapult::{
+    fs::LoadError,
     numerics::AlignedBlock,
-    search::{AdjacencyGraph as InternalGraph, graph_algo::FlatSearch},
-    sets::catapults::FifoSet,
+    search::{AdjacencyGraph as InternalGraph, SearchStrategy},
+    sets::catapults::LruSet,
     statistics::Stats,
 };
-use pyo3::{PyResult, pyclass, pymethods};
-use std::path::PathBuf;
+use numpy::{IntoPyArray, PyArray2, PyReadonlyArray1, PyReadonlyArray2, ndarray::Array2};
+use pyo3::{
+    Bound, PyResult, Python,
+    exceptions::{PyFileNotFoundError, PyIOError, PyValueError},
+    pyclass, pymethods,
+};
+use std::{path::PathBuf, thread};
 
 use crate::vecpy::VecPy;
 
@@ -15,12 +21,12 @@ use crate::vecpy::VecPy;
 /// using beam search with optional LSH-cached starting points (catapults).
 ///
 /// # Thread Safety
-/// This class is marked as `unsendable`, meaning it should not be accessed from
-/// multiple Python threads. The implementation uses internal mutations that are
-/// not thread-safe from Python's perspective.
-#[pyclass(unsendable)]
+/// The graph can be shared between Python threads. Searches only mutate the catapult
+/// buckets, each of which sits behind its own `RwLock`, so concurrent searches (including
+/// the worker threads of `beam_search_many`) are safe.
+#[pyclass(frozen)]
 pub struct AdjacencyGraph {
-    inner: InternalGraph<FifoSet<30>, FlatSearch>,
+    inner: InternalGraph<LruSet>,
 }
 
 #[pymethods]
@@ -32,26 +38,53 @@ impl AdjacencyGraph {
     ///     payload_path: Path to the graph payload/data file
     ///     catapults_enabled: Whether to enable LSH-based catapult acceleration
     ///     num_hash: Number of hash functions for LSH (default: 5)
+    ///     bucket_cap: Maximum number of catapults per LSH bucket (default: 30)
     ///     seed: Random seed for LSH hash functions (default: 42)
     ///
     /// Returns:
     ///     A new AdjacencyGraph instance ready for search
+    ///
+    /// Raises:
+    ///     FileNotFoundError: If one of the files does not exist
+    ///     OSError: If one of the files cannot be read
+    ///     ValueError: If the files are not a valid graph
     #[staticmethod]
-    #[pyo3(signature = (graph_path, payload_path, catapults_enabled, num_hash=5, seed=42))]
+    #[pyo3(signature = (graph_path, payload_path, catapults_enabled, num_hash=5, bucket_cap=30, seed=42))]
     pub fn load(
         graph_path: String,
         payload_path: String,
         catapults_enabled: bool,
         num_hash: usize,
+        bucket_cap: usize,
         seed: u64,
     ) -> PyResult<Self> {
+        let (graph_path, payload_path) = (PathBuf::from(graph_path), PathBuf::from(payload_path));
+        for path in [&graph_path, &payload_path] {
+            if !path.is_file() {
+                return Err(PyFileNotFoundError::new_err(format!(
+                    "no such file: {}",
+                    path.display()
+                )));
+            }
+        }
+
+        let strategy = if catapults_enabled {
+            SearchStrategy::Catapult
+        } else {
+            SearchStrategy::Vanilla
+        };
         let graph = InternalGraph::load_flat_from_path(
-            PathBuf::from(graph_path),
-            PathBuf::from(payload_path),
+            graph_path,
+            payload_path,
             num_hash,
+            bucket_cap,
             seed,
-            catapults_enabled,
-        );
+            strategy,
+        )
+        .map_err(|error| match error {
+            LoadError::Io(cause) => PyIOError::new_err(cause.to_string()),
+            malformed => PyValueError::new_err(format!("invalid graph files: {malformed}")),
+        })?;
 
         Ok(Self { inner: graph })
     }
@@ -59,28 +92,123 @@ impl AdjacencyGraph {
     /// Perform beam search to find approximate nearest neighbors.
     ///
     /// Args:
-    ///     query: Query vector as a list of f32 values
-    ///     num_neighbors: Number of nearest neighbors to return (k)
-    ///     beam_width: Width of the search beam (larger = more accurate but slower)
+    ///     query: Query vector as a 1-dimensional float32 NumPy array of the graph's
+    ///         dimension
+    ///     k: Number of nearest neighbors to return
+    ///     beam_width: Width of the search beam (at least k; larger = more accurate
+    ///         but slower)
     ///
     /// Returns:
     ///     A list of node IDs representing the k approximate nearest neighbors,
     ///     ordered by distance (closest first)
-    pub fn search(&mut self, query: VecPy, num_neighbors: usize, beam_width: usize) -> Vec<usize> {
-        // Convert the query to aligned blocks
-        let query_aligned = align_query(&query.inner);
+    ///
+    /// Raises:
+    ///     ValueError: If the query dimension differs from the graph's, or if
+    ///         beam_width is smaller than k
+    pub fn beam_search(
+        &self,
+        query: PyReadonlyArray1<'_, f32>,
+        k: usize,
+        beam_width: usize,
+    ) -> PyResult<Vec<usize>> {
+        let query: Vec<f32> = query.as_array().iter().copied().collect();
+        self.search_checked(&query, k, beam_width)
+    }
 
-        // Perform the search
-        let mut stats = Stats::new();
-        let results = self
-            .inner
-            .beam_search(&query_aligned, num_neighbors, beam_width, &mut stats);
+    /// Search many queries at once on a pool of Rust threads, with the GIL released.
+    ///
+    /// Queries are split into `threads` contiguous chunks, each searched in order by one
+    /// thread. Catapults learned by any thread are visible to all of them, so results can
+    /// depend on scheduling when catapults are enabled, exactly as with concurrent calls
+    /// to beam_search.
+    ///
+    /// Args:
+    ///     queries: Query matrix as a 2-dimensional float32 NumPy array of shape
+    ///         (N, dim)
+    ///     k: Number of nearest neighbors to return per query
+    ///     beam_width: Width of the search beam (at least k)
+    ///     threads: Number of worker threads (default: 1)
+    ///
+    /// Returns:
+    ///     An int64 NumPy array of shape (N, k) holding the node IDs of the nearest
+    ///     neighbors of each query, closest first. Rows are padded with -1 when the
+    ///     search finds fewer than k nodes.
+    ///
+    /// Raises:
+    ///     ValueError: If the query dimension differs from the graph's, beam_width is
+    ///         smaller than k, or threads is 0
+    #[pyo3(signature = (queries, k, beam_width, threads=1))]
+    pub fn beam_search_many<'py>(
+        &self,
+        py: Python<'py>,
+        queries: PyReadonlyArray2<'py, f32>,
+        k: usize,
+        beam_width: usize,
+        threads: usize,
+    ) -> PyResult<Bound<'py, PyArray2<i64>>> {
+        let queries = queries.as_array();
+        let num_queries = queries.nrows();
+        self.check_search_args(queries.ncols(), k, beam_width)?;
+        if threads == 0 {
+            return Err(PyValueError::new_err("threads must be at least 1"));
+        }
 
-        // Convert CandidateEntry to usize node IDs
-        results
+        // copy the queries while the GIL still protects the NumPy buffer
+        let aligned: Vec<Vec<AlignedBlock>> = queries
+            .rows()
             .into_iter()
-            .map(|candidate| candidate.index.internal)
-            .collect()
+            .map(|row| AlignedBlock::allocate_padded(row.iter().copied().collect()))
+            .collect();
+
+        let graph = &self.inner;
+        let ids = py.allow_threads(|| {
+            let chunk_len = num_queries.div_ceil(threads).max(1);
+            thread::scope(|scope| {
+                let handles: Vec<_> = aligned
+                    .chunks(chunk_len)
+                    .map(|chunk| {
+                        scope.spawn(move || {
+                            graph.beam_search_batch(chunk, k, beam_width, &mut Stats::new())
+                        })
+                    })
+                    .collect();
+
+                let mut ids = Vec::with_capacity(num_queries * k);
+                for handle in handles {
+                    for results in handle.join().expect("search thread panicked") {
+                        ids.extend(results.iter().map(|c| c.index.internal as i64));
+                        ids.extend(std::iter::repeat_n(-1, k - results.len()));
+                    }
+                }
+                ids
+            })
+        });
+
+        let ids =
+            Array2::from_shape_vec((num_queries, k), ids).expect("one row of k ids per query");
+        Ok(ids.into_pyarray(py))
+    }
+
+    /// Perform beam search to find approximate nearest neighbors.
+    ///
+    /// Args:
+    ///     query: Query vector as a list of f32 values
+    ///     num_neighbors: Number of nearest neighbors to return (k)
+    ///     beam_width: Width of the search beam (larger = more accurate but slower)
+    ///
+    /// Returns:
+    ///     A list of node IDs representing the k approximate nearest neighbors,
+    ///     ordered by distance (closest first)
+    ///
+    /// Raises:
+    ///     ValueError: Same conditions as beam_search
+    pub fn search(
+        &self,
+        query: VecPy,
+        num_neighbors: usize,
+        beam_width: usize,
+    ) -> PyResult<Vec<usize>> {
+        self.search_checked(&query.inner, num_neighbors, beam_width)
     }
 
     /// Perform batch search for multiple queries.
@@ -92,25 +220,68 @@ impl AdjacencyGraph {
     ///
     /// Returns:
     ///     A list of result lists, one for each query
+    ///
+    /// Raises:
+    ///     ValueError: Same conditions as beam_search
     pub fn batch_search(
-        &mut self,
+        &self,
         queries: Vec<VecPy>,
         num_neighbors: usize,
         beam_width: usize,
-    ) -> Vec<Vec<usize>> {
+    ) -> PyResult<Vec<Vec<usize>>> {
         queries
             .into_iter()
             .map(|q| self.search(q, num_neighbors, beam_width))
             .collect()
     }
 
+    /// Get the dimension of the vectors stored in the graph.
+    pub fn dim(&self) -> usize {
+        self.inner.dim()
+    }
+
     /// Get the number of nodes in the graph.
     pub fn __len__(&self) -> usize {
         self.inner.len()
     }
 }
 
-/// Helper function to convert a query vector to aligned blocks.
+impl AdjacencyGraph {
+    /// Checks that queries of dimension `dim` can be searched with `k` and `beam_width`.
+    fn check_search_args(&self, dim: usize, k: usize, beam_width: usize) -> PyResult<()> {
+        if dim != self.inner.dim() {
+            return Err(PyValueError::new_err(format!(
+                "query has dimension {dim}, but the graph has dimension {}",
+                self.inner.dim()
+            )));
+        }
+        if beam_width < k {
+            return Err(PyValueError::new_err(format!(
+                "beam_width ({beam_width}) must be at least k ({k})"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Validates the search arguments, then runs the search and returns node IDs.
+    fn search_checked(&self, query: &[f32], k: usize, beam_width: usize) -> PyResult<Vec<usize>> {
+        self.check_search_args(query.len(), k, beam_width)?;
+
+        let mut stats = Stats::new();
+        let results = self
+            .inner
+            .beam_search(&align_query(query), k, beam_width, &mut stats);
+
+        // Convert CandidateEntry to usize node IDs
+        Ok(results
+            .into_iter()
+            .map(|candidate| candidate.index.internal)
+            .collect())
+    }
+}
+
+/// Helper function to convert a query vector to aligned blocks, zero-padding the last
+/// block to the graph's padded dimension.
 fn align_query(query: &[f32]) -> Vec<AlignedBlock> {
     AlignedBlock::allocate_padded(query.to_vec())
 }
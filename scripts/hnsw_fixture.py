@@ -0,0 +1,36 @@
+"""Writes test/hnsw/ring.graph and test/hnsw/ring.data, a tiny hierarchical graph.
+
+The graph holds 12 points on a circle of radius 10 in 4 dimensions. Level 0 links each
+point to its two neighbors on either side, points 0 and 6 also live on level 1, and
+point 0, the entry point, alone on level 2: the same graph as test/hnswlib/ring.bin, in
+the format read by `AdjacencyGraph::load_hnsw_from_path`.
+"""
+
+import math
+import struct
+
+N, DIM = 12, 4
+
+UPPER_LEVELS = {0: [[6], []], 6: [[0]]}
+
+
+def point(i):
+    angle = 2 * math.pi * i / N
+    return [10 * math.cos(angle), 10 * math.sin(angle), 0.0, 0.0]
+
+
+graph = bytearray(struct.pack("<Q", N))
+for i in range(N):
+    levels = [[(i + d) % N for d in (-2, -1, 1, 2)]] + UPPER_LEVELS.get(i, [])
+    graph += struct.pack("<I", len(levels))
+    for links in levels:
+        graph += struct.pack(f"<I{len(links)}I", len(links), *links)
+
+data = bytearray(struct.pack("<II", N, DIM))
+for i in range(N):
+    data += struct.pack(f"<{DIM}f", *point(i))
+
+with open("test/hnsw/ring.graph", "wb") as f:
+    f.write(graph)
+with open("test/hnsw/ring.data", "wb") as f:
+    f.write(data)
@@ -0,0 +1,66 @@
+"""Writes test/hnswlib/ring.bin, a tiny index in hnswlib's `saveIndex` layout.
+
+The index holds 12 points on a circle of radius 10 in 4 dimensions. Level 0 links each
+point to its two neighbors on either side, points 0 and 6 also live on level 1, and
+point 0, the entry point, alone on level 2. Labels are 1000 + 7 * internal id.
+
+With hnswlib installed, an equivalent index can be produced by adding the same points
+with `hnswlib.Index(space="l2", dim=4)`; this script only exists so the fixture can be
+regenerated without it.
+"""
+
+import math
+import struct
+
+N, DIM, M = 12, 4, 2
+MAX_M, MAX_M0 = M, 2 * M
+MAX_ELEMENTS = 16
+
+SIZE_LINKS_LEVEL0 = MAX_M0 * 4 + 4
+SIZE_LINKS_PER_ELEMENT = MAX_M * 4 + 4
+OFFSET_DATA = SIZE_LINKS_LEVEL0
+LABEL_OFFSET = OFFSET_DATA + DIM * 4
+SIZE_DATA_PER_ELEMENT = LABEL_OFFSET + 8
+
+LEVELS = {0: [[6], []], 6: [[0]]}
+
+
+def point(i):
+    angle = 2 * math.pi * i / N
+    return [10 * math.cos(angle), 10 * math.sin(angle), 0.0, 0.0]
+
+
+out = bytearray()
+out += struct.pack(
+    "<QQQQQQiIQQQdQ",
+    0,  # offsetLevel0_
+    MAX_ELEMENTS,
+    N,  # cur_element_count
+    SIZE_DATA_PER_ELEMENT,
+    LABEL_OFFSET,
+    OFFSET_DATA,
+    2,  # maxlevel_
+    0,  # enterpoint_node_
+    MAX_M,
+    MAX_M0,
+    M,
+    1 / math.log(M),  # mult_
+    200,  # ef_construction_
+)
+
+for i in range(N):
+    links = [(i + d) % N for d in (-2, -1, 1, 2)]
+    out += struct.pack("<I", len(links))
+    out += struct.pack(f"<{MAX_M0}I", *links)
+    out += struct.pack(f"<{DIM}f", *point(i))
+    out += struct.pack("<Q", 1000 + 7 * i)
+
+for i in range(N):
+    levels = LEVELS.get(i, [])
+    out += struct.pack("<I", SIZE_LINKS_PER_ELEMENT * len(levels))
+    for links in levels:
+        out += struct.pack("<I", len(links))
+        out += struct.pack(f"<{MAX_M}I", *(links + [0] * (MAX_M - len(links))))
+
+with open("test/hnswlib/ring.bin", "wb") as f:
+    f.write(out)
@@ -0,0 +1,39 @@
+"""Writes small .npy query files of other dtypes than f32 to test/npy/.
+
+Each file holds the 3x5 matrix `10 * row + column`, negated in the int8 one, as float64,
+uint8, int8 and complex64 (which the loader rejects), and as float32 in Fortran
+(column-major) order. The .npy format is simple enough
+to be written by hand, which spares a numpy dependency.
+"""
+
+import struct
+
+ROWS, COLS = 3, 5
+
+DTYPES = {
+    "f64": ("<f8", "<d", 1),
+    "u8": ("|u1", "<B", 1),
+    "i8": ("|i1", "<b", -1),
+    "c64": ("<c8", "<ff", 1),
+}
+
+
+def npy(descr, values, fortran=False):
+    header = f"{{'descr': '{descr}', 'fortran_order': {fortran}, 'shape': ({ROWS}, {COLS}), }}"
+    # magic, version and header length take 10 bytes; the header ends on a 64-byte boundary
+    header += " " * (-(10 + len(header) + 1) % 64) + "\n"
+    return b"\x93NUMPY\x01\x00" + struct.pack("<H", len(header)) + header.encode() + values
+
+
+for name, (descr, fmt, sign) in DTYPES.items():
+    values = b"".join(
+        struct.pack(fmt, *([sign * (10 * r + c)] + [0] * (len(fmt) - 2)))
+        for r in range(ROWS)
+        for c in range(COLS)
+    )
+    with open(f"test/npy/queries_{name}.npy", "wb") as f:
+        f.write(npy(descr, values))
+
+values = b"".join(struct.pack("<f", 10 * r + c) for c in range(COLS) for r in range(ROWS))
+with open("test/npy/queries_fortran.npy", "wb") as f:
+    f.write(npy("<f4", values, fortran=True))
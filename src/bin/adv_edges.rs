@@ -125,7 +125,7 @@ fn percentile(sorted: &[u32], p: f64) -> u32 {
 fn edge_report(
     mut edges: Vec<(usize, usize, u32)>,
 ) -> (Vec<AdversarialEdge>, ConsiderationCountStats) {
-    edges.sort_unstable_by(|a, b| b.2.cmp(&a.2));
+    edges.sort_unstable_by_key(|e| std::cmp::Reverse(e.2));
 
     let top: Vec<AdversarialEdge> = edges
         .iter()
@@ -267,7 +267,7 @@ fn run_analysis(
     let used_edge_count = used_edges_raw.len();
 
     // Sort descending by times_used for the top-1000 list.
-    used_edges_raw.sort_unstable_by(|a, b| b.3.cmp(&a.3));
+    used_edges_raw.sort_unstable_by_key(|e| std::cmp::Reverse(e.3));
 
     let top_used_edges: Vec<UsedEdge> = used_edges_raw
         .iter()
@@ -348,14 +348,17 @@ fn main() {
         for &seed in &args.seeds {
             eprintln!("\n--- mode={} seed={} ---", mode, seed);
             eprintln!("  Loading graph...");
-            let graph = Arc::new(AdjacencyGraph::<LruSet>::load_flat_from_path(
-                PathBuf::from_str(&args.graph).unwrap(),
-                PathBuf::from_str(&args.payload).unwrap(),
-                NUM_HASH,
-                BUCKET_SIZE,
-                seed,
-                SearchStrategy::from_string(mode, None),
-            ));
+            let graph = Arc::new(
+                AdjacencyGraph::<LruSet>::load_flat_from_path(
+                    PathBuf::from_str(&args.graph).unwrap(),
+                    PathBuf::from_str(&args.payload).unwrap(),
+                    NUM_HASH,
+                    BUCKET_SIZE,
+                    seed,
+                    SearchStrategy::from_string(mode, None),
+                )
+                .expect("Failed to load graph"),
+            );
             eprintln!(
                 "  Graph: {} nodes, {} edges",
                 graph.len(),
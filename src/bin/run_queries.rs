@@ -1,15 +1,17 @@
 use catapult::{
-    fs::Queries,
+    fs::{Queries, load_from_ivecs},
     numerics::{AlignedBlock, SIMD_LANECOUNT},
-    search::{AdjacencyGraph, LshApgArgs, SearchStrategy},
-    sets::catapults::LruSet,
-    statistics::Stats,
+    search::{AdjacencyGraph, LshApgArgs, SearchContext, SearchStrategy, generate_queries},
+    sets::{catapults::LruSet, visited::CompressedBitset},
+    statistics::{Stats, recall_at_k},
 };
-use clap::Parser;
+use clap::{CommandFactory, Parser, error::ErrorKind};
 use serde::{Deserialize, Serialize};
 use std::{
+    fs::File,
     hint::black_box,
-    path::PathBuf,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
     str::FromStr,
     sync::{
         Arc,
@@ -21,7 +23,6 @@ use tracing::info_span;
 use tracing_subscriber::prelude::*;
 
 const NUM_HASH: usize = 8;
-const BUCKET_SIZE: usize = 40;
 const BATCH_SIZE: usize = 4096;
 const LIMITATION: Option<usize> = Some(1_000_000);
 
@@ -32,6 +33,7 @@ struct SearchJobResult {
     beam_width: usize,
     num_queries: usize,
     catapults_enabled: bool,
+    csr_layout: bool,
     bucket_capacity: usize,
     num_hashes: usize,
     elapsed_secs: f64,
@@ -42,11 +44,28 @@ struct SearchJobResult {
     searches_with_catapults: Option<usize>,
     catapult_usage_pct: Option<f64>,
     avg_catapults_added: Option<f64>,
+    /// Mean recall@num_neighbors, present only when --ground-truth is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recall: Option<f64>,
     /// Per-query neighbors in query order, present only when --output-neighbors is set
     #[serde(skip_serializing_if = "Option::is_none")]
     neighbors: Option<Vec<Vec<usize>>>,
 }
 
+/// Metrics of a single search, written to --stats-out
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct QueryStats {
+    seed: u64,
+    num_threads: usize,
+    beam_width: usize,
+    bucket_capacity: usize,
+    query: usize,
+    nodes_expanded: usize,
+    dists_computed: usize,
+    used_catapults: bool,
+    latency_us: f64,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 struct BenchmarkResults {
     results: Vec<SearchJobResult>,
@@ -58,8 +77,16 @@ struct BenchmarkResults {
 #[command(about = "A vector search engine using adjacency graphs", long_about = None)]
 struct Args {
     /// Path to the queries file (numpy format)
-    #[arg(short, long)]
-    queries: String,
+    #[arg(short, long, required_unless_present = "synthetic_queries")]
+    queries: Option<String>,
+
+    /// Number of queries to generate around random stored vectors, instead of --queries
+    #[arg(long, conflicts_with = "queries")]
+    synthetic_queries: Option<usize>,
+
+    /// Standard deviation of the Gaussian noise added to each entry of synthetic queries
+    #[arg(long, default_value_t = 0.01, requires = "synthetic_queries")]
+    query_noise: f32,
 
     /// Path to the graph metadata file
     #[arg(short, long)]
@@ -81,6 +108,10 @@ struct Args {
     #[arg(long, value_delimiter = ',')]
     beam_width: Vec<usize>,
 
+    /// Maximum number of catapults per LSH bucket (comma-separated list, e.g., "10,40,160")
+    #[arg(long, value_delimiter = ',', default_value = "40")]
+    bucket_capacity: Vec<usize>,
+
     /// Seeds for random number generation (comma-separated list, e.g., "42,123,456")
     #[arg(long, value_delimiter = ',', default_value = "42")]
     seeds: Vec<u64>,
@@ -92,16 +123,42 @@ struct Args {
     /// Include per-query neighbor results (index + distance) in the output JSON
     #[arg(long, default_value_t = false)]
     output_neighbors: bool,
+
+    /// Pack neighbor lists into a single CSR buffer before searching
+    #[arg(long, default_value_t = false)]
+    csr: bool,
+
+    /// Keep using catapults but skip writing search results back, to measure the cost of
+    /// the catapult write locks
+    #[arg(long, default_value_t = false)]
+    no_catapult_writes: bool,
+
+    /// Path to the true neighbors of each query (.ivecs format), to report recall
+    #[arg(long)]
+    ground_truth: Option<String>,
+
+    /// Number of neighbors recall is computed over, with --ground-truth
+    #[arg(long, default_value_t = 10)]
+    num_neighbors: usize,
+
+    /// Path to write per-query metrics of every job to, as JSON if it ends in .json and
+    /// CSV otherwise
+    #[arg(long)]
+    stats_out: Option<String>,
 }
 
 /// Runs beam search over all queries using a thread pool with work-stealing batches.
-/// Returns results sorted by query index and aggregate stats.
+/// Returns results sorted by query index and aggregate stats. With `per_query`, each
+/// search also gets its own stats, returned as `(query index, stats, latency in µs)`
+/// triples sorted by query index.
+#[allow(clippy::type_complexity)]
 fn parallel_beam_search(
     graph: &Arc<AdjacencyGraph<LruSet>>,
     queries: &Arc<Vec<Vec<AlignedBlock>>>,
     num_threads: usize,
     beam_width: usize,
-) -> (Vec<(usize, Vec<usize>)>, Stats) {
+    per_query: bool,
+) -> (Vec<(usize, Vec<usize>)>, Stats, Vec<(usize, Stats, f64)>) {
     let num_queries = queries.len();
     let next_batch = Arc::new(AtomicUsize::new(0));
 
@@ -114,6 +171,8 @@ fn parallel_beam_search(
             thread::spawn(move || {
                 let mut local_results: Vec<(usize, Vec<usize>)> = Vec::new();
                 let mut local_stats = Stats::new();
+                let mut local_query_stats = Vec::new();
+                let mut ctx = SearchContext::<CompressedBitset>::new();
 
                 loop {
                     let batch_start = next_batch.fetch_add(BATCH_SIZE, Ordering::Relaxed);
@@ -122,37 +181,89 @@ fn parallel_beam_search(
                     }
                     let batch_end = (batch_start + BATCH_SIZE).min(num_queries);
 
-                    for (offset, query) in queries[batch_start..batch_end].iter().enumerate() {
-                        let result = black_box(graph.beam_search(
-                            query,
-                            beam_width,
-                            beam_width,
-                            &mut local_stats,
-                        ));
-                        local_results.push((
-                            batch_start + offset,
-                            result.iter().map(|e| e.index.internal).collect(),
-                        ));
+                    for (index, query) in queries[batch_start..batch_end]
+                        .iter()
+                        .enumerate()
+                        .map(|(offset, query)| (batch_start + offset, query))
+                    {
+                        let result = if per_query {
+                            let mut query_stats = Stats::new();
+                            let start = std::time::Instant::now();
+                            let result = black_box(graph.beam_search_with_ctx(
+                                query,
+                                beam_width,
+                                beam_width,
+                                &mut query_stats,
+                                &mut ctx,
+                            ));
+                            let latency_us = start.elapsed().as_secs_f64() * 1e6;
+                            local_stats.merge_from(&query_stats);
+                            local_query_stats.push((index, query_stats, latency_us));
+                            result
+                        } else {
+                            black_box(graph.beam_search_with_ctx(
+                                query,
+                                beam_width,
+                                beam_width,
+                                &mut local_stats,
+                                &mut ctx,
+                            ))
+                        };
+                        local_results
+                            .push((index, result.iter().map(|e| e.index.internal).collect()));
                     }
                 }
 
-                (local_results, local_stats)
+                (local_results, local_stats, local_query_stats)
             })
         })
         .collect();
 
     let mut results: Vec<(usize, Vec<usize>)> = Vec::with_capacity(num_queries);
     let mut combined_stats = Stats::new();
+    let mut query_stats = Vec::new();
     for handle in handles {
-        let (local_results, local_stats) = handle.join().expect("Thread panicked");
+        let (local_results, local_stats, local_query_stats) =
+            handle.join().expect("Thread panicked");
         results.extend(local_results);
-        combined_stats = combined_stats.merge(&local_stats);
+        combined_stats.merge_from(&local_stats);
+        query_stats.extend(local_query_stats);
     }
 
     // Restore query order (threads may complete batches out of order)
     results.sort_unstable_by_key(|(idx, _)| *idx);
+    query_stats.sort_unstable_by_key(|(idx, _, _)| *idx);
 
-    (results, combined_stats)
+    (results, combined_stats, query_stats)
+}
+
+/// Writes per-query metrics as a JSON array if `path` ends in `.json`, as CSV otherwise.
+fn write_query_stats(path: &Path, rows: &[QueryStats]) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::to_writer_pretty(&mut out, rows)?;
+    } else {
+        writeln!(
+            out,
+            "seed,num_threads,beam_width,bucket_capacity,query,nodes_expanded,dists_computed,used_catapults,latency_us"
+        )?;
+        for row in rows {
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{},{},{:.3}",
+                row.seed,
+                row.num_threads,
+                row.beam_width,
+                row.bucket_capacity,
+                row.query,
+                row.nodes_expanded,
+                row.dists_computed,
+                row.used_catapults,
+                row.latency_us
+            )?;
+        }
+    }
+    out.flush()
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -162,10 +273,14 @@ fn run_search_job(
     num_threads: usize,
     beam_width: usize,
     catapults_enabled: bool,
+    csr_layout: bool,
     seed: u64,
     bucket_capacity: usize,
     num_hashes: usize,
     output_neighbors: bool,
+    ground_truth: Option<&[Vec<usize>]>,
+    num_neighbors: usize,
+    query_stats_out: Option<&mut Vec<QueryStats>>,
 ) -> SearchJobResult {
     let num_queries = queries.len();
     eprintln!("\n==========");
@@ -176,8 +291,31 @@ fn run_search_job(
     eprintln!("==========");
 
     let start_time = std::time::Instant::now();
-    let (results, combined_stats) = parallel_beam_search(&graph, &queries, num_threads, beam_width);
+    let (results, combined_stats, query_stats) = parallel_beam_search(
+        &graph,
+        &queries,
+        num_threads,
+        beam_width,
+        query_stats_out.is_some(),
+    );
     let elapsed = start_time.elapsed();
+    if let Some(rows) = query_stats_out {
+        rows.extend(
+            query_stats
+                .into_iter()
+                .map(|(query, stats, latency_us)| QueryStats {
+                    seed,
+                    num_threads,
+                    beam_width,
+                    bucket_capacity,
+                    query,
+                    nodes_expanded: stats.get_nodes_visited(),
+                    dists_computed: stats.get_computed_dists(),
+                    used_catapults: stats.get_searches_with_catapults() > 0,
+                    latency_us,
+                }),
+        );
+    }
 
     let total_qps = num_queries as f64 / elapsed.as_secs_f64();
     let avg_dists_computed = combined_stats.get_computed_dists() as f64 / num_queries as f64;
@@ -192,27 +330,19 @@ fn run_search_job(
             0.0
         };
         let avg = n as f64 / num_queries as f64;
-
-        eprintln!(
-            "Catapult stats: {}/{} searches used catapults ({:.2}%)",
-            n, num_queries, usage_pct,
-        );
-        eprintln!(
-            "  Avg per search: {:.2} dists computed, {:.2} nodes visited, {:.2} catapult used",
-            avg_dists_computed, avg_nodes_visited, avg
-        );
-
         (Some(n), Some(usage_pct), Some(avg))
     } else {
-        eprintln!(
-            "Avg per search: {:.2} dists computed, {:.2} visited nodes",
-            avg_dists_computed, avg_nodes_visited
-        );
-
         (None, None, None)
     };
+    eprintln!("{combined_stats}");
 
     eprintln!("Checksum: {:?}", checksum);
+    let recall = ground_truth.map(|truth| {
+        let found: Vec<Vec<usize>> = results.iter().map(|(_, res)| res.clone()).collect();
+        let recall = recall_at_k(&found, truth, num_neighbors);
+        eprintln!("Recall@{num_neighbors}: {recall:.4}");
+        recall
+    });
     eprintln!(
         "Completed {} searches in {:.2}s ({:.2} QPS)",
         num_queries,
@@ -232,6 +362,7 @@ fn run_search_job(
         beam_width,
         num_queries,
         catapults_enabled,
+        csr_layout,
         bucket_capacity,
         num_hashes,
         elapsed_secs: elapsed.as_secs_f64(),
@@ -242,6 +373,7 @@ fn run_search_job(
         searches_with_catapults,
         catapult_usage_pct,
         avg_catapults_added,
+        recall,
         neighbors,
     }
 }
@@ -266,29 +398,78 @@ fn main() {
     let args = Args::parse();
 
     // Load the queries
-    eprintln!("Loading queries...");
-    let queries: Vec<Vec<AlignedBlock>> = {
-        let _span = info_span!("load_queries", path = %args.queries).entered();
-        Vec::<Vec<AlignedBlock>>::load_from_npy(&args.queries, LIMITATION)
+    let queries: Vec<Vec<AlignedBlock>> = match (&args.queries, args.synthetic_queries) {
+        (Some(path), _) => {
+            eprintln!("Loading queries...");
+            let _span = info_span!("load_queries", path = %path).entered();
+            Vec::<Vec<AlignedBlock>>::load_from_npy(path, LIMITATION)
+        }
+        (None, Some(count)) => {
+            eprintln!("Generating {count} synthetic queries...");
+            let _span = info_span!("generate_queries", count).entered();
+            let seed = args.seeds[0];
+            let graph = AdjacencyGraph::<LruSet>::load_flat_from_path(
+                PathBuf::from_str(&args.graph).unwrap(),
+                PathBuf::from_str(&args.payload).unwrap(),
+                NUM_HASH,
+                1,
+                seed,
+                SearchStrategy::Vanilla,
+            )
+            .expect("Failed to load graph");
+            generate_queries(&graph, count, args.query_noise, seed)
+        }
+        (None, None) => unreachable!("clap requires --queries or --synthetic-queries"),
     };
     let queries = Arc::new(queries);
 
+    let ground_truth: Option<Vec<Vec<usize>>> = args.ground_truth.as_ref().map(|path| {
+        let _span = info_span!("load_ground_truth", path = %path).entered();
+        let truth = load_from_ivecs(path, LIMITATION).unwrap_or_else(|err| {
+            Args::command()
+                .error(
+                    ErrorKind::Io,
+                    format!("cannot read ground truth {path}: {err}"),
+                )
+                .exit()
+        });
+        if truth.len() != queries.len() {
+            Args::command()
+                .error(
+                    ErrorKind::ValueValidation,
+                    format!(
+                        "ground truth {path} holds {} rows but there are {} queries",
+                        truth.len(),
+                        queries.len()
+                    ),
+                )
+                .exit()
+        }
+        truth
+    });
+
     eprintln!("\nStarting cartesian product sweep:");
     eprintln!("  Seeds: {:?}", args.seeds);
     eprintln!("  Threads: {:?}", args.threads);
+    eprintln!("  Bucket capacities: {:?}", args.bucket_capacity);
     eprintln!("  Beam widths: {:?}", args.beam_width);
     eprintln!(
         "  Total jobs: {}",
-        args.seeds.len() * args.threads.len() * args.beam_width.len()
+        args.seeds.len() * args.bucket_capacity.len() * args.threads.len() * args.beam_width.len()
     );
 
     let mut all_results = Vec::new();
-
-    // Run cartesian product of seeds, threads, and beam_width
-    for &seed in &args.seeds {
+    let mut query_stats = args.stats_out.as_ref().map(|_| Vec::new());
+
+    // Run cartesian product of seeds, bucket capacities, threads, and beam_width
+    for (&seed, &bucket_cap) in args
+        .seeds
+        .iter()
+        .flat_map(|seed| args.bucket_capacity.iter().map(move |cap| (seed, cap)))
+    {
         eprintln!(
             "\n--- Loading adjacency graph with seed={}, num_hash={}, bucket_cap={} ---",
-            seed, NUM_HASH, BUCKET_SIZE
+            seed, NUM_HASH, bucket_cap
         );
 
         let apgargs = if args.mode == "lshapg" {
@@ -303,15 +484,23 @@ fn main() {
         };
 
         let full_graph = {
-            let _span = info_span!("load_graph", seed, num_hash = NUM_HASH, bucket_cap = BUCKET_SIZE, mode = %args.mode).entered();
-            Arc::new(AdjacencyGraph::<LruSet>::load_flat_from_path(
+            let _span =
+                info_span!("load_graph", seed, num_hash = NUM_HASH, bucket_cap, mode = %args.mode)
+                    .entered();
+            let mut graph = AdjacencyGraph::<LruSet>::load_flat_from_path(
                 PathBuf::from_str(&args.graph).unwrap(),
                 PathBuf::from_str(&args.payload).unwrap(),
                 NUM_HASH,
-                BUCKET_SIZE,
+                bucket_cap,
                 seed,
                 SearchStrategy::from_string(&args.mode, apgargs),
-            ))
+            )
+            .expect("Failed to load graph");
+            if args.csr {
+                graph.use_csr_layout();
+            }
+            graph.set_catapult_writes(!args.no_catapult_writes);
+            Arc::new(graph)
         };
         let graph_size = full_graph.len();
         eprintln!("Adjacency graph loaded with {graph_size} nodes");
@@ -329,10 +518,14 @@ fn main() {
                         num_threads,
                         beam_width,
                         args.mode == "catapult",
+                        args.csr,
                         seed,
-                        BUCKET_SIZE,
+                        bucket_cap,
                         NUM_HASH,
                         args.output_neighbors,
+                        ground_truth.as_deref(),
+                        args.num_neighbors,
+                        query_stats.as_mut(),
                     )
                 };
                 all_results.push(result);
@@ -351,4 +544,54 @@ fn main() {
     let json_output = serde_json::to_string_pretty(&benchmark_results).unwrap();
     std::fs::write(&args.output, json_output).expect("Failed to write output file");
     eprintln!("Results written to: {}", args.output);
+
+    if let (Some(path), Some(rows)) = (&args.stats_out, &query_stats) {
+        write_query_stats(Path::new(path), rows).expect("Failed to write per-query stats");
+        eprintln!("Per-query stats written to: {path}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_out_has_one_row_per_query() {
+        let graph = AdjacencyGraph::<LruSet>::load_flat_from_path(
+            PathBuf::from("test/index/ann"),
+            PathBuf::from("test/index/ann_vectors.bin"),
+            NUM_HASH,
+            40,
+            42,
+            SearchStrategy::Catapult,
+        )
+        .unwrap();
+        let queries = Vec::<Vec<AlignedBlock>>::load_from_npy("test/index/vectors.npy", None);
+        let num_queries = queries.len();
+
+        let mut rows = Vec::new();
+        run_search_job(
+            Arc::new(graph),
+            Arc::new(queries),
+            2,
+            4,
+            true,
+            false,
+            42,
+            40,
+            NUM_HASH,
+            false,
+            None,
+            10,
+            Some(&mut rows),
+        );
+        assert_eq!(rows.len(), num_queries);
+        assert!(rows.iter().enumerate().all(|(i, row)| row.query == i));
+
+        let path = std::env::temp_dir().join(format!("catapult-{}-stats.csv", std::process::id()));
+        write_query_stats(&path, &rows).unwrap();
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(csv.lines().count(), 1 + num_queries);
+    }
 }
@@ -1,5 +1,6 @@
 use crate::{
-    numerics::{AlignedBlock, SIMD_LANECOUNT},
+    fs::{LoadError, LoadedFile, MappedPayloads, MappedVector},
+    numerics::{AlignedBlock, HalfBlock, HalfVector, Payload, SIMD_LANECOUNT},
     search::{
         AdjacencyGraph, Node, NodeId, SearchStrategy,
         hash_start::{EngineStarter, EngineStarterParams},
@@ -7,111 +8,394 @@ use crate::{
     sets::{catapults::CatapultEvictionPolicy, fixed::FlatFixedSet},
 };
 
+use memmap2::Mmap;
 use std::{
     fs::File,
-    io::{BufReader, Error, Read},
-    path::PathBuf,
-    vec,
+    io::{BufReader, Read},
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread, vec,
 };
 use tracing::info_span;
 
-impl<T: CatapultEvictionPolicy> AdjacencyGraph<T> {
-    /// Reads the next N bytes from a byte iterator.
+/// Capacity of the buffers the graph and payload files are read through.
+const READ_BUFFER_BYTES: usize = 1 << 20;
+
+/// Size of the graph file header: `full_size`, `max_degree`, `entry_point`, `num_frozen`.
+pub(super) const GRAPH_HEADER_BYTES: usize = 8 + 4 + 4 + 8;
+
+/// The validated header of a graph file in the format of
+/// [`load_flat_from_path`](AdjacencyGraph::load_flat_from_path).
+///
+/// The format is DiskANN's and has no magic number, but its `full_size` field holds the
+/// length of the file in bytes. Requiring the two to agree rejects unrelated files, files
+/// of a newer layout and truncated ones before any node is allocated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GraphHeader {
+    /// Total size of the graph file in bytes
+    pub full_size: u64,
+
+    /// Largest number of neighbors of a node
+    pub max_degree: u32,
+
+    /// Index of the node searches start from
+    pub entry_point: u32,
+
+    /// Number of frozen points, counted among the nodes
+    pub num_frozen: u64,
+}
+
+impl GraphHeader {
+    /// Reads and validates the header at the start of `reader`.
     ///
     /// # Arguments
-    /// * `iter` - Iterator over bytes (with error handling)
+    /// * `reader` - Reader positioned at the start of the graph file
+    /// * `file_len` - Length of the graph file in bytes
+    /// * `npoints` - Number of nodes declared by the payloads of the graph
+    ///
+    /// # Errors
+    /// Returns [`LoadError::BadHeader`] if `full_size` differs from `file_len`, or if the
+    /// entry point or the number of frozen points exceed `npoints`, and
+    /// [`LoadError::Io`] for any I/O error raised while reading
+    pub fn read(reader: &mut impl Read, file_len: u64, npoints: usize) -> Result<Self, LoadError> {
+        if file_len < GRAPH_HEADER_BYTES as u64 {
+            return Err(LoadError::BadHeader(format!(
+                "the file holds {file_len} bytes, fewer than a header"
+            )));
+        }
+        let mut bytes = [0u8; GRAPH_HEADER_BYTES];
+        reader.read_exact(&mut bytes)?;
+        let header = GraphHeader {
+            full_size: u64::from_le_bytes(bytes[0..8].try_into().unwrap()),
+            max_degree: u32::from_le_bytes(bytes[8..12].try_into().unwrap()),
+            entry_point: u32::from_le_bytes(bytes[12..16].try_into().unwrap()),
+            num_frozen: u64::from_le_bytes(bytes[16..24].try_into().unwrap()),
+        };
+        if header.full_size != file_len {
+            return Err(LoadError::BadHeader(format!(
+                "it declares {} bytes but the file holds {file_len}, is this a graph file?",
+                header.full_size
+            )));
+        }
+        if header.entry_point as usize >= npoints.max(1) {
+            return Err(LoadError::BadHeader(format!(
+                "entry point {} of a graph of {npoints} nodes",
+                header.entry_point
+            )));
+        }
+        if header.num_frozen > npoints as u64 {
+            return Err(LoadError::BadHeader(format!(
+                "{} frozen points in a graph of {npoints} nodes",
+                header.num_frozen
+            )));
+        }
+        Ok(header)
+    }
+
+    /// Reads and validates the header of the graph file at `graph_path`, see
+    /// [`read`](GraphHeader::read).
+    ///
+    /// # Errors
+    /// Same as [`read`](GraphHeader::read), and any I/O error raised while opening the file
+    pub fn read_from_path(graph_path: impl AsRef<Path>, npoints: usize) -> Result<Self, LoadError> {
+        let mut file = File::open(graph_path)?;
+        let file_len = file.metadata()?.len();
+        Self::read(&mut file, file_len, npoints)
+    }
+}
+
+/// Returns a [`LoadError::DimensionMismatch`] unless a payload file of `file_bytes` bytes
+/// holds exactly its 8-byte header and `npoints` vectors of `dim` elements.
+fn check_payload_size(
+    npoints: usize,
+    dim: usize,
+    element_bytes: usize,
+    file_bytes: u64,
+) -> Result<(), LoadError> {
+    let expected = npoints
+        .checked_mul(dim)
+        .and_then(|elements| elements.checked_mul(element_bytes))
+        .and_then(|bytes| bytes.checked_add(8));
+    if expected.map(|bytes| bytes as u64) == Some(file_bytes) {
+        Ok(())
+    } else {
+        Err(LoadError::DimensionMismatch {
+            npoints,
+            dim,
+            file_bytes,
+        })
+    }
+}
+
+/// Element type of the vectors stored in a payload file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadFormat {
+    /// Little-endian f32 values, as written by DiskANN
+    F32,
+
+    /// Little-endian IEEE 754 half-precision values
+    F16,
+}
+
+impl<T: CatapultEvictionPolicy, P: Payload> AdjacencyGraph<T, P> {
+    /// Reads the next N bytes from a reader.
+    ///
+    /// # Arguments
+    /// * `iter` - Buffered reader over the file
     ///
     /// # Returns
     /// `Some([u8; N])` if N bytes were successfully read, `None` otherwise
     fn next_bytes<I, const N: usize>(iter: &mut I) -> Option<[u8; N]>
     where
-        I: Iterator<Item = Result<u8, Error>>,
+        I: Read,
     {
         let mut bytes = [0u8; N];
-        for b in &mut bytes {
-            let next = iter.next();
-            if let Some(Ok(byte)) = next {
-                *b = byte
-            } else {
-                return None;
-            }
-        }
+        iter.read_exact(&mut bytes).ok()?;
         Some(bytes)
     }
 
+    /// Reads the next `count` values of `N` bytes each in a single bulk read.
+    ///
+    /// # Arguments
+    /// * `iter` - Buffered reader over the file
+    /// * `count` - Number of values to read
+    /// * `parse` - Decodes one value from its bytes
+    ///
+    /// # Returns
+    /// `Some` of the decoded values if all bytes were successfully read, `None` otherwise
+    fn next_values<I, V, const N: usize>(
+        iter: &mut I,
+        count: usize,
+        parse: impl Fn([u8; N]) -> V,
+    ) -> Option<Vec<V>>
+    where
+        I: Read,
+    {
+        let mut bytes = vec![0u8; count * N];
+        iter.read_exact(&mut bytes).ok()?;
+        Some(
+            bytes
+                .chunks_exact(N)
+                .map(|chunk| parse(chunk.try_into().unwrap()))
+                .collect(),
+        )
+    }
+
     /// Reads and parses the next 4 bytes as a little-endian u32.
     ///
     /// # Arguments
-    /// * `iter` - Iterator over bytes
+    /// * `iter` - Buffered reader over the file
     ///
     /// # Returns
     /// `Some(u32)` if 4 bytes were successfully read and parsed, `None` otherwise
     fn next_u32<I>(iter: &mut I) -> Option<u32>
     where
-        I: Iterator<Item = Result<u8, Error>>,
+        I: Read,
     {
         Self::next_bytes::<I, 4>(iter).map(u32::from_le_bytes)
     }
 
-    /// Reads and parses the next 8 bytes as a little-endian u64.
+    /// Reads a vector payload as a sequence of aligned blocks.
+    ///
+    /// Reads `size` f32 values and packs them into `AlignedBlock` instances, zero-padding
+    /// the last block when `size` is not a multiple of `SIMD_LANECOUNT`.
     ///
     /// # Arguments
-    /// * `iter` - Iterator over bytes
+    /// * `iter` - Buffered reader over the file
+    /// * `size` - Number of f32 elements to read
     ///
     /// # Returns
-    /// `Some(u64)` if 8 bytes were successfully read and parsed, `None` otherwise
-    fn next_u64<I>(iter: &mut I) -> Option<u64>
+    /// `Some(Vec<AlignedBlock>)` if all bytes were successfully read, `None` otherwise
+    fn next_payload<I>(iter: &mut I, size: usize) -> Option<Vec<AlignedBlock>>
     where
-        I: Iterator<Item = Result<u8, Error>>,
+        I: Read,
     {
-        Self::next_bytes::<I, 8>(iter).map(u64::from_le_bytes)
+        let values = Self::next_values(iter, size, f32::from_le_bytes)?;
+        Some(AlignedBlock::allocate_padded(values))
     }
 
-    /// Reads and parses the next 4 bytes as a little-endian f32.
+    /// Reads a half-precision vector payload as a sequence of half blocks, zero-padding the
+    /// last block when `size` is not a multiple of `SIMD_LANECOUNT`.
     ///
     /// # Arguments
-    /// * `iter` - Iterator over bytes
+    /// * `iter` - Buffered reader over the file
+    /// * `size` - Number of f16 elements to read
     ///
     /// # Returns
-    /// `Some(f32)` if 4 bytes were successfully read and parsed, `None` otherwise
-    fn next_f32<I>(iter: &mut I) -> Option<f32>
+    /// `Some(Vec<HalfBlock>)` if all bytes were successfully read, `None` otherwise
+    fn next_half_payload<I>(iter: &mut I, size: usize) -> Option<Vec<HalfBlock>>
     where
-        I: Iterator<Item = Result<u8, Error>>,
+        I: Read,
     {
-        Self::next_bytes::<I, 4>(iter).map(f32::from_le_bytes)
+        let values = Self::next_values(iter, size, f16::from_le_bytes)?;
+        Some(
+            values
+                .chunks(SIMD_LANECOUNT)
+                .map(|lanes| {
+                    let mut block = HalfBlock {
+                        data: [0.0; SIMD_LANECOUNT],
+                    };
+                    block.data[..lanes.len()].copy_from_slice(lanes);
+                    block
+                })
+                .collect(),
+        )
     }
 
-    /// Reads a vector payload as a sequence of aligned blocks.
+    /// Loads a flat graph, decoding each node's payload with `read_payload`.
     ///
-    /// Reads `size` f32 values and packs them into `AlignedBlock` instances.
+    /// Shared by the loaders for the different payload representations; see
+    /// [`load_flat_from_path`](AdjacencyGraph::load_flat_from_path) for the file format.
     ///
     /// # Arguments
-    /// * `iter` - Iterator over bytes
-    /// * `size` - Number of f32 elements to read (must be multiple of `SIMD_LANECOUNT`)
+    /// * `element_bytes` - Size of one payload element in the payload file
+    /// * `read_payload` - Reads one payload of the given number of elements from the
+    ///   payload file, returning `None` if the file ends early
     ///
-    /// # Returns
-    /// `Some(Vec<AlignedBlock>)` if all bytes were successfully read, `None` otherwise
-    ///
-    /// # Panics
-    /// Panics if `size` is not a multiple of `SIMD_LANECOUNT`
-    fn next_payload<I>(iter: &mut I, size: usize) -> Option<Vec<AlignedBlock>>
+    /// # Errors
+    /// Same conditions as `load_flat_from_path`
+    #[allow(clippy::too_many_arguments)]
+    fn load_flat_with<R>(
+        graph_path: PathBuf,
+        payload_path: PathBuf,
+        num_hash: usize,
+        bucket_cap: usize,
+        seed: u64,
+        running_mode: SearchStrategy,
+        element_bytes: usize,
+        mut read_payload: R,
+    ) -> Result<Self, LoadError>
     where
-        I: Iterator<Item = Result<u8, Error>>,
+        R: FnMut(&mut BufReader<File>, usize) -> Option<P>,
     {
-        assert!(size.is_multiple_of(SIMD_LANECOUNT));
-
-        let final_length = size / SIMD_LANECOUNT;
-        let mut payload = Vec::with_capacity(final_length);
-        for _ in 0..final_length {
-            let mut block = [0.0; SIMD_LANECOUNT];
-            for entry in block.iter_mut() {
-                *entry = Self::next_f32(iter)?;
+        let payload_file = File::open(payload_path)?;
+        let file_bytes = payload_file.metadata()?.len();
+        let mut payload_file = BufReader::with_capacity(READ_BUFFER_BYTES, payload_file);
+
+        let mut header = || {
+            Self::next_u32(&mut payload_file).ok_or(LoadError::Truncated {
+                file: LoadedFile::Payload,
+            })
+        };
+        let npoints = header()? as usize;
+        let payload_dim = header()? as usize;
+        check_payload_size(npoints, payload_dim, element_bytes, file_bytes)?;
+
+        Self::load_graph_with(
+            graph_path,
+            npoints,
+            payload_dim,
+            num_hash,
+            bucket_cap,
+            seed,
+            running_mode,
+            || read_payload(&mut payload_file, payload_dim),
+        )
+    }
+
+    /// Loads the graph structure file, taking the payload of each successive node from
+    /// `next_payload`.
+    ///
+    /// # Arguments
+    /// * `npoints` - Number of points declared by the payload source
+    /// * `payload_dim` - Logical dimension of the payloads in f32 elements
+    /// * `next_payload` - Returns the payload of the next node, or `None` if there is none
+    ///
+    /// # Errors
+    /// Same conditions as `load_flat_from_path`
+    #[allow(clippy::too_many_arguments)]
+    fn load_graph_with(
+        graph_path: PathBuf,
+        npoints: usize,
+        payload_dim: usize,
+        num_hash: usize,
+        bucket_cap: usize,
+        seed: u64,
+        running_mode: SearchStrategy,
+        mut next_payload: impl FnMut() -> Option<P>,
+    ) -> Result<Self, LoadError> {
+        let graph_file = File::open(graph_path)?;
+        let file_len = graph_file.metadata()?.len();
+        let mut graph_file = BufReader::with_capacity(READ_BUFFER_BYTES, graph_file);
+
+        let GraphHeader {
+            full_size,
+            max_degree,
+            entry_point,
+            num_frozen,
+        } = GraphHeader::read(&mut graph_file, file_len, npoints)?;
+
+        println!(
+            "size {full_size} - degree {max_degree} - entry point {entry_point} - num frozen {num_frozen} - npoints {npoints} - payload_dim {payload_dim}",
+        );
+
+        let truncated = || LoadError::Truncated {
+            file: LoadedFile::Graph,
+        };
+        let mut links = Vec::new();
+        {
+            let _span = info_span!("parse_nodes", full_size).entered();
+            // the header check guarantees that `full_size` is the length of the file
+            let mut remaining = full_size - GRAPH_HEADER_BYTES as u64;
+            while remaining > 0 {
+                let pointsize = Self::next_u32(&mut graph_file).ok_or_else(truncated)? as u64;
+                // checked before allocating, so a corrupt degree cannot exhaust memory
+                let node_bytes = 4 * (1 + pointsize);
+                if node_bytes > remaining {
+                    return Err(truncated());
+                }
+                remaining -= node_bytes;
+                let neighs = Self::next_values(&mut graph_file, pointsize as usize, |b| {
+                    u32::from_le_bytes(b) as usize
+                })
+                .ok_or_else(truncated)?;
+
+                // out-of-bounds indices are kept, for `check_bounds` to report them
+                links.push(if neighs.iter().all(|&n| n < npoints) {
+                    FlatFixedSet::compact(neighs, npoints)
+                } else {
+                    FlatFixedSet::new(neighs)
+                });
             }
-            payload.push(AlignedBlock::new(block));
         }
-        Some(payload)
+
+        if links.len() != npoints {
+            return Err(LoadError::NodeCountMismatch {
+                graph_nodes: links.len(),
+                payload_nodes: npoints,
+            });
+        }
+        let mut adjacency = Vec::with_capacity(npoints);
+        for neighbors in links {
+            let payload = next_payload().ok_or(LoadError::Truncated {
+                file: LoadedFile::Payload,
+            })?;
+            adjacency.push(Node { neighbors, payload });
+        }
+
+        let entry_point_id = NodeId {
+            internal: entry_point as usize,
+        };
+
+        // Hash in the logical dimension, so that padding lanes carry no weight
+        let engine_params = EngineStarterParams::new(
+            num_hash,
+            bucket_cap,
+            payload_dim,
+            entry_point_id,
+            seed,
+            running_mode.uses_catapults(),
+        );
+
+        Ok(AdjacencyGraph::new_flat(
+            adjacency,
+            EngineStarter::<T>::new(engine_params),
+            running_mode,
+        ))
     }
+}
 
+impl<T: CatapultEvictionPolicy> AdjacencyGraph<T> {
     /// Loads a flat graph from binary files containing graph structure and node payloads.
     ///
     /// Reads two files: one containing the graph adjacency structure and another containing
@@ -120,7 +404,7 @@ impl<T: CatapultEvictionPolicy> AdjacencyGraph<T> {
     ///
     /// # Binary Format
     /// **Graph file header:**
-    /// - `full_size` (u64): Total number of nodes
+    /// - `full_size` (u64): Size of the graph file in bytes, header included
     /// - `max_degree` (u32): Maximum node degree
     /// - `entry_point` (u32): Starting node index
     /// - `num_frozen` (u64): Number of frozen nodes
@@ -131,11 +415,18 @@ impl<T: CatapultEvictionPolicy> AdjacencyGraph<T> {
     ///
     /// **Payload file header:**
     /// - `npoints` (u32): Number of points
-    /// - `payload_dim` (u32): Vector dimension
+    /// - `payload_dim` (u32): Vector dimension, padded with zeros in memory up to the next
+    ///   multiple of `SIMD_LANECOUNT`
     ///
     /// **Per node in payload file:**
     /// - `vector_data` (f32[]): Flat array of f32 values
     ///
+    /// The graph format is DiskANN's and has no magic number or version. A graph file is
+    /// only recognized by its `full_size` matching the actual file length (see
+    /// [`GraphHeader`]), and a payload file by its size matching `npoints` vectors of
+    /// `payload_dim` values. A file of another format that happens to pass these size
+    /// checks is not detected.
+    ///
     /// # Arguments
     /// * `graph_path` - Path to the binary graph structure file
     /// * `payload_path` - Path to the binary payload vectors file
@@ -143,14 +434,22 @@ impl<T: CatapultEvictionPolicy> AdjacencyGraph<T> {
     /// * `seed` - Random seed for LSH hyperplane generation
     /// * `enabled_catapults` - Whether to enable catapult acceleration
     ///
+    /// Neighbor indices are not checked while loading: graphs from untrusted sources can be
+    /// searched with [`beam_search_checked`](AdjacencyGraph::beam_search_checked), which
+    /// reports out-of-bounds indices as errors instead of panicking.
+    ///
     /// # Returns
     /// A new `AdjacencyGraph` with the entry point from the file used as the starting node
     ///
-    /// # Panics
-    /// * Panics if files cannot be opened
-    /// * Panics if file format is invalid or headers are missing
-    /// * Panics if vector dimension is not a multiple of `SIMD_LANECOUNT`
-    /// * Panics if the number of nodes in graph and payload files don't match
+    /// # Errors
+    /// * [`LoadError::Io`] if a file cannot be opened or read
+    /// * [`LoadError::BadHeader`] if the graph file is not a graph file, see
+    ///   [`GraphHeader`]
+    /// * [`LoadError::Truncated`] if the payload header is missing or a neighbor list runs
+    ///   past the end of the graph file
+    /// * [`LoadError::DimensionMismatch`] if the payload file size does not match its header
+    /// * [`LoadError::NodeCountMismatch`] if the number of nodes in graph and payload files
+    ///   don't match
     pub fn load_flat_from_path(
         graph_path: PathBuf,
         payload_path: PathBuf,
@@ -158,85 +457,238 @@ impl<T: CatapultEvictionPolicy> AdjacencyGraph<T> {
         bucket_cap: usize,
         seed: u64,
         running_mode: SearchStrategy,
-    ) -> Self {
-        let mut graph_file = BufReader::new(File::open(graph_path).expect("FNF")).bytes();
-        let mut payload_file = BufReader::new(File::open(payload_path).expect("FNF")).bytes();
-
-        let full_size = Self::next_u64(&mut graph_file).expect("Misconfigured header");
-        let max_degree = Self::next_u32(&mut graph_file).expect("Misconfigured header");
-        let entry_point = Self::next_u32(&mut graph_file).expect("Misconfigured header");
-        let num_frozen = Self::next_u64(&mut graph_file).expect("Misconfigured header");
-
-        let npoints = Self::next_u32(&mut payload_file).expect("Misconfigured header");
-        let payload_dim = Self::next_u32(&mut payload_file).expect("Misconfigured header") as usize;
-
-        println!(
-            "size {full_size} - degree {max_degree} - entry point {entry_point} - num frozen {num_frozen} - npoints {npoints} - payload_dim {payload_dim}",
-        );
-
-        let mut adjacency = Vec::new();
-
-        {
-            let _span = info_span!("parse_nodes", full_size).entered();
-            while let Some(pointsize) = Self::next_u32(&mut graph_file) {
-                let mut neighs = vec![];
-
-                for _ in 0..pointsize {
-                    neighs.push(
-                        Self::next_u32(&mut graph_file)
-                            .expect("Graph file declared more nodes than actually found")
-                            as usize,
-                    );
-                }
+    ) -> Result<Self, LoadError> {
+        Self::load_flat_with(
+            graph_path,
+            payload_path,
+            num_hash,
+            bucket_cap,
+            seed,
+            running_mode,
+            size_of::<f32>(),
+            |file, dim| Self::next_payload(file, dim).map(Vec::into_boxed_slice),
+        )
+    }
+}
 
-                let associated_payload = Self::next_payload(&mut payload_file, payload_dim)
-                    .expect("Error while parsing payloads");
+impl<T: CatapultEvictionPolicy> AdjacencyGraph<T> {
+    /// Loads the same graph as [`load_flat_from_path`](AdjacencyGraph::load_flat_from_path),
+    /// parsing payloads on several threads.
+    ///
+    /// The payload file is memory-mapped instead of being read byte by byte, and its nodes
+    /// are split into `num_threads` contiguous ranges, each parsed on its own thread. The
+    /// payloads are then attached to the nodes in file order, so the returned graph is
+    /// identical to the one of the serial loader. The graph file is still read serially.
+    ///
+    /// # Arguments
+    /// * `graph_path` - Path to the binary graph structure file
+    /// * `payload_path` - Path to the binary payload vectors file
+    /// * `num_hash` - Number of LSH hash bits (creates 2^num_hash buckets)
+    /// * `bucket_cap` - Maximum number of catapults per bucket
+    /// * `seed` - Random seed for LSH hyperplane generation
+    /// * `running_mode` - Search strategy of the returned graph
+    /// * `num_threads` - Number of threads parsing payloads, at least 1
+    ///
+    /// # Returns
+    /// A new `AdjacencyGraph` with the entry point from the file used as the starting node
+    ///
+    /// # Errors
+    /// Same conditions as `load_flat_from_path`, and [`LoadError::Io`] if the payload file
+    /// cannot be mapped
+    ///
+    /// # Panics
+    /// Panics if `num_threads` is 0
+    pub fn load_flat_parallel_from_path(
+        graph_path: PathBuf,
+        payload_path: PathBuf,
+        num_hash: usize,
+        bucket_cap: usize,
+        seed: u64,
+        running_mode: SearchStrategy,
+        num_threads: usize,
+    ) -> Result<Self, LoadError> {
+        assert!(num_threads > 0, "at least one thread must parse payloads");
+        let file = File::open(payload_path)?;
+        // SAFETY: the mapping is read-only and dropped before returning, and payload files
+        // are not modified while graphs are loaded from them.
+        let map = unsafe { Mmap::map(&file) }?;
 
-                adjacency.push(Node {
-                    neighbors: FlatFixedSet::new(neighs),
-                    payload: associated_payload.into_boxed_slice(),
-                });
-            }
+        if map.len() < 8 {
+            return Err(LoadError::Truncated {
+                file: LoadedFile::Payload,
+            });
         }
+        let header = |at: usize| u32::from_le_bytes(map[at..at + 4].try_into().unwrap()) as usize;
+        let (npoints, payload_dim) = (header(0), header(4));
+        check_payload_size(npoints, payload_dim, size_of::<f32>(), map.len() as u64)?;
+        let vector_bytes = payload_dim * size_of::<f32>();
+        let data = &map[8..];
 
-        // we should have read all of the file contents by now.
-        assert!(graph_file.count() == 0);
-        assert!(payload_file.count() == 0);
-
-        let entry_point_id = NodeId {
-            internal: entry_point as usize,
+        let nodes_per_thread = npoints.div_ceil(num_threads).max(1);
+        let payloads: Vec<Box<[AlignedBlock]>> = {
+            let _span = info_span!("parse_payloads", npoints, num_threads).entered();
+            thread::scope(|scope| {
+                let handles: Vec<_> = (0..npoints)
+                    .step_by(nodes_per_thread)
+                    .map(|first| {
+                        let last = (first + nodes_per_thread).min(npoints);
+                        let bytes = &data[first * vector_bytes..last * vector_bytes];
+                        scope.spawn(move || {
+                            (first..last)
+                                .map(|node| {
+                                    let offset = (node - first) * vector_bytes;
+                                    let values = bytes[offset..offset + vector_bytes]
+                                        .chunks_exact(size_of::<f32>())
+                                        .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+                                        .collect();
+                                    AlignedBlock::allocate_padded(values).into_boxed_slice()
+                                })
+                                .collect::<Vec<_>>()
+                        })
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .flat_map(|handle| handle.join().expect("payload parser panicked"))
+                    .collect()
+            })
         };
+        drop(map);
 
-        // Determine plane_dim from the first node's payload
-        let plane_dim = adjacency[0].payload.len() * SIMD_LANECOUNT;
+        let mut payloads = payloads.into_iter();
+        Self::load_graph_with(
+            graph_path,
+            npoints,
+            payload_dim,
+            num_hash,
+            bucket_cap,
+            seed,
+            running_mode,
+            || payloads.next(),
+        )
+    }
+}
 
-        let engine_params = EngineStarterParams::new(
+impl<T: CatapultEvictionPolicy> AdjacencyGraph<T, HalfVector> {
+    /// Loads a flat graph whose payloads are kept in half precision.
+    ///
+    /// Uses the same graph file and payload header as
+    /// [`load_flat_from_path`](AdjacencyGraph::load_flat_from_path). With
+    /// [`PayloadFormat::F16`], the payload file stores 2-byte little-endian f16 values;
+    /// with [`PayloadFormat::F32`], it is a regular f32 payload file whose values are
+    /// rounded to f16 while loading. Either way, each payload takes half the memory of
+    /// the f32 graph and is widened back to f32 inside the distance computation.
+    ///
+    /// # Arguments
+    /// * `graph_path` - Path to the binary graph structure file
+    /// * `payload_path` - Path to the binary payload vectors file
+    /// * `num_hash` - Number of LSH hash bits (creates 2^num_hash buckets)
+    /// * `bucket_cap` - Maximum number of catapults per bucket
+    /// * `seed` - Random seed for LSH hyperplane generation
+    /// * `running_mode` - Search strategy of the returned graph
+    /// * `payload_format` - Element type of the vectors in the payload file
+    ///
+    /// # Returns
+    /// A new `AdjacencyGraph` storing `HalfVector` payloads
+    ///
+    /// # Errors
+    /// Same conditions as `load_flat_from_path`
+    pub fn load_flat_f16_from_path(
+        graph_path: PathBuf,
+        payload_path: PathBuf,
+        num_hash: usize,
+        bucket_cap: usize,
+        seed: u64,
+        running_mode: SearchStrategy,
+        payload_format: PayloadFormat,
+    ) -> Result<Self, LoadError> {
+        let element_bytes = match payload_format {
+            PayloadFormat::F32 => size_of::<f32>(),
+            PayloadFormat::F16 => size_of::<f16>(),
+        };
+        Self::load_flat_with(
+            graph_path,
+            payload_path,
             num_hash,
             bucket_cap,
-            plane_dim,
-            entry_point_id,
             seed,
-            matches!(running_mode, SearchStrategy::Catapult),
-        );
+            running_mode,
+            element_bytes,
+            |file, dim| match payload_format {
+                PayloadFormat::F32 => {
+                    Self::next_payload(file, dim).map(|blocks| HalfVector::from_blocks(&blocks))
+                }
+                PayloadFormat::F16 => {
+                    Self::next_half_payload(file, dim).map(HalfVector::from_half_blocks)
+                }
+            },
+        )
+    }
+}
 
-        AdjacencyGraph::new_flat(
-            adjacency,
-            EngineStarter::<T>::new(engine_params),
+impl<T: CatapultEvictionPolicy> AdjacencyGraph<T, MappedVector> {
+    /// Loads a flat graph whose payloads stay in a memory-mapped file.
+    ///
+    /// The graph structure is read into memory as by
+    /// [`load_flat_from_path`](AdjacencyGraph::load_flat_from_path), but each payload is
+    /// only a view into the mapped payload file, so datasets larger than RAM can be
+    /// searched and the operating system pages vectors in and out as needed. The payload
+    /// file must use the block-aligned layout described in [`MappedPayloads`]; a regular
+    /// payload file can be converted with [`MappedPayloads::convert_from_flat`].
+    ///
+    /// # Arguments
+    /// * `graph_path` - Path to the binary graph structure file
+    /// * `mapped_payload_path` - Path to the block-aligned payload file
+    /// * `num_hash` - Number of LSH hash bits (creates 2^num_hash buckets)
+    /// * `bucket_cap` - Maximum number of catapults per bucket
+    /// * `seed` - Random seed for LSH hyperplane generation
+    /// * `running_mode` - Search strategy of the returned graph
+    ///
+    /// # Returns
+    /// A new `AdjacencyGraph` storing `MappedVector` payloads
+    ///
+    /// # Errors
+    /// Same conditions as `load_flat_from_path`, and [`LoadError::Io`] with the error of
+    /// [`MappedPayloads::open`] if the payload file cannot be mapped or fails its validation
+    pub fn load_flat_mmap_from_path(
+        graph_path: PathBuf,
+        mapped_payload_path: PathBuf,
+        num_hash: usize,
+        bucket_cap: usize,
+        seed: u64,
+        running_mode: SearchStrategy,
+    ) -> Result<Self, LoadError> {
+        let store = Arc::new(MappedPayloads::open(mapped_payload_path)?);
+        let mut vectors = MappedPayloads::vectors(&store);
+
+        Self::load_graph_with(
+            graph_path,
+            store.len(),
+            store.dim(),
+            num_hash,
+            bucket_cap,
+            seed,
             running_mode,
+            || vectors.next(),
         )
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::{GraphHeader, PayloadFormat};
     use crate::{
+        fs::{LoadError, LoadedFile, temp_path},
+        numerics::{AlignedBlock, SIMD_LANECOUNT},
         search::{
-            AdjacencyGraph,
+            AdjacencyGraph, NodeId,
             SearchStrategy::{Catapult, LshApg, Vanilla},
             hash_start::zorder_index::ZOrderIndex,
         },
-        sets::catapults::LruSet,
+        sets::{candidates::CandidateEntry, catapults::LruSet},
+        statistics::Stats,
     };
+    use std::io::ErrorKind;
 
     #[test]
     fn loading_example_graph() {
@@ -250,7 +702,8 @@ mod tests {
             40,
             42,       // seed
             Catapult, // enabled_catapults
-        );
+        )
+        .unwrap();
 
         let graphed2 = AdjacencyGraph::<LruSet>::load_flat_from_path(
             graph_path.into(),
@@ -259,7 +712,8 @@ mod tests {
             40,
             42,      // seed
             Vanilla, // enabled_catapults
-        );
+        )
+        .unwrap();
 
         let graphed3 = AdjacencyGraph::<LruSet>::load_flat_from_path(
             graph_path.into(),
@@ -268,10 +722,313 @@ mod tests {
             40,
             42, // seed
             LshApg([ZOrderIndex::new(4, 16, 4, 1.0)]),
-        );
+        )
+        .unwrap();
 
         assert!(graphed1.len() == 4);
         assert!(graphed2.len() == 4);
         assert_eq!(graphed3.len(), 4);
     }
+
+    #[test]
+    fn graph_header_of_the_fixture_is_valid() {
+        let header = GraphHeader::read_from_path("test/index/ann", 4).unwrap();
+        assert_eq!(header.full_size, 64);
+        assert_eq!(header.num_frozen, 0);
+        assert!(header.entry_point < 4);
+    }
+
+    #[test]
+    fn graph_header_rejects_swapped_files() {
+        // a payload file passed as the graph file, as when two paths get swapped
+        let swapped = GraphHeader::read_from_path("test/index/ann_vectors.bin", 4);
+        assert!(matches!(swapped, Err(LoadError::BadHeader(_))));
+
+        let path = temp_path("load-short");
+        std::fs::write(&path, [1u8; 7]).unwrap();
+        let short = GraphHeader::read_from_path(&path, 4);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(short, Err(LoadError::BadHeader(_))));
+
+        let out_of_range = GraphHeader::read_from_path("test/index/ann", 1);
+        assert!(matches!(out_of_range, Err(LoadError::BadHeader(_))));
+    }
+
+    /// Loads the fixture graph after `edit_graph` and `edit_payloads` altered its files.
+    fn load_altered(
+        name: &str,
+        edit_graph: impl FnOnce(&mut Vec<u8>),
+        edit_payloads: impl FnOnce(&mut Vec<u8>),
+    ) -> Result<AdjacencyGraph<LruSet>, LoadError> {
+        let mut graph = std::fs::read("test/index/ann").unwrap();
+        let mut payloads = std::fs::read("test/index/ann_vectors.bin").unwrap();
+        edit_graph(&mut graph);
+        edit_payloads(&mut payloads);
+        let (graph_path, payload_path) = (
+            temp_path(&format!("load-{name}")),
+            temp_path(&format!("load-{name}.bin")),
+        );
+        std::fs::write(&graph_path, graph).unwrap();
+        std::fs::write(&payload_path, payloads).unwrap();
+        let loaded = AdjacencyGraph::<LruSet>::load_flat_from_path(
+            graph_path.clone(),
+            payload_path.clone(),
+            4,
+            40,
+            42,
+            Vanilla,
+        );
+        std::fs::remove_file(graph_path).unwrap();
+        std::fs::remove_file(payload_path).unwrap();
+        loaded
+    }
+
+    #[test]
+    fn loader_reports_missing_files_and_swapped_paths() {
+        let missing = AdjacencyGraph::<LruSet>::load_flat_from_path(
+            "test/index/missing".into(),
+            "test/index/ann_vectors.bin".into(),
+            4,
+            40,
+            42,
+            Vanilla,
+        );
+        assert!(matches!(missing, Err(LoadError::Io(e)) if e.kind() == ErrorKind::NotFound));
+
+        let swapped = AdjacencyGraph::<LruSet>::load_flat_from_path(
+            "test/index/ann_vectors.bin".into(),
+            "test/index/ann_vectors.bin".into(),
+            4,
+            40,
+            42,
+            Vanilla,
+        );
+        assert!(matches!(swapped, Err(LoadError::BadHeader(_))));
+    }
+
+    #[test]
+    fn loader_reports_truncated_files() {
+        let no_header = load_altered("no-header", |_| {}, |payloads| payloads.truncate(6));
+        assert!(matches!(
+            no_header,
+            Err(LoadError::Truncated {
+                file: LoadedFile::Payload
+            })
+        ));
+
+        // the first node claims more neighbors than the file holds
+        let long_list = load_altered(
+            "long-list",
+            |graph| graph[24..28].copy_from_slice(&1000u32.to_le_bytes()),
+            |_| {},
+        );
+        assert!(matches!(
+            long_list,
+            Err(LoadError::Truncated {
+                file: LoadedFile::Graph
+            })
+        ));
+    }
+
+    #[test]
+    fn loader_reports_payload_size_mismatches() {
+        let extra_value = load_altered("extra", |_| {}, |payloads| payloads.extend([0; 4]));
+        assert!(matches!(
+            extra_value,
+            Err(LoadError::DimensionMismatch {
+                npoints: 4,
+                dim: 16,
+                file_bytes: 268
+            })
+        ));
+
+        // a wider dimension, with its file otherwise unchanged
+        let wider = load_altered(
+            "wider",
+            |_| {},
+            |payloads| payloads[4..8].copy_from_slice(&17u32.to_le_bytes()),
+        );
+        assert!(matches!(wider, Err(LoadError::DimensionMismatch { .. })));
+    }
+
+    #[test]
+    fn loader_reports_disagreeing_node_counts() {
+        // the payload file loses its last vector, and says so in its header
+        let fewer = load_altered(
+            "fewer",
+            |_| {},
+            |payloads| {
+                payloads.truncate(payloads.len() - 16 * 4);
+                payloads[0..4].copy_from_slice(&3u32.to_le_bytes());
+            },
+        );
+        assert!(matches!(
+            fewer,
+            Err(LoadError::NodeCountMismatch {
+                graph_nodes: 4,
+                payload_nodes: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn bulk_reads_decode_every_value_of_the_fixture() {
+        let graph_path = "test/index/ann";
+        let payload_path = "test/index/ann_vectors.bin";
+        let graph = AdjacencyGraph::<LruSet>::load_flat_from_path(
+            graph_path.into(),
+            payload_path.into(),
+            4,
+            40,
+            42,
+            Vanilla,
+        )
+        .unwrap();
+
+        // decode both files by hand, one little-endian u32 or f32 at a time
+        let u32_at = |bytes: &[u8], at: usize| {
+            u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as usize
+        };
+        let payloads = std::fs::read(payload_path).unwrap();
+        let dim = u32_at(&payloads, 4);
+        let structure = std::fs::read(graph_path).unwrap();
+        let mut offset = 24;
+        for node in 0..graph.len() {
+            let values: Vec<f32> = (0..dim)
+                .map(|i| f32::from_bits(u32_at(&payloads, 8 + 4 * (node * dim + i)) as u32))
+                .collect();
+            assert_eq!(
+                **graph.payload(NodeId { internal: node }),
+                AlignedBlock::allocate_padded(values)[..]
+            );
+
+            let degree = u32_at(&structure, offset);
+            let neighbors: Vec<usize> = (0..degree)
+                .map(|i| u32_at(&structure, offset + 4 * (1 + i)))
+                .collect();
+            let loaded: Vec<usize> = graph
+                .neighbors_of(node)
+                .iter()
+                .map(|n| n.internal)
+                .collect();
+            assert_eq!(loaded, neighbors);
+            offset += 4 * (1 + degree);
+        }
+        assert_eq!(offset, structure.len());
+    }
+
+    #[test]
+    fn parallel_loader_matches_serial_loader() {
+        let graph_path = "test/index/ann";
+        let payload_path = "test/index/ann_vectors.bin";
+        let serial = AdjacencyGraph::<LruSet>::load_flat_from_path(
+            graph_path.into(),
+            payload_path.into(),
+            4,
+            40,
+            42,
+            Vanilla,
+        )
+        .unwrap();
+
+        // more threads than nodes leave some without work
+        for num_threads in [1, 3, 8] {
+            let parallel = AdjacencyGraph::<LruSet>::load_flat_parallel_from_path(
+                graph_path.into(),
+                payload_path.into(),
+                4,
+                40,
+                42,
+                Vanilla,
+                num_threads,
+            )
+            .unwrap();
+            assert_eq!(parallel.len(), serial.len());
+            for node in 0..serial.len() {
+                assert_eq!(
+                    parallel.payload(NodeId { internal: node }),
+                    serial.payload(NodeId { internal: node })
+                );
+                assert_eq!(
+                    parallel.neighbors_of(node).to_vec(),
+                    serial.neighbors_of(node).to_vec()
+                );
+            }
+        }
+    }
+
+    /// Rewrites an f32 payload file with the same header and f16 values.
+    fn write_f16_copy(f32_path: &str, f16_path: &std::path::Path) {
+        let bytes = std::fs::read(f32_path).unwrap();
+        let mut out = bytes[..8].to_vec();
+        for chunk in bytes[8..].chunks_exact(4) {
+            let x = f32::from_le_bytes(chunk.try_into().unwrap());
+            out.extend_from_slice(&(x as f16).to_le_bytes());
+        }
+        std::fs::write(f16_path, out).unwrap();
+    }
+
+    #[test]
+    fn loading_example_graph_as_f16() {
+        let graph_path = "test/index/ann";
+        let payload_path = "test/index/ann_vectors.bin";
+        let f16_path = temp_path("ann_f16.bin");
+        write_f16_copy(payload_path, &f16_path);
+
+        let full = AdjacencyGraph::<LruSet>::load_flat_from_path(
+            graph_path.into(),
+            payload_path.into(),
+            4,
+            40,
+            42,
+            Vanilla,
+        )
+        .unwrap();
+        let narrowed = AdjacencyGraph::<LruSet, _>::load_flat_f16_from_path(
+            graph_path.into(),
+            payload_path.into(),
+            4,
+            40,
+            42,
+            Vanilla,
+            PayloadFormat::F32,
+        )
+        .unwrap();
+        let half = AdjacencyGraph::<LruSet, _>::load_flat_f16_from_path(
+            graph_path.into(),
+            f16_path.clone(),
+            4,
+            40,
+            42,
+            Vanilla,
+            PayloadFormat::F16,
+        )
+        .unwrap();
+        std::fs::remove_file(&f16_path).unwrap();
+
+        assert_eq!(half.len(), 4);
+        let query = vec![AlignedBlock::new([0.25; SIMD_LANECOUNT])];
+        let mut stats = Stats::new();
+        let by_node = |results: Vec<CandidateEntry>| {
+            let mut results: Vec<(usize, f32)> = results
+                .into_iter()
+                .map(|c| (c.index.internal, c.distance.0))
+                .collect();
+            results.sort_by_key(|&(i, _)| i);
+            results
+        };
+        let exact = by_node(full.beam_search(&query, 4, 4, &mut stats));
+        let from_f16 = by_node(half.beam_search(&query, 4, 4, &mut stats));
+        let from_narrowed = by_node(narrowed.beam_search(&query, 4, 4, &mut stats));
+
+        assert_eq!(from_f16, from_narrowed);
+        assert_eq!(exact.len(), from_f16.len());
+        for ((i, e), (j, h)) in exact.into_iter().zip(from_f16) {
+            assert_eq!(i, j);
+            assert!(
+                (e - h).abs() <= 1e-3 * e.max(1e-6),
+                "node {i}: exact={e} f16={h}"
+            );
+        }
+    }
 }
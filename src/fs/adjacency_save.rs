@@ -0,0 +1,221 @@
+use crate::{
+    numerics::Payload,
+    search::{AdjacencyGraph, NodeId},
+    sets::catapults::CatapultEvictionPolicy,
+};
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, ErrorKind, Write},
+    path::Path,
+};
+
+use super::adjacency_load::GRAPH_HEADER_BYTES;
+
+/// Converts a count to the u32 used by the on-disk format.
+pub(super) fn to_u32(value: usize, what: &str) -> io::Result<u32> {
+    u32::try_from(value).map_err(|_| {
+        io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("{what} {value} does not fit in the u32 of the file format"),
+        )
+    })
+}
+
+impl<T: CatapultEvictionPolicy, P: Payload> AdjacencyGraph<T, P> {
+    /// Writes the graph in the format read by
+    /// [`load_flat_from_path`](AdjacencyGraph::load_flat_from_path).
+    ///
+    /// Like the loader, all values are little-endian. The graph header holds the total
+    /// size of the graph file in bytes as `full_size` (as DiskANN does), the largest
+    /// neighbor count as `max_degree`, the graph's starting node as `entry_point` and zero
+    /// frozen points. Payloads are written as f32 in the graph's logical
+    /// [`dim`](AdjacencyGraph::dim), without padding, reconstructed from the stored
+    /// representation, so a quantized or half-precision graph is saved with its rounded
+    /// values. Catapults are not part of this format; see
+    /// [`save_catapults`](AdjacencyGraph::save_catapults).
+    ///
+    /// # Arguments
+    /// * `graph_path` - Destination of the graph structure file, created or truncated
+    /// * `payload_path` - Destination of the payload vectors file, created or truncated
+    ///
+    /// # Errors
+    /// Returns an `InvalidInput` error if the node count, a degree, or the dimension does
+    /// not fit in a u32, or any I/O error raised while writing
+    pub fn save_to_path(
+        &self,
+        graph_path: impl AsRef<Path>,
+        payload_path: impl AsRef<Path>,
+    ) -> io::Result<()> {
+        to_u32(self.len(), "node count")?;
+        let mut max_degree = 0;
+        let mut full_size = GRAPH_HEADER_BYTES;
+        for node in 0..self.len() {
+            let degree = self.neighbors_of(node).len();
+            max_degree = max_degree.max(degree);
+            full_size += 4 * (1 + degree);
+        }
+
+        let mut graph = BufWriter::new(File::create(graph_path)?);
+        graph.write_all(&(full_size as u64).to_le_bytes())?;
+        graph.write_all(&to_u32(max_degree, "degree")?.to_le_bytes())?;
+        graph.write_all(&to_u32(self.starting_node().internal, "entry point")?.to_le_bytes())?;
+        graph.write_all(&0u64.to_le_bytes())?;
+        for node in 0..self.len() {
+            let neighbors = self.neighbors_of(node);
+            graph.write_all(&to_u32(neighbors.len(), "degree")?.to_le_bytes())?;
+            for neighbor in neighbors {
+                graph.write_all(&to_u32(neighbor.internal, "node index")?.to_le_bytes())?;
+            }
+        }
+        graph.flush()?;
+        self.write_payload_file(payload_path)
+    }
+
+    /// Writes the payload file of [`save_to_path`](Self::save_to_path).
+    pub(super) fn write_payload_file(&self, payload_path: impl AsRef<Path>) -> io::Result<()> {
+        let num_nodes = to_u32(self.len(), "node count")?;
+        let dim = self.dim();
+        let mut payloads = BufWriter::new(File::create(payload_path)?);
+        payloads.write_all(&num_nodes.to_le_bytes())?;
+        payloads.write_all(&to_u32(dim, "dimension")?.to_le_bytes())?;
+        for internal in 0..self.len() {
+            let blocks = self.payload(NodeId { internal }).to_blocks();
+            for value in blocks.iter().flat_map(|b| b.data).take(dim) {
+                payloads.write_all(&value.to_le_bytes())?;
+            }
+        }
+        payloads.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fs::temp_path,
+        numerics::Payload,
+        search::{AdjacencyGraph, GraphBuilder, NodeId, SearchStrategy::Vanilla, hash_start},
+        sets::catapults::LruSet,
+    };
+    use std::path::PathBuf;
+
+    fn load(graph_path: PathBuf, payload_path: PathBuf) -> AdjacencyGraph<LruSet> {
+        AdjacencyGraph::<LruSet>::load_flat_from_path(graph_path, payload_path, 4, 40, 42, Vanilla)
+            .unwrap()
+    }
+
+    fn assert_same_graph(a: &AdjacencyGraph<LruSet>, b: &AdjacencyGraph<LruSet>) {
+        assert_eq!(a.len(), b.len());
+        assert_eq!(a.starting_node(), b.starting_node());
+        for node in 0..a.len() {
+            assert_eq!(a.neighbors_of(node), b.neighbors_of(node));
+            assert_eq!(
+                a.payload(NodeId { internal: node }).to_blocks(),
+                b.payload(NodeId { internal: node }).to_blocks()
+            );
+        }
+    }
+
+    #[test]
+    fn test_round_trip_of_example_graph_is_byte_identical() {
+        let graph_path: PathBuf = "test/index/ann".into();
+        let payload_path: PathBuf = "test/index/ann_vectors.bin".into();
+        let original = load(graph_path.clone(), payload_path.clone());
+
+        let saved_graph = temp_path("saved-ann");
+        let saved_payload = temp_path("saved-ann_vectors.bin");
+        original.save_to_path(&saved_graph, &saved_payload).unwrap();
+        let reloaded = load(saved_graph.clone(), saved_payload.clone());
+
+        assert_same_graph(&original, &reloaded);
+        assert_eq!(
+            std::fs::read(&saved_graph).unwrap(),
+            std::fs::read(&graph_path).unwrap()
+        );
+        assert_eq!(
+            std::fs::read(&saved_payload).unwrap(),
+            std::fs::read(&payload_path).unwrap()
+        );
+        std::fs::remove_file(saved_graph).unwrap();
+        std::fs::remove_file(saved_payload).unwrap();
+    }
+
+    #[test]
+    fn test_round_trip_of_built_graph() {
+        use crate::numerics::{AlignedBlock, SIMD_LANECOUNT};
+
+        let points: Vec<Vec<AlignedBlock>> = (0..50)
+            .map(|i| {
+                vec![
+                    AlignedBlock::new([(i % 7) as f32; SIMD_LANECOUNT]),
+                    AlignedBlock::new([(i % 11) as f32 - 0.5; SIMD_LANECOUNT]),
+                ]
+            })
+            .collect();
+        let params = hash_start::EngineStarterParams::new(
+            4,
+            40,
+            2 * SIMD_LANECOUNT,
+            NodeId { internal: 0 },
+            42,
+            false,
+        );
+        let mut built = AdjacencyGraph::new_flat(
+            GraphBuilder::new(6).build(&points),
+            hash_start::EngineStarter::<LruSet>::new(params),
+            Vanilla,
+        );
+        built.use_csr_layout();
+
+        let graph_path = temp_path("built-graph");
+        let payload_path = temp_path("built-payload");
+        built.save_to_path(&graph_path, &payload_path).unwrap();
+        let reloaded = load(graph_path.clone(), payload_path.clone());
+        std::fs::remove_file(graph_path).unwrap();
+        std::fs::remove_file(payload_path).unwrap();
+
+        assert_same_graph(&built, &reloaded);
+    }
+
+    #[test]
+    fn test_round_trip_of_unaligned_dimension() {
+        use crate::{numerics::AlignedBlock, statistics::Stats};
+        use rand::prelude::*;
+
+        let dim = 100;
+        let mut rng = StdRng::seed_from_u64(5);
+        let points: Vec<Vec<AlignedBlock>> = (0..200)
+            .map(|_| {
+                let values = (0..dim).map(|_| rng.random_range(-1.0..1.0)).collect();
+                AlignedBlock::allocate_padded(values)
+            })
+            .collect();
+        let params =
+            hash_start::EngineStarterParams::new(4, 40, dim, NodeId { internal: 0 }, 42, false);
+        let built = AdjacencyGraph::new_flat(
+            GraphBuilder::new(8).build(&points),
+            hash_start::EngineStarter::<LruSet>::new(params),
+            Vanilla,
+        );
+        assert_eq!(built.dim(), dim);
+
+        let graph_path = temp_path("unaligned-graph");
+        let payload_path = temp_path("unaligned-payload");
+        built.save_to_path(&graph_path, &payload_path).unwrap();
+        let payload_bytes = std::fs::read(&payload_path).unwrap();
+        let reloaded = load(graph_path.clone(), payload_path.clone());
+        std::fs::remove_file(graph_path).unwrap();
+        std::fs::remove_file(payload_path).unwrap();
+
+        assert_eq!(payload_bytes[4..8], (dim as u32).to_le_bytes());
+        assert_eq!(payload_bytes.len(), 8 + 200 * dim * 4);
+        assert_eq!(reloaded.dim(), dim);
+        assert_same_graph(&built, &reloaded);
+
+        let query = &points[17];
+        let mut stats = Stats::new();
+        let found = reloaded.beam_search(query, 1, 16, &mut stats);
+        assert_eq!(found[0].index, NodeId { internal: 17 });
+        assert_eq!(found[0].distance.0, 0.0);
+    }
+}
@@ -0,0 +1,351 @@
+use crate::{
+    search::{
+        NodeId,
+        hash_start::{EngineStarter, LshFamily},
+    },
+    sets::catapults::CatapultEvictionPolicy,
+};
+
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use super::{invalid_data, read_u64, read_u128};
+
+/// Name of catapult files in error messages.
+const FILE_KIND: &str = "catapult file";
+
+/// Returns an `InvalidData` error if the stored and current values of a setting differ.
+fn check_matches(name: &str, stored: u64, current: u64) -> io::Result<()> {
+    if stored == current {
+        Ok(())
+    } else {
+        Err(invalid_data(
+            FILE_KIND,
+            format!("saved with {name}={stored}, but the current starter has {name}={current}"),
+        ))
+    }
+}
+
+/// Encodes a hash family as a u64: 0 for hyperplanes, the bits of `w` for p-stable LSH.
+fn encode_family(family: LshFamily) -> u64 {
+    match family {
+        LshFamily::Hyperplane => 0,
+        LshFamily::PStable { w } => u64::from(w.to_bits()),
+    }
+}
+
+/// Decodes a hash family written by [`encode_family`].
+fn decode_family(encoded: u64) -> LshFamily {
+    match encoded {
+        0 => LshFamily::Hyperplane,
+        bits => LshFamily::PStable {
+            w: f32::from_bits(bits as u32),
+        },
+    }
+}
+
+impl<T: CatapultEvictionPolicy> EngineStarter<T> {
+    /// Writes every catapult bucket to `path`, so a warmed-up cache survives a restart.
+    ///
+    /// # File Format
+    /// All values are little-endian u64, except for p-stable codes:
+    /// - Header: `num_hash`, `plane_dim`, `seed`, number of buckets, and the hash family
+    ///   (0 for [`LshFamily::Hyperplane`], the bits of `w` for [`LshFamily::PStable`])
+    /// - For each bucket in signature order: its length, followed by that many node ids
+    ///   in the order returned by [`CatapultEvictionPolicy::to_vec`]
+    /// - For [`LshFamily::PStable`] only: the number of catapults in the Z-order index,
+    ///   followed by each one's p-stable code (u128) and node id, oldest first
+    ///
+    /// # Arguments
+    /// * `path` - Destination file, created or truncated
+    ///
+    /// # Errors
+    /// Returns any I/O error raised while creating or writing the file
+    pub fn save_catapults(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        for header in [
+            self.num_hash() as u64,
+            self.plane_dim() as u64,
+            self.seed(),
+            self.num_buckets() as u64,
+            encode_family(self.lsh_family()),
+        ] {
+            out.write_all(&header.to_le_bytes())?;
+        }
+
+        for signature in 0..self.num_buckets() {
+            let bucket = self.bucket_catapults(signature);
+            out.write_all(&(bucket.len() as u64).to_le_bytes())?;
+            for id in bucket {
+                out.write_all(&(id.internal as u64).to_le_bytes())?;
+            }
+        }
+        if let Some(catapults) = self.pstable_catapults() {
+            out.write_all(&(catapults.len() as u64).to_le_bytes())?;
+            for (code, id) in catapults {
+                out.write_all(&code.to_le_bytes())?;
+                out.write_all(&(id.internal as u64).to_le_bytes())?;
+            }
+        }
+        out.flush()
+    }
+
+    /// Replaces all catapults with the ones stored at `path` by [`save_catapults`](Self::save_catapults).
+    ///
+    /// Catapults are only meaningful for the hash functions that produced their bucket
+    /// indices or p-stable codes, so the stored `num_hash`, `plane_dim`, `seed` and hash
+    /// family must match this starter. Catapults are reinserted oldest first through
+    /// [`new_catapult`](Self::new_catapult), or into the Z-order index under their stored
+    /// codes, so bucket capacities and the memory budget of this starter still apply.
+    ///
+    /// # Arguments
+    /// * `path` - File previously written by `save_catapults`
+    ///
+    /// # Errors
+    /// Returns an `InvalidData` error if the hasher configuration differs or the file is
+    /// malformed, or any I/O error raised while reading. On error, catapults already held
+    /// by this starter are left untouched.
+    pub fn load_catapults(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.load_catapults_below(path, usize::MAX)
+    }
+
+    /// Same as [`load_catapults`](Self::load_catapults), but also returns an `InvalidData`
+    /// error, leaving the catapults untouched, if the file references a node id of at
+    /// least `num_nodes`.
+    pub(crate) fn load_catapults_below(
+        &self,
+        path: impl AsRef<Path>,
+        num_nodes: usize,
+    ) -> io::Result<()> {
+        let mut input = BufReader::new(File::open(path)?);
+
+        check_matches("num_hash", read_u64(&mut input)?, self.num_hash() as u64)?;
+        check_matches("plane_dim", read_u64(&mut input)?, self.plane_dim() as u64)?;
+        check_matches("seed", read_u64(&mut input)?, self.seed())?;
+        check_matches(
+            "bucket count",
+            read_u64(&mut input)?,
+            self.num_buckets() as u64,
+        )?;
+        let family = decode_family(read_u64(&mut input)?);
+        if family != self.lsh_family() {
+            return Err(invalid_data(
+                FILE_KIND,
+                format!(
+                    "saved with {family:?}, but the current starter uses {:?}",
+                    self.lsh_family()
+                ),
+            ));
+        }
+
+        let mut buckets = Vec::with_capacity(self.num_buckets());
+        for _ in 0..self.num_buckets() {
+            let len = read_u64(&mut input)?;
+            let bucket = (0..len)
+                .map(|_| {
+                    read_u64(&mut input).map(|id| NodeId {
+                        internal: id as usize,
+                    })
+                })
+                .collect::<io::Result<Vec<_>>>()?;
+            buckets.push(bucket);
+        }
+        let mut pstable = Vec::new();
+        if let LshFamily::PStable { .. } = family {
+            for _ in 0..read_u64(&mut input)? {
+                let code = read_u128(&mut input)?;
+                let id = read_u64(&mut input)? as usize;
+                pstable.push((code, NodeId { internal: id }));
+            }
+        }
+        if input.read(&mut [0u8])? != 0 {
+            return Err(invalid_data(
+                FILE_KIND,
+                "trailing bytes after the last catapult bucket",
+            ));
+        }
+        let mut stored = buckets
+            .iter()
+            .flatten()
+            .chain(pstable.iter().map(|(_, id)| id));
+        if let Some(id) = stored.find(|id| id.internal >= num_nodes) {
+            return Err(invalid_data(
+                FILE_KIND,
+                format!(
+                    "references node {}, but the graph has {num_nodes} nodes",
+                    id.internal
+                ),
+            ));
+        }
+
+        self.clear_all_catapults();
+        for (signature, bucket) in buckets.into_iter().enumerate() {
+            for id in bucket {
+                self.new_catapult(signature, id);
+            }
+        }
+        for (code, id) in pstable {
+            self.restore_pstable_catapult(code, id);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fs::temp_path,
+        numerics::{AlignedBlock, SIMD_LANECOUNT},
+        search::hash_start::EngineStarterParams,
+        sets::catapults::LruSet,
+    };
+    use std::io::ErrorKind;
+
+    fn params(num_hash: usize, seed: u64) -> EngineStarterParams {
+        EngineStarterParams::new(
+            num_hash,
+            4,
+            SIMD_LANECOUNT,
+            NodeId { internal: 0 },
+            seed,
+            true,
+        )
+    }
+
+    fn test_queries() -> Vec<Vec<AlignedBlock>> {
+        (0..50)
+            .map(|i| {
+                let mut data = [0.0; SIMD_LANECOUNT];
+                for (lane, v) in data.iter_mut().enumerate() {
+                    *v = ((i * 7 + lane * 13) % 11) as f32 - 5.0;
+                }
+                vec![AlignedBlock::new(data)]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip_restores_starting_points() {
+        let warmed = EngineStarter::<LruSet>::new(params(6, 42));
+        let queries = test_queries();
+        for (i, q) in queries.iter().enumerate() {
+            let signature = warmed.select_starting_points(q).signature;
+            warmed.new_catapult(signature, NodeId { internal: i });
+        }
+
+        let path = temp_path("round-trip");
+        warmed.save_catapults(&path).unwrap();
+
+        let restored = EngineStarter::<LruSet>::new(params(6, 42));
+        restored.load_catapults(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(restored.catapult_count(), warmed.catapult_count());
+        for q in &queries {
+            assert_eq!(
+                restored.select_starting_points(q).catapults,
+                warmed.select_starting_points(q).catapults
+            );
+        }
+    }
+
+    #[test]
+    fn test_pstable_round_trip_restores_starting_points() {
+        // room for every catapult, even when several queries share a p-stable code
+        let pstable = |w| {
+            EngineStarter::<LruSet>::new(
+                EngineStarterParams {
+                    bucket_capacity: 50,
+                    ..params(6, 42)
+                }
+                .with_pstable_lsh(w),
+            )
+        };
+        let warmed = pstable(2.0);
+        let queries = test_queries();
+        for (i, q) in queries.iter().enumerate() {
+            let signature = warmed.signature(q);
+            warmed.learn_catapult(q, signature, NodeId { internal: i });
+        }
+        assert_eq!(warmed.catapult_count(), queries.len());
+
+        let path = temp_path("pstable-round-trip");
+        warmed.save_catapults(&path).unwrap();
+
+        let restored = pstable(2.0);
+        restored.load_catapults(&path).unwrap();
+        let other_w = pstable(3.0).load_catapults(&path).unwrap_err();
+        let hyperplane = EngineStarter::<LruSet>::new(params(6, 42))
+            .load_catapults(&path)
+            .unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(restored.catapult_count(), warmed.catapult_count());
+        for q in &queries {
+            assert_eq!(
+                restored.select_starting_points(q).catapults,
+                warmed.select_starting_points(q).catapults
+            );
+        }
+        assert_eq!(other_w.kind(), ErrorKind::InvalidData);
+        assert!(hyperplane.to_string().contains("PStable"));
+    }
+
+    #[test]
+    fn test_load_replaces_existing_catapults() {
+        let saved = EngineStarter::<LruSet>::new(params(2, 1));
+        saved.new_catapult(3, NodeId { internal: 9 });
+        let path = temp_path("replace");
+        saved.save_catapults(&path).unwrap();
+
+        let target = EngineStarter::<LruSet>::new(params(2, 1));
+        target.new_catapult(0, NodeId { internal: 5 });
+        target.load_catapults(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(target.bucket_catapults(0).is_empty());
+        assert_eq!(target.bucket_catapults(3), vec![NodeId { internal: 9 }]);
+        assert_eq!(target.catapult_count(), 1);
+    }
+
+    #[test]
+    fn test_load_rejects_mismatched_configuration() {
+        let saved = EngineStarter::<LruSet>::new(params(4, 42));
+        saved.new_catapult(1, NodeId { internal: 3 });
+        let path = temp_path("mismatch");
+        saved.save_catapults(&path).unwrap();
+
+        let other_hash = EngineStarter::<LruSet>::new(params(5, 42));
+        let err = other_hash.load_catapults(&path).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().contains("num_hash"));
+
+        let other_seed = EngineStarter::<LruSet>::new(params(4, 7));
+        other_seed.new_catapult(2, NodeId { internal: 8 });
+        let err = other_seed.load_catapults(&path).unwrap_err();
+        assert!(err.to_string().contains("seed"));
+        assert_eq!(other_seed.bucket_catapults(2), vec![NodeId { internal: 8 }]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_load_rejects_truncated_file() {
+        let saved = EngineStarter::<LruSet>::new(params(3, 42));
+        saved.new_catapult(7, NodeId { internal: 1 });
+        let path = temp_path("truncated");
+        saved.save_catapults(&path).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 4]).unwrap();
+
+        let target = EngineStarter::<LruSet>::new(params(3, 42));
+        let err = target.load_catapults(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,273 @@
+use crate::{
+    numerics::{AlignedBlock, Payload},
+    search::{
+        AdjacencyGraph, Node, NodeId, SearchStrategy,
+        hash_start::{EngineStarter, EngineStarterParams},
+    },
+    sets::{catapults::CatapultEvictionPolicy, fixed::FlatFixedSet},
+};
+
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use super::{adjacency_save::to_u32, invalid_data, read_f32s, read_u32, read_u64};
+
+/// First bytes of every combined graph file.
+pub const COMBINED_MAGIC: [u8; 8] = *b"CATAPULT";
+
+/// Version of the combined format written by
+/// [`save_combined`](AdjacencyGraph::save_combined), the only one the loader accepts.
+pub const COMBINED_VERSION: u32 = 1;
+
+/// Byte-order mark of the header, read back swapped from a file of the other endianness.
+const BYTE_ORDER_MARK: u32 = 0x0102_0304;
+
+/// Name of combined graph files in error messages.
+const FILE_KIND: &str = "combined graph file";
+
+impl<T: CatapultEvictionPolicy, P: Payload> AdjacencyGraph<T, P> {
+    /// Writes the graph and its payloads to a single file, read by
+    /// [`load_combined`](AdjacencyGraph::load_combined).
+    ///
+    /// Keeping the structure and the vectors together means they cannot drift apart, as
+    /// the two files of [`save_to_path`](AdjacencyGraph::save_to_path) can.
+    ///
+    /// # File Format
+    /// All values are little-endian. The file holds:
+    /// - Header: the magic bytes [`COMBINED_MAGIC`], `version` (u32, [`COMBINED_VERSION`]),
+    ///   a byte-order mark (u32, `0x01020304`), `num_nodes` (u64), `dim` (u32) and
+    ///   `entry_point` (u32)
+    /// - Graph section, per node: `neighbor_count` (u32) followed by that many neighbor
+    ///   indices (u32)
+    /// - Payload section, per node: `dim` f32 values
+    ///
+    /// Payloads are written as by [`save_to_path`](AdjacencyGraph::save_to_path), in the
+    /// logical dimension and reconstructed from the stored representation.
+    ///
+    /// # Arguments
+    /// * `path` - Destination of the combined file, created or truncated
+    ///
+    /// # Errors
+    /// Returns an `InvalidInput` error if a degree, index, or the dimension does not fit
+    /// in a u32, and any I/O error raised while writing
+    pub fn save_combined(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let dim = self.dim();
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(&COMBINED_MAGIC)?;
+        out.write_all(&COMBINED_VERSION.to_le_bytes())?;
+        out.write_all(&BYTE_ORDER_MARK.to_le_bytes())?;
+        out.write_all(&(self.len() as u64).to_le_bytes())?;
+        out.write_all(&to_u32(dim, "dimension")?.to_le_bytes())?;
+        out.write_all(&to_u32(self.starting_node().internal, "entry point")?.to_le_bytes())?;
+        for node in 0..self.len() {
+            let neighbors = self.neighbors_of(node);
+            out.write_all(&to_u32(neighbors.len(), "degree")?.to_le_bytes())?;
+            for neighbor in neighbors {
+                out.write_all(&to_u32(neighbor.internal, "node index")?.to_le_bytes())?;
+            }
+        }
+        for internal in 0..self.len() {
+            let blocks = self.payload(NodeId { internal }).to_blocks();
+            for value in blocks.iter().flat_map(|b| b.data).take(dim) {
+                out.write_all(&value.to_le_bytes())?;
+            }
+        }
+        out.flush()
+    }
+}
+
+impl<T: CatapultEvictionPolicy> AdjacencyGraph<T> {
+    /// Loads a graph written by [`save_combined`](AdjacencyGraph::save_combined).
+    ///
+    /// The header is validated before anything else is read, so an unrelated file or one
+    /// of another format version is rejected without allocating for its contents.
+    ///
+    /// # Arguments
+    /// * `path` - Path to the combined graph file
+    /// * `num_hash` - Number of LSH hash bits (creates 2^num_hash buckets)
+    /// * `bucket_cap` - Maximum number of catapults per bucket
+    /// * `seed` - Random seed for LSH hyperplane generation
+    /// * `running_mode` - Search strategy of the returned graph
+    ///
+    /// # Returns
+    /// A new `AdjacencyGraph` with the entry point from the file used as the starting node
+    ///
+    /// # Errors
+    /// Returns an `InvalidData` error if the magic bytes, version, or byte-order mark do
+    /// not match, if the entry point or a neighbor index is out of bounds, or if the file
+    /// does not end after its last payload, and any I/O error raised while reading,
+    /// including `UnexpectedEof` for truncated files
+    pub fn load_combined(
+        path: impl AsRef<Path>,
+        num_hash: usize,
+        bucket_cap: usize,
+        seed: u64,
+        running_mode: SearchStrategy,
+    ) -> io::Result<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+
+        let mut magic = [0u8; COMBINED_MAGIC.len()];
+        file.read_exact(&mut magic)?;
+        if magic != COMBINED_MAGIC {
+            return Err(invalid_data(FILE_KIND, "missing magic bytes"));
+        }
+        let version = read_u32(&mut file)?;
+        if version != COMBINED_VERSION {
+            return Err(invalid_data(
+                FILE_KIND,
+                format!(
+                    "format version {version}, but only version {COMBINED_VERSION} is supported"
+                ),
+            ));
+        }
+        let byte_order = read_u32(&mut file)?;
+        if byte_order != BYTE_ORDER_MARK {
+            return Err(invalid_data(
+                FILE_KIND,
+                format!("byte-order mark {byte_order:#010x}, the file is not little-endian"),
+            ));
+        }
+        let num_nodes = read_u64(&mut file)? as usize;
+        let dim = read_u32(&mut file)? as usize;
+        let entry_point = read_u32(&mut file)? as usize;
+        if entry_point >= num_nodes.max(1) {
+            return Err(invalid_data(
+                FILE_KIND,
+                format!("entry point {entry_point}, but the graph holds {num_nodes} nodes"),
+            ));
+        }
+
+        let mut links = Vec::new();
+        for node in 0..num_nodes {
+            let count = read_u32(&mut file)?;
+            let mut neighbors = Vec::new();
+            for _ in 0..count {
+                let neighbor = read_u32(&mut file)? as usize;
+                if neighbor >= num_nodes {
+                    return Err(invalid_data(
+                        FILE_KIND,
+                        format!(
+                            "node {node} links to {neighbor}, but the graph holds {num_nodes} nodes"
+                        ),
+                    ));
+                }
+                neighbors.push(neighbor);
+            }
+            links.push(FlatFixedSet::compact(neighbors, num_nodes));
+        }
+
+        let mut adjacency = Vec::with_capacity(links.len());
+        for neighbors in links {
+            adjacency.push(Node {
+                neighbors,
+                payload: AlignedBlock::allocate_padded(read_f32s(&mut file, dim)?)
+                    .into_boxed_slice(),
+            });
+        }
+        if file.read(&mut [0u8])? != 0 {
+            return Err(invalid_data(
+                FILE_KIND,
+                "trailing bytes after the last payload",
+            ));
+        }
+
+        let engine_params = EngineStarterParams::new(
+            num_hash,
+            bucket_cap,
+            dim,
+            NodeId {
+                internal: entry_point,
+            },
+            seed,
+            running_mode.uses_catapults(),
+        );
+        Ok(AdjacencyGraph::new_flat(
+            adjacency,
+            EngineStarter::<T>::new(engine_params),
+            running_mode,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{COMBINED_MAGIC, COMBINED_VERSION};
+    use crate::{
+        fs::temp_path,
+        numerics::Payload,
+        search::{AdjacencyGraph, NodeId, SearchStrategy::Vanilla},
+        sets::catapults::LruSet,
+    };
+    use std::{io::ErrorKind, path::PathBuf};
+
+    fn load_fixture() -> AdjacencyGraph<LruSet> {
+        AdjacencyGraph::<LruSet>::load_flat_from_path(
+            "test/index/ann".into(),
+            "test/index/ann_vectors.bin".into(),
+            4,
+            40,
+            42,
+            Vanilla,
+        )
+        .unwrap()
+    }
+
+    fn load_combined(path: &PathBuf) -> std::io::Result<AdjacencyGraph<LruSet>> {
+        AdjacencyGraph::<LruSet>::load_combined(path, 4, 40, 42, Vanilla)
+    }
+
+    #[test]
+    fn combined_file_matches_the_two_file_fixture() {
+        let original = load_fixture();
+        let path = temp_path("combined-ann");
+        original.save_combined(&path).unwrap();
+        let reloaded = load_combined(&path);
+        std::fs::remove_file(&path).unwrap();
+        let reloaded = reloaded.unwrap();
+
+        assert_eq!(reloaded.len(), original.len());
+        assert_eq!(reloaded.dim(), original.dim());
+        assert_eq!(reloaded.starting_node(), original.starting_node());
+        for node in 0..original.len() {
+            assert_eq!(reloaded.neighbors_of(node), original.neighbors_of(node));
+            assert_eq!(
+                reloaded.payload(NodeId { internal: node }).to_blocks(),
+                original.payload(NodeId { internal: node }).to_blocks()
+            );
+        }
+    }
+
+    #[test]
+    fn combined_loader_rejects_bad_headers() {
+        let path = temp_path("combined-header");
+        load_fixture().save_combined(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes[..8], COMBINED_MAGIC);
+
+        // the two-file graph format starts with its size instead of the magic bytes
+        std::fs::write(&path, std::fs::read("test/index/ann").unwrap()).unwrap();
+        let foreign = load_combined(&path);
+
+        let mut newer = bytes.clone();
+        newer[8..12].copy_from_slice(&(COMBINED_VERSION + 1).to_le_bytes());
+        std::fs::write(&path, &newer).unwrap();
+        let newer = load_combined(&path);
+
+        let mut swapped = bytes.clone();
+        swapped[12..16].reverse();
+        std::fs::write(&path, &swapped).unwrap();
+        let swapped = load_combined(&path);
+
+        std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        let truncated = load_combined(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        for rejected in [foreign, newer, swapped] {
+            assert_eq!(rejected.err().unwrap().kind(), ErrorKind::InvalidData);
+        }
+        assert_eq!(truncated.err().unwrap().kind(), ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,287 @@
+use crate::{
+    numerics::{AlignedBlock, Payload},
+    search::{
+        AdjacencyGraph, Node, NodeId, SearchStrategy, UpperLevels,
+        hash_start::{EngineStarter, EngineStarterParams},
+    },
+    sets::{catapults::CatapultEvictionPolicy, fixed::HierarchicalFixedSet},
+};
+
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, ErrorKind, Read, Write},
+    path::Path,
+};
+
+use super::{adjacency_save::to_u32, invalid_data, read_f32s, read_u32, read_u64};
+
+/// Name of hierarchical graphs in error messages.
+const FILE_KIND: &str = "hierarchical graph";
+
+/// Returns an `InvalidData` error if `reader` holds more bytes.
+fn expect_end(reader: &mut impl Read, file: &str) -> io::Result<()> {
+    if reader.read(&mut [0u8])? != 0 {
+        return Err(invalid_data(
+            FILE_KIND,
+            format!("trailing bytes at the end of the {file} file"),
+        ));
+    }
+    Ok(())
+}
+
+impl<T: CatapultEvictionPolicy> AdjacencyGraph<T> {
+    /// Loads a hierarchical (HNSW) graph, whose searches descend its upper levels first.
+    ///
+    /// Level 0 becomes the adjacency of the returned graph and the levels above are
+    /// attached with [`with_upper_levels`](AdjacencyGraph::with_upper_levels). The top
+    /// level must hold exactly one node, the entry point of every descent, as in canonical
+    /// HNSW graphs; the highest level of the graph is the highest level any node lives on.
+    ///
+    /// # File Format
+    /// All values are little-endian. The graph file holds:
+    /// - Header: `num_nodes` (u64)
+    /// - Per node: `num_levels` (u32, at least 1), then per level from 0 upwards a
+    ///   `neighbor_count` (u32) followed by that many neighbor indices (u32)
+    ///
+    /// The payload file is the one of
+    /// [`load_flat_from_path`](AdjacencyGraph::load_flat_from_path): `npoints` (u32) and
+    /// `payload_dim` (u32), then `payload_dim` f32 values per node.
+    ///
+    /// # Arguments
+    /// * `graph_path` - Path to the hierarchical graph file
+    /// * `payload_path` - Path to the payload vectors file
+    /// * `num_hash` - Number of LSH hash bits (creates 2^num_hash buckets)
+    /// * `bucket_cap` - Maximum number of catapults per bucket
+    /// * `seed` - Random seed for LSH hyperplane generation
+    /// * `running_mode` - Search strategy of the returned graph
+    ///
+    /// # Returns
+    /// The graph, starting its searches from a descent of the upper levels
+    ///
+    /// # Errors
+    /// Returns an `InvalidData` error if the files disagree on the number of nodes, a node
+    /// has no level, a neighbor index is out of bounds, the top level does not hold exactly
+    /// one node, or a file does not end after its last node, and any I/O error raised while
+    /// reading, including `UnexpectedEof` for truncated files
+    pub fn load_hnsw_from_path(
+        graph_path: impl AsRef<Path>,
+        payload_path: impl AsRef<Path>,
+        num_hash: usize,
+        bucket_cap: usize,
+        seed: u64,
+        running_mode: SearchStrategy,
+    ) -> io::Result<Self> {
+        let mut graph = BufReader::new(File::open(graph_path)?);
+        let num_nodes = read_u64(&mut graph)? as usize;
+        let mut links = Vec::with_capacity(num_nodes);
+        for node in 0..num_nodes {
+            let num_levels = read_u32(&mut graph)? as usize;
+            if num_levels == 0 {
+                return Err(invalid_data(
+                    FILE_KIND,
+                    format!("node {node} lives on no level"),
+                ));
+            }
+            let mut levels = Vec::with_capacity(num_levels);
+            for _ in 0..num_levels {
+                let count = read_u32(&mut graph)?;
+                let mut neighbors = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let neighbor = read_u32(&mut graph)? as usize;
+                    if neighbor >= num_nodes {
+                        return Err(invalid_data(
+                            FILE_KIND,
+                            format!(
+                                "node {node} links to {neighbor}, but the graph holds \
+                                 {num_nodes} nodes"
+                            ),
+                        ));
+                    }
+                    neighbors.push(neighbor);
+                }
+                levels.push(neighbors);
+            }
+            links.push(HierarchicalFixedSet::compact(levels, num_nodes));
+        }
+        expect_end(&mut graph, "graph")?;
+
+        let max_level = links.iter().map(HierarchicalFixedSet::max_level).max();
+        let top_nodes = links
+            .iter()
+            .filter(|node| Some(node.max_level()) == max_level)
+            .count();
+        if top_nodes != 1 {
+            return Err(invalid_data(
+                FILE_KIND,
+                format!("{top_nodes} nodes live on the top level, instead of a single entry point"),
+            ));
+        }
+
+        let mut payloads = BufReader::new(File::open(payload_path)?);
+        let npoints = read_u32(&mut payloads)? as usize;
+        let dim = read_u32(&mut payloads)? as usize;
+        if npoints != num_nodes {
+            return Err(invalid_data(
+                FILE_KIND,
+                format!("the payload file holds {npoints} vectors for {num_nodes} nodes"),
+            ));
+        }
+        let (level0, upper_levels) = UpperLevels::split(links);
+        let mut adjacency = Vec::with_capacity(num_nodes);
+        for neighbors in level0 {
+            adjacency.push(Node {
+                neighbors,
+                payload: AlignedBlock::allocate_padded(read_f32s(&mut payloads, dim)?)
+                    .into_boxed_slice(),
+            });
+        }
+        expect_end(&mut payloads, "payload")?;
+
+        let entry_point = upper_levels
+            .as_ref()
+            .map_or(NodeId { internal: 0 }, UpperLevels::entry_point);
+        let engine_params = EngineStarterParams::new(
+            num_hash,
+            bucket_cap,
+            dim,
+            entry_point,
+            seed,
+            running_mode.uses_catapults(),
+        );
+        let graph = AdjacencyGraph::new_flat(
+            adjacency,
+            EngineStarter::<T>::new(engine_params),
+            running_mode,
+        );
+        Ok(match upper_levels {
+            Some(levels) => graph.with_upper_levels(levels),
+            None => graph,
+        })
+    }
+}
+
+impl<T: CatapultEvictionPolicy, P: Payload> AdjacencyGraph<T, P> {
+    /// Writes a hierarchical graph in the format read by
+    /// [`load_hnsw_from_path`](AdjacencyGraph::load_hnsw_from_path).
+    ///
+    /// Level 0 is written from the graph's adjacency and the levels above from its
+    /// [`upper_levels`](AdjacencyGraph::upper_levels). Payloads are written as by
+    /// [`save_to_path`](AdjacencyGraph::save_to_path).
+    ///
+    /// # Arguments
+    /// * `graph_path` - Destination of the hierarchical graph file, created or truncated
+    /// * `payload_path` - Destination of the payload vectors file, created or truncated
+    ///
+    /// # Errors
+    /// Returns an `InvalidInput` error if the graph has no upper levels, or if a count or
+    /// index does not fit in a u32, and any I/O error raised while writing
+    pub fn save_hnsw_to_path(
+        &self,
+        graph_path: impl AsRef<Path>,
+        payload_path: impl AsRef<Path>,
+    ) -> io::Result<()> {
+        let Some(upper_levels) = self.upper_levels() else {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "the graph has no upper levels, save it with save_to_path instead",
+            ));
+        };
+
+        let mut graph = BufWriter::new(File::create(graph_path)?);
+        graph.write_all(&(self.len() as u64).to_le_bytes())?;
+        for node in 0..self.len() {
+            let max_level = upper_levels.level_of(NodeId { internal: node });
+            graph.write_all(&to_u32(max_level + 1, "level count")?.to_le_bytes())?;
+            for level in 0..=max_level {
+                let neighbors = match level {
+                    0 => self.neighbors_of(node),
+                    _ => upper_levels.to_level(NodeId { internal: node }, level),
+                };
+                graph.write_all(&to_u32(neighbors.len(), "degree")?.to_le_bytes())?;
+                for neighbor in neighbors {
+                    graph.write_all(&to_u32(neighbor.internal, "node index")?.to_le_bytes())?;
+                }
+            }
+        }
+        graph.flush()?;
+        self.write_payload_file(payload_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fs::temp_path,
+        numerics::AlignedBlock,
+        search::{AdjacencyGraph, NodeId, SearchStrategy::Vanilla},
+        sets::catapults::LruSet,
+        statistics::Stats,
+    };
+    use std::io::ErrorKind;
+
+    const GRAPH: &str = "test/hnsw/ring.graph";
+    const PAYLOADS: &str = "test/hnsw/ring.data";
+
+    fn load(graph_path: &str) -> std::io::Result<AdjacencyGraph<LruSet>> {
+        AdjacencyGraph::<LruSet>::load_hnsw_from_path(graph_path, PAYLOADS, 4, 8, 42, Vanilla)
+    }
+
+    #[test]
+    fn hnsw_fixture_survives_a_round_trip() {
+        let graph = load(GRAPH).unwrap();
+        let levels = graph.upper_levels().unwrap();
+        assert_eq!(levels.max_level(), 2);
+        assert_eq!(levels.entry_point(), NodeId { internal: 0 });
+        assert_eq!(levels.len(), 2);
+        assert_eq!(
+            levels.to_level(NodeId { internal: 0 }, 1).to_vec(),
+            vec![NodeId { internal: 6 }]
+        );
+        assert_eq!(graph.neighbors_of(0).len(), 4);
+
+        // the descent jumps to point 6 on level 1, next to the query
+        let query = AlignedBlock::allocate_padded(vec![-10.0, -1.0, 0.0, 0.0]);
+        let mut stats = Stats::new();
+        let results = graph.beam_search(&query, 2, 2, &mut stats);
+        let ids: Vec<usize> = results.iter().map(|c| c.index.internal).collect();
+        assert_eq!(ids, vec![6, 7]);
+        assert!(
+            stats.get_nodes_visited() <= 4,
+            "{}",
+            stats.get_nodes_visited()
+        );
+
+        let graph_path = temp_path("saved-ring.graph");
+        let payload_path = temp_path("saved-ring.data");
+        graph.save_hnsw_to_path(&graph_path, &payload_path).unwrap();
+        let saved = (
+            std::fs::read(&graph_path).unwrap(),
+            std::fs::read(&payload_path).unwrap(),
+        );
+        std::fs::remove_file(graph_path).unwrap();
+        std::fs::remove_file(payload_path).unwrap();
+        assert_eq!(saved.0, std::fs::read(GRAPH).unwrap());
+        assert_eq!(saved.1, std::fs::read(PAYLOADS).unwrap());
+    }
+
+    #[test]
+    fn hnsw_top_level_must_hold_one_node() {
+        let mut bytes = std::fs::read(GRAPH).unwrap();
+        let path = temp_path("two-tops.graph");
+
+        // node 6 gains a third, empty level, beside the entry point
+        let node6 = 8 + 4 * (1 + 1 + 4 + 1 + 1 + 1) + 5 * 4 * (1 + 1 + 4);
+        assert_eq!(bytes[node6..node6 + 4], 2u32.to_le_bytes());
+        bytes[node6..node6 + 4].copy_from_slice(&3u32.to_le_bytes());
+        bytes.splice(node6 + 4 * 8..node6 + 4 * 8, 0u32.to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+        let two_tops = load(path.to_str().unwrap());
+
+        std::fs::write(&path, &bytes[..bytes.len() - 2]).unwrap();
+        let truncated = load(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(two_tops.err().unwrap().kind(), ErrorKind::InvalidData);
+        assert_eq!(truncated.err().unwrap().kind(), ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,401 @@
+use crate::{
+    numerics::AlignedBlock,
+    search::{
+        AdjacencyGraph, Node, NodeId, SearchStrategy,
+        hash_start::{EngineStarter, EngineStarterParams},
+    },
+    sets::{catapults::CatapultEvictionPolicy, fixed::HierarchicalFixedSet},
+};
+
+use std::{
+    fs::File,
+    io::{self, BufReader, Read},
+    path::Path,
+};
+
+use super::{invalid_data, read_u32, read_u64};
+
+/// Size of the hnswlib header in bytes.
+const HEADER_BYTES: u64 = 96;
+
+/// Name of hnswlib indexes in error messages.
+const FILE_KIND: &str = "hnswlib index";
+
+/// Reads one hnswlib link list: a count word followed by `capacity` u32 slots.
+///
+/// Only the low 16 bits of the count word hold the number of links; hnswlib keeps its
+/// deletion mark in the third byte.
+fn read_links(
+    reader: &mut impl Read,
+    capacity: usize,
+    num_elements: usize,
+) -> io::Result<Vec<usize>> {
+    let count = (read_u32(reader)? & 0xFFFF) as usize;
+    if count > capacity {
+        return Err(invalid_data(
+            FILE_KIND,
+            format!("link list holds {count} links, more than its capacity of {capacity}"),
+        ));
+    }
+    let mut links = Vec::with_capacity(count);
+    for slot in 0..capacity {
+        let link = read_u32(reader)? as usize;
+        if slot < count {
+            if link >= num_elements {
+                return Err(invalid_data(
+                    FILE_KIND,
+                    format!("link to element {link}, but the index holds {num_elements}"),
+                ));
+            }
+            links.push(link);
+        }
+    }
+    Ok(links)
+}
+
+/// The contents of an hnswlib index file.
+struct HnswlibIndex {
+    /// Logical dimension of the vectors
+    dim: usize,
+    /// Per-element vectors, zero-padded to whole blocks
+    payloads: Vec<Box<[AlignedBlock]>>,
+    /// Per-element external labels
+    labels: Vec<u64>,
+    /// Per-element link lists of every level the element lives on
+    links: Vec<HierarchicalFixedSet>,
+    /// The element hnswlib starts its descent from
+    entry_point: usize,
+}
+
+/// Reads an index written by hnswlib's `HierarchicalNSW::saveIndex`, see
+/// [`AdjacencyGraph::load_from_hnswlib`] for the format.
+fn read_hnswlib(path: impl AsRef<Path>) -> io::Result<HnswlibIndex> {
+    let file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+    let mut input = BufReader::new(file);
+
+    let offset_level0 = read_u64(&mut input)?;
+    let _max_elements = read_u64(&mut input)?;
+    let num_elements = read_u64(&mut input)? as usize;
+    let size_data_per_element = read_u64(&mut input)? as usize;
+    let label_offset = read_u64(&mut input)? as usize;
+    let offset_data = read_u64(&mut input)? as usize;
+    let max_level = read_u32(&mut input)? as i32;
+    let entry_point = read_u32(&mut input)? as usize;
+    let max_m = read_u64(&mut input)? as usize;
+    let max_m0 = read_u64(&mut input)? as usize;
+    let _m = read_u64(&mut input)?;
+    let _mult = read_u64(&mut input)?;
+    let _ef_construction = read_u64(&mut input)?;
+
+    // header fields are untrusted, so none of this arithmetic may overflow
+    if offset_level0 != 0
+        || max_m0.checked_mul(4).and_then(|bytes| bytes.checked_add(4)) != Some(offset_data)
+        || label_offset < offset_data
+        || !(label_offset - offset_data).is_multiple_of(4)
+        || label_offset.checked_add(8) != Some(size_data_per_element)
+    {
+        return Err(invalid_data(
+            FILE_KIND,
+            format!(
+                "element layout (links at {offset_level0}, data at {offset_data}, label at \
+                 {label_offset}, {size_data_per_element} bytes) does not hold {max_m0} u32 \
+                 level-0 links followed by f32 data and a u64 label"
+            ),
+        ));
+    }
+    if max_level < 0 || (num_elements > 0 && entry_point >= num_elements) {
+        return Err(invalid_data(
+            FILE_KIND,
+            format!(
+                "entry point {entry_point} at level {max_level} is not one of the \
+                 {num_elements} elements"
+            ),
+        ));
+    }
+    // every element also holds at least the u32 size of its upper-level links, checked
+    // against the file length before allocating for the elements
+    let element_bytes = size_data_per_element
+        .checked_add(4)
+        .and_then(|bytes| bytes.checked_mul(num_elements));
+    if element_bytes.is_none_or(|bytes| bytes as u64 > file_len.saturating_sub(HEADER_BYTES)) {
+        return Err(invalid_data(
+            FILE_KIND,
+            format!(
+                "{num_elements} elements of {size_data_per_element} bytes do not fit in a file \
+                 of {file_len} bytes"
+            ),
+        ));
+    }
+    let Some(level_size) = max_m.checked_mul(4).and_then(|bytes| bytes.checked_add(4)) else {
+        return Err(invalid_data(
+            FILE_KIND,
+            format!("upper levels of {max_m} links are too large"),
+        ));
+    };
+    let dim = (label_offset - offset_data) / 4;
+
+    let mut level0 = Vec::with_capacity(num_elements);
+    let mut payloads = Vec::with_capacity(num_elements);
+    let mut labels = Vec::with_capacity(num_elements);
+    for _ in 0..num_elements {
+        level0.push(read_links(&mut input, max_m0, num_elements)?);
+        let mut values = Vec::with_capacity(dim);
+        for _ in 0..dim {
+            values.push(f32::from_bits(read_u32(&mut input)?));
+        }
+        labels.push(read_u64(&mut input)?);
+        payloads.push(AlignedBlock::allocate_padded(values).into_boxed_slice());
+    }
+
+    let mut links = Vec::with_capacity(num_elements);
+    for (element, neighbors) in level0.into_iter().enumerate() {
+        let size = read_u32(&mut input)? as usize;
+        if !size.is_multiple_of(level_size) || size / level_size > max_level as usize {
+            return Err(invalid_data(
+                FILE_KIND,
+                format!(
+                    "element {element} has {size} bytes of upper-level links, which is not \
+                     a whole number of levels up to {max_level}"
+                ),
+            ));
+        }
+        let mut levels = vec![neighbors];
+        for _ in 0..size / level_size {
+            levels.push(read_links(&mut input, max_m, num_elements)?);
+        }
+        links.push(HierarchicalFixedSet::compact(levels, num_elements));
+    }
+    if input.read(&mut [0u8])? != 0 {
+        return Err(invalid_data(
+            FILE_KIND,
+            "trailing bytes after the last link list",
+        ));
+    }
+
+    Ok(HnswlibIndex {
+        dim,
+        payloads,
+        labels,
+        links,
+        entry_point,
+    })
+}
+
+impl<T: CatapultEvictionPolicy> AdjacencyGraph<T> {
+    /// Loads an index written by hnswlib's `HierarchicalNSW::saveIndex`.
+    ///
+    /// Supports the format of hnswlib 0.7 and 0.8 (the `hnswlib` Python package's
+    /// `Index.save_index`) for the float spaces `l2`, `ip` and `cosine`. The level-0 links
+    /// become the flat adjacency of the returned graph, and hnswlib's entry point becomes
+    /// its starting node, so the hnswlib graph is searched with beam search and catapults
+    /// instead of a greedy descent through the upper levels. Upper-level link lists are
+    /// validated, then dropped.
+    ///
+    /// `running_mode` chooses where searches enter the graph. With
+    /// [`SearchStrategy::Vanilla`], every search starts from hnswlib's designated top-level
+    /// entry point, as classic HNSW does; without the upper levels to descend first, a
+    /// query far from that point pays for the whole walk across level 0. With
+    /// [`SearchStrategy::Catapult`], searches also start from the catapults of the
+    /// query's LSH bucket, so repeated or similar queries skip most of that walk, at the
+    /// cost of hashing each query and storing catapults.
+    ///
+    /// Nodes keep hnswlib's internal ids, and the external label of each node is returned
+    /// alongside the graph. The graph always searches by L2 distance, which ranks neighbors
+    /// as hnswlib does for `l2` and for `cosine` (whose vectors hnswlib stores normalized),
+    /// but not for `ip` over vectors of different norms. Elements marked as deleted stay in
+    /// the graph and can be returned by searches.
+    ///
+    /// # File Format
+    /// All values are little-endian:
+    /// - Header: `offsetLevel0` (u64), `max_elements` (u64), `cur_element_count` (u64),
+    ///   `size_data_per_element` (u64), `label_offset` (u64), `offsetData` (u64),
+    ///   `maxlevel` (i32), `enterpoint_node` (u32), `maxM` (u64), `maxM0` (u64), `M` (u64),
+    ///   `mult` (f64), `ef_construction` (u64)
+    /// - Per element, `size_data_per_element` bytes: a level-0 link count (u32) and `maxM0`
+    ///   link slots (u32), the vector (f32 values up to `label_offset`) and the label (u64)
+    /// - Per element: the byte size of its upper-level link lists (u32), followed by one
+    ///   link count (u32) and `maxM` link slots (u32) per level above 0
+    ///
+    /// # Arguments
+    /// * `path` - Path to the hnswlib index file
+    /// * `num_hash` - Number of LSH hash bits (creates 2^num_hash buckets)
+    /// * `bucket_cap` - Maximum number of catapults per bucket
+    /// * `seed` - Random seed for LSH hyperplane generation
+    /// * `running_mode` - Search strategy of the returned graph
+    ///
+    /// # Returns
+    /// The graph, and the hnswlib label of each node indexed by node id
+    ///
+    /// # Errors
+    /// Returns an `InvalidData` error if the header is inconsistent, a link points outside
+    /// the index, or the file does not end after the last link list, and any I/O error
+    /// raised while reading, including `UnexpectedEof` for truncated files
+    pub fn load_from_hnswlib(
+        path: impl AsRef<Path>,
+        num_hash: usize,
+        bucket_cap: usize,
+        seed: u64,
+        running_mode: SearchStrategy,
+    ) -> io::Result<(Self, Vec<u64>)> {
+        let HnswlibIndex {
+            dim,
+            payloads,
+            labels,
+            links,
+            entry_point,
+        } = read_hnswlib(path)?;
+        let adjacency = payloads
+            .into_iter()
+            .zip(links)
+            .map(|(payload, links)| Node {
+                neighbors: links.into_levels().swap_remove(0),
+                payload,
+            })
+            .collect();
+
+        let entry_point_id = NodeId {
+            internal: entry_point,
+        };
+        let engine_params = EngineStarterParams::new(
+            num_hash,
+            bucket_cap,
+            dim,
+            entry_point_id,
+            seed,
+            running_mode.uses_catapults(),
+        );
+        let graph = AdjacencyGraph::new_flat(
+            adjacency,
+            EngineStarter::<T>::new(engine_params),
+            running_mode,
+        );
+        Ok((graph, labels))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fs::temp_path,
+        numerics::AlignedBlock,
+        search::{
+            AdjacencyGraph, NodeId,
+            SearchStrategy::{Catapult, Vanilla},
+        },
+        sets::catapults::LruSet,
+        statistics::Stats,
+    };
+    use std::io::ErrorKind;
+
+    const FIXTURE: &str = "test/hnswlib/ring.bin";
+
+    #[test]
+    fn loading_hnswlib_fixture_finds_ring_neighbors() {
+        let (graph, labels) =
+            AdjacencyGraph::<LruSet>::load_from_hnswlib(FIXTURE, 4, 8, 42, Catapult).unwrap();
+        assert_eq!(graph.len(), 12);
+        assert_eq!(graph.dim(), 4);
+        assert_eq!(labels, (0..12).map(|i| 1000 + 7 * i).collect::<Vec<u64>>());
+
+        // a query next to point 3 of the ring, slightly towards point 4
+        let angle = 2.0 * std::f32::consts::PI * 3.2 / 12.0;
+        let query =
+            AlignedBlock::allocate_padded(vec![10.0 * angle.cos(), 10.0 * angle.sin(), 0.0, 0.0]);
+        let results = graph.beam_search(&query, 3, 4, &mut Stats::new());
+        let ids: Vec<usize> = results.iter().map(|c| c.index.internal).collect();
+        assert_eq!(ids, vec![3, 4, 2]);
+        assert_eq!(labels[ids[0]], 1021);
+    }
+
+    #[test]
+    fn upper_levels_keep_their_own_links() {
+        let index = super::read_hnswlib(FIXTURE).unwrap();
+        let ids = |indices: &[usize]| {
+            indices
+                .iter()
+                .map(|&internal| NodeId { internal })
+                .collect::<Vec<_>>()
+        };
+
+        // the entry point reaches level 2, alone, and meets point 6 on level 1
+        let entry = &index.links[index.entry_point];
+        assert_eq!(entry.max_level(), 2);
+        assert_eq!(entry.to_level(0).to_vec(), ids(&[10, 11, 1, 2]));
+        assert_eq!(entry.to_level(1).to_vec(), ids(&[6]));
+        assert!(entry.to_level(2).is_empty());
+        assert_ne!(entry.to_level(0), entry.to_level(2));
+
+        assert_eq!(index.links[6].max_level(), 1);
+        assert_eq!(index.links[6].to_level(1).to_vec(), ids(&[0]));
+        assert_eq!(index.links[3].max_level(), 0);
+        assert!(index.links[3].to_level(1).is_empty());
+    }
+
+    #[test]
+    fn catapult_entries_shortcut_the_fixed_entry_point() {
+        let load = |strategy| {
+            AdjacencyGraph::<LruSet>::load_from_hnswlib(FIXTURE, 4, 8, 42, strategy)
+                .unwrap()
+                .0
+        };
+        let (fixed, lsh) = (load(Vanilla), load(Catapult));
+        // the point opposite to the entry point on the ring
+        let query = AlignedBlock::allocate_padded(vec![-10.0, 0.0, 0.0, 0.0]);
+
+        let mut visited = Vec::new();
+        for graph in [&fixed, &lsh] {
+            graph.beam_search(&query, 1, 2, &mut Stats::new());
+            let mut stats = Stats::new();
+            let results = graph.beam_search(&query, 1, 2, &mut stats);
+            assert_eq!(results[0].index.internal, 6);
+            visited.push(stats.get_nodes_visited());
+        }
+        // the second search of the catapult graph starts next to its answer
+        assert!(visited[1] < visited[0], "{visited:?}");
+    }
+
+    #[test]
+    fn loading_truncated_hnswlib_index_fails() {
+        let bytes = std::fs::read(FIXTURE).unwrap();
+        let path = temp_path("hnswlib_truncated.bin");
+
+        std::fs::write(&path, &bytes[..bytes.len() - 4]).unwrap();
+        let truncated = AdjacencyGraph::<LruSet>::load_from_hnswlib(&path, 4, 8, 42, Catapult);
+        assert_eq!(truncated.err().unwrap().kind(), ErrorKind::UnexpectedEof);
+
+        // the first level-0 link of element 0 now points outside the index
+        let mut corrupted = bytes.clone();
+        corrupted[100..104].copy_from_slice(&12u32.to_le_bytes());
+        std::fs::write(&path, &corrupted).unwrap();
+        let corrupted = AdjacencyGraph::<LruSet>::load_from_hnswlib(&path, 4, 8, 42, Catapult);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(corrupted.err().unwrap().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn loading_hnswlib_index_with_corrupted_header_fails() {
+        let bytes = std::fs::read(FIXTURE).unwrap();
+        let path = temp_path("hnswlib_header.bin");
+        let mut rejected = Vec::new();
+        // cur_element_count, maxM0 and maxM, each set to values that overflow or cannot
+        // fit in the file
+        for (at, value) in [
+            (16, u64::MAX),
+            (16, 1 << 40),
+            (64, u64::MAX / 2),
+            (56, u64::MAX),
+        ] {
+            let mut corrupted = bytes.clone();
+            corrupted[at..at + 8].copy_from_slice(&value.to_le_bytes());
+            std::fs::write(&path, &corrupted).unwrap();
+            rejected.push(AdjacencyGraph::<LruSet>::load_from_hnswlib(
+                &path, 4, 8, 42, Catapult,
+            ));
+        }
+        std::fs::remove_file(&path).unwrap();
+        for result in rejected {
+            assert_eq!(result.err().unwrap().kind(), ErrorKind::InvalidData);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::{
+    fmt::{self, Display},
+    io,
+};
+
+/// One of the two files a flat graph is loaded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadedFile {
+    /// The graph structure file
+    Graph,
+
+    /// The payload vectors file
+    Payload,
+}
+
+impl Display for LoadedFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadedFile::Graph => write!(f, "graph"),
+            LoadedFile::Payload => write!(f, "payload"),
+        }
+    }
+}
+
+/// Why a flat graph could not be loaded.
+///
+/// Returned by [`AdjacencyGraph::load_flat_from_path`] and its variants instead of
+/// panicking, so that a process loading graphs from an external pipeline survives a
+/// malformed file.
+///
+/// [`AdjacencyGraph::load_flat_from_path`]: crate::search::AdjacencyGraph::load_flat_from_path
+#[derive(Debug)]
+pub enum LoadError {
+    /// Opening, mapping or reading a file failed.
+    Io(io::Error),
+
+    /// A file ended before the data its headers declare.
+    Truncated {
+        /// The file that is too short
+        file: LoadedFile,
+    },
+
+    /// The graph file header is not that of a graph file, see
+    /// [`GraphHeader`](crate::fs::GraphHeader).
+    BadHeader(String),
+
+    /// The size of the payload file does not match its declared number of vectors and
+    /// dimension.
+    DimensionMismatch {
+        /// Number of vectors declared by the payload header
+        npoints: usize,
+        /// Dimension declared by the payload header
+        dim: usize,
+        /// Actual size of the payload file in bytes
+        file_bytes: u64,
+    },
+
+    /// The graph and payload files hold different numbers of nodes.
+    NodeCountMismatch {
+        /// Number of nodes in the graph file
+        graph_nodes: usize,
+        /// Number of vectors in the payload file
+        payload_nodes: usize,
+    },
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(error) => write!(f, "failed to read graph files: {error}"),
+            LoadError::Truncated { file } => write!(f, "the {file} file is truncated"),
+            LoadError::BadHeader(reason) => write!(f, "bad graph header: {reason}"),
+            LoadError::DimensionMismatch {
+                npoints,
+                dim,
+                file_bytes,
+            } => write!(
+                f,
+                "the payload file holds {file_bytes} bytes, which is not {npoints} vectors of \
+                 dimension {dim}"
+            ),
+            LoadError::NodeCountMismatch {
+                graph_nodes,
+                payload_nodes,
+            } => write!(
+                f,
+                "the graph file holds {graph_nodes} nodes but the payload file {payload_nodes} \
+                 vectors"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(error: io::Error) -> Self {
+        LoadError::Io(error)
+    }
+}
@@ -0,0 +1,395 @@
+use crate::numerics::{AlignedBlock, Payload, SIMD_LANECOUNT, VectorLike};
+
+use memmap2::Mmap;
+use std::{
+    fmt::Debug,
+    fs::File,
+    io::{self, BufReader, BufWriter, ErrorKind, Read, Write},
+    path::Path,
+    sync::Arc,
+};
+
+/// Size of the file header, which keeps the first vector on a block boundary.
+const HEADER_BYTES: usize = 64;
+
+/// Size of one aligned block on disk.
+const BLOCK_BYTES: usize = size_of::<AlignedBlock>();
+
+/// A read-only, memory-mapped file of payload vectors laid out as aligned blocks.
+///
+/// Vectors are read in place from the mapping instead of being copied to the heap, so
+/// the operating system only keeps the pages that searches actually touch in memory.
+/// This lets a graph be searched over a payload file much larger than RAM.
+///
+/// # File Format
+/// Every value is little-endian:
+/// - Header, 64 bytes: number of vectors (u64), logical dimension in f32 elements (u64),
+///   then 48 zero bytes
+/// - For each vector: its f32 entries, zero-padded to the next multiple of
+///   `SIMD_LANECOUNT` entries
+///
+/// The header size and the padding keep every vector at an offset that is a multiple of
+/// 64 bytes, and mappings start on a page boundary, so each vector can be used directly
+/// as a slice of 64-byte aligned [`AlignedBlock`]s. Zero padding leaves L2 distances and
+/// dot products unchanged. Files in this layout are produced by [`write`](Self::write)
+/// and [`convert_from_flat`](Self::convert_from_flat).
+pub struct MappedPayloads {
+    map: Mmap,
+    len: usize,
+    dim: usize,
+}
+
+impl MappedPayloads {
+    /// Maps a payload file and validates its layout.
+    ///
+    /// The file must not be modified or truncated while it is mapped; doing so is
+    /// undefined behavior, as with any memory-mapped file.
+    ///
+    /// # Arguments
+    /// * `path` - File in the layout described in [`MappedPayloads`]
+    ///
+    /// # Returns
+    /// The mapped vectors
+    ///
+    /// # Errors
+    /// Returns an `InvalidData` error if the header is malformed or the file size does not
+    /// match the number of vectors and their dimension, an `Unsupported` error on
+    /// big-endian targets or if the mapping is not aligned for `AlignedBlock`, or any I/O
+    /// error raised while opening or mapping the file
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        if cfg!(target_endian = "big") {
+            return Err(io::Error::new(
+                ErrorKind::Unsupported,
+                "mapped payloads are stored little-endian and cannot be read in place on a big-endian target",
+            ));
+        }
+
+        let file = File::open(path)?;
+        // SAFETY: the mapping is read-only, and callers are told not to modify the file
+        // while it is mapped.
+        let map = unsafe { Mmap::map(&file)? };
+
+        let invalid = |message: String| io::Error::new(ErrorKind::InvalidData, message);
+        if map.len() < HEADER_BYTES {
+            return Err(invalid(format!(
+                "payload file has {} bytes, shorter than its {HEADER_BYTES}-byte header",
+                map.len()
+            )));
+        }
+        let header_u64 = |i: usize| u64::from_le_bytes(map[8 * i..8 * (i + 1)].try_into().unwrap());
+        let (len, dim) = (header_u64(0) as usize, header_u64(1) as usize);
+        if dim == 0 || map[16..HEADER_BYTES].iter().any(|&b| b != 0) {
+            return Err(invalid(format!(
+                "payload file header is malformed (dimension {dim})"
+            )));
+        }
+
+        let expected = dim
+            .div_ceil(SIMD_LANECOUNT)
+            .checked_mul(BLOCK_BYTES)
+            .and_then(|bytes| bytes.checked_mul(len))
+            .and_then(|bytes| bytes.checked_add(HEADER_BYTES));
+        if expected != Some(map.len()) {
+            return Err(invalid(format!(
+                "payload file has {} bytes, but {len} vectors of dimension {dim} need {}",
+                map.len(),
+                expected.map_or("more".to_string(), |bytes| bytes.to_string())
+            )));
+        }
+
+        if !map.as_ptr().cast::<AlignedBlock>().is_aligned() {
+            return Err(io::Error::new(
+                ErrorKind::Unsupported,
+                "payload mapping is not aligned for AlignedBlock",
+            ));
+        }
+
+        Ok(MappedPayloads { map, len, dim })
+    }
+
+    /// Returns the number of vectors in the file.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the file holds no vector.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the logical dimension of the vectors in f32 elements, excluding padding.
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Returns vector `index` as a slice of the mapping.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds
+    pub fn vector(&self, index: usize) -> &[AlignedBlock] {
+        assert!(index < self.len, "vector index out of bounds");
+        let blocks = self.dim.div_ceil(SIMD_LANECOUNT);
+        let start = HEADER_BYTES + index * blocks * BLOCK_BYTES;
+        let bytes = &self.map[start..start + blocks * BLOCK_BYTES];
+        // SAFETY: `open` checked that the mapping is aligned for `AlignedBlock` and holds
+        // every vector in full. Vectors start at multiples of the block size, and an
+        // `AlignedBlock` is plain f32 data, for which any bit pattern is valid.
+        unsafe { std::slice::from_raw_parts(bytes.as_ptr().cast::<AlignedBlock>(), blocks) }
+    }
+
+    /// Returns a payload handle for every vector of `store`, in file order.
+    pub fn vectors(store: &Arc<Self>) -> impl ExactSizeIterator<Item = MappedVector> + use<> {
+        let store = Arc::clone(store);
+        (0..store.len).map(move |index| MappedVector {
+            store: Arc::clone(&store),
+            index,
+        })
+    }
+
+    /// Writes vectors to `path` in the layout expected by [`open`](Self::open).
+    ///
+    /// Only the first `dim` entries of each vector are taken from `vectors`; padding is
+    /// always written as zeros.
+    ///
+    /// # Arguments
+    /// * `path` - Destination file, created or truncated
+    /// * `dim` - Logical dimension of the vectors in f32 elements
+    /// * `vectors` - Vectors as aligned blocks, each spanning `dim` entries
+    ///
+    /// # Errors
+    /// Returns an `InvalidInput` error if a vector does not have the number of blocks
+    /// needed for `dim`, or any I/O error raised while writing
+    pub fn write<'a>(
+        path: impl AsRef<Path>,
+        dim: usize,
+        vectors: impl ExactSizeIterator<Item = &'a [AlignedBlock]>,
+    ) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        write_header(&mut out, vectors.len(), dim)?;
+
+        for (i, vector) in vectors.enumerate() {
+            if vector.len() != dim.div_ceil(SIMD_LANECOUNT) {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "vector {i} has {} blocks, expected dimension {dim}",
+                        vector.len()
+                    ),
+                ));
+            }
+            for value in vector.iter().flat_map(|b| b.data).take(dim) {
+                out.write_all(&value.to_le_bytes())?;
+            }
+            write_padding(&mut out, dim)?;
+        }
+        out.flush()
+    }
+
+    /// Rewrites a regular payload file, as read by
+    /// [`load_flat_from_path`](crate::search::AdjacencyGraph::load_flat_from_path), in
+    /// the block-aligned layout. Vectors are streamed one at a time, so the conversion
+    /// works for files that do not fit in memory.
+    ///
+    /// # Arguments
+    /// * `flat_path` - Payload file with a `npoints` (u32), `dim` (u32) header followed by
+    ///   the f32 entries of every vector
+    /// * `path` - Destination file, created or truncated
+    ///
+    /// # Errors
+    /// Returns an `UnexpectedEof` error if the source ends inside a vector, an `InvalidData`
+    /// error if it has trailing bytes, or any I/O error raised while reading or writing
+    pub fn convert_from_flat(
+        flat_path: impl AsRef<Path>,
+        path: impl AsRef<Path>,
+    ) -> io::Result<()> {
+        let mut input = BufReader::new(File::open(flat_path)?);
+        let mut header = [0u8; 8];
+        input.read_exact(&mut header)?;
+        let npoints = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+        let dim = u32::from_le_bytes(header[4..].try_into().unwrap()) as usize;
+
+        let mut out = BufWriter::new(File::create(path)?);
+        write_header(&mut out, npoints, dim)?;
+        let mut vector = vec![0u8; 4 * dim];
+        for _ in 0..npoints {
+            input.read_exact(&mut vector)?;
+            out.write_all(&vector)?;
+            write_padding(&mut out, dim)?;
+        }
+
+        if input.read(&mut [0u8])? != 0 {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "trailing bytes after the last payload vector",
+            ));
+        }
+        out.flush()
+    }
+}
+
+impl Debug for MappedPayloads {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MappedPayloads")
+            .field("len", &self.len)
+            .field("dim", &self.dim)
+            .finish()
+    }
+}
+
+/// Writes the 64-byte file header.
+fn write_header(out: &mut impl Write, len: usize, dim: usize) -> io::Result<()> {
+    out.write_all(&(len as u64).to_le_bytes())?;
+    out.write_all(&(dim as u64).to_le_bytes())?;
+    out.write_all(&[0u8; HEADER_BYTES - 16])
+}
+
+/// Writes the zero entries completing the last block of a vector of dimension `dim`.
+fn write_padding(out: &mut impl Write, dim: usize) -> io::Result<()> {
+    let padding = dim.next_multiple_of(SIMD_LANECOUNT) - dim;
+    out.write_all(&vec![0u8; 4 * padding])
+}
+
+/// A node payload living in a [`MappedPayloads`] file.
+///
+/// Holds a shared handle to the mapping, so the file stays mapped as long as any node
+/// refers to it.
+#[derive(Debug, Clone)]
+pub struct MappedVector {
+    store: Arc<MappedPayloads>,
+    index: usize,
+}
+
+impl MappedVector {
+    /// Returns the vector as a slice of the mapping.
+    pub fn as_blocks(&self) -> &[AlignedBlock] {
+        self.store.vector(self.index)
+    }
+}
+
+impl Payload for MappedVector {
+    #[inline]
+    fn l2_squared_to(&self, query: &[AlignedBlock]) -> f32 {
+        self.as_blocks().l2_squared(query)
+    }
+
+    fn to_blocks(&self) -> Vec<AlignedBlock> {
+        self.as_blocks().to_vec()
+    }
+
+    fn num_blocks(&self) -> usize {
+        self.store.dim.div_ceil(SIMD_LANECOUNT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fs::temp_path,
+        search::{AdjacencyGraph, NodeId, SearchStrategy::Vanilla},
+        sets::catapults::LruSet,
+        statistics::Stats,
+    };
+
+    fn ramp_vectors(n: usize, dim: usize) -> Vec<Vec<AlignedBlock>> {
+        (0..n)
+            .map(|i| {
+                AlignedBlock::allocate_padded((0..dim).map(|j| (i * dim + j) as f32).collect())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_write_and_open() {
+        let vectors = ramp_vectors(5, 100);
+        let path = temp_path("mapped-100.bin");
+        MappedPayloads::write(&path, 100, vectors.iter().map(Vec::as_slice)).unwrap();
+        assert_eq!(
+            std::fs::metadata(&path).unwrap().len(),
+            (HEADER_BYTES + 5 * 7 * BLOCK_BYTES) as u64
+        );
+
+        let store = Arc::new(MappedPayloads::open(&path).unwrap());
+        assert_eq!((store.len(), store.dim()), (5, 100));
+        for (i, vector) in vectors.iter().enumerate() {
+            assert_eq!(store.vector(i), vector.as_slice());
+        }
+
+        let query = &vectors[3];
+        let mapped: Vec<MappedVector> = MappedPayloads::vectors(&store).collect();
+        assert_eq!(mapped[3].l2_squared_to(query), 0.0);
+        assert_eq!(mapped[4].l2_squared_to(query), vectors[4].l2_squared(query));
+
+        drop((store, mapped));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_open_rejects_bad_files() {
+        let vectors = ramp_vectors(2, SIMD_LANECOUNT);
+        let path = temp_path("mapped-bad.bin");
+        MappedPayloads::write(&path, SIMD_LANECOUNT, vectors.iter().map(Vec::as_slice)).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+
+        std::fs::write(&path, &bytes[..bytes.len() - 4]).unwrap();
+        let err = MappedPayloads::open(&path).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let mut bad_header = bytes.clone();
+        bad_header[20] = 1;
+        std::fs::write(&path, bad_header).unwrap();
+        let err = MappedPayloads::open(&path).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        std::fs::write(&path, &bytes[..10]).unwrap();
+        let err = MappedPayloads::open(&path).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_mapped_graph_matches_loaded_graph() {
+        let graph_path = "test/index/ann";
+        let payload_path = "test/index/ann_vectors.bin";
+        let mapped_path = temp_path("mapped-ann.bin");
+        MappedPayloads::convert_from_flat(payload_path, &mapped_path).unwrap();
+
+        let loaded = AdjacencyGraph::<LruSet>::load_flat_from_path(
+            graph_path.into(),
+            payload_path.into(),
+            4,
+            40,
+            42,
+            Vanilla,
+        )
+        .unwrap();
+        let mapped = AdjacencyGraph::<LruSet, MappedVector>::load_flat_mmap_from_path(
+            graph_path.into(),
+            mapped_path.clone(),
+            4,
+            40,
+            42,
+            Vanilla,
+        )
+        .unwrap();
+
+        assert_eq!(mapped.len(), loaded.len());
+        assert_eq!(mapped.dim(), loaded.dim());
+        for node in 0..loaded.len() {
+            assert_eq!(
+                mapped.payload(NodeId { internal: node }).to_blocks(),
+                loaded.payload(NodeId { internal: node }).to_blocks()
+            );
+        }
+        let query = vec![AlignedBlock::new([0.25; SIMD_LANECOUNT]); loaded.dim() / SIMD_LANECOUNT];
+        let mut stats = Stats::new();
+        assert_eq!(
+            mapped.beam_search(&query, 3, 4, &mut stats),
+            loaded.beam_search(&query, 3, 4, &mut stats)
+        );
+
+        drop(mapped);
+        std::fs::remove_file(&mapped_path).unwrap();
+    }
+}
@@ -1,9 +1,84 @@
 //! File system I/O operations for loading graphs and queries.
 //!
-//! This module provides functionality for loading proximity graphs and query vectors
-//! from disk, supporting NumPy format for queries and custom binary formats for graphs.
+//! This module provides functionality for loading and saving proximity graphs and loading
+//! vectors from disk, supporting NumPy and `.fvecs`/`.bvecs` formats for vectors, `.ivecs`
+//! for ground truth and custom binary formats for flat and hierarchical graphs, as well as
+//! single files holding a graph with its payloads, importing hnswlib indexes, memory-mapping
+//! payloads that do not fit in RAM, persisting learned catapults across restarts, storing
+//! query transforms and recording query workloads for deterministic replay.
 
 mod adjacency_load;
+mod adjacency_save;
+mod catapult_store;
+mod combined_store;
+mod hnsw_store;
+mod hnswlib_load;
+mod load_error;
+mod mapped_payloads;
 mod query_load;
+mod transform_store;
+mod vecs_load;
+mod workload;
 
+pub use adjacency_load::{GraphHeader, PayloadFormat};
+pub use combined_store::{COMBINED_MAGIC, COMBINED_VERSION};
+pub use load_error::*;
+pub use mapped_payloads::{MappedPayloads, MappedVector};
 pub use query_load::*;
+pub use vecs_load::*;
+pub use workload::*;
+
+use std::{
+    fmt::Display,
+    io::{self, ErrorKind, Read},
+};
+
+/// Returns an `InvalidData` error describing a malformed file.
+///
+/// # Arguments
+/// * `context` - What the file holds, e.g. `"hnswlib index"`
+/// * `reason` - What is wrong with it
+fn invalid_data(context: &str, reason: impl Display) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidData,
+        format!("invalid {context}: {reason}"),
+    )
+}
+
+/// Reads a little-endian u32 from `reader`.
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Reads a little-endian u64 from `reader`.
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Reads a little-endian u128 from `reader`.
+fn read_u128(reader: &mut impl Read) -> io::Result<u128> {
+    let mut bytes = [0u8; 16];
+    reader.read_exact(&mut bytes)?;
+    Ok(u128::from_le_bytes(bytes))
+}
+
+/// Reads `len` little-endian f32 values from `reader`.
+fn read_f32s(reader: &mut impl Read, len: usize) -> io::Result<Vec<f32>> {
+    let mut values = Vec::with_capacity(len);
+    let mut bytes = [0u8; 4];
+    for _ in 0..len {
+        reader.read_exact(&mut bytes)?;
+        values.push(f32::from_le_bytes(bytes));
+    }
+    Ok(values)
+}
+
+/// Returns a path in the system temporary directory, unique to this process and `name`.
+#[cfg(test)]
+pub(crate) fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("catapult-{}-{name}", std::process::id()))
+}
@@ -1,6 +1,40 @@
+use npyz::{DType, NpyFile, Order, TypeChar};
 use tqdm::tqdm;
 
-use crate::numerics::{AlignedBlock, SIMD_LANECOUNT};
+use crate::numerics::AlignedBlock;
+
+/// Reads the first `rows` rows of `dim` values of type `T`, converted to f32 and padded to
+/// blocks, from a file in either memory order.
+fn read_rows<T: npyz::Deserialize>(
+    npy: NpyFile<&[u8]>,
+    rows: usize,
+    dim: usize,
+    to_f32: impl Fn(T) -> f32,
+) -> Vec<Vec<AlignedBlock>> {
+    let num_rows = npy.shape()[0] as usize;
+    let fortran = matches!(npy.order(), Order::Fortran);
+    let mut iter = npy.data::<T>().unwrap();
+    // column-major files store each column contiguously, so a row is only complete once
+    // every column has been read
+    let columns: Vec<f32> = if fortran {
+        iter.by_ref().map(|x| to_f32(x.unwrap())).collect()
+    } else {
+        Vec::new()
+    };
+
+    let mut result = Vec::with_capacity(rows);
+    for r in tqdm(0..rows).desc(Some("Loading .npy queries")) {
+        let row: Vec<f32> = if fortran {
+            (0..dim).map(|c| columns[c * num_rows + r]).collect()
+        } else {
+            (0..dim)
+                .map(|_| to_f32(iter.next().unwrap().unwrap()))
+                .collect()
+        };
+        result.push(AlignedBlock::allocate_padded(row));
+    }
+    result
+}
 
 /// A trait for loading query vectors from NumPy format files.
 ///
@@ -19,15 +53,20 @@ pub trait Queries {
     /// * Panics if the file cannot be read
     /// * Panics if the .npy format is invalid
     /// * Panics if the shape is not 2-dimensional
-    /// * Panics if the vector dimension is not a multiple of `SIMD_LANECOUNT`
     fn load_from_npy(path: &str, limit: Option<usize>) -> Self;
 }
 
 impl Queries for Vec<Vec<AlignedBlock>> {
-    /// Loads a 2D array of f32 vectors from a NumPy file and converts to aligned blocks.
+    /// Loads a 2D array of vectors from a NumPy file and converts to aligned blocks.
     ///
-    /// Expects a 2D NumPy array where each row is a query vector. Vectors are converted
+    /// Expects a 2D NumPy array where each row is a query vector, of dtype float32,
+    /// float64, uint8 or int8; values of the last three are converted to f32, float64
+    /// ones losing their extra precision. Arrays saved in Fortran (column-major) order are
+    /// transposed back to rows, at the cost of reading the whole file first even when
+    /// `limit` keeps only a few rows. Vectors are converted
     /// to sequences of `AlignedBlock` instances for SIMD-accelerated distance computation.
+    /// When the dimension is not a multiple of `SIMD_LANECOUNT`, the last block of every
+    /// query is padded with zeros, matching the payloads of a graph of the same dimension.
     ///
     /// # Arguments
     /// * `path` - Path to the .npy file
@@ -38,7 +77,7 @@ impl Queries for Vec<Vec<AlignedBlock>> {
     /// # Panics
     /// * Panics if the file cannot be read
     /// * Panics if the .npy data is not 2-dimensional
-    /// * Panics if the vector dimension is not a multiple of `SIMD_LANECOUNT`
+    /// * Panics if the dtype is not one of the above, naming it
     fn load_from_npy(path: &str, limit: Option<usize>) -> Self {
         let bytes = std::fs::read(path).unwrap();
         let npy = npyz::NpyFile::new(&bytes[..]).unwrap();
@@ -50,24 +89,21 @@ impl Queries for Vec<Vec<AlignedBlock>> {
             d1 = limit;
         }
 
-        assert!(d2.is_multiple_of(SIMD_LANECOUNT));
-
-        let mut iter = npy.data::<f32>().unwrap();
-        let mut result = Vec::with_capacity(d1);
-        for _ in tqdm(0..d1).desc(Some("Loading .npy queries")) {
-            let d2_capacity = d2 / SIMD_LANECOUNT;
-            let mut row = Vec::with_capacity(d2_capacity);
-            for _ in 0..d2_capacity {
-                let mut buffer = [0.0; SIMD_LANECOUNT];
-                for entry in buffer.iter_mut() {
-                    *entry = iter.next().unwrap().unwrap();
-                }
-                row.push(AlignedBlock::new(buffer));
-            }
-            result.push(row);
+        let dtype = npy.dtype();
+        let plain = match &dtype {
+            DType::Plain(ty) => Some((ty.type_char(), ty.size_field())),
+            _ => None,
+        };
+        match plain {
+            Some((TypeChar::Float, 4)) => read_rows(npy, d1, d2, |x: f32| x),
+            Some((TypeChar::Float, 8)) => read_rows(npy, d1, d2, |x: f64| x as f32),
+            Some((TypeChar::Uint, 1)) => read_rows(npy, d1, d2, |x: u8| x as f32),
+            Some((TypeChar::Int, 1)) => read_rows(npy, d1, d2, |x: i8| x as f32),
+            _ => panic!(
+                "unsupported .npy dtype {}, expected float32, float64, uint8 or int8",
+                dtype.descr()
+            ),
         }
-
-        result
     }
 }
 
@@ -84,4 +120,44 @@ mod tests {
     fn test_load_4vecs_limited() {
         let _ = Vec::<Vec<AlignedBlock>>::load_from_npy("test/index/vectors.npy", Some(2));
     }
+
+    #[test]
+    fn test_other_dtypes_convert_to_f32() {
+        let expected = |sign: f32| -> Vec<Vec<AlignedBlock>> {
+            (0..3)
+                .map(|r| {
+                    AlignedBlock::allocate_padded(
+                        (0..5).map(|c| sign * (10 * r + c) as f32).collect(),
+                    )
+                })
+                .collect()
+        };
+        for (path, sign) in [
+            ("test/npy/queries_f64.npy", 1.0),
+            ("test/npy/queries_u8.npy", 1.0),
+            ("test/npy/queries_i8.npy", -1.0),
+        ] {
+            let queries = Vec::<Vec<AlignedBlock>>::load_from_npy(path, None);
+            assert_eq!(queries, expected(sign), "{path}");
+        }
+        let limited = Vec::<Vec<AlignedBlock>>::load_from_npy("test/npy/queries_f64.npy", Some(1));
+        assert_eq!(limited.len(), 1);
+    }
+
+    #[test]
+    fn test_fortran_order_keeps_logical_rows() {
+        let queries = Vec::<Vec<AlignedBlock>>::load_from_npy("test/npy/queries_fortran.npy", None);
+        let row_major = Vec::<Vec<AlignedBlock>>::load_from_npy("test/npy/queries_f64.npy", None);
+        assert_eq!(queries, row_major);
+
+        let limited =
+            Vec::<Vec<AlignedBlock>>::load_from_npy("test/npy/queries_fortran.npy", Some(2));
+        assert_eq!(limited, row_major[..2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "unsupported .npy dtype '<c8'")]
+    fn test_complex_dtype_is_rejected() {
+        let _ = Vec::<Vec<AlignedBlock>>::load_from_npy("test/npy/queries_c64.npy", None);
+    }
 }
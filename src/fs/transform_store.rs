@@ -0,0 +1,125 @@
+use crate::numerics::{AffineTransform, AlignedBlock};
+
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, ErrorKind, Read, Write},
+    path::Path,
+};
+
+use super::{read_f32s, read_u64};
+
+/// Writes the logical `dim` values of a zero-padded vector to `out`.
+fn write_f32s(out: &mut impl Write, blocks: &[AlignedBlock], dim: usize) -> io::Result<()> {
+    for value in blocks.iter().flat_map(|b| b.data).take(dim) {
+        out.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+impl AffineTransform {
+    /// Writes the transform to `path`, to be stored next to the graph it was applied to.
+    ///
+    /// [`AdjacencyGraph::save_to_path`](crate::search::AdjacencyGraph::save_to_path) saves
+    /// payloads as they are stored, that is already transformed, so reloading such a graph
+    /// takes this file and
+    /// [`assume_affine_transform`](crate::search::AdjacencyGraph::assume_affine_transform).
+    ///
+    /// # File Format
+    /// All values are little-endian:
+    /// - Header: `input_dim` (u64), `output_dim` (u64), and whether a projection follows
+    ///   (u64, 0 or 1)
+    /// - The mean vector: `input_dim` f32 values
+    /// - With a projection, `output_dim` rows of `input_dim` f32 values
+    ///
+    /// # Arguments
+    /// * `path` - Destination file, created or truncated
+    ///
+    /// # Errors
+    /// Returns any I/O error raised while creating or writing the file
+    pub fn save_to_path(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        let input_dim = self.input_dim();
+        for header in [
+            input_dim as u64,
+            self.output_dim() as u64,
+            self.projection().is_some() as u64,
+        ] {
+            out.write_all(&header.to_le_bytes())?;
+        }
+        write_f32s(&mut out, self.mean(), input_dim)?;
+        for row in self.projection().unwrap_or_default() {
+            write_f32s(&mut out, row, input_dim)?;
+        }
+        out.flush()
+    }
+
+    /// Reads a transform written by [`save_to_path`](Self::save_to_path).
+    ///
+    /// # Arguments
+    /// * `path` - File previously written by `save_to_path`
+    ///
+    /// # Returns
+    /// The stored transform
+    ///
+    /// # Errors
+    /// Returns an `InvalidData` error if the header is inconsistent or bytes follow the
+    /// last row, and any I/O error raised while reading, including `UnexpectedEof` for
+    /// truncated files
+    pub fn load_from_path(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut input = BufReader::new(File::open(path)?);
+        let input_dim = read_u64(&mut input)? as usize;
+        let output_dim = read_u64(&mut input)? as usize;
+        let projected = read_u64(&mut input)?;
+        if projected > 1 || (projected == 0 && output_dim != input_dim) || output_dim == 0 {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "invalid transform header: {input_dim} to {output_dim} dimensions, \
+                     projection flag {projected}"
+                ),
+            ));
+        }
+
+        let mut transform = AffineTransform::new(read_f32s(&mut input, input_dim)?);
+        if projected == 1 {
+            let rows = (0..output_dim)
+                .map(|_| read_f32s(&mut input, input_dim))
+                .collect::<io::Result<Vec<_>>>()?;
+            transform = transform.with_projection(rows);
+        }
+        if input.read(&mut [0u8])? != 0 {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "trailing bytes after the last projection row",
+            ));
+        }
+        Ok(transform)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::temp_path;
+
+    #[test]
+    fn transforms_survive_a_round_trip() {
+        let path = temp_path("transform.bin");
+        let centering = AffineTransform::new((0..20).map(|i| i as f32 * 0.5).collect());
+        let projected =
+            centering
+                .clone()
+                .with_projection(vec![vec![1.0; 20], vec![-0.25; 20], vec![3.0; 20]]);
+
+        for transform in [centering, projected] {
+            transform.save_to_path(&path).unwrap();
+            assert_eq!(AffineTransform::load_from_path(&path).unwrap(), transform);
+        }
+
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        let truncated = AffineTransform::load_from_path(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(truncated.unwrap_err().kind(), ErrorKind::UnexpectedEof);
+    }
+}
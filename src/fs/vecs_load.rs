@@ -0,0 +1,262 @@
+use crate::numerics::AlignedBlock;
+
+use std::{
+    fs::File,
+    io::{self, BufReader, ErrorKind, Read},
+    path::Path,
+};
+
+/// Reads every record of a `.fvecs`-style file, decoding each entry of `N` bytes with
+/// `decode`.
+///
+/// Each record is a little-endian i32 dimension followed by that many entries. All records
+/// must report the same dimension. The end of the file may only fall between records.
+fn load_vecs<const N: usize, E>(
+    path: &Path,
+    limit: Option<usize>,
+    decode: impl Fn([u8; N]) -> E,
+) -> io::Result<Vec<Vec<E>>> {
+    let mut input = BufReader::new(File::open(path)?);
+    let mut result = Vec::new();
+    let mut dim = None;
+
+    while limit.is_none_or(|limit| result.len() < limit) {
+        let mut header = [0u8; 4];
+        if input.read(&mut header[..1])? == 0 {
+            break;
+        }
+        input.read_exact(&mut header[1..])?;
+        let record_dim = i32::from_le_bytes(header);
+
+        let expected = *dim.get_or_insert(record_dim);
+        if record_dim != expected || record_dim <= 0 {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "vector {} of {} has dimension {record_dim}, expected {expected}",
+                    result.len(),
+                    path.display()
+                ),
+            ));
+        }
+
+        let mut row = Vec::with_capacity(record_dim as usize);
+        for _ in 0..record_dim {
+            let mut entry = [0u8; N];
+            input.read_exact(&mut entry)?;
+            row.push(decode(entry));
+        }
+        result.push(row);
+    }
+
+    Ok(result)
+}
+
+/// Pads every row to whole aligned blocks.
+fn into_blocks(rows: Vec<Vec<f32>>) -> Vec<Vec<AlignedBlock>> {
+    rows.into_iter()
+        .map(AlignedBlock::allocate_padded)
+        .collect()
+}
+
+/// Loads vectors from a `.fvecs` file, as shipped with SIFT1M, GIST1M and similar benchmarks.
+///
+/// Each vector is stored as a little-endian i32 dimension followed by that many
+/// little-endian f32 values. When the dimension is not a multiple of `SIMD_LANECOUNT`, the
+/// last block of every vector is padded with zeros, which leaves L2 distances unchanged.
+///
+/// # Arguments
+/// * `path` - Path to the .fvecs file
+/// * `limit` - Maximum number of vectors to load, or `None` for all of them
+///
+/// # Returns
+/// One vector of aligned blocks per stored vector, in file order
+///
+/// # Errors
+/// Returns an `InvalidData` error if a vector's dimension is not positive or differs from
+/// the first vector's, `UnexpectedEof` if the file ends inside a vector, or any I/O error
+/// raised while reading
+pub fn load_from_fvecs(
+    path: impl AsRef<Path>,
+    limit: Option<usize>,
+) -> io::Result<Vec<Vec<AlignedBlock>>> {
+    load_vecs(path.as_ref(), limit, f32::from_le_bytes).map(into_blocks)
+}
+
+/// Loads vectors from a `.bvecs` file, as shipped with SIFT1B and similar benchmarks.
+///
+/// Each vector is stored as a little-endian i32 dimension followed by that many unsigned
+/// bytes, which are converted to f32. Padding follows [`load_from_fvecs`].
+///
+/// # Arguments
+/// * `path` - Path to the .bvecs file
+/// * `limit` - Maximum number of vectors to load, or `None` for all of them
+///
+/// # Returns
+/// One vector of aligned blocks per stored vector, in file order
+///
+/// # Errors
+/// Same as [`load_from_fvecs`]
+pub fn load_from_bvecs(
+    path: impl AsRef<Path>,
+    limit: Option<usize>,
+) -> io::Result<Vec<Vec<AlignedBlock>>> {
+    load_vecs(path.as_ref(), limit, |[byte]| byte as f32).map(into_blocks)
+}
+
+/// Loads neighbor ids from a `.ivecs` file, the format of the ground truth shipped with
+/// SIFT1M, GIST1M and similar benchmarks.
+///
+/// Each row is stored as a little-endian i32 count followed by that many little-endian
+/// i32 ids, usually the true nearest neighbors of one query, closest first. The rows can
+/// be passed as ground truth to [`recall_at_k`](crate::statistics::recall_at_k).
+///
+/// # Arguments
+/// * `path` - Path to the .ivecs file
+/// * `limit` - Maximum number of rows to load, or `None` for all of them
+///
+/// # Returns
+/// One row of ids per stored row, in file order
+///
+/// # Errors
+/// Returns an `InvalidData` error if an id is negative, otherwise same as
+/// [`load_from_fvecs`]
+pub fn load_from_ivecs(
+    path: impl AsRef<Path>,
+    limit: Option<usize>,
+) -> io::Result<Vec<Vec<usize>>> {
+    let path = path.as_ref();
+    let rows = load_vecs(path, limit, i32::from_le_bytes)?;
+    rows.into_iter()
+        .enumerate()
+        .map(|(i, row)| {
+            row.into_iter()
+                .map(|id| {
+                    usize::try_from(id).map_err(|_| {
+                        io::Error::new(
+                            ErrorKind::InvalidData,
+                            format!("row {i} of {} holds negative id {id}", path.display()),
+                        )
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fs::temp_path, numerics::SIMD_LANECOUNT};
+
+    fn write_fvecs(path: &Path, vectors: &[Vec<f32>]) {
+        let mut bytes = Vec::new();
+        for v in vectors {
+            bytes.extend_from_slice(&(v.len() as i32).to_le_bytes());
+            for x in v {
+                bytes.extend_from_slice(&x.to_le_bytes());
+            }
+        }
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn test_fvecs_with_padding() {
+        let path = temp_path("padded.fvecs");
+        let vectors: Vec<Vec<f32>> = (0..3)
+            .map(|i| (0..20).map(|j| (i * 20 + j) as f32).collect())
+            .collect();
+        write_fvecs(&path, &vectors);
+
+        let loaded = load_from_fvecs(&path, None).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.len(), 3);
+        for (row, original) in loaded.iter().zip(&vectors) {
+            assert_eq!(row.len(), 2);
+            let flat: Vec<f32> = row.iter().flat_map(|b| b.data).collect();
+            assert_eq!(&flat[..20], original.as_slice());
+            assert!(flat[20..].iter().all(|&x| x == 0.0));
+        }
+    }
+
+    #[test]
+    fn test_fvecs_limit() {
+        let path = temp_path("limited.fvecs");
+        write_fvecs(&path, &vec![vec![1.0; SIMD_LANECOUNT]; 5]);
+
+        let loaded = load_from_fvecs(&path, Some(2)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.len(), 2);
+    }
+
+    #[test]
+    fn test_bvecs() {
+        let path = temp_path("bytes.bvecs");
+        let mut bytes = Vec::new();
+        for i in 0..2u8 {
+            bytes.extend_from_slice(&(SIMD_LANECOUNT as i32).to_le_bytes());
+            bytes.extend((0..SIMD_LANECOUNT as u8).map(|j| 200 + i + j));
+        }
+        std::fs::write(&path, bytes).unwrap();
+
+        let loaded = load_from_bvecs(&path, None).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[1][0].data[0], 201.0);
+        assert_eq!(loaded[1][0].data[SIMD_LANECOUNT - 1], 216.0);
+    }
+
+    #[test]
+    fn test_mismatched_dimensions_are_rejected() {
+        let path = temp_path("mismatched.fvecs");
+        write_fvecs(&path, &[vec![0.0; 16], vec![0.0; 16], vec![0.0; 8]]);
+
+        let err = load_from_fvecs(&path, None).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(
+            err.to_string().contains("vector 2") && err.to_string().contains("expected 16"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_truncated_file_is_rejected() {
+        let path = temp_path("truncated.fvecs");
+        write_fvecs(&path, &[vec![0.0; 16]]);
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 2]).unwrap();
+
+        let err = load_from_fvecs(&path, None).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_ivecs() {
+        let path = temp_path("truth.ivecs");
+        let mut bytes = Vec::new();
+        for row in [[3i32, 1, 4], [1, 5, 9]] {
+            bytes.extend_from_slice(&3i32.to_le_bytes());
+            for id in row {
+                bytes.extend_from_slice(&id.to_le_bytes());
+            }
+        }
+        std::fs::write(&path, &bytes).unwrap();
+        assert_eq!(
+            load_from_ivecs(&path, None).unwrap(),
+            vec![vec![3, 1, 4], vec![1, 5, 9]]
+        );
+
+        let last = bytes.len() - 4;
+        bytes[last..].copy_from_slice(&(-1i32).to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+        let err = load_from_ivecs(&path, None).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().contains("row 1"), "{err}");
+    }
+}
@@ -0,0 +1,280 @@
+use crate::{
+    fs::read_u64,
+    numerics::{AlignedBlock, Payload, SIMD_LANECOUNT},
+    search::AdjacencyGraph,
+    sets::{candidates::CandidateEntry, catapults::CatapultEvictionPolicy},
+    statistics::Stats,
+};
+
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, ErrorKind, Read, Write},
+    path::Path,
+};
+
+/// One recorded search: the query vector and the parameters it was run with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkloadQuery {
+    /// Query vector as aligned blocks
+    pub query: Vec<AlignedBlock>,
+
+    /// Number of nearest neighbors requested
+    pub k: usize,
+
+    /// Beam width of the search
+    pub beam_width: usize,
+}
+
+/// An ordered sequence of searches that can be saved to disk and replayed.
+///
+/// Catapults make search results depend on every query that came before, so a benchmark
+/// is only reproducible if the same queries run in the same order from the same catapult
+/// state. A `Workload` captures that order; [`replay`](Self::replay) clears all catapults
+/// and runs the queries one by one, so two replays on the same graph produce identical
+/// results and leave identical catapults behind.
+///
+/// # Examples
+/// ```
+/// use catapult::{
+///     fs::Workload,
+///     numerics::{AlignedBlock, SIMD_LANECOUNT},
+/// };
+///
+/// let mut workload = Workload::new();
+/// workload.record(vec![AlignedBlock::new([1.0; SIMD_LANECOUNT])], 10, 32);
+/// assert_eq!(workload.len(), 1);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Workload {
+    queries: Vec<WorkloadQuery>,
+}
+
+impl Workload {
+    /// Creates an empty workload.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a search to the end of the workload.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `k` - Number of nearest neighbors requested
+    /// * `beam_width` - Beam width of the search (must be ≥ k)
+    pub fn record(&mut self, query: Vec<AlignedBlock>, k: usize, beam_width: usize) {
+        self.queries.push(WorkloadQuery {
+            query,
+            k,
+            beam_width,
+        });
+    }
+
+    /// Returns the recorded searches, in order.
+    pub fn queries(&self) -> &[WorkloadQuery] {
+        &self.queries
+    }
+
+    /// Returns the number of recorded searches.
+    pub fn len(&self) -> usize {
+        self.queries.len()
+    }
+
+    /// Returns whether no search has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.queries.is_empty()
+    }
+
+    /// Clears every catapult of `graph`, then runs the recorded searches in order.
+    ///
+    /// Searches go through [`beam_search`](AdjacencyGraph::beam_search), so catapults are
+    /// learned exactly as they would be in production. Replays must not overlap with other
+    /// searches on the same graph, which would interleave catapult updates.
+    ///
+    /// # Arguments
+    /// * `graph` - Graph to run the workload against
+    /// * `stats` - Statistics tracker accumulating over the whole replay
+    ///
+    /// # Returns
+    /// The results of every search, in workload order
+    pub fn replay<E, P>(
+        &self,
+        graph: &AdjacencyGraph<E, P>,
+        stats: &mut Stats,
+    ) -> Vec<Vec<CandidateEntry>>
+    where
+        E: CatapultEvictionPolicy,
+        P: Payload,
+    {
+        graph.clear_all_catapults();
+        self.queries
+            .iter()
+            .map(|q| graph.beam_search(&q.query, q.k, q.beam_width, stats))
+            .collect()
+    }
+
+    /// Writes the workload to `path`.
+    ///
+    /// # File Format
+    /// All integers are little-endian u64, vector entries little-endian f32:
+    /// - Header: number of searches
+    /// - For each search: `k`, `beam_width`, the vector dimension in f32 elements, then
+    ///   the vector entries
+    ///
+    /// # Arguments
+    /// * `path` - Destination file, created or truncated
+    ///
+    /// # Errors
+    /// Returns any I/O error raised while creating or writing the file
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(&(self.queries.len() as u64).to_le_bytes())?;
+
+        for q in &self.queries {
+            for header in [q.k, q.beam_width, q.query.len() * SIMD_LANECOUNT] {
+                out.write_all(&(header as u64).to_le_bytes())?;
+            }
+            for value in q.query.iter().flat_map(|b| b.data) {
+                out.write_all(&value.to_le_bytes())?;
+            }
+        }
+        out.flush()
+    }
+
+    /// Reads a workload written by [`save`](Self::save).
+    ///
+    /// # Arguments
+    /// * `path` - File previously written by `save`
+    ///
+    /// # Returns
+    /// The recorded searches, in their original order
+    ///
+    /// # Errors
+    /// Returns an `InvalidData` error if a vector dimension is not a multiple of
+    /// `SIMD_LANECOUNT` or the file has trailing bytes, or any I/O error raised while
+    /// reading (`UnexpectedEof` for a truncated file)
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut input = BufReader::new(File::open(path)?);
+        let count = read_u64(&mut input)?;
+
+        let mut workload = Workload::new();
+        for _ in 0..count {
+            let k = read_u64(&mut input)? as usize;
+            let beam_width = read_u64(&mut input)? as usize;
+            let dim = read_u64(&mut input)? as usize;
+            if !dim.is_multiple_of(SIMD_LANECOUNT) {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("query dimension {dim} is not a multiple of {SIMD_LANECOUNT}"),
+                ));
+            }
+
+            let mut query = Vec::with_capacity(dim / SIMD_LANECOUNT);
+            for _ in 0..dim / SIMD_LANECOUNT {
+                let mut block = [0.0; SIMD_LANECOUNT];
+                for entry in block.iter_mut() {
+                    let mut bytes = [0u8; 4];
+                    input.read_exact(&mut bytes)?;
+                    *entry = f32::from_le_bytes(bytes);
+                }
+                query.push(AlignedBlock::new(block));
+            }
+            workload.record(query, k, beam_width);
+        }
+
+        if input.read(&mut [0u8])? != 0 {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "trailing bytes after the last workload query",
+            ));
+        }
+        Ok(workload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fs::temp_path,
+        search::{
+            GraphBuilder, NodeId, SearchStrategy,
+            hash_start::{EngineStarter, EngineStarterParams},
+        },
+        sets::catapults::LruSet,
+    };
+    use rand::prelude::*;
+
+    fn random_points(n: usize, seed: u64) -> Vec<Vec<AlignedBlock>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..n)
+            .map(|_| {
+                let mut data = [0.0; SIMD_LANECOUNT];
+                for v in data.iter_mut() {
+                    *v = rng.random_range(-1.0..1.0);
+                }
+                vec![AlignedBlock::new(data)]
+            })
+            .collect()
+    }
+
+    fn catapult_graph() -> AdjacencyGraph<LruSet> {
+        let nodes = GraphBuilder::new(8).build(&random_points(200, 1));
+        let params =
+            EngineStarterParams::new(4, 4, SIMD_LANECOUNT, NodeId { internal: 0 }, 42, true);
+        AdjacencyGraph::new_flat(nodes, EngineStarter::new(params), SearchStrategy::Catapult)
+    }
+
+    #[test]
+    fn test_replays_are_identical() {
+        let graph = catapult_graph();
+        let mut workload = Workload::new();
+        for (i, query) in random_points(40, 2).into_iter().enumerate() {
+            workload.record(query, 1 + i % 5, 8);
+        }
+
+        let path = temp_path("workload");
+        workload.save(&path).unwrap();
+        let loaded = Workload::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, workload);
+
+        let mut first_stats = Stats::new();
+        let first = loaded.replay(&graph, &mut first_stats);
+        let first_catapults = graph.export_catapult_edges();
+
+        let mut second_stats = Stats::new();
+        let second = loaded.replay(&graph, &mut second_stats);
+
+        assert!(!first_catapults.is_empty());
+        assert_eq!(first, second);
+        assert_eq!(graph.export_catapult_edges(), first_catapults);
+        assert_eq!(
+            first_stats.get_computed_dists(),
+            second_stats.get_computed_dists()
+        );
+        for (results, q) in first.iter().zip(workload.queries()) {
+            assert_eq!(results.len(), q.k);
+        }
+    }
+
+    #[test]
+    fn test_load_rejects_malformed_files() {
+        let mut workload = Workload::new();
+        workload.record(random_points(1, 3).remove(0), 2, 4);
+        let path = temp_path("workload-truncated");
+        workload.save(&path).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 4]).unwrap();
+        let err = Workload::load(&path).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        std::fs::write(&path, trailing).unwrap();
+        let err = Workload::load(&path).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
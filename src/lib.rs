@@ -1,4 +1,5 @@
 #![feature(portable_simd)]
+#![feature(f16)]
 
 //! # Catapult: Approximate Nearest Neighbor Search with LSH-Cached Starting Points
 //!
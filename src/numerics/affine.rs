@@ -0,0 +1,199 @@
+use crate::numerics::{AlignedBlock, SIMD_LANECOUNT, VectorLike};
+
+/// An affine preprocessing step: subtract a mean vector, then optionally project.
+///
+/// Meant for embeddings that search better once centered, or once reduced by PCA: the
+/// output of [`apply`](Self::apply) is `P (v - mean)`, or `v - mean` without a projection
+/// matrix `P`. Centering is a translation, so it preserves L2 distances exactly up to
+/// rounding; a projection changes them unless its rows are orthonormal.
+///
+/// The same transform must be applied to payloads and to queries, otherwise distances are
+/// computed between vectors living in different spaces.
+///
+/// # Examples
+/// ```
+/// use catapult::numerics::{AffineTransform, AlignedBlock};
+///
+/// let transform = AffineTransform::new(vec![1.0, 2.0]);
+/// let v = AlignedBlock::allocate_padded(vec![3.0, 3.0]);
+/// assert_eq!(transform.apply(&v), AlignedBlock::allocate_padded(vec![2.0, 1.0]));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct AffineTransform {
+    /// Logical dimension of input vectors, excluding padding
+    input_dim: usize,
+    /// Mean vector, zero-padded to whole blocks
+    mean: Vec<AlignedBlock>,
+    /// One zero-padded row per output dimension
+    projection: Option<Vec<Vec<AlignedBlock>>>,
+}
+
+impl AffineTransform {
+    /// Creates a transform that subtracts `mean` from every vector.
+    ///
+    /// # Arguments
+    /// * `mean` - Mean vector, of the logical input dimension
+    ///
+    /// # Returns
+    /// A centering transform, whose output dimension equals its input dimension
+    pub fn new(mean: Vec<f32>) -> Self {
+        AffineTransform {
+            input_dim: mean.len(),
+            mean: AlignedBlock::allocate_padded(mean),
+            projection: None,
+        }
+    }
+
+    /// Creates the transform centering a sample on its mean.
+    ///
+    /// # Arguments
+    /// * `sample` - Vectors to average, zero-padded to whole blocks
+    /// * `dim` - Logical dimension of the vectors
+    ///
+    /// # Returns
+    /// A centering transform subtracting the mean of `sample`
+    ///
+    /// # Panics
+    /// Panics if `sample` is empty or a vector does not span the blocks of `dim`
+    pub fn centering(sample: &[Vec<AlignedBlock>], dim: usize) -> Self {
+        assert!(!sample.is_empty(), "cannot center an empty sample");
+        let mut sum = vec![0.0f64; dim.next_multiple_of(SIMD_LANECOUNT)];
+        for vector in sample {
+            assert_eq!(
+                vector.len(),
+                dim.div_ceil(SIMD_LANECOUNT),
+                "sample vector has wrong dimension"
+            );
+            for (total, &value) in sum.iter_mut().zip(vector.iter().flat_map(|b| &b.data)) {
+                *total += value as f64;
+            }
+        }
+        let count = sample.len() as f64;
+        Self::new(
+            sum[..dim]
+                .iter()
+                .map(|&total| (total / count) as f32)
+                .collect(),
+        )
+    }
+
+    /// Adds a projection applied after centering.
+    ///
+    /// # Arguments
+    /// * `rows` - One row per output dimension, each of the input dimension, e.g. the top
+    ///   principal components of the centered data
+    ///
+    /// # Returns
+    /// The transform, projecting to `rows.len()` dimensions
+    ///
+    /// # Panics
+    /// Panics if there are no rows, or a row's length differs from the input dimension
+    pub fn with_projection(mut self, rows: Vec<Vec<f32>>) -> Self {
+        assert!(!rows.is_empty(), "projection must have at least one row");
+        assert!(
+            rows.iter().all(|row| row.len() == self.input_dim),
+            "projection rows must have the input dimension"
+        );
+        self.projection = Some(
+            rows.into_iter()
+                .map(AlignedBlock::allocate_padded)
+                .collect(),
+        );
+        self
+    }
+
+    /// Returns the logical dimension of input vectors, in f32 elements.
+    pub fn input_dim(&self) -> usize {
+        self.input_dim
+    }
+
+    /// Returns the logical dimension of output vectors, in f32 elements.
+    pub fn output_dim(&self) -> usize {
+        self.projection
+            .as_ref()
+            .map_or(self.input_dim, |rows| rows.len())
+    }
+
+    /// Returns the mean vector, zero-padded to whole blocks.
+    pub fn mean(&self) -> &[AlignedBlock] {
+        &self.mean
+    }
+
+    /// Returns the rows of the projection matrix, each zero-padded to whole blocks.
+    pub fn projection(&self) -> Option<&[Vec<AlignedBlock>]> {
+        self.projection.as_deref()
+    }
+
+    /// Applies the transform to `vector`.
+    ///
+    /// # Arguments
+    /// * `vector` - Vector of the input dimension, zero-padded to whole blocks
+    ///
+    /// # Returns
+    /// The transformed vector, zero-padded to whole blocks of the output dimension
+    ///
+    /// # Panics
+    /// Panics if the dimension of `vector` does not match
+    pub fn apply(&self, vector: &[AlignedBlock]) -> Vec<AlignedBlock> {
+        assert_eq!(
+            vector.len(),
+            self.mean.len(),
+            "vector dimension differs from the transform's"
+        );
+        // padding lanes are zero in both, so they stay zero
+        let centered: Vec<AlignedBlock> = vector
+            .iter()
+            .zip(&self.mean)
+            .map(|(v, m)| AlignedBlock::new(std::array::from_fn(|i| v.data[i] - m.data[i])))
+            .collect();
+        match &self.projection {
+            None => centered,
+            Some(rows) => {
+                AlignedBlock::allocate_padded(rows.iter().map(|row| row.dot(&centered)).collect())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn centering_subtracts_the_sample_mean() {
+        let sample = vec![
+            AlignedBlock::allocate_padded(vec![1.0, 4.0, -2.0]),
+            AlignedBlock::allocate_padded(vec![3.0, 0.0, -4.0]),
+        ];
+        let transform = AffineTransform::centering(&sample, 3);
+        assert_eq!(
+            transform.mean(),
+            AlignedBlock::allocate_padded(vec![2.0, 2.0, -3.0])
+        );
+        assert_eq!(
+            transform.apply(&sample[0]),
+            AlignedBlock::allocate_padded(vec![-1.0, 2.0, 1.0])
+        );
+        assert_eq!(transform.output_dim(), 3);
+    }
+
+    #[test]
+    fn projection_reduces_dimension_after_centering() {
+        let transform = AffineTransform::new(vec![1.0; 20])
+            .with_projection(vec![vec![1.0; 20], (0..20).map(|i| i as f32).collect()]);
+        assert_eq!((transform.input_dim(), transform.output_dim()), (20, 2));
+
+        let v = AlignedBlock::allocate_padded(vec![2.0; 20]);
+        // centered to all ones: the rows sum to 20 and to 0 + 1 + ... + 19
+        assert_eq!(
+            transform.apply(&v),
+            AlignedBlock::allocate_padded(vec![20.0, 190.0])
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "projection rows must have the input dimension")]
+    fn projection_rows_must_match_input() {
+        AffineTransform::new(vec![0.0; 4]).with_projection(vec![vec![1.0; 3]]);
+    }
+}
@@ -41,6 +41,7 @@ impl AlignedBlock {
     /// # Returns
     /// A vector of `AlignedBlock` instances. If the input length is an exact multiple
     /// of `SIMD_LANECOUNT`, no padding is added. Otherwise, the final block is zero-padded.
+    /// Padding lanes are always exactly `0.0`, which hashing checks in debug builds.
     pub fn allocate_padded(data: Vec<f32>) -> Vec<AlignedBlock> {
         let mut returned = Vec::with_capacity(data.len().div_ceil(SIMD_LANECOUNT));
 
@@ -117,4 +118,20 @@ mod tests {
         assert_eq!(blocks[1].data[0..4], [1.0; 4]);
         assert_eq!(blocks[1].data[4..], [0.0; 12]);
     }
+
+    #[test]
+    fn test_padding_preserves_distances() {
+        use crate::numerics::VectorLike;
+
+        let a: Vec<f32> = (0..100).map(|i| (i as f32 * 0.1).cos()).collect();
+        let b: Vec<f32> = (0..100).map(|i| (i as f32 * 0.3).sin()).collect();
+        let l2_squared: f32 = a.iter().zip(&b).map(|(x, y)| (x - y) * (x - y)).sum();
+        let dot: f32 = a.iter().zip(&b).map(|(x, y)| x * y).sum();
+
+        let padded_a = AlignedBlock::allocate_padded(a);
+        let padded_b = AlignedBlock::allocate_padded(b);
+        assert_eq!(padded_a.len(), 7);
+        assert!((padded_a.l2_squared(&padded_b) - l2_squared).abs() <= 1e-4 * l2_squared);
+        assert!((padded_a.dot(&padded_b) - dot).abs() <= 1e-4 * dot.abs().max(1.0));
+    }
 }
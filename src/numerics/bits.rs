@@ -0,0 +1,198 @@
+use crate::numerics::{AlignedBlock, SIMD_LANECOUNT, payload::Payload};
+
+/// Number of blocks whose lanes fit in one u64 word, one bit per lane.
+const BLOCKS_PER_WORD: usize = u64::BITS as usize / SIMD_LANECOUNT;
+
+/// Counts the bits that differ between two bit-packed codes.
+///
+/// # Arguments
+/// * `a` - First code, as u64 words
+/// * `b` - Second code, with as many words as `a`
+///
+/// # Returns
+/// The Hamming distance between the codes
+///
+/// # Panics
+/// Panics if the codes have different lengths
+#[inline]
+pub fn hamming_distance(a: &[u64], b: &[u64]) -> u32 {
+    assert_eq!(a.len(), b.len(), "codes have different lengths");
+    a.iter().zip(b).map(|(x, y)| (x ^ y).count_ones()).sum()
+}
+
+/// Packs the lanes of `vector` into bits, setting those of non-zero lanes.
+fn pack(vector: &[AlignedBlock]) -> Box<[u64]> {
+    vector
+        .chunks(BLOCKS_PER_WORD)
+        .map(|blocks| {
+            blocks
+                .iter()
+                .flat_map(|b| b.data)
+                .enumerate()
+                .filter(|&(_, value)| value != 0.0)
+                .fold(0u64, |word, (bit, _)| word | (1 << bit))
+        })
+        .collect()
+}
+
+/// A binary code stored one bit per coordinate, searched by Hamming distance.
+///
+/// Meant for binary embeddings such as 256-bit hash codes. Each coordinate of the code is
+/// a 0 or a 1, and the code is seen by the rest of the library as the f32 vector of those
+/// values: the squared L2 distance between two such vectors is exactly their Hamming
+/// distance. Queries are therefore passed as 0/1 f32 vectors, e.g. built with
+/// [`to_blocks`](Payload::to_blocks) of a code, and packed into bits once per batch of
+/// distance computations, which then reduce to an XOR and a popcount per 64 coordinates.
+/// Any non-zero query coordinate counts as a 1.
+///
+/// At one bit per coordinate, payloads take 32x less memory than f32 storage.
+///
+/// # Examples
+/// ```
+/// use catapult::numerics::{BitVector, Payload};
+///
+/// let a = BitVector::from_words(vec![0b1011], 4);
+/// let b = BitVector::from_words(vec![0b0110], 4);
+/// assert_eq!(a.l2_squared_to(&b.to_blocks()), 3.0);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitVector {
+    /// One bit per coordinate, zero past the logical dimension
+    words: Box<[u64]>,
+    /// Number of aligned blocks spanned by the code
+    num_blocks: usize,
+}
+
+impl BitVector {
+    /// Wraps a bit-packed code, coordinate `i` being bit `i % 64` of word `i / 64`.
+    ///
+    /// # Arguments
+    /// * `words` - The code, `dim.div_ceil(64)` words long
+    /// * `dim` - Number of coordinates of the code
+    ///
+    /// # Returns
+    /// The code as a payload spanning `dim.div_ceil(SIMD_LANECOUNT)` blocks
+    ///
+    /// # Panics
+    /// Panics if the number of words does not match `dim`, or a bit past `dim` is set
+    pub fn from_words(words: Vec<u64>, dim: usize) -> Self {
+        assert_eq!(
+            words.len(),
+            dim.div_ceil(u64::BITS as usize),
+            "word count does not match the dimension"
+        );
+        let tail = dim % u64::BITS as usize;
+        assert!(
+            tail == 0 || words.last().is_none_or(|&last| last >> tail == 0),
+            "bits are set past the dimension"
+        );
+        BitVector {
+            words: words.into_boxed_slice(),
+            num_blocks: dim.div_ceil(SIMD_LANECOUNT),
+        }
+    }
+
+    /// Packs a 0/1 vector, setting the bits of its non-zero coordinates.
+    pub fn from_blocks(vector: &[AlignedBlock]) -> Self {
+        BitVector {
+            words: pack(vector),
+            num_blocks: vector.len(),
+        }
+    }
+
+    /// Returns the bit-packed code.
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+}
+
+impl Payload for BitVector {
+    #[inline]
+    fn l2_squared_to(&self, query: &[AlignedBlock]) -> f32 {
+        assert_eq!(self.num_blocks, query.len());
+        hamming_distance(&self.words, &pack(query)) as f32
+    }
+
+    fn l2_squared_batch_to(payloads: &[&Self], query: &[AlignedBlock]) -> Vec<f32> {
+        let packed = pack(query);
+        payloads
+            .iter()
+            .map(|payload| {
+                assert_eq!(payload.num_blocks, query.len());
+                hamming_distance(&payload.words, &packed) as f32
+            })
+            .collect()
+    }
+
+    fn to_blocks(&self) -> Vec<AlignedBlock> {
+        (0..self.num_blocks)
+            .map(|block| {
+                let word = self.words[block / BLOCKS_PER_WORD];
+                let shift = (block % BLOCKS_PER_WORD) * SIMD_LANECOUNT;
+                AlignedBlock::new(std::array::from_fn(|lane| {
+                    ((word >> (shift + lane)) & 1) as f32
+                }))
+            })
+            .collect()
+    }
+
+    fn num_blocks(&self) -> usize {
+        self.num_blocks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numerics::VectorLike;
+    use rand::prelude::*;
+
+    /// Hamming distance of codes given as one bool per coordinate.
+    fn scalar_hamming(a: &[bool], b: &[bool]) -> u32 {
+        a.iter().zip(b).filter(|(x, y)| x != y).count() as u32
+    }
+
+    fn to_words(bits: &[bool]) -> Vec<u64> {
+        bits.chunks(64)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0, |word, (i, &bit)| word | ((bit as u64) << i))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hamming_matches_scalar_count_on_short_codes() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for dim in [1, 7, 16, 63, 64, 65, 100, 256] {
+            for _ in 0..20 {
+                let a: Vec<bool> = (0..dim).map(|_| rng.random()).collect();
+                let b: Vec<bool> = (0..dim).map(|_| rng.random()).collect();
+                let (code_a, code_b) = (
+                    BitVector::from_words(to_words(&a), dim),
+                    BitVector::from_words(to_words(&b), dim),
+                );
+                let expected = scalar_hamming(&a, &b);
+
+                assert_eq!(hamming_distance(code_a.words(), code_b.words()), expected);
+                // the 0/1 vectors are at a squared L2 distance equal to the Hamming one
+                let (blocks_a, blocks_b) = (code_a.to_blocks(), code_b.to_blocks());
+                assert_eq!(blocks_a.l2_squared(&blocks_b), expected as f32);
+                assert_eq!(code_a.l2_squared_to(&blocks_b), expected as f32);
+                assert_eq!(
+                    BitVector::l2_squared_batch_to(&[&code_a, &code_b], &blocks_b),
+                    vec![expected as f32, 0.0]
+                );
+                assert_eq!(BitVector::from_blocks(&blocks_a), code_a);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "bits are set past the dimension")]
+    fn padding_bits_must_be_clear() {
+        BitVector::from_words(vec![1 << 10], 10);
+    }
+}
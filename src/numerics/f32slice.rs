@@ -1,15 +1,172 @@
 use std::simd::{Simd, num::SimdFloat};
 
-use crate::numerics::aligned_block::{AlignedBlock, SIMD_LANECOUNT};
+use crate::numerics::{
+    aligned_block::{AlignedBlock, SIMD_LANECOUNT},
+    scalar::{
+        dot_scalar, l2_squared_scalar, l2_squared_weighted_scalar, project_interleaved_scalar,
+    },
+};
 
 type SimdF32 = Simd<f32, SIMD_LANECOUNT>;
 
+/// SIMD kernel behind [`VectorLike::l2_squared`].
+#[inline]
+pub(crate) fn l2_squared_simd(a: &[AlignedBlock], b: &[AlignedBlock]) -> f32 {
+    assert_eq!(a.len(), b.len());
+
+    let mut intermediate_sum_lanes = SimdF32::splat(0.0);
+
+    for (&slice_self, &slice_othr) in a.iter().zip(b.iter()) {
+        let f32simd_slf = SimdF32::from_array(slice_self.data);
+        let f32simd_oth = SimdF32::from_array(slice_othr.data);
+        let diff = f32simd_slf - f32simd_oth;
+        intermediate_sum_lanes += diff * diff;
+    }
+
+    intermediate_sum_lanes.reduce_sum() // 8-to-1 sum
+}
+
+/// SIMD kernel behind [`VectorLike::l2_squared_weighted`].
+#[inline]
+pub(crate) fn l2_squared_weighted_simd(
+    a: &[AlignedBlock],
+    b: &[AlignedBlock],
+    weights: &[AlignedBlock],
+) -> f32 {
+    assert_eq!(a.len(), b.len());
+    assert_eq!(a.len(), weights.len());
+
+    let mut intermediate_sum_lanes = SimdF32::splat(0.0);
+
+    for ((&slice_self, &slice_othr), &slice_wght) in a.iter().zip(b.iter()).zip(weights) {
+        let f32simd_slf = SimdF32::from_array(slice_self.data);
+        let f32simd_oth = SimdF32::from_array(slice_othr.data);
+        let diff = f32simd_slf - f32simd_oth;
+        intermediate_sum_lanes += SimdF32::from_array(slice_wght.data) * (diff * diff);
+    }
+
+    intermediate_sum_lanes.reduce_sum()
+}
+
+/// SIMD kernel behind [`VectorLike::dot`].
+#[inline]
+pub(crate) fn dot_simd(a: &[AlignedBlock], b: &[AlignedBlock]) -> f32 {
+    assert_eq!(a.len(), b.len());
+
+    let mut intermediate_sum_lanes = SimdF32::splat(0.0);
+
+    for (&slice_self, &slice_othr) in a.iter().zip(b.iter()) {
+        let f32simd_slf = SimdF32::from_array(slice_self.data);
+        let f32simd_oth = SimdF32::from_array(slice_othr.data);
+        intermediate_sum_lanes += f32simd_slf * f32simd_oth;
+    }
+
+    intermediate_sum_lanes.reduce_sum() // 8-to-1 sum
+}
+
+/// Number of candidates whose distances [`l2_squared_batch`] accumulates together.
+const BATCH_WIDTH: usize = 4;
+
+/// Computes the squared L2 distance from `query` to each of `candidates`.
+///
+/// Candidates are processed four at a time: each block of the query is loaded once and
+/// compared against the matching block of four candidates, whose sums build up in four
+/// independent accumulators. A single distance is one long chain of dependent additions,
+/// so interleaving four chains keeps more SIMD units busy than computing the distances one
+/// after the other. Each accumulator still sees its candidate's blocks in order, so the
+/// results are bit-identical to [`VectorLike::l2_squared`]. With the `scalar-fallback`
+/// feature, distances are computed one at a time by the scalar kernel.
+///
+/// # Arguments
+/// * `query` - The vector to measure distances from
+/// * `candidates` - The vectors to measure distances to, each as long as `query`
+///
+/// # Returns
+/// The squared L2 distance to each candidate, in the order of `candidates`
+///
+/// # Panics
+/// Panics if a candidate's length differs from the query's
+pub fn l2_squared_batch(query: &[AlignedBlock], candidates: &[&[AlignedBlock]]) -> Vec<f32> {
+    if cfg!(feature = "scalar-fallback") {
+        return candidates
+            .iter()
+            .map(|candidate| l2_squared_scalar(candidate, query))
+            .collect();
+    }
+
+    let mut distances = Vec::with_capacity(candidates.len());
+    let mut groups = candidates.chunks_exact(BATCH_WIDTH);
+    for group in &mut groups {
+        let [a, b, c, d] = [group[0], group[1], group[2], group[3]];
+        for candidate in [a, b, c, d] {
+            assert_eq!(candidate.len(), query.len());
+        }
+        let mut sums = [SimdF32::splat(0.0); BATCH_WIDTH];
+        for ((((q, a), b), c), d) in query.iter().zip(a).zip(b).zip(c).zip(d) {
+            let q = SimdF32::from_array(q.data);
+            for (sum, block) in sums.iter_mut().zip([a, b, c, d]) {
+                let diff = SimdF32::from_array(block.data) - q;
+                *sum += diff * diff;
+            }
+        }
+        distances.extend(sums.map(|sum| sum.reduce_sum()));
+    }
+    distances.extend(
+        groups
+            .remainder()
+            .iter()
+            .map(|candidate| l2_squared_simd(candidate, query)),
+    );
+    distances
+}
+
+/// Computes the dot product of `vector` with each of `num_planes` planes stored interleaved.
+///
+/// Block `b` of plane `p` is stored at `planes[b * num_planes + p]`, so the blocks every
+/// plane needs for one block of `vector` sit next to each other. Each block of `vector`
+/// is then loaded once and multiplied into all planes' accumulators, instead of once per
+/// plane. Each accumulator still sees its plane's blocks in order, so the results are
+/// bit-identical to [`VectorLike::dot`]. With the `scalar-fallback` feature, the scalar
+/// kernel is used instead.
+///
+/// # Arguments
+/// * `vector` - The vector to project
+/// * `planes` - The interleaved blocks of every plane, each plane as long as `vector`
+/// * `num_planes` - Number of planes
+///
+/// # Returns
+/// The dot product of `vector` with each plane, in plane order
+///
+/// # Panics
+/// Panics if `planes` does not hold `num_planes` planes as long as `vector`
+pub fn project_interleaved(
+    vector: &[AlignedBlock],
+    planes: &[AlignedBlock],
+    num_planes: usize,
+) -> Vec<f32> {
+    if cfg!(feature = "scalar-fallback") {
+        return project_interleaved_scalar(vector, planes, num_planes);
+    }
+    assert_eq!(planes.len(), vector.len() * num_planes);
+
+    let mut sums = vec![SimdF32::splat(0.0); num_planes];
+    for (block, plane_blocks) in vector.iter().zip(planes.chunks_exact(num_planes.max(1))) {
+        let block = SimdF32::from_array(block.data);
+        for (sum, plane_block) in sums.iter_mut().zip(plane_blocks) {
+            *sum += SimdF32::from_array(plane_block.data) * block;
+        }
+    }
+    sums.into_iter().map(|sum| sum.reduce_sum()).collect()
+}
+
 /// A trait for vector‐like slices of `f32`, supporting common linear‐algebra
 /// operations (dot product, L2 distance, normalization). The trait only has one
 /// implementation, and exists because I could otherwise not add random Impl blocks
 /// to the existing `[f32]` type from stdlib.
 ///
-/// Implemented for `[f32]` using portable SIMD with lane-width [`SIMD_LANECOUNT`].
+/// Implemented for `[f32]` using portable SIMD with lane-width [`SIMD_LANECOUNT`]. When the
+/// `scalar-fallback` feature is enabled, `l2_squared` and `dot` use the plain loops of
+/// [`scalar`](super::scalar) instead, which produce bit-identical results.
 ///
 /// # Contract
 ///
@@ -24,6 +181,16 @@ pub trait VectorLike {
     /// The squared L2 distance
     fn l2_squared(&self, othr: &Self) -> f32;
 
+    /// Computes the squared L2 distance between two vectors, weighting each dimension.
+    ///
+    /// # Arguments
+    /// * `othr` - The other vector, must have same length
+    /// * `weights` - Weight of each dimension, must have same length
+    ///
+    /// # Returns
+    /// The weighted squared L2 distance
+    fn l2_squared_weighted(&self, othr: &Self, weights: &Self) -> f32;
+
     /// Computes the L2 (Euclidean) distance between two vectors.
     ///
     /// # Arguments
@@ -41,6 +208,18 @@ pub trait VectorLike {
     /// # Returns
     /// The dot product
     fn dot(&self, othr: &Self) -> f32;
+
+    /// Computes the L2 norm (Euclidean length) of the vector.
+    ///
+    /// # Returns
+    /// The norm, `0.0` for the zero vector
+    fn norm(&self) -> f32;
+
+    /// Scales the vector to unit norm, so that its dot product with another unit vector
+    /// is their cosine similarity.
+    ///
+    /// The zero vector has no direction and is left unchanged, so its norm stays 0.
+    fn normalize_in_place(&mut self);
 }
 
 impl VectorLike for [AlignedBlock] {
@@ -60,18 +239,34 @@ impl VectorLike for [AlignedBlock] {
     /// Panics if the two vectors have different lengths
     #[inline]
     fn l2_squared(&self, othr: &[AlignedBlock]) -> f32 {
-        assert_eq!(self.len(), othr.len());
-
-        let mut intermediate_sum_lanes = SimdF32::splat(0.0);
-
-        for (&slice_self, &slice_othr) in self.iter().zip(othr.iter()) {
-            let f32simd_slf = SimdF32::from_array(slice_self.data);
-            let f32simd_oth = SimdF32::from_array(slice_othr.data);
-            let diff = f32simd_slf - f32simd_oth;
-            intermediate_sum_lanes += diff * diff;
+        if cfg!(feature = "scalar-fallback") {
+            l2_squared_scalar(self, othr)
+        } else {
+            l2_squared_simd(self, othr)
         }
+    }
 
-        intermediate_sum_lanes.reduce_sum() // 8-to-1 sum
+    /// Computes the weighted squared L2 distance between two vectors using SIMD operations.
+    ///
+    /// Calculates `Σ_i weights[i] * (self[i] - other[i])²` across all aligned blocks in
+    /// parallel. With all weights at 1.0, this is [`l2_squared`](VectorLike::l2_squared).
+    ///
+    /// # Arguments
+    /// * `othr` - The other vector to compute distance to, must have same length as `self`
+    /// * `weights` - Weight of each dimension, must have same length as `self`
+    ///
+    /// # Returns
+    /// The weighted squared L2 distance as an f32
+    ///
+    /// # Panics
+    /// Panics if the three vectors do not all have the same length
+    #[inline]
+    fn l2_squared_weighted(&self, othr: &[AlignedBlock], weights: &[AlignedBlock]) -> f32 {
+        if cfg!(feature = "scalar-fallback") {
+            l2_squared_weighted_scalar(self, othr, weights)
+        } else {
+            l2_squared_weighted_simd(self, othr, weights)
+        }
     }
 
     /// Computes the L2 (Euclidean) distance between two vectors using SIMD operations.
@@ -106,17 +301,29 @@ impl VectorLike for [AlignedBlock] {
     /// Panics if the two vectors have different lengths
     #[inline]
     fn dot(&self, othr: &[AlignedBlock]) -> f32 {
-        assert_eq!(self.len(), othr.len());
+        if cfg!(feature = "scalar-fallback") {
+            dot_scalar(self, othr)
+        } else {
+            dot_simd(self, othr)
+        }
+    }
 
-        let mut intermediate_sum_lanes = SimdF32::splat(0.0);
+    /// Computes the L2 norm as the square root of the vector's dot product with itself.
+    #[inline]
+    fn norm(&self) -> f32 {
+        self.dot(self).sqrt()
+    }
 
-        for (&slice_self, &slice_othr) in self.iter().zip(othr.iter()) {
-            let f32simd_slf = SimdF32::from_array(slice_self.data);
-            let f32simd_oth = SimdF32::from_array(slice_othr.data);
-            intermediate_sum_lanes += f32simd_slf * f32simd_oth;
+    /// Multiplies every block by the inverse norm, leaving zero vectors unchanged.
+    fn normalize_in_place(&mut self) {
+        let norm = self.norm();
+        if norm == 0.0 {
+            return;
+        }
+        let inverse = SimdF32::splat(norm.recip());
+        for block in self.iter_mut() {
+            block.data = (SimdF32::from_array(block.data) * inverse).to_array();
         }
-
-        intermediate_sum_lanes.reduce_sum() // 8-to-1 sum
     }
 }
 
@@ -161,6 +368,102 @@ mod tests {
         assert!(approx_eq(simd, scalar, EPS), "simd={simd} scalar={scalar}");
     }
 
+    fn random_vector(blocks: usize, seed: u64) -> Vec<AlignedBlock> {
+        use rand::{Rng, SeedableRng, rngs::StdRng};
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..blocks)
+            .map(|_| AlignedBlock::new(std::array::from_fn(|_| rng.random_range(-1.0..1.0))))
+            .collect()
+    }
+
+    #[test]
+    fn weighted_l2_scales_each_dimension() {
+        let x = random_vector(2, 40);
+        let y = random_vector(2, 41);
+        let ones = vec![AlignedBlock::new([1.0; SIMD_LANECOUNT]); 2];
+        assert_eq!(
+            x.l2_squared_weighted(&y, &ones).to_bits(),
+            x.l2_squared(&y).to_bits()
+        );
+
+        // only the first dimension counts, twice
+        let mut weights = vec![AlignedBlock::new([0.0; SIMD_LANECOUNT]); 2];
+        weights[0].data[0] = 2.0;
+        let diff = x[0].data[0] - y[0].data[0];
+        assert!(approx_eq(
+            x.l2_squared_weighted(&y, &weights),
+            2.0 * diff * diff,
+            EPS
+        ));
+    }
+
+    #[test]
+    fn batch_matches_one_at_a_time_bit_for_bit() {
+        let query = random_vector(3, 0);
+        let candidates: Vec<Vec<AlignedBlock>> =
+            (1..=7).map(|seed| random_vector(3, seed)).collect();
+        let refs: Vec<&[AlignedBlock]> = candidates.iter().map(Vec::as_slice).collect();
+
+        let batched = l2_squared_batch(&query, &refs);
+        assert_eq!(batched.len(), 7);
+        for (distance, candidate) in batched.iter().zip(&candidates) {
+            assert_eq!(distance.to_bits(), candidate.l2_squared(&query).to_bits());
+        }
+        assert!(l2_squared_batch(&query, &[]).is_empty());
+    }
+
+    /// Times the batched kernel against a loop of single distances on degree-32 nodes.
+    /// Run with `cargo test --release -- --ignored --nocapture batch_throughput`.
+    #[test]
+    #[ignore]
+    fn batch_throughput() {
+        use std::{hint::black_box, time::Instant};
+        const ROUNDS: usize = 100_000;
+        for blocks in [1, 8, 64] {
+            let query = random_vector(blocks, 0);
+            let candidates: Vec<Vec<AlignedBlock>> =
+                (1..=32).map(|seed| random_vector(blocks, seed)).collect();
+            let refs: Vec<&[AlignedBlock]> = candidates.iter().map(Vec::as_slice).collect();
+
+            let start = Instant::now();
+            for _ in 0..ROUNDS {
+                black_box(l2_squared_batch(black_box(&query), black_box(&refs)));
+            }
+            let batched = start.elapsed();
+
+            let start = Instant::now();
+            for _ in 0..ROUNDS {
+                let distances: Vec<f32> = black_box(&refs)
+                    .iter()
+                    .map(|candidate| candidate.l2_squared(black_box(&query)))
+                    .collect();
+                black_box(distances);
+            }
+            let single = start.elapsed();
+            println!(
+                "dim {}: batched {:?}, one at a time {:?} per degree-32 node",
+                blocks * SIMD_LANECOUNT,
+                batched / ROUNDS as u32,
+                single / ROUNDS as u32
+            );
+        }
+    }
+
+    #[test]
+    fn normalized_vector_has_unit_norm() {
+        let mut x = random_vector(4, 9);
+        let direction = x.clone();
+        x.normalize_in_place();
+        assert!(approx_eq(x.norm(), 1.0, EPS), "norm={}", x.norm());
+        // same direction: the cosine with the original is 1
+        assert!(approx_eq(x.dot(&direction) / direction.norm(), 1.0, EPS));
+
+        let mut zero = vec![AlignedBlock::new([0.0; SIMD_LANECOUNT]); 2];
+        zero.normalize_in_place();
+        assert_eq!(zero, vec![AlignedBlock::new([0.0; SIMD_LANECOUNT]); 2]);
+        assert_eq!(zero.norm(), 0.0);
+    }
+
     #[test]
     fn l2_is_sqrt_of_l2_squared() {
         let x = [AlignedBlock::new([
@@ -0,0 +1,146 @@
+use std::simd::{Simd, num::SimdFloat};
+
+use crate::numerics::{AlignedBlock, SIMD_LANECOUNT, payload::Payload};
+
+type SimdF32 = Simd<f32, SIMD_LANECOUNT>;
+
+/// Sixteen half-precision values, the f16 counterpart of one [`AlignedBlock`].
+///
+/// Aligned to 32 bytes so that a block never straddles a cache line.
+#[repr(align(32))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HalfBlock {
+    /// Array of 16 f16 values representing a portion of a vector.
+    pub data: [f16; SIMD_LANECOUNT],
+}
+
+impl HalfBlock {
+    /// Rounds every value of a full-precision block to the nearest f16.
+    pub fn from_f32(block: &AlignedBlock) -> Self {
+        HalfBlock {
+            data: block.data.map(|x| x as f16),
+        }
+    }
+
+    /// Widens every value back to f32.
+    #[inline]
+    pub fn to_f32(&self) -> AlignedBlock {
+        AlignedBlock::new(self.data.map(|x| x as f32))
+    }
+}
+
+/// A vector stored in half precision and widened to f32 inside the distance loop.
+///
+/// Halves payload memory compared to f32 storage. Each block is widened to an f32 SIMD
+/// vector right before use, so distances are computed in f32 against an f32 query; the
+/// only loss is the rounding of stored coordinates to 11 significant bits (a relative
+/// error of at most 2^-11 per coordinate for values in the normal f16 range).
+///
+/// # Examples
+/// ```
+/// use catapult::numerics::{AlignedBlock, HalfVector, Payload, SIMD_LANECOUNT};
+///
+/// let v = vec![AlignedBlock::new([0.5; SIMD_LANECOUNT])];
+/// let h = HalfVector::from_blocks(&v);
+/// assert_eq!(h.l2_squared_to(&v), 0.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct HalfVector {
+    blocks: Box<[HalfBlock]>,
+}
+
+impl HalfVector {
+    /// Narrows a full-precision vector to half precision.
+    ///
+    /// Values outside the f16 range become infinite.
+    pub fn from_blocks(vector: &[AlignedBlock]) -> Self {
+        HalfVector {
+            blocks: vector.iter().map(HalfBlock::from_f32).collect(),
+        }
+    }
+
+    /// Wraps blocks that are already in half precision, e.g. read from an f16 file.
+    pub fn from_half_blocks(blocks: Vec<HalfBlock>) -> Self {
+        HalfVector {
+            blocks: blocks.into_boxed_slice(),
+        }
+    }
+}
+
+impl Payload for HalfVector {
+    #[inline]
+    fn l2_squared_to(&self, query: &[AlignedBlock]) -> f32 {
+        assert_eq!(self.blocks.len(), query.len());
+
+        let mut intermediate_sum_lanes = SimdF32::splat(0.0);
+
+        for (stored, q) in self.blocks.iter().zip(query) {
+            let widened = SimdF32::from_array(stored.to_f32().data);
+            let diff = SimdF32::from_array(q.data) - widened;
+            intermediate_sum_lanes += diff * diff;
+        }
+
+        intermediate_sum_lanes.reduce_sum()
+    }
+
+    fn to_blocks(&self) -> Vec<AlignedBlock> {
+        self.blocks.iter().map(HalfBlock::to_f32).collect()
+    }
+
+    fn num_blocks(&self) -> usize {
+        self.blocks.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numerics::VectorLike;
+    use rand::prelude::*;
+
+    fn random_vector(rng: &mut StdRng, blocks: usize) -> Vec<AlignedBlock> {
+        (0..blocks)
+            .map(|_| {
+                let mut data = [0.0; SIMD_LANECOUNT];
+                for v in data.iter_mut() {
+                    *v = rng.random_range(-50.0..50.0);
+                }
+                AlignedBlock::new(data)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn f16_distances_match_f32_within_relative_tolerance() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for blocks in [1, 4, 8] {
+            for _ in 0..100 {
+                let stored = random_vector(&mut rng, blocks);
+                let query = random_vector(&mut rng, blocks);
+
+                let exact = stored.l2_squared(&query);
+                let half = HalfVector::from_blocks(&stored).l2_squared_to(&query);
+                assert!(
+                    (exact - half).abs() <= 1e-3 * exact,
+                    "exact={exact} half={half}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn representable_values_round_trip_exactly() {
+        let v = vec![AlignedBlock::new([
+            0.0, 1.0, -2.0, 0.5, 0.25, 1024.0, -0.125, 3.0, 7.5, 100.0, -100.0, 0.75, 2.0, 4.0,
+            8.0, 16.0,
+        ])];
+        let h = HalfVector::from_blocks(&v);
+        assert_eq!(h.to_blocks(), v);
+        assert_eq!(h.num_blocks(), 1);
+    }
+
+    #[test]
+    fn half_blocks_are_half_the_size() {
+        assert_eq!(size_of::<HalfBlock>() * 2, size_of::<AlignedBlock>());
+    }
+}
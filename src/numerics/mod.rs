@@ -2,9 +2,27 @@
 //!
 //! This module provides SIMD-accelerated distance computations and vector operations
 //! using 64-byte aligned blocks of 16 f32 values for efficient parallel processing.
+//! Bit-identical [`scalar`] kernels can replace them through the `scalar-fallback` feature.
+//! Node vectors are stored behind the [`Payload`] trait, either in full precision, in half
+//! precision as [`HalfVector`]s, as int8 [`QuantizedVector`]s, or as binary codes searched
+//! by Hamming distance in [`BitVector`]s. A [`Rotation`] can map payloads and queries to a
+//! space that hashes better, and an [`AffineTransform`] can center or project them first.
 
+mod affine;
 mod aligned_block;
+mod bits;
 mod f32slice;
+mod half;
+mod payload;
+mod quantized;
+mod rotation;
+pub mod scalar;
 
+pub use affine::AffineTransform;
 pub use aligned_block::{AlignedBlock, SIMD_LANECOUNT};
-pub use f32slice::VectorLike;
+pub use bits::{BitVector, hamming_distance};
+pub use f32slice::{VectorLike, l2_squared_batch, project_interleaved};
+pub use half::{HalfBlock, HalfVector};
+pub use payload::Payload;
+pub use quantized::{QuantizedBlock, QuantizedVector};
+pub use rotation::Rotation;
@@ -0,0 +1,88 @@
+use crate::numerics::{AlignedBlock, VectorLike, l2_squared_batch};
+
+/// A stored node vector that can be compared against an f32 query.
+///
+/// Graph nodes are generic over their payload so that the same search code can run on
+/// full-precision vectors or on compressed representations. Queries always stay in f32;
+/// compressed payloads compute an asymmetric distance against them.
+pub trait Payload {
+    /// Computes the squared L2 distance between `query` and the stored vector.
+    ///
+    /// # Arguments
+    /// * `query` - Full-precision query, with as many blocks as the stored vector
+    ///
+    /// # Returns
+    /// The (possibly approximate) squared L2 distance
+    fn l2_squared_to(&self, query: &[AlignedBlock]) -> f32;
+
+    /// Computes the squared L2 distance between `query` and each of several payloads.
+    ///
+    /// The default computes the distances one at a time; payload types with a batched
+    /// kernel override it.
+    ///
+    /// # Arguments
+    /// * `payloads` - Stored vectors, each with as many blocks as `query`
+    /// * `query` - Full-precision query
+    ///
+    /// # Returns
+    /// The (possibly approximate) squared L2 distance to each payload, in order
+    fn l2_squared_batch_to(payloads: &[&Self], query: &[AlignedBlock]) -> Vec<f32>
+    where
+        Self: Sized,
+    {
+        payloads
+            .iter()
+            .map(|payload| payload.l2_squared_to(query))
+            .collect()
+    }
+
+    /// Computes the weighted squared L2 distance between `query` and the stored vector.
+    ///
+    /// The default reconstructs the stored vector with [`to_blocks`](Self::to_blocks);
+    /// full-precision payloads override it to skip the copy.
+    ///
+    /// # Arguments
+    /// * `query` - Full-precision query, with as many blocks as the stored vector
+    /// * `weights` - Weight of each dimension, with as many blocks as the stored vector
+    ///
+    /// # Returns
+    /// The (possibly approximate) weighted squared L2 distance
+    fn l2_squared_weighted_to(&self, query: &[AlignedBlock], weights: &[AlignedBlock]) -> f32 {
+        self.to_blocks().l2_squared_weighted(query, weights)
+    }
+
+    /// Reconstructs the stored vector in full precision.
+    ///
+    /// # Returns
+    /// The vector as aligned blocks, exact for uncompressed payloads
+    fn to_blocks(&self) -> Vec<AlignedBlock>;
+
+    /// Returns the number of aligned blocks of the stored vector.
+    fn num_blocks(&self) -> usize;
+}
+
+impl Payload for Box<[AlignedBlock]> {
+    #[inline]
+    fn l2_squared_to(&self, query: &[AlignedBlock]) -> f32 {
+        self.l2_squared(query)
+    }
+
+    #[inline]
+    fn l2_squared_batch_to(payloads: &[&Self], query: &[AlignedBlock]) -> Vec<f32> {
+        let blocks: Vec<&[AlignedBlock]> = payloads.iter().map(|payload| &***payload).collect();
+        l2_squared_batch(query, &blocks)
+    }
+
+    #[inline]
+    fn l2_squared_weighted_to(&self, query: &[AlignedBlock], weights: &[AlignedBlock]) -> f32 {
+        self.l2_squared_weighted(query, weights)
+    }
+
+    fn to_blocks(&self) -> Vec<AlignedBlock> {
+        self.to_vec()
+    }
+
+    fn num_blocks(&self) -> usize {
+        self.len()
+    }
+}
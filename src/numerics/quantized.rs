@@ -0,0 +1,231 @@
+use std::simd::{
+    Simd,
+    num::{SimdFloat, SimdInt},
+};
+
+use crate::numerics::{AlignedBlock, SIMD_LANECOUNT, payload::Payload};
+
+type SimdF32 = Simd<f32, SIMD_LANECOUNT>;
+type SimdI8 = Simd<i8, SIMD_LANECOUNT>;
+
+/// Largest magnitude of an int8 code. `-128` is left unused so the code range is symmetric.
+const CODE_MAX: f32 = 127.0;
+
+/// Sixteen int8 codes, the quantized counterpart of one [`AlignedBlock`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuantizedBlock {
+    /// One code per lane of the original block.
+    pub codes: [i8; SIMD_LANECOUNT],
+}
+
+/// A vector stored with int8 scalar quantization and a per-vector scale and offset.
+///
+/// Each coordinate `x` is stored as the code `round((x - offset) / scale)` in
+/// `[-127, 127]`, where `offset` is the midpoint of the vector's value range and `scale`
+/// maps that range onto the codes. Coordinates are reconstructed as
+/// `offset + scale * code`, with an error of at most `scale / 2`. At one byte per
+/// coordinate plus eight bytes of parameters, this is about 4x smaller than f32 storage.
+///
+/// Distances are asymmetric: the query stays in f32 and codes are widened to f32 lanes
+/// on the fly, so only the stored side carries quantization error. Widened codes are
+/// accumulated in f32 rather than i32 lanes, since the query itself is never quantized.
+///
+/// # Examples
+/// ```
+/// use catapult::numerics::{AlignedBlock, Payload, QuantizedVector, SIMD_LANECOUNT};
+///
+/// let v = vec![AlignedBlock::new([0.5; SIMD_LANECOUNT])];
+/// let q = QuantizedVector::quantize(&v);
+/// assert!(q.l2_squared_to(&v) < 1e-6);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantizedVector {
+    blocks: Box<[QuantizedBlock]>,
+    scale: f32,
+    offset: f32,
+}
+
+impl QuantizedVector {
+    /// Quantizes a full-precision vector.
+    ///
+    /// Padding lanes take part in the value range like any other coordinate, so a
+    /// zero-padded vector still reconstructs its padding to (almost) zero.
+    ///
+    /// # Arguments
+    /// * `vector` - The vector to quantize
+    ///
+    /// # Returns
+    /// The quantized vector
+    pub fn quantize(vector: &[AlignedBlock]) -> Self {
+        let (min, max) = vector
+            .iter()
+            .flat_map(|b| b.data)
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), x| {
+                (lo.min(x), hi.max(x))
+            });
+        if vector.is_empty() {
+            return QuantizedVector {
+                blocks: Box::new([]),
+                scale: 1.0,
+                offset: 0.0,
+            };
+        }
+
+        let offset = (min + max) / 2.0;
+        let half_range = (max - min) / 2.0;
+        // constant vectors still need a non-zero scale to divide by
+        let scale = if half_range > 0.0 {
+            half_range / CODE_MAX
+        } else {
+            1.0
+        };
+
+        let blocks = vector
+            .iter()
+            .map(|b| QuantizedBlock {
+                codes: b
+                    .data
+                    .map(|x| ((x - offset) / scale).round().clamp(-CODE_MAX, CODE_MAX) as i8),
+            })
+            .collect();
+
+        QuantizedVector {
+            blocks,
+            scale,
+            offset,
+        }
+    }
+
+    /// Returns the distance between two reconstructed coordinates one code apart.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Returns the value that code 0 reconstructs to.
+    pub fn offset(&self) -> f32 {
+        self.offset
+    }
+}
+
+impl Payload for QuantizedVector {
+    #[inline]
+    fn l2_squared_to(&self, query: &[AlignedBlock]) -> f32 {
+        assert_eq!(self.blocks.len(), query.len());
+
+        let scale = SimdF32::splat(self.scale);
+        let offset = SimdF32::splat(self.offset);
+        let mut intermediate_sum_lanes = SimdF32::splat(0.0);
+
+        for (stored, q) in self.blocks.iter().zip(query) {
+            let widened = SimdI8::from_array(stored.codes).cast::<f32>();
+            let diff = SimdF32::from_array(q.data) - (offset + scale * widened);
+            intermediate_sum_lanes += diff * diff;
+        }
+
+        intermediate_sum_lanes.reduce_sum()
+    }
+
+    fn to_blocks(&self) -> Vec<AlignedBlock> {
+        self.blocks
+            .iter()
+            .map(|b| AlignedBlock::new(b.codes.map(|c| self.offset + self.scale * c as f32)))
+            .collect()
+    }
+
+    fn num_blocks(&self) -> usize {
+        self.blocks.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numerics::VectorLike;
+    use rand::prelude::*;
+
+    fn random_vector(rng: &mut StdRng, blocks: usize, range: f32) -> Vec<AlignedBlock> {
+        (0..blocks)
+            .map(|_| {
+                let mut data = [0.0; SIMD_LANECOUNT];
+                for v in data.iter_mut() {
+                    *v = rng.random_range(-range..range);
+                }
+                AlignedBlock::new(data)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reconstruction_error_is_bounded_by_half_a_step() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let v = random_vector(&mut rng, 8, 3.0);
+        let q = QuantizedVector::quantize(&v);
+
+        for (orig, rec) in v.iter().zip(q.to_blocks()) {
+            for (x, y) in orig.data.iter().zip(rec.data) {
+                assert!((x - y).abs() <= q.scale() / 2.0 + 1e-6, "x={x} y={y}");
+            }
+        }
+    }
+
+    #[test]
+    fn asymmetric_distance_matches_reconstructed_distance() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let v = random_vector(&mut rng, 4, 5.0);
+        let query = random_vector(&mut rng, 4, 5.0);
+        let q = QuantizedVector::quantize(&v);
+
+        let asymmetric = q.l2_squared_to(&query);
+        let reconstructed = q.to_blocks().l2_squared(&query);
+        assert!((asymmetric - reconstructed).abs() < 1e-3 * reconstructed);
+    }
+
+    #[test]
+    fn constant_vector_round_trips() {
+        let v = vec![AlignedBlock::new([4.25; SIMD_LANECOUNT])];
+        let q = QuantizedVector::quantize(&v);
+        assert_eq!(q.to_blocks(), v);
+        assert_eq!(q.num_blocks(), 1);
+    }
+
+    #[test]
+    fn quantized_ordering_matches_f32_for_well_separated_points() {
+        let mut rng = StdRng::seed_from_u64(3);
+        // points on a line, 10 units apart, each with a little noise
+        let points: Vec<Vec<AlignedBlock>> = (0..20)
+            .map(|i| {
+                let noise = random_vector(&mut rng, 2, 0.5);
+                noise
+                    .iter()
+                    .map(|b| AlignedBlock::new(b.data.map(|x| x + 10.0 * i as f32)))
+                    .collect()
+            })
+            .collect();
+        let quantized: Vec<QuantizedVector> = points
+            .iter()
+            .map(|p| QuantizedVector::quantize(p))
+            .collect();
+
+        for _ in 0..20 {
+            let query = random_vector(&mut rng, 2, 100.0);
+            let mut exact: Vec<usize> = (0..points.len()).collect();
+            exact.sort_by(|&a, &b| {
+                points[a]
+                    .l2_squared(&query)
+                    .total_cmp(&points[b].l2_squared(&query))
+            });
+            let mut approx: Vec<usize> = (0..points.len()).collect();
+            approx.sort_by(|&a, &b| {
+                quantized[a]
+                    .l2_squared_to(&query)
+                    .total_cmp(&quantized[b].l2_squared_to(&query))
+            });
+            assert_eq!(exact, approx);
+        }
+    }
+
+    #[test]
+    fn quantized_is_four_times_smaller() {
+        assert_eq!(size_of::<QuantizedBlock>() * 4, size_of::<AlignedBlock>(),);
+    }
+}
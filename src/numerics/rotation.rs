@@ -0,0 +1,157 @@
+use crate::numerics::{AlignedBlock, SIMD_LANECOUNT, VectorLike};
+
+/// A square linear transform applied to vectors before hashing and distance computation.
+///
+/// Meant to hold a learned orthonormal rotation (as produced by OPQ), which spreads
+/// variance evenly over dimensions so that random hyperplanes split the data better.
+/// Row `i` is stored as aligned blocks and output coordinate `i` is its dot product with
+/// the input vector. An orthonormal matrix preserves L2 distances, so search results are
+/// unchanged up to rounding; any other matrix changes the metric.
+///
+/// The same rotation must be applied to payloads and to queries, otherwise distances are
+/// computed between vectors living in different spaces.
+///
+/// # Examples
+/// ```
+/// use catapult::numerics::{AlignedBlock, Rotation, SIMD_LANECOUNT};
+///
+/// let rotation = Rotation::identity(SIMD_LANECOUNT);
+/// let v = vec![AlignedBlock::new([2.0; SIMD_LANECOUNT])];
+/// assert_eq!(rotation.apply(&v), v);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rotation {
+    rows: Vec<Vec<AlignedBlock>>,
+}
+
+impl Rotation {
+    /// Creates a rotation from its rows.
+    ///
+    /// # Arguments
+    /// * `rows` - One row per output dimension, each with as many f32 entries as rows
+    ///
+    /// # Returns
+    /// A new `Rotation`
+    ///
+    /// # Panics
+    /// Panics if the matrix is not square or its dimension is not a multiple of
+    /// `SIMD_LANECOUNT`
+    pub fn new(rows: Vec<Vec<AlignedBlock>>) -> Self {
+        let dim = rows.len();
+        assert!(
+            dim.is_multiple_of(SIMD_LANECOUNT),
+            "rotation dimension must be a multiple of SIMD_LANECOUNT"
+        );
+        assert!(
+            rows.iter().all(|row| row.len() * SIMD_LANECOUNT == dim),
+            "rotation matrix must be square"
+        );
+        Rotation { rows }
+    }
+
+    /// Creates the identity rotation of dimension `dim`.
+    ///
+    /// # Panics
+    /// Panics if `dim` is not a multiple of `SIMD_LANECOUNT`
+    pub fn identity(dim: usize) -> Self {
+        Self::permutation(&(0..dim).collect::<Vec<_>>())
+    }
+
+    /// Creates the rotation moving input coordinate `perm[i]` to output coordinate `i`.
+    ///
+    /// # Panics
+    /// Panics if `perm.len()` is not a multiple of `SIMD_LANECOUNT` or an entry is out of
+    /// range
+    pub fn permutation(perm: &[usize]) -> Self {
+        let dim = perm.len();
+        let rows = perm
+            .iter()
+            .map(|&source| {
+                assert!(source < dim, "permutation entry out of range");
+                let mut row = vec![AlignedBlock::new([0.0; SIMD_LANECOUNT]); dim / SIMD_LANECOUNT];
+                row[source / SIMD_LANECOUNT].data[source % SIMD_LANECOUNT] = 1.0;
+                row
+            })
+            .collect();
+        Self::new(rows)
+    }
+
+    /// Returns the dimension of the vectors this rotation applies to, in f32 elements.
+    pub fn dim(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Applies the rotation to `vector`.
+    ///
+    /// # Arguments
+    /// * `vector` - Vector of dimension [`dim`](Self::dim)
+    ///
+    /// # Returns
+    /// The rotated vector
+    ///
+    /// # Panics
+    /// Panics if the dimension of `vector` does not match
+    pub fn apply(&self, vector: &[AlignedBlock]) -> Vec<AlignedBlock> {
+        self.rows
+            .chunks(SIMD_LANECOUNT)
+            .map(|rows| {
+                let mut block = [0.0; SIMD_LANECOUNT];
+                for (out, row) in block.iter_mut().zip(rows) {
+                    *out = row.dot(vector);
+                }
+                AlignedBlock::new(block)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ramp(dim: usize) -> Vec<AlignedBlock> {
+        (0..dim / SIMD_LANECOUNT)
+            .map(|b| AlignedBlock::new(std::array::from_fn(|l| (b * SIMD_LANECOUNT + l) as f32)))
+            .collect()
+    }
+
+    #[test]
+    fn test_identity_is_a_no_op() {
+        let v = ramp(48);
+        assert_eq!(Rotation::identity(48).apply(&v), v);
+    }
+
+    #[test]
+    fn test_permutation_relabels_dimensions() {
+        let perm: Vec<usize> = (0..32).rev().collect();
+        let rotated = Rotation::permutation(&perm).apply(&ramp(32));
+
+        let flat: Vec<f32> = rotated.iter().flat_map(|b| b.data).collect();
+        let expected: Vec<f32> = (0..32).rev().map(|x| x as f32).collect();
+        assert_eq!(flat, expected);
+    }
+
+    #[test]
+    fn test_permutation_preserves_distances() {
+        let perm: Vec<usize> = (0..32).map(|i| (i * 7) % 32).collect();
+        let rotation = Rotation::permutation(&perm);
+        let a = ramp(32);
+        let b: Vec<AlignedBlock> = a
+            .iter()
+            .map(|blk| AlignedBlock::new(blk.data.map(|x| x * 0.5 - 3.0)))
+            .collect();
+
+        let before = a.l2_squared(&b);
+        let after = rotation.apply(&a).l2_squared(&rotation.apply(&b));
+        assert!((before - after).abs() <= 1e-4 * before);
+    }
+
+    #[test]
+    #[should_panic(expected = "rotation matrix must be square")]
+    fn test_non_square_matrix_is_rejected() {
+        Rotation::new(vec![
+            vec![AlignedBlock::new([0.0; SIMD_LANECOUNT]); 2];
+            SIMD_LANECOUNT
+        ]);
+    }
+}
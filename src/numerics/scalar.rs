@@ -0,0 +1,160 @@
+//! Portable scalar distance kernels.
+//!
+//! These mirror the SIMD kernels of [`VectorLike`](super::VectorLike) lane by lane: one
+//! accumulator per lane, updated block after block, then summed in lane order. Since
+//! Rust never fuses or reorders float operations on its own, the results are bit-identical
+//! to the SIMD path for the same inputs, not just equal within a tolerance.
+//!
+//! They are used in place of the SIMD kernels when the crate is built with the
+//! `scalar-fallback` feature, for CPUs on which wide vectors are emulated or slow. They
+//! are always available so that the two paths can be compared. Note that the crate itself
+//! still requires a nightly toolchain, since other modules use `portable_simd`.
+
+use crate::numerics::{AlignedBlock, SIMD_LANECOUNT};
+
+/// Sums per-lane accumulators in lane order, matching `Simd::reduce_sum`.
+#[inline]
+fn reduce_lanes(lanes: [f32; SIMD_LANECOUNT]) -> f32 {
+    lanes.iter().fold(-0.0, |acc, &x| acc + x)
+}
+
+/// Computes the squared L2 distance between two vectors without SIMD types.
+///
+/// # Panics
+/// Panics if the two vectors have different lengths
+#[inline]
+pub fn l2_squared_scalar(a: &[AlignedBlock], b: &[AlignedBlock]) -> f32 {
+    assert_eq!(a.len(), b.len());
+
+    let mut lanes = [0.0; SIMD_LANECOUNT];
+    for (block_a, block_b) in a.iter().zip(b) {
+        for (lane, (x, y)) in lanes.iter_mut().zip(block_a.data.iter().zip(&block_b.data)) {
+            let diff = x - y;
+            *lane += diff * diff;
+        }
+    }
+    reduce_lanes(lanes)
+}
+
+/// Computes the weighted squared L2 distance between two vectors without SIMD types.
+///
+/// # Panics
+/// Panics if the three vectors do not all have the same length
+#[inline]
+pub fn l2_squared_weighted_scalar(
+    a: &[AlignedBlock],
+    b: &[AlignedBlock],
+    weights: &[AlignedBlock],
+) -> f32 {
+    assert_eq!(a.len(), b.len());
+    assert_eq!(a.len(), weights.len());
+
+    let mut lanes = [0.0; SIMD_LANECOUNT];
+    for ((block_a, block_b), block_w) in a.iter().zip(b).zip(weights) {
+        for (lane, ((x, y), w)) in lanes
+            .iter_mut()
+            .zip(block_a.data.iter().zip(&block_b.data).zip(&block_w.data))
+        {
+            let diff = x - y;
+            *lane += w * (diff * diff);
+        }
+    }
+    reduce_lanes(lanes)
+}
+
+/// Computes the dot product of two vectors without SIMD types.
+///
+/// # Panics
+/// Panics if the two vectors have different lengths
+#[inline]
+pub fn dot_scalar(a: &[AlignedBlock], b: &[AlignedBlock]) -> f32 {
+    assert_eq!(a.len(), b.len());
+
+    let mut lanes = [0.0; SIMD_LANECOUNT];
+    for (block_a, block_b) in a.iter().zip(b) {
+        for (lane, (x, y)) in lanes.iter_mut().zip(block_a.data.iter().zip(&block_b.data)) {
+            *lane += x * y;
+        }
+    }
+    reduce_lanes(lanes)
+}
+
+/// Computes the dot product of a vector with every plane of an interleaved set, without
+/// SIMD types.
+///
+/// # Panics
+/// Panics if `planes` does not hold `num_planes` planes as long as `vector`
+pub fn project_interleaved_scalar(
+    vector: &[AlignedBlock],
+    planes: &[AlignedBlock],
+    num_planes: usize,
+) -> Vec<f32> {
+    assert_eq!(planes.len(), vector.len() * num_planes);
+
+    let mut lanes = vec![[0.0; SIMD_LANECOUNT]; num_planes];
+    for (block, plane_blocks) in vector.iter().zip(planes.chunks_exact(num_planes.max(1))) {
+        for (plane_lanes, plane_block) in lanes.iter_mut().zip(plane_blocks) {
+            for (lane, (x, y)) in plane_lanes
+                .iter_mut()
+                .zip(plane_block.data.iter().zip(&block.data))
+            {
+                *lane += x * y;
+            }
+        }
+    }
+    lanes.into_iter().map(reduce_lanes).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numerics::f32slice::{dot_simd, l2_squared_simd, l2_squared_weighted_simd};
+    use rand::prelude::*;
+
+    fn random_vector(rng: &mut StdRng, blocks: usize) -> Vec<AlignedBlock> {
+        (0..blocks)
+            .map(|_| {
+                let mut data = [0.0; SIMD_LANECOUNT];
+                for v in data.iter_mut() {
+                    *v = rng.random_range(-100.0..100.0);
+                }
+                AlignedBlock::new(data)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn scalar_matches_simd_bit_for_bit_on_random_vectors() {
+        let mut rng = StdRng::seed_from_u64(13);
+        for blocks in [1, 2, 3, 8, 48] {
+            for _ in 0..50 {
+                let a = random_vector(&mut rng, blocks);
+                let b = random_vector(&mut rng, blocks);
+
+                assert_eq!(
+                    l2_squared_scalar(&a, &b).to_bits(),
+                    l2_squared_simd(&a, &b).to_bits()
+                );
+                assert_eq!(dot_scalar(&a, &b).to_bits(), dot_simd(&a, &b).to_bits());
+                let w = random_vector(&mut rng, blocks);
+                assert_eq!(
+                    l2_squared_weighted_scalar(&a, &b, &w).to_bits(),
+                    l2_squared_weighted_simd(&a, &b, &w).to_bits()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn empty_vectors() {
+        assert_eq!(l2_squared_scalar(&[], &[]), 0.0);
+        assert_eq!(dot_scalar(&[], &[]), 0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn mismatched_lengths_panic() {
+        let a = [AlignedBlock::new([1.0; SIMD_LANECOUNT])];
+        l2_squared_scalar(&a, &[]);
+    }
+}
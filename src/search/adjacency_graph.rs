@@ -1,19 +1,229 @@
-use hashbrown::HashSet;
-
 use crate::{
-    numerics::{AlignedBlock, VectorLike},
+    numerics::{
+        AffineTransform, AlignedBlock, BitVector, Payload, QuantizedVector, Rotation,
+        SIMD_LANECOUNT, VectorLike,
+    },
     search::{
-        NodeId, SearchStrategy,
-        hash_start::{EngineStarter, StartingPoints},
-        node::Node,
+        NodeId, RankedResults, ScoredResults, SearchError, SearchOutcome, SearchParams,
+        SearchStrategy, Termination, UpperLevels, VerifiedResults,
+        hash_start::{EngineStarter, EngineStarterParams, StartingPoints},
+        node::{Node, compute_medoid, farthest_point_sample},
     },
     sets::{
-        candidates::{CandidateEntry, SmallestKCandidates},
+        candidates::{CandidateEntry, Distance, SmallestKCandidates},
         catapults::CatapultEvictionPolicy,
+        fixed::{CsrNeighbors, FlatFixedSet, NeighborSlice},
+        visited::{CompressedBitset, VisitorSet},
+    },
+    statistics::{Stats, query_recall_at_k, recall_at_k},
+};
+
+use std::{
+    borrow::Cow,
+    cmp::Reverse,
+    collections::BinaryHeap,
+    hash::{DefaultHasher, Hash, Hasher},
+    io,
+    ops::Range,
+    path::Path,
+    sync::{
+        OnceLock,
+        atomic::{AtomicBool, Ordering},
     },
-    statistics::Stats,
+    time::{Duration, Instant},
 };
 
+/// Number of expansions between two reads of the clock by searches with a deadline.
+const DEADLINE_CHECK_INTERVAL: usize = 8;
+
+/// Relative slack subtracted from triangle-inequality lower bounds, per unit of the two
+/// distances they are derived from, so that float rounding never prunes a node that could
+/// have entered the beam.
+const PRUNING_TOLERANCE: f32 = 1e-4;
+
+/// The distance from every node to one pivot node, used to bound query distances from
+/// below with the triangle inequality.
+struct PivotDistances {
+    /// The node every distance is measured to
+    pivot: NodeId,
+    /// L2 distance (not squared) from node `i` to the pivot, weighted like searches
+    distances: Vec<f32>,
+}
+
+/// Reusable buffers for a single beam search.
+///
+/// Allocating a fresh candidate beam and visited set per query shows up in profiles at
+/// high QPS, so callers that run many searches in a row keep one of these around and
+/// reset it between queries instead.
+///
+/// The visited set is generic so very large graphs can swap in a sparser representation.
+struct SearchScratch<Visited: VisitorSet = CompressedBitset> {
+    candidates: SmallestKCandidates,
+    visited: Visited,
+    /// Neighbors of the expanded node that are not candidates yet.
+    fresh: Vec<NodeId>,
+}
+
+impl<Visited: VisitorSet + Default> SearchScratch<Visited> {
+    /// Creates empty buffers for searches using the given beam width.
+    fn new(beam_width: usize) -> Self {
+        Self {
+            candidates: SmallestKCandidates::new(beam_width),
+            visited: Visited::default(),
+            fresh: Vec::new(),
+        }
+    }
+}
+
+impl<Visited: VisitorSet> SearchScratch<Visited> {
+    /// Empties both buffers while keeping their allocations.
+    fn reset(&mut self) {
+        self.candidates.clear();
+        self.visited.clear();
+    }
+}
+
+/// Search buffers owned by one thread and reused by all of its searches.
+///
+/// [`beam_search`](AdjacencyGraph::beam_search) allocates a candidate beam and a visited
+/// set for every query. With many threads searching at once, those allocations contend in
+/// the allocator; a worker that keeps one context for its whole lifetime and searches with
+/// [`beam_search_with_ctx`](AdjacencyGraph::beam_search_with_ctx) allocates them once.
+/// The buffers are sized for a beam width on first use and reallocated only if a search
+/// asks for another one.
+///
+/// # Type Parameters
+/// * `Visited` - The visited set implementation, see
+///   [`beam_search_batch_with`](AdjacencyGraph::beam_search_batch_with)
+pub struct SearchContext<Visited: VisitorSet = CompressedBitset> {
+    scratch: Option<(usize, SearchScratch<Visited>)>,
+}
+
+impl<Visited: VisitorSet + Default> SearchContext<Visited> {
+    /// Creates a context whose buffers are allocated by its first search.
+    pub fn new() -> Self {
+        SearchContext { scratch: None }
+    }
+
+    /// Returns the buffers for `beam_width`, allocating them if needed.
+    fn scratch_for(&mut self, beam_width: usize) -> &mut SearchScratch<Visited> {
+        if self
+            .scratch
+            .as_ref()
+            .is_none_or(|(width, _)| *width != beam_width)
+        {
+            self.scratch = Some((beam_width, SearchScratch::new(beam_width)));
+        }
+        &mut self.scratch.as_mut().expect("allocated above").1
+    }
+}
+
+impl<Visited: VisitorSet + Default> Default for SearchContext<Visited> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A beam search that advances one expansion per item, returned by
+/// [`search_iter`](AdjacencyGraph::search_iter).
+///
+/// Each call to [`next`](Iterator::next) expands the closest candidate of the beam that
+/// has not been expanded yet, adds its neighbors to the beam and yields it. The iterator
+/// ends once every candidate left in the beam has been expanded, which is when
+/// [`beam_search`](AdjacencyGraph::beam_search) would have returned.
+///
+/// Items come in roughly increasing distance order, not strictly sorted: a node found
+/// late in the search may be closer than nodes yielded before it. The search also starts
+/// from a far-away entry point, so the first items are usually the worst ones.
+pub struct SearchIter<'a, EvictPolicy, P>
+where
+    EvictPolicy: CatapultEvictionPolicy,
+    P: Payload,
+{
+    graph: &'a AdjacencyGraph<EvictPolicy, P>,
+    /// Query vector, already prepared
+    query: Vec<AlignedBlock>,
+    stats: &'a mut Stats,
+    scratch: SearchScratch,
+}
+
+impl<EvictPolicy, P> Iterator for SearchIter<'_, EvictPolicy, P>
+where
+    EvictPolicy: CatapultEvictionPolicy,
+    P: Payload,
+{
+    type Item = CandidateEntry;
+
+    fn next(&mut self) -> Option<CandidateEntry> {
+        let SearchScratch {
+            candidates,
+            visited,
+            fresh,
+        } = &mut self.scratch;
+        loop {
+            let best = candidates
+                .iter()
+                .filter(|&elem| !visited.get(elem.index.internal))
+                .min()
+                .copied()?;
+
+            let neighbors = self.graph.neighbors_of(best.index.internal);
+            fresh.clear();
+            fresh.extend(
+                neighbors
+                    .iter()
+                    .filter(|&n| !self.graph.in_beam(candidates, n)),
+            );
+            self.stats.bump_skipped_dists(neighbors.len() - fresh.len());
+            let neighbor_distances = self.graph.distances_from_indices(
+                fresh.iter().copied(),
+                &self.query,
+                best.has_catapult_ancestor,
+                self.stats,
+                &SearchOptions::default(),
+            );
+            self.graph.insert_into_beam(candidates, &neighbor_distances);
+
+            visited.set(best.index.internal);
+            self.stats.bump_nodes_visited();
+            // deleted nodes are still traversed, but never yielded
+            if !self.graph.is_deleted(best.index) {
+                return Some(best);
+            }
+        }
+    }
+}
+
+/// Per-search knobs threaded from the public entry points down to the beam search.
+#[derive(Clone, Copy, Default)]
+struct SearchOptions<'a> {
+    /// Whether the best result is written back as a catapult (and useful catapults touched).
+    learn_catapults: bool,
+    /// Additive per-node penalty applied on top of the squared L2 distance.
+    penalty: Option<&'a dyn Fn(usize) -> f32>,
+    /// Maximum number of nodes expanded before the search gives up.
+    max_iterations: Option<usize>,
+    /// Maximum number of distances computed before the search stops expanding nodes.
+    max_distance_computations: Option<usize>,
+    /// Instant after which the search stops expanding nodes.
+    deadline: Option<Instant>,
+    /// Whether the results may be returned in arbitrary order, skipping the final sort.
+    unsorted: bool,
+    /// Size of a result pool recording every scored node, which the results are taken
+    /// from instead of the beam.
+    ef_results: Option<usize>,
+}
+
+impl SearchOptions<'_> {
+    /// Options of a regular search, which learns catapults.
+    fn learning() -> Self {
+        Self {
+            learn_catapults: true,
+            ..Self::default()
+        }
+    }
+}
+
 /// An in-memory proximity graph for approximate nearest neighbor (ANN) search.
 ///
 /// This structure stores a graph where each node contains a vector embedding (payload)
@@ -22,11 +232,14 @@ use crate::{
 ///
 /// # Type Parameters
 /// * `EvictPolicy` - The eviction strategy for catapult storage (e.g., `FifoSet<30>`)
-/// * `Algo` - The graph search algorithm type (e.g., `FlatSearch` for single-layer graphs)
+/// * `P` - How node vectors are stored: full-precision blocks by default, or a compressed
+///   [`Payload`] such as [`QuantizedVector`]
 ///
 /// # Invariants
 /// - `adjacency[i]` represents node `i` in the graph
 /// - Each `Node.neighbors` entry is a valid index into `adjacency`
+/// - Once [`use_csr_layout`](Self::use_csr_layout) has been called, neighbors are read from the
+///   packed CSR buffer and the per-node neighbor sets are left empty
 /// - All node payloads have the same dimensionality
 ///
 /// # Core Algorithm
@@ -36,18 +249,48 @@ use crate::{
 /// 3. Repeatedly expands the best unvisited candidate, adding its neighbors
 /// 4. Stops when all candidates in the beam have been visited
 /// 5. Caches the best result as a catapult for future similar queries
-pub struct AdjacencyGraph<EvictPolicy>
+pub struct AdjacencyGraph<EvictPolicy, P = Box<[AlignedBlock]>>
 where
     EvictPolicy: CatapultEvictionPolicy,
+    P: Payload,
 {
-    adjacency: Vec<Node>,
+    adjacency: Vec<Node<P>>,
     starter: EngineStarter<EvictPolicy>,
     strategy: SearchStrategy,
+    /// Packed neighbor lists, replacing the per-node sets when present.
+    csr: Option<CsrNeighbors>,
+    /// Lazily computed by [`mean_edge_length`](Self::mean_edge_length).
+    mean_edge_length: OnceLock<f32>,
+    /// Neighbor part of [`check_bounds`](Self::check_bounds), computed lazily.
+    bounds_check: OnceLock<Result<(), SearchError>>,
+    /// Applied to every query before hashing and distance computation, ahead of the
+    /// rotation. Payloads are stored already transformed.
+    transform: Option<AffineTransform>,
+    /// Applied to every query before hashing and distance computation. Payloads are
+    /// stored already rotated.
+    rotation: Option<Rotation>,
+    /// Payload hash of every node, present when the beam collapses duplicate payloads.
+    payload_hashes: Option<Box<[u64]>>,
+    /// Nodes deleted since the last compaction, still traversed but never returned.
+    tombstones: CompressedBitset,
+    /// Number of nodes set in `tombstones`.
+    num_deleted: usize,
+    /// Levels above the adjacency of a hierarchical graph, descended to pick the node
+    /// each search starts from.
+    upper_levels: Option<UpperLevels>,
+    /// Per-dimension weights of the distances scored by beam searches, zero-padded.
+    weights: Option<Box<[AlignedBlock]>>,
+    /// Distances to a pivot node, present when searches prune with the triangle inequality.
+    pivot_distances: Option<PivotDistances>,
+    /// Whether learning searches write their result back, see
+    /// [`set_catapult_writes`](Self::set_catapult_writes).
+    catapult_writes: AtomicBool,
 }
 
-impl<EvictPolicy> AdjacencyGraph<EvictPolicy>
+impl<EvictPolicy, P> AdjacencyGraph<EvictPolicy, P>
 where
     EvictPolicy: CatapultEvictionPolicy,
+    P: Payload,
 {
     /// Creates a new flat (single-layer) adjacency graph for ANN search.
     ///
@@ -58,424 +301,4688 @@ where
     ///
     /// # Returns
     /// A new `AdjacencyGraph` instance ready for beam search
+    ///
+    /// # Panics
+    /// Panics if the payloads do not span exactly the blocks needed by the engine's
+    /// dimension, which is the logical dimension of the graph
     pub fn new_flat(
-        adj: Vec<Node>,
+        adj: Vec<Node<P>>,
         engine: EngineStarter<EvictPolicy>,
         strategy: SearchStrategy,
     ) -> Self {
+        assert!(
+            adj.first().is_none_or(
+                |node| node.payload.num_blocks() == engine.plane_dim().div_ceil(SIMD_LANECOUNT)
+            ),
+            "payload dimension does not match the engine's dimension"
+        );
         Self {
             adjacency: adj,
             starter: engine,
             strategy,
+            csr: None,
+            mean_edge_length: OnceLock::new(),
+            bounds_check: OnceLock::new(),
+            transform: None,
+            rotation: None,
+            payload_hashes: None,
+            tombstones: CompressedBitset::new(),
+            num_deleted: 0,
+            upper_levels: None,
+            weights: None,
+            pivot_distances: None,
+            catapult_writes: AtomicBool::new(true),
         }
     }
-}
 
-impl<EvictPolicy> AdjacencyGraph<EvictPolicy>
-where
-    EvictPolicy: CatapultEvictionPolicy,
-{
-    /// Computes distances from the query to a set of node indices.
+    /// Repacks all neighbor lists into a single compressed-sparse-row buffer.
     ///
-    /// Creates candidate entries for each provided index by computing the squared L2
-    /// distance from the query to that node's payload.
+    /// Neighbors of each node then live in one contiguous slice of a shared allocation
+    /// instead of a separate heap allocation per node, which improves cache behavior during
+    /// traversal. The per-node neighbor sets are freed. Search results are unchanged. Calling
+    /// this more than once is a no-op.
+    pub fn use_csr_layout(&mut self) {
+        if self.csr.is_some() {
+            return;
+        }
+        let csr = CsrNeighbors::from_lists(self.adjacency.iter().map(|n| n.neighbors.as_slice()));
+        for node in &mut self.adjacency {
+            node.neighbors = FlatFixedSet::new(Vec::new());
+        }
+        self.csr = Some(csr);
+    }
+
+    /// Moves neighbor lists from the CSR buffer back to per-node sets, so they can grow.
     ///
-    /// # Arguments
-    /// * `indices` - Node indices to compute distances for
-    /// * `query` - Query vector as aligned blocks
-    /// * `catapult_marker` - Whether to mark these candidates as catapult-derived
-    /// * `stats` - Statistics tracker to update with distance computations
+    /// `num_nodes` sizes the index width of the sets, and must cover any node about to
+    /// be added. Does nothing without the CSR layout.
+    fn unpack_csr_layout(&mut self, num_nodes: usize) {
+        if let Some(csr) = self.csr.take() {
+            for (i, node) in self.adjacency.iter_mut().enumerate() {
+                node.neighbors = FlatFixedSet::compact(
+                    csr.neighbors(i).iter().map(|n| n.internal).collect(),
+                    num_nodes,
+                );
+            }
+        }
+    }
+
+    /// Returns whether neighbor lists are stored in the packed CSR layout.
+    pub fn has_csr_layout(&self) -> bool {
+        self.csr.is_some()
+    }
+
+    /// Makes searches treat nodes with identical payloads as a single beam entry.
     ///
-    /// # Returns
-    /// A vector of candidate entries with computed distances
-    fn distances_from_indices(
-        &self,
-        indices: &[NodeId],
-        query: &[AlignedBlock],
-        catapult_marker: bool,
-        stats: &mut Stats,
-    ) -> Vec<CandidateEntry> {
-        stats.bump_computed_dists(indices.len());
+    /// Datasets often contain exact duplicate vectors, and each copy otherwise takes its
+    /// own slot in the beam, crowding out distinct neighbors. This hashes every payload
+    /// once and stores the hashes alongside the graph; afterwards, a candidate is kept out
+    /// of the beam when a member at the same distance has the same payload hash. Only one
+    /// node of each group of duplicates can then be returned or expanded per search,
+    /// so duplicates reachable only through another copy's edges are skipped as well.
+    /// Calling this more than once is a no-op.
+    pub fn collapse_duplicate_payloads(&mut self) {
+        if self.payload_hashes.is_none() {
+            self.payload_hashes = Some(
+                self.adjacency
+                    .iter()
+                    .map(|n| hash_payload(&n.payload))
+                    .collect(),
+            );
+        }
+    }
 
-        indices
-            .iter()
-            .map(|&index| {
-                let starting_point = &self.adjacency[index.internal];
-                let starting_score = starting_point.payload.l2_squared(query);
+    /// Returns whether the beam collapses nodes with identical payloads.
+    pub fn collapses_duplicate_payloads(&self) -> bool {
+        self.payload_hashes.is_some()
+    }
 
-                CandidateEntry {
-                    distance: starting_score.into(),
-                    index,
-                    has_catapult_ancestor: catapult_marker,
-                }
-            })
-            .collect()
+    /// Makes searches skip the distance computations the triangle inequality rules out.
+    ///
+    /// The distance from every node to a pivot, the current starting node, is computed
+    /// once and stored alongside the graph, and each search measures its query's distance
+    /// `d(q, p)` to the pivot. Since `d(q, n) >= |d(q, p) - d(n, p)|`, a neighbor whose
+    /// bound already exceeds the worst distance of a full beam could never enter it, so
+    /// its distance is not computed and [`Stats::get_pruned_dists`] counts it instead.
+    ///
+    /// Pruning only drops nodes that are provably worse than every beam member, with some
+    /// slack for float rounding, so results are exactly those of an unpruned search; only
+    /// the work changes. It pays off for moderate dimensions, where distances to a single
+    /// pivot still spread out; in high dimensions the bounds are rarely tight enough to
+    /// prune. Searches with a per-node penalty do not prune, since penalties may lower
+    /// distances. The pivot distances follow insertions, compactions and changes of
+    /// payloads or weights. Calling this more than once is a no-op.
+    pub fn use_triangle_pruning(&mut self) {
+        if self.pivot_distances.is_none() {
+            self.pivot_distances = Some(self.pivot_distances_to(self.starter.starting_node()));
+        }
     }
 
-    /// Performs a best-first beam search starting from the given candidates.
+    /// Returns whether searches prune distance computations with the triangle inequality.
+    pub fn uses_triangle_pruning(&self) -> bool {
+        self.pivot_distances.is_some()
+    }
+
+    /// Measures the distance from every node to `pivot`, as searches would.
+    fn pivot_distances_to(&self, pivot: NodeId) -> PivotDistances {
+        let pivot_payload = self.adjacency[pivot.internal].payload.to_blocks();
+        PivotDistances {
+            pivot,
+            distances: self
+                .adjacency
+                .iter()
+                .map(|node| {
+                    self.distance_to_blocks(&node.payload, &pivot_payload)
+                        .sqrt()
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the squared distance from `payload` to `blocks`, weighted like searches.
+    fn distance_to_blocks(&self, payload: &P, blocks: &[AlignedBlock]) -> f32 {
+        match &self.weights {
+            Some(weights) => payload.l2_squared_weighted_to(blocks, weights),
+            None => payload.l2_squared_to(blocks),
+        }
+    }
+
+    /// Measures pivot distances to `pivot` if given, e.g. to refresh them after the
+    /// payloads changed.
+    fn with_pivot_of(mut self, pivot: Option<NodeId>) -> Self {
+        self.pivot_distances = pivot.map(|pivot| self.pivot_distances_to(pivot));
+        self
+    }
+
+    /// Appends a new node to the graph and links it to the given neighbors.
     ///
-    /// This is the core search algorithm that maintains a beam of at most `beam_width`
-    /// candidates and iteratively expands the closest unvisited candidate until exhaustion.
-    /// The search explores the graph by following neighbor edges, maintaining the k-best
-    /// nodes encountered.
+    /// The new node gets the next free index and an outgoing edge to every node of
+    /// `neighbors`. With `back_link_degree` set to `Some(max_degree)`, each of those
+    /// neighbors also gets an edge back to the new node, unless it already has
+    /// `max_degree` neighbors; without back-links, the new node is only reachable through
+    /// edges added later or as a catapult.
     ///
-    /// # Algorithm Steps
-    /// 1. Initialize candidate beam with starting points
-    /// 2. Find the best unvisited candidate in the beam
-    /// 3. Expand it by computing distances to all its neighbors
-    /// 4. Add neighbors to the beam (with automatic eviction if over capacity)
-    /// 5. Mark the expanded node as visited
-    /// 6. Repeat from step 2 until no unvisited candidates remain
-    /// 7. Return the top-k candidates by distance
+    /// If the graph uses the CSR layout, neighbor lists are first moved back to per-node
+    /// sets, since the packed buffer cannot grow. The cached
+    /// [`mean_edge_length`](Self::mean_edge_length) is reset. Catapults are kept, and the
+    /// Z-order indexes of the [`SearchStrategy::LshApg`] strategy are not updated, so the
+    /// new node is never proposed as an LSH-APG starting point.
+    ///
+    /// # Thread Safety
+    /// Insertion takes `&mut self`, so it cannot run concurrently with searches on the
+    /// same graph. To interleave insertions with concurrent searches, wrap the graph in a
+    /// `RwLock`: searches take the read lock, insertions the write lock. A
+    /// [`ConcurrentGraph`](crate::search::ConcurrentGraph) avoids blocking every search
+    /// on each insertion, with per-node locks on the neighbor lists.
     ///
     /// # Arguments
-    /// * `query` - Target query vector as aligned blocks
-    /// * `starting_candidates` - Initial candidates to seed the search
-    /// * `k` - Number of nearest neighbors to return
-    /// * `beam_width` - Maximum number of candidates to maintain (must be ≥ k)
-    /// * `layer` - layer context for neighbor retrieval (unit type for flat graphs)
-    /// * `stats` - Statistics tracker for performance monitoring
+    /// * `payload` - Vector of the new node
+    /// * `neighbors` - Indices of existing nodes the new node links to
+    /// * `back_link_degree` - Degree up to which neighbors link back to the new node, or
+    ///   `None` to leave existing nodes untouched
     ///
     /// # Returns
-    /// A vector of the k nearest candidate entries, sorted by distance
+    /// The id of the inserted node
     ///
     /// # Panics
-    /// * Panics if `beam_width < k`
-    /// * Panics if starting_candidates is empty
-    /// * Panics if neighbor indices are out of bounds (graph invariant violation)
-    #[tracing::instrument(level = "trace", skip_all, fields(k, beam_width))]
-    fn beam_search_raw(
-        &self,
-        query: &[AlignedBlock],
-        starting_candidates: &[CandidateEntry],
-        k: usize,
-        beam_width: usize,
-        stats: &mut Stats,
-    ) -> Vec<CandidateEntry> {
-        assert!(beam_width >= k);
-        stats.bump_beam_calls();
+    /// * Panics if a neighbor index does not refer to an existing node
+    /// * Panics if the payload dimension differs from the graph's
+    pub fn insert(
+        &mut self,
+        payload: P,
+        neighbors: Vec<usize>,
+        back_link_degree: Option<usize>,
+    ) -> NodeId {
+        let id = NodeId {
+            internal: self.adjacency.len(),
+        };
+        assert!(
+            neighbors.iter().all(|&n| n < id.internal),
+            "neighbor index out of bounds"
+        );
+        if let Some(first) = self.adjacency.first() {
+            assert_eq!(
+                payload.num_blocks(),
+                first.payload.num_blocks(),
+                "payload dimension differs from the graph's"
+            );
+        }
 
-        let mut candidates: SmallestKCandidates = SmallestKCandidates::new(beam_width);
-        let mut visited = HashSet::new();
+        self.unpack_csr_layout(id.internal + 1);
+        self.mean_edge_length = OnceLock::new();
+        self.bounds_check = OnceLock::new();
 
-        candidates.insert_batch(starting_candidates);
+        if let Some(max_degree) = back_link_degree {
+            for &n in &neighbors {
+                let set = &mut self.adjacency[n].neighbors;
+                if set.len() < max_degree && !set.as_slice().contains(&id) {
+                    set.push(id);
+                }
+            }
+        }
+        if let Some(hashes) = self.payload_hashes.take() {
+            let mut hashes = hashes.into_vec();
+            hashes.push(hash_payload(&payload));
+            self.payload_hashes = Some(hashes.into_boxed_slice());
+        }
+        if let Some(mut pivot) = self.pivot_distances.take() {
+            let pivot_payload = self.adjacency[pivot.pivot.internal].payload.to_blocks();
+            pivot
+                .distances
+                .push(self.distance_to_blocks(&payload, &pivot_payload).sqrt());
+            self.pivot_distances = Some(pivot);
+        }
+        self.adjacency.push(Node {
+            payload,
+            neighbors: FlatFixedSet::compact(neighbors, id.internal + 1),
+        });
+        id
+    }
 
-        // among the suggested entry points, one of them is 'the best'. Let's identify it.
-        let initial_best_node = candidates.iter().min().copied().expect(
-            "Corrupted starting point entries. Provide a non-empty starting_indices lists.",
+    /// Marks a node as deleted, so that searches stop returning it.
+    ///
+    /// Deletion is logical: the node keeps its payload, index and edges until the next
+    /// [`compaction`](Self::compaction). Searches still score and expand deleted nodes,
+    /// which is intentional: their edges often are the only short path between regions of
+    /// the graph, and cutting them at once would strand the nodes behind them. Deleted
+    /// nodes are only dropped from the final results, which may therefore hold fewer than
+    /// `k` entries when the beam is barely wider than `k` and holds deleted nodes. Exact
+    /// searches skip deleted nodes as well.
+    ///
+    /// # Arguments
+    /// * `node` - The node to delete
+    ///
+    /// # Returns
+    /// `true` if the node was live, `false` if it had already been deleted
+    ///
+    /// # Panics
+    /// Panics if `node` is not a node of the graph, or if it is the starting node, which
+    /// every search needs as an entry point
+    pub fn delete(&mut self, node: NodeId) -> bool {
+        assert!(node.internal < self.len(), "node index out of bounds");
+        assert_ne!(
+            node,
+            self.starter.starting_node(),
+            "the starting node cannot be deleted"
         );
+        if self.tombstones.get(node.internal) {
+            return false;
+        }
+        self.tombstones.set(node.internal);
+        self.num_deleted += 1;
+        true
+    }
 
-        let mut best_candidate: Option<CandidateEntry> = Some(initial_best_node);
+    /// Returns whether `node` was deleted since the last compaction.
+    pub fn is_deleted(&self, node: NodeId) -> bool {
+        self.num_deleted > 0 && self.tombstones.get(node.internal)
+    }
 
-        // while we have some node on which to expand (at first, the best LSH entry point),
-        // we keep expanding it (i.e. looking at its neighbors for better guesses)
-        while let Some(best_candidate_node) = best_candidate {
-            let best_candidate_neighs =
-                &self.adjacency[best_candidate_node.index.internal].neighbors;
-            // identify the neighbors of our current best guess.
-            // All of these guys become candidates for expansion. if we have too many candidates
-            // (beam width parameter), the `candidates` data structure takes care of removing the
-            // worst ones (and the duplicates).
-            let neighbors = best_candidate_neighs.to_slice();
+    /// Returns the number of nodes deleted since the last compaction.
+    pub fn num_deleted(&self) -> usize {
+        self.num_deleted
+    }
 
-            // Record each (src → dst) edge as considered before computing distances.
-            if stats.has_adv_tracking() {
-                let src = best_candidate_node.index.internal;
-                for &dst in neighbors.iter() {
-                    stats.record_considered_edge(src, dst.internal);
-                }
+    /// Rebuilds the graph without its deleted nodes, renumbering the remaining ones.
+    ///
+    /// Live nodes keep their relative order and are numbered from 0, edges to deleted
+    /// nodes are dropped, and the starting node is renumbered. Paths that went through a
+    /// deleted node are lost, so nodes reachable only that way become unreachable; compact
+    /// once deletions no longer leave such gaps, or relink their neighbors first. Stored
+    /// catapults refer to the old numbering and are cleared. The CSR layout and duplicate
+    /// collapsing are kept; as with [`insert`](Self::insert), the Z-order indexes of the
+    /// [`SearchStrategy::LshApg`] strategy are not updated. Upper levels are renumbered
+    /// like the adjacency, and dropped if their entry point was deleted.
+    ///
+    /// # Returns
+    /// The new id of every old node, indexed by old id, or `None` for deleted nodes
+    pub fn compaction(&mut self) -> Vec<Option<NodeId>> {
+        let mut remap = Vec::with_capacity(self.len());
+        let mut live = 0;
+        for i in 0..self.len() {
+            if self.is_deleted(NodeId { internal: i }) {
+                remap.push(None);
+            } else {
+                remap.push(Some(NodeId { internal: live }));
+                live += 1;
             }
+        }
+        if self.num_deleted == 0 {
+            return remap;
+        }
 
-            let neighbor_distances = self.distances_from_indices(
-                &neighbors,
-                query,
-                best_candidate_node.has_catapult_ancestor,
-                stats,
+        let neighbor_lists: Vec<Vec<usize>> = (0..self.len())
+            .filter(|&i| remap[i].is_some())
+            .map(|i| {
+                self.neighbors_of(i)
+                    .iter()
+                    .filter_map(|n| remap[n.internal])
+                    .map(|n| n.internal)
+                    .collect()
+            })
+            .collect();
+        let had_csr = self.csr.take().is_some();
+        let nodes = std::mem::take(&mut self.adjacency);
+        self.adjacency = nodes
+            .into_iter()
+            .zip(&remap)
+            .filter(|(_, new_id)| new_id.is_some())
+            .zip(neighbor_lists)
+            .map(|((node, _), neighbors)| Node {
+                payload: node.payload,
+                neighbors: FlatFixedSet::compact(neighbors, live),
+            })
+            .collect();
+        if had_csr {
+            self.use_csr_layout();
+        }
+        if let Some(hashes) = self.payload_hashes.take() {
+            self.payload_hashes = Some(
+                hashes
+                    .iter()
+                    .zip(&remap)
+                    .filter(|(_, new_id)| new_id.is_some())
+                    .map(|(&hash, _)| hash)
+                    .collect(),
             );
-
-            candidates.insert_batch(&neighbor_distances);
-
-            // mark our current node as visited (not to be expanded again)
-            visited.insert(best_candidate_node.index);
-            stats.bump_nodes_visited();
-
-            // and find some other guy to expand, if possible. If not, we call it a day and return our best guesses.
-            best_candidate = candidates
-                .iter()
-                .filter(|&elem| !visited.contains(&elem.index))
-                .min()
-                .copied()
         }
 
-        // Post-search: record used edges — (src, dst) where both src and dst were visited
-        // in this search. Done once per search to avoid cross-query contamination.
-        if stats.has_adv_tracking() {
-            for &src in &visited {
-                for &dst in self.adjacency[src.internal].neighbors.to_slice().iter() {
-                    if visited.contains(&dst) {
-                        stats.record_used_edge(src.internal, dst.internal);
-                    }
-                }
-            }
+        let starting_node = remap[self.starter.starting_node().internal]
+            .expect("the starting node cannot be deleted");
+        self.starter.set_starting_node(starting_node);
+        let entry_points = self
+            .starter
+            .entry_points()
+            .iter()
+            .filter_map(|entry| remap[entry.internal])
+            .collect();
+        self.starter.set_entry_points(entry_points);
+        self.upper_levels = self
+            .upper_levels
+            .take()
+            .and_then(|levels| levels.remap(&remap));
+        if let Some(pivot) = self.pivot_distances.take() {
+            // a deleted pivot is replaced by the starting node, which survives compaction
+            let pivot = remap[pivot.pivot.internal].unwrap_or(starting_node);
+            self.pivot_distances = Some(self.pivot_distances_to(pivot));
         }
+        self.starter.clear_all_catapults();
+        self.mean_edge_length = OnceLock::new();
+        self.bounds_check = OnceLock::new();
+        self.tombstones.clear();
+        self.num_deleted = 0;
+        remap
+    }
 
-        // we have beam_width neighbors, we only need k so we need to rerank
-        let mut candidate_vec = candidates.into_iter().collect::<Vec<_>>();
-        candidate_vec.sort(); // note: implicitly relying on CandidateEntry ordering here
+    /// Returns the affine transform applied to queries, if any.
+    pub fn affine_transform(&self) -> Option<&AffineTransform> {
+        self.transform.as_ref()
+    }
 
-        // and return the best k, job done :)
-        candidate_vec.into_iter().take(k).collect()
+    /// Attaches the upper levels of a hierarchical graph whose level 0 is this graph.
+    ///
+    /// Searches then descend the upper levels greedily before running beam search on
+    /// level 0, as HNSW does, starting from the node the descent reaches. The entry point
+    /// of the upper levels becomes the starting node, which remains the node
+    /// [`validate`](Self::validate) and saving rely on.
+    ///
+    /// # Arguments
+    /// * `levels` - Levels above 0, numbered like the nodes of this graph
+    ///
+    /// # Returns
+    /// The same graph, descending the upper levels
+    ///
+    /// # Panics
+    /// Panics if the entry point of `levels` is not a node of the graph
+    pub fn with_upper_levels(mut self, levels: UpperLevels) -> Self {
+        assert!(
+            levels.entry_point().internal < self.len(),
+            "entry point is not a node of the graph"
+        );
+        self.starter.set_starting_node(levels.entry_point());
+        self.upper_levels = Some(levels);
+        self
     }
-}
 
-impl<EvictPolicy> AdjacencyGraph<EvictPolicy>
-where
-    EvictPolicy: CatapultEvictionPolicy,
-{
-    /// Performs approximate k-nearest neighbor search using LSH-accelerated beam search.
+    /// Weights each dimension in the distances scored by beam searches.
     ///
-    /// This is the main entry point for querying the graph. It uses LSH to map the query
-    /// to a catapult bucket, retrieves cached starting points from similar previous queries,
-    /// runs beam search, and caches the best result for future queries.
+    /// Candidates are then ranked by `Σ_i weights[i] * (query[i] - payload[i])²`, computed
+    /// on the fly instead of on scaled copies of the payloads. Weights apply to the space
+    /// payloads are stored in, i.e. after any affine transform or rotation of the queries.
+    ///
+    /// Exact searches, radius counts and [`mean_edge_length`](Self::mean_edge_length) are
+    /// weighted as well. Catapult bucketing still hashes unweighted queries, and graph
+    /// edges were built for unweighted distances, so heavily skewed weights degrade recall.
     ///
     /// # Arguments
-    /// * `query` - Query vector as aligned blocks
-    /// * `k` - Number of nearest neighbors to return
-    /// * `beam_width` - Maximum beam size during search (must be ≥ k)
-    /// * `stats` - Statistics tracker for performance monitoring
+    /// * `weights` - Non-negative weight of each of the [`dim`](Self::dim) dimensions
     ///
     /// # Returns
-    /// A vector of the k nearest candidate entries, sorted by ascending distance
+    /// The same graph, scoring weighted distances
     ///
-    /// # Behavior
-    /// 1. Hashes query to LSH signature
-    /// 2. Retrieves catapults from the corresponding bucket + base starting node
-    /// 3. Runs beam search from these starting points
-    /// 4. Caches the best result as a catapult (if catapults enabled)
-    /// 5. Updates statistics with catapult usage
-    #[tracing::instrument(level = "trace", skip_all, fields(k, beam_width))]
-    pub fn beam_search(
+    /// # Panics
+    /// Panics if the number of weights differs from the dimension of the graph, or if a
+    /// weight is negative or not finite
+    pub fn with_dimension_weights(mut self, weights: Vec<f32>) -> Self {
+        assert_eq!(
+            weights.len(),
+            self.dim(),
+            "one weight is needed per dimension"
+        );
+        assert!(
+            weights.iter().all(|w| w.is_finite() && *w >= 0.0),
+            "weights must be finite and non-negative"
+        );
+        self.weights = Some(AlignedBlock::allocate_padded(weights).into_boxed_slice());
+        self.mean_edge_length = OnceLock::new();
+        let pivot = self.pivot_distances.take().map(|pivot| pivot.pivot);
+        self.with_pivot_of(pivot)
+    }
+
+    /// Returns the per-dimension weights of scored distances, zero-padded, if any.
+    pub fn dimension_weights(&self) -> Option<&[AlignedBlock]> {
+        self.weights.as_deref()
+    }
+
+    /// Returns the levels descended before each search, if the graph is hierarchical.
+    pub fn upper_levels(&self) -> Option<&UpperLevels> {
+        self.upper_levels.as_ref()
+    }
+
+    /// Returns the rotation applied to queries, if any.
+    pub fn rotation(&self) -> Option<&Rotation> {
+        self.rotation.as_ref()
+    }
+
+    /// Maps a query to the space the payloads are stored in.
+    fn prepare_query<'q>(&self, query: &'q [AlignedBlock]) -> Cow<'q, [AlignedBlock]> {
+        let query = match &self.transform {
+            Some(transform) => Cow::Owned(transform.apply(query)),
+            None => Cow::Borrowed(query),
+        };
+        match &self.rotation {
+            Some(rotation) => Cow::Owned(rotation.apply(&query)),
+            None => query,
+        }
+    }
+
+    /// Returns the neighbors of `node`, from whichever layout is in use.
+    #[inline]
+    pub(crate) fn neighbors_of(&self, node: usize) -> NeighborSlice<'_> {
+        match &self.csr {
+            Some(csr) => NeighborSlice::Wide(csr.neighbors(node)),
+            None => self.adjacency[node].neighbors.as_slice(),
+        }
+    }
+
+    /// Returns the L2 distance a candidate must be within to enter the beam and the result
+    /// pool, or `None` while either still accepts any candidate.
+    fn pruning_bound(
+        beam: &SmallestKCandidates,
+        pool: Option<&SmallestKCandidates>,
+    ) -> Option<f32> {
+        let mut bound = beam.worst_if_full()?.distance.0;
+        if let Some(pool) = pool {
+            bound = bound.max(pool.worst_if_full()?.distance.0);
+        }
+        Some(bound.sqrt())
+    }
+
+    /// Adds `entries` to the beam, collapsing duplicate payloads if enabled.
+    #[inline]
+    fn insert_into_beam(&self, beam: &mut SmallestKCandidates, entries: &[CandidateEntry]) {
+        match &self.payload_hashes {
+            Some(hashes) => beam.insert_batch_collapsing(entries, |id| hashes[id.internal]),
+            None => beam.insert_batch(entries),
+        };
+    }
+
+    /// Returns whether `node` is already in the beam.
+    ///
+    /// Duplicates of a member's payload are not caught here, as collapsing them also
+    /// depends on their distance; the beam rejects them once it is computed.
+    #[inline]
+    fn in_beam(&self, beam: &SmallestKCandidates, node: NodeId) -> bool {
+        beam.contains(node)
+    }
+
+    /// Returns the node every search starts from, in addition to catapults.
+    pub(crate) fn starting_node(&self) -> NodeId {
+        self.starter.starting_node()
+    }
+
+    /// Returns the nodes every search starts from: the starting node and entry points.
+    pub(crate) fn fixed_entry_nodes(&self) -> Vec<NodeId> {
+        std::iter::once(self.starter.starting_node())
+            .chain(self.starter.entry_points().iter().copied())
+            .collect()
+    }
+
+    /// Replaces the neighbor list of `node`, leaving the CSR layout if it was used.
+    pub(crate) fn set_neighbors(&mut self, node: usize, neighbors: Vec<usize>) {
+        let num_nodes = self.adjacency.len();
+        self.unpack_csr_layout(num_nodes);
+        self.adjacency[node].neighbors = FlatFixedSet::compact(neighbors, num_nodes);
+        self.mean_edge_length = OnceLock::new();
+        self.bounds_check = OnceLock::new();
+    }
+}
+
+impl<EvictPolicy> AdjacencyGraph<EvictPolicy>
+where
+    EvictPolicy: CatapultEvictionPolicy,
+{
+    /// Rotates every payload and makes all searches rotate their query the same way.
+    ///
+    /// Queries passed to search methods stay in the original space: they are rotated
+    /// internally before hashing and distance computation, so payloads and queries always
+    /// meet in the rotated space. Vectors passed to methods that work on payloads directly,
+    /// such as [`insert`](AdjacencyGraph::insert) and
+    /// [`quantization_error`](AdjacencyGraph::quantization_error), must be rotated by the
+    /// caller with [`Rotation::apply`]. Catapults learned before the rotation was set are
+    /// cleared, since their buckets were chosen from unrotated queries. The rotation is
+    /// kept by [`quantize`](Self::quantize).
+    ///
+    /// # Arguments
+    /// * `rotation` - Orthonormal matrix of the payload dimension, typically learned by OPQ
+    ///
+    /// # Returns
+    /// The same graph with rotated payloads
+    ///
+    /// # Panics
+    /// Panics if a rotation is already set, or if the rotation and payload dimensions differ
+    pub fn with_rotation(mut self, rotation: Rotation) -> Self {
+        assert!(
+            self.rotation.is_none(),
+            "graph payloads are already rotated"
+        );
+        assert_eq!(
+            rotation.dim(),
+            self.dim(),
+            "rotation dimension differs from the graph's"
+        );
+        for node in &mut self.adjacency {
+            node.payload = rotation.apply(&node.payload).into_boxed_slice();
+        }
+        self.starter.clear_all_catapults();
+        self.mean_edge_length = OnceLock::new();
+        self.rotation = Some(rotation);
+        let pivot = self.pivot_distances.take().map(|pivot| pivot.pivot);
+        self.with_pivot_of(pivot)
+    }
+
+    /// Transforms every payload and makes all searches transform their query the same way.
+    ///
+    /// Queries passed to search methods stay in the original space: they go through the
+    /// transform internally, before any [`Rotation`], so payloads and queries always meet
+    /// in the transformed space. Vectors passed to methods that work on payloads directly,
+    /// such as [`insert`](AdjacencyGraph::insert), must be transformed by the caller with
+    /// [`AffineTransform::apply`]. Catapults learned before are cleared. If the transform
+    /// projects to a different dimension, the LSH hyperplanes are regenerated for the new
+    /// dimension with the same seed.
+    ///
+    /// Saved graphs hold the transformed payloads: store the transform next to them with
+    /// [`AffineTransform::save_to_path`], and reattach it after loading with
+    /// [`assume_affine_transform`](Self::assume_affine_transform) rather than this method,
+    /// which would transform the payloads a second time.
+    ///
+    /// # Arguments
+    /// * `transform` - Transform whose input dimension is the payload dimension
+    ///
+    /// # Returns
+    /// The same graph with transformed payloads
+    ///
+    /// # Panics
+    /// Panics if a transform or rotation is already set, if the transform's input
+    /// dimension differs from the payloads', or if it changes the dimension of a graph
+    /// using the [`SearchStrategy::LshApg`] strategy, whose Z-order indexes are fixed
+    pub fn with_affine_transform(mut self, transform: AffineTransform) -> Self {
+        assert!(
+            self.transform.is_none() && self.rotation.is_none(),
+            "a transform must be the first preprocessing step of a graph"
+        );
+        assert_eq!(
+            transform.input_dim(),
+            self.dim(),
+            "transform dimension differs from the graph's"
+        );
+        for node in &mut self.adjacency {
+            node.payload = transform.apply(&node.payload).into_boxed_slice();
+        }
+        if transform.output_dim() != self.dim() {
+            assert!(
+                !matches!(self.strategy, SearchStrategy::LshApg(_)),
+                "LSH-APG graphs cannot change dimension"
+            );
+            let entry_points = self.starter.entry_points().to_vec();
+            self.starter = EngineStarter::new(EngineStarterParams {
+                plane_dim: transform.output_dim(),
+                ..self.starter.params()
+            });
+            self.starter.set_entry_points(entry_points);
+        } else {
+            self.starter.clear_all_catapults();
+        }
+        if self.payload_hashes.is_some() {
+            self.payload_hashes = None;
+            self.collapse_duplicate_payloads();
+        }
+        self.mean_edge_length = OnceLock::new();
+        self.transform = Some(transform);
+        let pivot = self.pivot_distances.take().map(|pivot| pivot.pivot);
+        self.with_pivot_of(pivot)
+    }
+
+    /// Makes all searches transform their query, for payloads that are already transformed.
+    ///
+    /// This reattaches the transform of a graph saved after
+    /// [`with_affine_transform`](Self::with_affine_transform). Payloads are left as they
+    /// are, and catapults are kept.
+    ///
+    /// # Arguments
+    /// * `transform` - Transform whose output dimension is the payload dimension
+    ///
+    /// # Returns
+    /// The same graph, transforming queries
+    ///
+    /// # Panics
+    /// Panics if a transform or rotation is already set, or if the transform's output
+    /// dimension differs from the payloads'
+    pub fn assume_affine_transform(mut self, transform: AffineTransform) -> Self {
+        assert!(
+            self.transform.is_none() && self.rotation.is_none(),
+            "a transform must be the first preprocessing step of a graph"
+        );
+        assert_eq!(
+            transform.output_dim(),
+            self.dim(),
+            "transform dimension differs from the graph's"
+        );
+        self.transform = Some(transform);
+        self
+    }
+
+    /// Makes the medoid of the graph the node every search starts from.
+    ///
+    /// Graph files name an entry point, which may sit far from the center of the data;
+    /// starting from the [`compute_medoid`] of the payloads instead keeps the walk to any
+    /// query short. Catapults are kept, since node ids do not change.
+    ///
+    /// # Returns
+    /// The new starting node
+    ///
+    /// # Panics
+    /// Panics if the graph is empty
+    pub fn use_medoid_as_starting_node(&mut self) -> NodeId {
+        let medoid = compute_medoid(&self.adjacency);
+        self.starter.set_starting_node(medoid);
+        medoid
+    }
+
+    /// Seeds every search from `n` well-spread nodes instead of the starting node alone.
+    ///
+    /// The entry points are chosen by farthest-point sampling from the starting node, see
+    /// [`EngineStarter::with_diverse_entry_points`]. Choosing them costs `n` passes over
+    /// the payloads, and each search then computes up to `n - 1` extra distances.
+    ///
+    /// # Arguments
+    /// * `n` - Number of diverse seeds, including the starting node
+    ///
+    /// # Returns
+    /// The entry points added besides the starting node
+    pub fn use_diverse_entry_points(&mut self, n: usize) -> &[NodeId] {
+        let entry_points = farthest_point_sample(&self.adjacency, self.starter.starting_node(), n)
+            .into_iter()
+            .filter(|&entry| entry != self.starter.starting_node())
+            .collect();
+        self.starter.set_entry_points(entry_points);
+        self.starter.entry_points()
+    }
+
+    /// Converts every payload to an int8 [`QuantizedVector`], cutting payload memory about 4x.
+    ///
+    /// Neighbor lists, catapults and the search strategy are kept as they are. Searches on
+    /// the returned graph compute asymmetric distances between the f32 query and the
+    /// quantized payloads; [`quantization_error`](AdjacencyGraph::quantization_error) can be
+    /// used to check the accuracy cost against the original vectors.
+    ///
+    /// # Returns
+    /// The same graph with quantized payloads
+    pub fn quantize(self) -> AdjacencyGraph<EvictPolicy, QuantizedVector> {
+        self.map_payloads(QuantizedVector::quantize)
+    }
+
+    /// Converts every payload to a [`BitVector`], searching the graph by Hamming distance.
+    ///
+    /// Payloads must be binary codes stored as 0/1 coordinates; any non-zero coordinate
+    /// becomes a 1. Searches on the returned graph take 0/1 queries and rank nodes by
+    /// their Hamming distance to the query, which is the squared L2 distance the original
+    /// graph would compute, so results are unchanged while payloads shrink 32x and
+    /// distances reduce to popcounts. LSH hashing of the starting points sees the same
+    /// 0/1 vectors as before.
+    ///
+    /// # Returns
+    /// The same graph with bit-packed payloads
+    pub fn binarize(self) -> AdjacencyGraph<EvictPolicy, BitVector> {
+        self.map_payloads(BitVector::from_blocks)
+    }
+
+    /// Rebuilds the graph with every payload converted by `convert`, keeping the rest.
+    fn map_payloads<Q: Payload>(
+        self,
+        convert: impl Fn(&[AlignedBlock]) -> Q,
+    ) -> AdjacencyGraph<EvictPolicy, Q> {
+        AdjacencyGraph {
+            adjacency: self
+                .adjacency
+                .into_iter()
+                .map(|node| Node {
+                    payload: convert(&node.payload),
+                    neighbors: node.neighbors,
+                })
+                .collect(),
+            starter: self.starter,
+            strategy: self.strategy,
+            csr: self.csr,
+            mean_edge_length: OnceLock::new(),
+            bounds_check: OnceLock::new(),
+            transform: self.transform,
+            rotation: self.rotation,
+            payload_hashes: self.payload_hashes,
+            tombstones: self.tombstones,
+            num_deleted: self.num_deleted,
+            upper_levels: self.upper_levels,
+            weights: self.weights,
+            pivot_distances: None,
+            catapult_writes: self.catapult_writes,
+        }
+        .with_pivot_of(self.pivot_distances.map(|pivot| pivot.pivot))
+    }
+}
+
+impl<EvictPolicy, P> AdjacencyGraph<EvictPolicy, P>
+where
+    EvictPolicy: CatapultEvictionPolicy,
+    P: Payload,
+{
+    /// Computes distances from the query to a set of node indices.
+    ///
+    /// Creates candidate entries for each provided index by computing the squared L2
+    /// distance from the query to that node's payload, weighted per dimension if the graph
+    /// has weights, plus the node's penalty if the options carry one.
+    ///
+    /// # Arguments
+    /// * `indices` - Node indices to compute distances for
+    /// * `query` - Query vector as aligned blocks
+    /// * `catapult_marker` - Whether to mark these candidates as catapult-derived
+    /// * `stats` - Statistics tracker to update with distance computations
+    /// * `options` - Search options (only the penalty is used here)
+    ///
+    /// # Returns
+    /// A vector of candidate entries with computed distances
+    fn distances_from_indices(
         &self,
+        indices: impl ExactSizeIterator<Item = NodeId>,
         query: &[AlignedBlock],
-        k: usize,
-        beam_width: usize,
+        catapult_marker: bool,
         stats: &mut Stats,
+        options: &SearchOptions<'_>,
     ) -> Vec<CandidateEntry> {
-        let hash_search = if let SearchStrategy::LshApg(lsh_apg) = &self.strategy {
-            let mut lshapg_candidates = Vec::new();
-            for candidate_set in lsh_apg
+        stats.bump_computed_dists(indices.len());
+
+        let indices: Vec<NodeId> = indices.collect();
+        let payloads: Vec<&P> = indices
+            .iter()
+            .map(|index| &self.adjacency[index.internal].payload)
+            .collect();
+        let distances = match &self.weights {
+            Some(weights) => payloads
                 .iter()
-                .map(|zorder| zorder.query_k_closest(query, 4 * k))
+                .map(|payload| payload.l2_squared_weighted_to(query, weights))
+                .collect(),
+            None => P::l2_squared_batch_to(&payloads, query),
+        };
+
+        indices
+            .into_iter()
+            .zip(distances)
+            .map(|(index, mut score)| {
+                if let Some(penalty) = options.penalty {
+                    score += penalty(index.internal);
+                }
+
+                CandidateEntry {
+                    distance: score.into(),
+                    index,
+                    has_catapult_ancestor: catapult_marker,
+                }
+            })
+            .collect()
+    }
+
+    /// Performs a best-first beam search starting from the given candidates.
+    ///
+    /// This is the core search algorithm that maintains a beam of at most `beam_width`
+    /// candidates and iteratively expands the closest unvisited candidate until exhaustion.
+    /// The search explores the graph by following neighbor edges, maintaining the k-best
+    /// nodes encountered.
+    ///
+    /// # Algorithm Steps
+    /// 1. Initialize candidate beam with starting points
+    /// 2. Find the best unvisited candidate in the beam
+    /// 3. Expand it by computing distances to all its neighbors
+    /// 4. Add neighbors to the beam (with automatic eviction if over capacity)
+    /// 5. Mark the expanded node as visited
+    /// 6. Repeat from step 2 until no unvisited candidates remain, until
+    ///    `options.max_iterations` nodes have been expanded, or until
+    ///    `options.max_distance_computations` distances have been computed
+    /// 7. Return the top-k candidates by distance
+    ///
+    /// # Arguments
+    /// * `query` - Target query vector as aligned blocks
+    /// * `starting_candidates` - Initial candidates to seed the search
+    /// * `k` - Number of nearest neighbors to return
+    /// * `beam_width` - Maximum number of candidates to maintain (must be ≥ k)
+    /// * `stats` - Statistics tracker for performance monitoring
+    /// * `scratch` - Reusable buffers sized for `beam_width`, reset before use
+    /// * `options` - Per-search options affecting distances
+    ///
+    /// # Returns
+    /// The k nearest candidate entries, sorted by distance, and why the search stopped
+    ///
+    /// # Panics
+    /// * Panics if `beam_width < k`
+    /// * Panics if starting_candidates is empty
+    /// * Panics if neighbor indices are out of bounds (graph invariant violation)
+    #[allow(clippy::too_many_arguments)]
+    #[tracing::instrument(level = "trace", skip_all, fields(k, beam_width))]
+    fn beam_search_raw<Visited: VisitorSet>(
+        &self,
+        query: &[AlignedBlock],
+        starting_candidates: &[CandidateEntry],
+        k: usize,
+        beam_width: usize,
+        stats: &mut Stats,
+        scratch: &mut SearchScratch<Visited>,
+        options: &SearchOptions<'_>,
+    ) -> SearchOutcome {
+        // with a result pool, the results come from the pool and the beam only explores
+        if options.ef_results.is_none() {
+            assert!(beam_width >= k);
+        }
+        stats.bump_beam_calls();
+
+        scratch.reset();
+        let SearchScratch {
+            candidates,
+            visited,
+            fresh,
+        } = scratch;
+
+        // only needed to replay the visited set for adversarial edge tracking
+        let mut expanded = Vec::new();
+
+        // best nodes seen so far, kept apart from the beam so its evictions do not lose them
+        let mut pool = options.ef_results.map(|ef| {
+            assert!(ef >= k, "ef_results must be at least k");
+            SmallestKCandidates::new(ef)
+        });
+        if let Some(pool) = &mut pool {
+            self.insert_into_beam(pool, starting_candidates);
+        }
+
+        self.insert_into_beam(candidates, starting_candidates);
+        let mut offers = starting_candidates.len();
+
+        // among the suggested entry points, one of them is 'the best'. Let's identify it.
+        let initial_best_node = candidates.iter().min().copied().expect(
+            "Corrupted starting point entries. Provide a non-empty starting_indices lists.",
+        );
+
+        let mut best_candidate: Option<CandidateEntry> = Some(initial_best_node);
+        let mut iterations = 0;
+        let mut termination = Termination::Exhausted;
+        // the starting candidates were scored by the caller, and count against the budget
+        let mut distances_computed = starting_candidates.len();
+
+        // penalties may lower distances below the triangle-inequality bound
+        let pruning = match &self.pivot_distances {
+            Some(pivot) if options.penalty.is_none() => {
+                let to_pivot = match starting_candidates.iter().find(|c| c.index == pivot.pivot) {
+                    Some(entry) => entry.distance.0,
+                    None => {
+                        distances_computed += 1;
+                        self.distances_from_indices(
+                            std::iter::once(pivot.pivot),
+                            query,
+                            false,
+                            stats,
+                            options,
+                        )[0]
+                        .distance
+                        .0
+                    }
+                };
+                Some((&pivot.distances, to_pivot.sqrt()))
+            }
+            _ => None,
+        };
+
+        // while we have some node on which to expand (at first, the best LSH entry point),
+        // we keep expanding it (i.e. looking at its neighbors for better guesses)
+        while let Some(best_candidate_node) = best_candidate {
+            if options.max_iterations.is_some_and(|cap| iterations >= cap) {
+                termination = Termination::IterationCap;
+                break;
+            }
+            if options
+                .max_distance_computations
+                .is_some_and(|budget| distances_computed >= budget)
             {
-                lshapg_candidates.extend(candidate_set.iter());
+                termination = Termination::DistanceBudget;
+                break;
             }
-            lshapg_candidates.sort();
-            lshapg_candidates.dedup();
-            StartingPoints {
-                signature: 0,
-                catapults: lshapg_candidates,
-                starting_node: self.starter.starting_node(),
+            // the starting points are scored and a few nodes expanded before the first
+            // check, so a late search still returns its best candidates so far
+            if iterations > 0
+                && iterations.is_multiple_of(DEADLINE_CHECK_INTERVAL)
+                && options
+                    .deadline
+                    .is_some_and(|deadline| Instant::now() >= deadline)
+            {
+                termination = Termination::Deadline;
+                stats.bump_deadline_hits();
+                break;
             }
-        } else {
-            self.starter.select_starting_points(query)
+            iterations += 1;
+
+            // identify the neighbors of our current best guess.
+            // All of these guys become candidates for expansion. if we have too many candidates
+            // (beam width parameter), the `candidates` data structure takes care of removing the
+            // worst ones (and the duplicates).
+            let neighbors = self.neighbors_of(best_candidate_node.index.internal);
+
+            // Record each (src → dst) edge as considered before computing distances.
+            if stats.has_adv_tracking() {
+                let src = best_candidate_node.index.internal;
+                for dst in neighbors.iter() {
+                    stats.record_considered_edge(src, dst.internal);
+                }
+            }
+
+            // neighbors already in the beam would only be rejected as duplicates
+            fresh.clear();
+            fresh.extend(neighbors.iter().filter(|&n| !self.in_beam(candidates, n)));
+            stats.bump_skipped_dists(neighbors.len() - fresh.len());
+            if let Some((pivot_distances, to_pivot)) = pruning
+                && let Some(bound) = Self::pruning_bound(candidates, pool.as_ref())
+            {
+                let unpruned = fresh.len();
+                fresh.retain(|n| {
+                    let from_pivot = pivot_distances[n.internal];
+                    let lower_bound =
+                        (to_pivot - from_pivot).abs() - PRUNING_TOLERANCE * (to_pivot + from_pivot);
+                    lower_bound <= bound
+                });
+                stats.bump_pruned_dists(unpruned - fresh.len());
+            }
+            distances_computed += fresh.len();
+
+            let neighbor_distances = self.distances_from_indices(
+                fresh.iter().copied(),
+                query,
+                best_candidate_node.has_catapult_ancestor,
+                stats,
+                options,
+            );
+
+            self.insert_into_beam(candidates, &neighbor_distances);
+            offers += neighbor_distances.len();
+            if let Some(pool) = &mut pool {
+                self.insert_into_beam(pool, &neighbor_distances);
+            }
+
+            // mark our current node as visited (not to be expanded again)
+            visited.set(best_candidate_node.index.internal);
+            if stats.has_adv_tracking() {
+                expanded.push(best_candidate_node.index);
+            }
+            stats.bump_nodes_visited();
+
+            // and find some other guy to expand, if possible. If not, we call it a day and return our best guesses.
+            best_candidate = candidates
+                .iter()
+                .filter(|&elem| !visited.get(elem.index.internal))
+                .min()
+                .copied()
+        }
+
+        // every iteration that got past the cap expanded exactly one node
+        stats.record_query_nodes_visited(iterations);
+        stats.bump_beam_usage(offers, candidates.evictions());
+
+        // Post-search: record used edges — (src, dst) where both src and dst were visited
+        // in this search. Done once per search to avoid cross-query contamination.
+        if stats.has_adv_tracking() {
+            for &src in &expanded {
+                for dst in self.neighbors_of(src.internal) {
+                    if visited.get(dst.internal) {
+                        stats.record_used_edge(src.internal, dst.internal);
+                    }
+                }
+            }
+        }
+
+        // we have beam_width (or ef_results) neighbors, we only need k so we need to rerank
+        let mut candidate_vec = pool
+            .as_ref()
+            .unwrap_or(candidates)
+            .iter()
+            .copied()
+            .collect::<Vec<_>>();
+        if self.num_deleted > 0 {
+            candidate_vec.retain(|c| !self.tombstones.get(c.index.internal));
+        }
+        if options.unsorted {
+            // the caller re-sorts anyway: only split off the best k, in linear time
+            if k < candidate_vec.len() {
+                candidate_vec.select_nth_unstable(k);
+            }
+        } else {
+            candidate_vec.sort(); // note: implicitly relying on CandidateEntry ordering here
+        }
+
+        // and return the best k, job done :)
+        SearchOutcome {
+            results: candidate_vec.into_iter().take(k).collect(),
+            termination,
+        }
+    }
+}
+
+impl<EvictPolicy, P> AdjacencyGraph<EvictPolicy, P>
+where
+    EvictPolicy: CatapultEvictionPolicy,
+    P: Payload,
+{
+    /// Performs approximate k-nearest neighbor search using LSH-accelerated beam search.
+    ///
+    /// This is the main entry point for querying the graph. It uses LSH to map the query
+    /// to a catapult bucket, retrieves cached starting points from similar previous queries,
+    /// runs beam search, and caches the best result for future queries.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `k` - Number of nearest neighbors to return
+    /// * `beam_width` - Maximum beam size during search (must be ≥ k)
+    /// * `stats` - Statistics tracker for performance monitoring
+    ///
+    /// # Returns
+    /// A vector of the k nearest candidate entries, sorted by ascending distance. The
+    /// [`has_catapult_ancestor`](CandidateEntry::has_catapult_ancestor) flag of each entry
+    /// tells whether it was reached through a catapult of this query's bucket.
+    ///
+    /// # Behavior
+    /// 1. Hashes query to LSH signature
+    /// 2. Retrieves catapults from the corresponding bucket + base starting node
+    /// 3. Runs beam search from these starting points
+    /// 4. Caches the best result as a catapult (if catapults enabled)
+    /// 5. Updates statistics with catapult usage
+    #[tracing::instrument(level = "trace", skip_all, fields(k, beam_width))]
+    pub fn beam_search(
+        &self,
+        query: &[AlignedBlock],
+        k: usize,
+        beam_width: usize,
+        stats: &mut Stats,
+    ) -> Vec<CandidateEntry> {
+        let mut scratch = SearchScratch::<CompressedBitset>::new(beam_width);
+        self.beam_search_with_scratch(
+            query,
+            k,
+            beam_width,
+            stats,
+            &mut scratch,
+            &SearchOptions::learning(),
+        )
+        .results
+    }
+
+    /// Performs [`beam_search`](Self::beam_search) and returns L2 distances instead of
+    /// squared ones.
+    ///
+    /// The search itself ranks candidates by squared distance; the square root is only
+    /// taken for the `k` results returned.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `k` - Number of nearest neighbors to return
+    /// * `beam_width` - Maximum beam size during search (must be ≥ k)
+    /// * `stats` - Statistics tracker for performance monitoring
+    ///
+    /// # Returns
+    /// The k nearest nodes with their [`Distance::L2`] to the query, sorted by ascending
+    /// distance
+    pub fn beam_search_l2(
+        &self,
+        query: &[AlignedBlock],
+        k: usize,
+        beam_width: usize,
+        stats: &mut Stats,
+    ) -> Vec<(NodeId, Distance)> {
+        self.beam_search(query, k, beam_width, stats)
+            .iter()
+            .map(|entry| (entry.index, entry.l2_distance()))
+            .collect()
+    }
+
+    /// Performs [`beam_search`](Self::beam_search) and indexes the results by node.
+    ///
+    /// The plain vector of `beam_search` is the cheaper choice for callers that only walk
+    /// the results; this one suits callers asking for the rank of many nodes.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `k` - Number of nearest neighbors to return
+    /// * `beam_width` - Maximum beam size during search (must be ≥ k)
+    /// * `stats` - Statistics tracker for performance monitoring
+    ///
+    /// # Returns
+    /// The k nearest candidate entries, whose rank and distance can be looked up by node
+    pub fn beam_search_ranked(
+        &self,
+        query: &[AlignedBlock],
+        k: usize,
+        beam_width: usize,
+        stats: &mut Stats,
+    ) -> RankedResults {
+        RankedResults::new(self.beam_search(query, k, beam_width, stats))
+    }
+
+    /// Performs [`beam_search`](Self::beam_search), optionally capped, and reports whether
+    /// the search converged.
+    ///
+    /// Without a cap, the search always runs until every candidate in the beam has been
+    /// expanded and the outcome is converged. With `max_iterations`, the search stops after
+    /// expanding that many nodes; if unexpanded candidates remain at that point, the
+    /// outcome is not converged and its results are only approximate. Catapults are learned
+    /// as in `beam_search` either way.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `k` - Number of nearest neighbors to return
+    /// * `beam_width` - Maximum beam size during search (must be ≥ k)
+    /// * `stats` - Statistics tracker for performance monitoring
+    /// * `max_iterations` - Maximum number of nodes to expand, or `None` for no cap
+    ///
+    /// # Returns
+    /// The k nearest candidate entries found and why the search stopped
+    pub fn beam_search_with_status(
+        &self,
+        query: &[AlignedBlock],
+        k: usize,
+        beam_width: usize,
+        stats: &mut Stats,
+        max_iterations: Option<usize>,
+    ) -> SearchOutcome {
+        let mut scratch = SearchScratch::<CompressedBitset>::new(beam_width);
+        let options = SearchOptions {
+            max_iterations,
+            ..SearchOptions::learning()
+        };
+        self.beam_search_with_scratch(query, k, beam_width, stats, &mut scratch, &options)
+    }
+
+    /// Performs [`beam_search`](Self::beam_search) within a budget of distance
+    /// computations, for searches that must answer in bounded time.
+    ///
+    /// Distance computations dominate the cost of a search, so capping them bounds its
+    /// latency more tightly than capping expansions, whose cost varies with the degree of
+    /// each expanded node. The budget counts the distances to the starting points and to
+    /// every neighbor scored during expansion, and is checked before each expansion: once
+    /// it is reached, the search stops and returns the current top-k of its beam. Because
+    /// the last expansion scores all of its node's unseen neighbors, a search may exceed
+    /// its budget by at most one node's out-degree.
+    ///
+    /// The smaller the budget, the more approximate the results: a search cut short only
+    /// saw the part of the graph around its starting points, and reports
+    /// [`Termination::DistanceBudget`] instead of converging. It still returns `k` entries
+    /// as long as it scored at least `k` nodes before stopping. Catapults are learned as in
+    /// `beam_search` either way, so a budgeted search may cache a worse catapult than a
+    /// full one would.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `k` - Number of nearest neighbors to return
+    /// * `beam_width` - Maximum beam size during search (must be ≥ k)
+    /// * `stats` - Statistics tracker for performance monitoring
+    /// * `max_distance_computations` - Maximum number of distances to compute, or `None`
+    ///   for no budget
+    ///
+    /// # Returns
+    /// The k nearest candidate entries found and why the search stopped
+    pub fn beam_search_with_budget(
+        &self,
+        query: &[AlignedBlock],
+        k: usize,
+        beam_width: usize,
+        stats: &mut Stats,
+        max_distance_computations: Option<usize>,
+    ) -> SearchOutcome {
+        let mut scratch = SearchScratch::<CompressedBitset>::new(beam_width);
+        let options = SearchOptions {
+            max_distance_computations,
+            ..SearchOptions::learning()
+        };
+        self.beam_search_with_scratch(query, k, beam_width, stats, &mut scratch, &options)
+    }
+
+    /// Checks that every index a search may follow points to a node of the graph.
+    ///
+    /// Scans every neighbor list once, then caches the outcome until the edges change, so
+    /// only the entry nodes are checked again on later calls. See
+    /// [`validate`](Self::validate) for a report of every problem rather than the first.
+    ///
+    /// # Returns
+    /// `Ok(())` if searches cannot run out of bounds
+    ///
+    /// # Errors
+    /// Returns the first out-of-bounds neighbor index in node order, or an entry node
+    /// that is not part of the graph
+    pub fn check_bounds(&self) -> Result<(), SearchError> {
+        let num_nodes = self.len();
+        for node in self.fixed_entry_nodes() {
+            if node.internal >= num_nodes {
+                return Err(SearchError::EntryOutOfBounds { node, num_nodes });
+            }
+        }
+        *self.bounds_check.get_or_init(|| {
+            for node in 0..num_nodes {
+                if let Some(neighbor) = self
+                    .neighbors_of(node)
+                    .iter()
+                    .find(|neighbor| neighbor.internal >= num_nodes)
+                {
+                    return Err(SearchError::NeighborOutOfBounds {
+                        node: NodeId { internal: node },
+                        neighbor: neighbor.internal,
+                        num_nodes,
+                    });
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Performs [`beam_search`](Self::beam_search) after checking the graph is well-formed.
+    ///
+    /// `beam_search` indexes nodes directly and trusts the graph to satisfy its
+    /// invariants, so a corrupt graph makes it panic mid-query. This method first runs
+    /// [`check_bounds`](Self::check_bounds), whose neighbor scan is paid once per graph
+    /// and cached, and reports a corrupt graph as an error instead. Catapults learned by
+    /// searches always point to nodes, so they are not checked. Trusted graphs can keep
+    /// using `beam_search`, which skips the check entirely.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `k` - Number of nearest neighbors to return
+    /// * `beam_width` - Maximum beam size during search (must be ≥ k)
+    /// * `stats` - Statistics tracker for performance monitoring
+    ///
+    /// # Returns
+    /// The k nearest candidate entries, sorted by ascending distance
+    ///
+    /// # Errors
+    /// Returns the error of `check_bounds` if the graph holds an out-of-bounds index
+    ///
+    /// # Panics
+    /// Panics if `beam_width < k`
+    pub fn beam_search_checked(
+        &self,
+        query: &[AlignedBlock],
+        k: usize,
+        beam_width: usize,
+        stats: &mut Stats,
+    ) -> Result<Vec<CandidateEntry>, SearchError> {
+        self.check_bounds()?;
+        Ok(self.beam_search(query, k, beam_width, stats))
+    }
+
+    /// Performs [`beam_search`](Self::beam_search) with a result pool wider than the beam.
+    ///
+    /// The beam bounds both what the search explores and what it remembers, which is why
+    /// `beam_search` needs `beam_width ≥ k`. With this method, every scored node is also
+    /// offered to a separate pool of the `ef_results` best nodes seen, and the `k` results
+    /// are taken from that pool instead of the beam. The beam may then be narrower than
+    /// `k`: a narrow `beam_width` keeps exploration cheap, while the pool keeps the nodes
+    /// the beam evicted on the way. The pool costs no extra distance computation, only one
+    /// more insertion per scored node.
+    ///
+    /// The beam always holds the best `beam_width` nodes seen, so with `beam_width ≥ k`
+    /// the results are those of `beam_search`. Pools larger than `k` pay off on graphs
+    /// with deleted nodes, which are filtered out after the search: the pool keeps
+    /// `ef_results - k` spare nodes to replace them. Growing `ef_results` never makes the
+    /// results worse, since the walk does not depend on it. Catapults are learned as in
+    /// `beam_search`.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `k` - Number of nearest neighbors to return
+    /// * `beam_width` - Maximum beam size during search, possibly smaller than k
+    /// * `ef_results` - Size of the result pool (must be ≥ k)
+    /// * `stats` - Statistics tracker for performance monitoring
+    ///
+    /// # Returns
+    /// The k nearest candidate entries, sorted by ascending distance
+    ///
+    /// # Panics
+    /// Panics if `ef_results < k`
+    pub fn beam_search_with_ef_results(
+        &self,
+        query: &[AlignedBlock],
+        k: usize,
+        beam_width: usize,
+        ef_results: usize,
+        stats: &mut Stats,
+    ) -> Vec<CandidateEntry> {
+        let mut scratch = SearchScratch::<CompressedBitset>::new(beam_width);
+        let options = SearchOptions {
+            ef_results: Some(ef_results),
+            ..SearchOptions::learning()
+        };
+        self.beam_search_with_scratch(query, k, beam_width, stats, &mut scratch, &options)
+            .results
+    }
+
+    /// Performs [`beam_search`](Self::beam_search), giving up on expanding nodes once a
+    /// wall-clock deadline has passed.
+    ///
+    /// The deadline bounds tail latency where a distance budget only bounds work. The clock
+    /// is read every 8 expansions rather than after each one, so a search may overrun its
+    /// deadline by up to 8 expansions, plus the final sort. The starting points are always
+    /// scored and the first 8 nodes expanded, so a search returns `k` entries as long as
+    /// it scored at least `k` nodes, however short the deadline. A search cut short reports
+    /// [`Termination::Deadline`] and is counted by [`Stats::get_deadline_hits`]. Catapults
+    /// are learned as in `beam_search`.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `k` - Number of nearest neighbors to return
+    /// * `beam_width` - Maximum beam size during search (must be ≥ k)
+    /// * `stats` - Statistics tracker for performance monitoring
+    /// * `timeout` - Time the search may take, counted from this call
+    ///
+    /// # Returns
+    /// The k nearest candidate entries found and why the search stopped
+    pub fn beam_search_with_deadline(
+        &self,
+        query: &[AlignedBlock],
+        k: usize,
+        beam_width: usize,
+        stats: &mut Stats,
+        timeout: Duration,
+    ) -> SearchOutcome {
+        let deadline = Instant::now() + timeout;
+        let mut scratch = SearchScratch::<CompressedBitset>::new(beam_width);
+        let options = SearchOptions {
+            deadline: Some(deadline),
+            ..SearchOptions::learning()
+        };
+        self.beam_search_with_scratch(query, k, beam_width, stats, &mut scratch, &options)
+    }
+
+    /// Performs [`beam_search`](Self::beam_search) with every setting taken from `params`.
+    ///
+    /// The iteration cap, distance budget and deadline of `params` apply together, and the
+    /// search stops at whichever is hit first; the deadline is counted from this call.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `params` - Search settings
+    /// * `stats` - Statistics tracker for performance monitoring
+    ///
+    /// # Returns
+    /// The k nearest candidate entries found and why the search stopped
+    ///
+    /// # Panics
+    /// Panics if the beam width of `params` is smaller than its `k`
+    pub fn beam_search_with(
+        &self,
+        query: &[AlignedBlock],
+        params: &SearchParams,
+        stats: &mut Stats,
+    ) -> SearchOutcome {
+        let (k, beam_width) = (params.k(), params.beam_width());
+        assert!(
+            beam_width >= k,
+            "beam width {beam_width} is smaller than k = {k}"
+        );
+        let mut scratch = SearchScratch::<CompressedBitset>::new(beam_width);
+        let options = SearchOptions {
+            learn_catapults: params.learns_catapults(),
+            max_iterations: params.max_iterations(),
+            max_distance_computations: params.max_distance_computations(),
+            deadline: params.timeout().map(|timeout| Instant::now() + timeout),
+            ..SearchOptions::default()
+        };
+        self.beam_search_with_scratch(query, k, beam_width, stats, &mut scratch, &options)
+    }
+
+    /// Performs [`beam_search`](Self::beam_search) with a beam that doubles until the
+    /// results stop changing, for when no beam width has been tuned for the dataset.
+    ///
+    /// The first round searches with a beam of width `k`. Each following round doubles the
+    /// width, up to `max_beam_width`, and searches again from scratch. The search settles
+    /// once the set of top-k nodes has come out identical for `stability` consecutive
+    /// doublings, once a round used `max_beam_width`, or once the beam can hold the whole
+    /// graph, which makes tiny graphs settle after a single round. With
+    /// `max_distance_computations`, the rounds share that budget: a round cut short by it
+    /// ends the search, which returns the last round that completed, if any.
+    ///
+    /// This trades latency variance for recall stability: easy queries settle after a few
+    /// cheap rounds, while hard ones pay for every doubling, so the cost per query varies
+    /// widely. Every round is counted as a search in `stats`. Catapults are used by every
+    /// round and learned once, from the results returned, and the catapults among these
+    /// results are touched as in `beam_search`.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `k` - Number of nearest neighbors to return
+    /// * `max_beam_width` - Widest beam to try (must be ≥ k)
+    /// * `stability` - Number of consecutive doublings that must leave the top-k unchanged
+    ///   (must be ≥ 1)
+    /// * `stats` - Statistics tracker for performance monitoring
+    /// * `max_distance_computations` - Budget of distance computations over all rounds, or
+    ///   `None` for no budget
+    ///
+    /// # Returns
+    /// The k nearest candidate entries, and the beam width of the round they come from
+    ///
+    /// # Panics
+    /// Panics if `k` is 0, `max_beam_width < k` or `stability` is 0
+    pub fn beam_search_adaptive(
+        &self,
+        query: &[AlignedBlock],
+        k: usize,
+        max_beam_width: usize,
+        stability: usize,
+        stats: &mut Stats,
+        max_distance_computations: Option<usize>,
+    ) -> (Vec<CandidateEntry>, usize) {
+        assert!(k > 0 && max_beam_width >= k, "need 0 < k <= max_beam_width");
+        assert!(stability > 0, "stability must be at least 1");
+        let top_k = |results: &[CandidateEntry]| {
+            let mut ids: Vec<NodeId> = results.iter().map(|e| e.index).collect();
+            ids.sort();
+            ids
+        };
+
+        let mut beam_width = k;
+        let mut settled: Option<(Vec<CandidateEntry>, usize)> = None;
+        let mut unchanged = 0;
+        let mut used = 0;
+        loop {
+            let mut scratch = SearchScratch::<CompressedBitset>::new(beam_width);
+            let options = SearchOptions {
+                max_distance_computations: max_distance_computations.map(|b| b - used),
+                ..SearchOptions::default()
+            };
+            let before = stats.get_computed_dists();
+            let outcome =
+                self.beam_search_with_scratch(query, k, beam_width, stats, &mut scratch, &options);
+            used += stats.get_computed_dists() - before;
+
+            if outcome.termination == Termination::DistanceBudget {
+                settled.get_or_insert((outcome.results, beam_width));
+                break;
+            }
+            if let Some((previous, _)) = &settled
+                && top_k(previous) == top_k(&outcome.results)
+            {
+                unchanged += 1;
+            } else {
+                unchanged = 0;
+            }
+            settled = Some((outcome.results, beam_width));
+            if unchanged >= stability
+                || beam_width >= max_beam_width
+                || beam_width >= self.len()
+                || max_distance_computations.is_some_and(|budget| used >= budget)
+            {
+                break;
+            }
+            beam_width = (2 * beam_width).min(max_beam_width);
+        }
+
+        let (results, beam_width) = settled.expect("at least one round ran");
+        if self.strategy.learns_catapults() && self.catapult_writes_enabled() {
+            let query = self.prepare_query(query);
+            let signature = self.starter.signature(&query);
+            // the bucket is only read when a catapult may be among the results
+            let catapults = if results.iter().any(|e| e.has_catapult_ancestor) {
+                self.starter.bucket_catapults(signature)
+            } else {
+                Vec::new()
+            };
+            self.learn_from_results(&query, signature, &catapults, &results, stats);
+        }
+        (results, beam_width)
+    }
+
+    /// Performs [`beam_search`](Self::beam_search) and scores how clearly each result
+    /// stands out from the next candidate, so clients can threshold on confidence.
+    ///
+    /// See [`ScoredResults`] for the definition of the score. The search keeps one
+    /// candidate beyond the `k` results when the beam allows it, so the k-th result is
+    /// scored against the best candidate that was not returned. Catapults are learned as in
+    /// `beam_search`.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `k` - Number of nearest neighbors to return
+    /// * `beam_width` - Maximum beam size during search (must be ≥ k)
+    /// * `stats` - Statistics tracker for performance monitoring
+    ///
+    /// # Returns
+    /// The k nearest candidate entries, sorted by ascending distance, with their confidence
+    pub fn beam_search_with_confidence(
+        &self,
+        query: &[AlignedBlock],
+        k: usize,
+        beam_width: usize,
+        stats: &mut Stats,
+    ) -> ScoredResults {
+        let mut scratch = SearchScratch::<CompressedBitset>::new(beam_width);
+        let beam = self
+            .beam_search_with_scratch(
+                query,
+                (k + 1).min(beam_width),
+                beam_width,
+                stats,
+                &mut scratch,
+                &SearchOptions::learning(),
+            )
+            .results;
+        ScoredResults::from_beam(beam, k)
+    }
+
+    /// Performs [`beam_search`](Self::beam_search) but returns the results in arbitrary
+    /// order.
+    ///
+    /// The k best candidates are split off the beam in linear time instead of being sorted.
+    /// This saves work when the results feed into a merge that sorts anyway, such as a
+    /// coordinator combining the results of several shards. The result set, catapult
+    /// updates and statistics are the same as for `beam_search`.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `k` - Number of nearest neighbors to return
+    /// * `beam_width` - Maximum beam size during search (must be ≥ k)
+    /// * `stats` - Statistics tracker for performance monitoring
+    ///
+    /// # Returns
+    /// The k nearest candidate entries, in no particular order
+    pub fn beam_search_unsorted(
+        &self,
+        query: &[AlignedBlock],
+        k: usize,
+        beam_width: usize,
+        stats: &mut Stats,
+    ) -> Vec<CandidateEntry> {
+        let mut scratch = SearchScratch::<CompressedBitset>::new(beam_width);
+        let options = SearchOptions {
+            unsorted: true,
+            ..SearchOptions::learning()
+        };
+        self.beam_search_with_scratch(query, k, beam_width, stats, &mut scratch, &options)
+            .results
+    }
+
+    /// Estimates how many nodes lie within L2 distance `radius` of `query`.
+    ///
+    /// A beam search first navigates to the region of the query, then the traversal floods
+    /// outwards from the beam results inside the ball, following edges only while they lead
+    /// to nodes inside the ball. Nodes are counted as they are reached and never collected,
+    /// so memory stays bounded by the visited set.
+    ///
+    /// The count is approximate: nodes inside the ball that are only linked to from nodes
+    /// outside it are missed, as is the whole ball when the beam search does not reach it.
+    /// It never overcounts. Deleted nodes are not counted, but the flood still walks
+    /// through them, as searches do. Catapults are used to start the search but not
+    /// learned.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `radius` - Radius of the ball around the query, as an L2 distance
+    /// * `beam_width` - Beam size of the initial navigation
+    ///
+    /// # Returns
+    /// The number of nodes found within `radius` of `query`
+    pub fn approx_count_within(
+        &self,
+        query: &[AlignedBlock],
+        radius: f32,
+        beam_width: usize,
+    ) -> usize {
+        let mut stats = Stats::new();
+        let mut scratch = SearchScratch::<CompressedBitset>::new(beam_width);
+        let beam = self
+            .beam_search_with_scratch(
+                query,
+                beam_width,
+                beam_width,
+                &mut stats,
+                &mut scratch,
+                &SearchOptions::default(),
+            )
+            .results;
+
+        let query = &*self.prepare_query(query);
+        let radius_squared = radius * radius;
+        let mut reached = CompressedBitset::default();
+        let mut frontier: Vec<NodeId> = Vec::new();
+        for entry in beam {
+            reached.set(entry.index.internal);
+            if entry.distance.0 <= radius_squared {
+                frontier.push(entry.index);
+            }
+        }
+
+        let mut count = frontier.len();
+        while let Some(node) = frontier.pop() {
+            for neighbor in self.neighbors_of(node.internal) {
+                if reached.get(neighbor.internal) {
+                    continue;
+                }
+                reached.set(neighbor.internal);
+                if self.distance_to_blocks(self.payload(neighbor), query) <= radius_squared {
+                    count += usize::from(!self.is_deleted(neighbor));
+                    frontier.push(neighbor);
+                }
+            }
+        }
+        count
+    }
+
+    /// Performs [`beam_search`](Self::beam_search) and yields the whole final beam lazily,
+    /// in ascending distance order.
+    ///
+    /// The search itself runs to completion before this returns; only the ordering is
+    /// deferred. The beam is heapified in linear time and each item costs one heap pop, so
+    /// a caller that stops early, e.g. with `take_while(|c| c.distance < threshold)`, does
+    /// not pay for sorting the rest. Catapults are learned as in `beam_search`.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `beam_width` - Maximum beam size during search, and the most items yielded
+    /// * `stats` - Statistics tracker for performance monitoring
+    ///
+    /// # Returns
+    /// An iterator over the candidates of the final beam, nearest first
+    pub fn beam_search_iter(
+        &self,
+        query: &[AlignedBlock],
+        beam_width: usize,
+        stats: &mut Stats,
+    ) -> impl Iterator<Item = CandidateEntry> + use<EvictPolicy, P> {
+        let mut scratch = SearchScratch::<CompressedBitset>::new(beam_width);
+        let options = SearchOptions {
+            unsorted: true,
+            ..SearchOptions::learning()
+        };
+        let beam = self
+            .beam_search_with_scratch(query, beam_width, beam_width, stats, &mut scratch, &options)
+            .results;
+
+        let mut heap: BinaryHeap<Reverse<CandidateEntry>> = beam.into_iter().map(Reverse).collect();
+        std::iter::from_fn(move || heap.pop().map(|Reverse(entry)| entry))
+    }
+
+    /// Starts a beam search whose expanded nodes are yielded lazily, as the beam expands.
+    ///
+    /// Unlike [`beam_search_iter`](Self::beam_search_iter), which runs the search to
+    /// completion first, the returned [`SearchIter`] only does the work of the items
+    /// pulled from it: the caller picks `k` by how many it takes, and stops the search
+    /// early once a downstream condition is met. The yielded order is approximate, see
+    /// [`SearchIter`]. The search starts from the same points as `beam_search`, catapults
+    /// included, but never learns catapults, since it has no final result to learn.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `beam_width` - Maximum beam size during search
+    /// * `stats` - Statistics tracker, updated as the search advances
+    ///
+    /// # Returns
+    /// An iterator over the expanded nodes, in roughly increasing distance order
+    pub fn search_iter<'a>(
+        &'a self,
+        query: &[AlignedBlock],
+        beam_width: usize,
+        stats: &'a mut Stats,
+    ) -> SearchIter<'a, EvictPolicy, P> {
+        stats.bump_beam_calls();
+        let query = self.prepare_query(query).into_owned();
+        let (_, starting_candidates) =
+            self.starting_candidates(&query, beam_width, stats, &SearchOptions::default());
+        let mut scratch = SearchScratch::new(beam_width);
+        self.insert_into_beam(&mut scratch.candidates, &starting_candidates);
+        SearchIter {
+            graph: self,
+            query,
+            stats,
+            scratch,
+        }
+    }
+
+    /// Performs beam search with a caller-supplied additive penalty on each node's distance.
+    ///
+    /// The effective score of node `i` is `l2_squared(query, payload_i) + penalty(i)`. The
+    /// penalty is applied wherever distances are computed, so it changes both the returned
+    /// ordering and the traversal itself (which candidate is expanded next), rather than
+    /// filtering results after the fact. A positive penalty downranks a node, a negative one
+    /// boosts it.
+    ///
+    /// Penalized searches use existing catapults but never store new ones, since a
+    /// caller-specific penalty would otherwise leak into the starting points of unrelated
+    /// queries.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `k` - Number of nearest neighbors to return
+    /// * `beam_width` - Maximum beam size during search (must be ≥ k)
+    /// * `stats` - Statistics tracker for performance monitoring
+    /// * `penalty` - Additive penalty for a node index
+    ///
+    /// # Returns
+    /// A vector of the k best candidate entries, sorted by ascending penalized distance.
+    /// The reported distances include the penalty.
+    pub fn beam_search_penalized(
+        &self,
+        query: &[AlignedBlock],
+        k: usize,
+        beam_width: usize,
+        stats: &mut Stats,
+        penalty: impl Fn(usize) -> f32,
+    ) -> Vec<CandidateEntry> {
+        let mut scratch = SearchScratch::<CompressedBitset>::new(beam_width);
+        let options = SearchOptions {
+            penalty: Some(&penalty),
+            ..SearchOptions::default()
+        };
+        self.beam_search_with_scratch(query, k, beam_width, stats, &mut scratch, &options)
+            .results
+    }
+
+    /// Performs beam search, falling back to an exact scan when the result looks unreliable.
+    ///
+    /// A beam search that got stuck in a poorly connected region typically returns a best
+    /// candidate that is far from the query compared to the typical distance between
+    /// neighboring nodes. If the L2 distance to the best beam result exceeds
+    /// `confidence_factor` times [`mean_edge_length`](Self::mean_edge_length), the beam
+    /// result is discarded and [`exact_search`](Self::exact_search) is run instead, which
+    /// always returns the true nearest neighbors at the cost of one distance per node.
+    ///
+    /// The beam search learns catapults as usual; the exact scan does not.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `k` - Number of nearest neighbors to return
+    /// * `beam_width` - Maximum beam size during search (must be ≥ k)
+    /// * `stats` - Statistics tracker, counting the distances of both searches
+    /// * `confidence_factor` - Multiple of the mean edge length above which the beam
+    ///   result is not trusted. Smaller values fall back more often.
+    ///
+    /// # Returns
+    /// A vector of the k nearest candidate entries, sorted by ascending distance. An empty
+    /// beam result also falls back to the exact scan.
+    pub fn beam_search_guaranteed(
+        &self,
+        query: &[AlignedBlock],
+        k: usize,
+        beam_width: usize,
+        stats: &mut Stats,
+        confidence_factor: f32,
+    ) -> Vec<CandidateEntry> {
+        let results = self.beam_search(query, k, beam_width, stats);
+        // empty for k = 0, or when every node reached is deleted
+        let Some(best) = results.first() else {
+            return self.exact_search(query, k, stats);
+        };
+
+        if best.distance.0.sqrt() > confidence_factor * self.mean_edge_length() {
+            self.exact_search(query, k, stats)
+        } else {
+            results
+        }
+    }
+
+    /// Finds the exact k nearest neighbors by computing the distance to every node.
+    ///
+    /// Ignores the graph structure and catapults entirely, and never stores new catapults.
+    /// Useful as ground truth and as a fallback for approximate searches. Distances are
+    /// weighted by the [dimension weights](Self::with_dimension_weights), if any.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `k` - Number of nearest neighbors to return
+    /// * `stats` - Statistics tracker to update with distance computations
+    ///
+    /// # Returns
+    /// A vector of the k nearest candidate entries (fewer if the graph holds fewer than
+    /// `k` nodes), sorted by ascending distance
+    pub fn exact_search(
+        &self,
+        query: &[AlignedBlock],
+        k: usize,
+        stats: &mut Stats,
+    ) -> Vec<CandidateEntry> {
+        let query = &*self.prepare_query(query);
+        stats.bump_computed_dists(self.adjacency.len());
+        let weights = self.weights.as_deref();
+        Self::exact_top_k(&self.adjacency, 0, self.live_filter(), weights, query, k)
+    }
+
+    /// Performs [`beam_search`](Self::beam_search) and checks its results against
+    /// [`exact_search`](Self::exact_search).
+    ///
+    /// Meant for tests and offline diagnosis of recall regressions: the exact search
+    /// computes a distance to every node, so this is O(N) per query and not for serving.
+    /// Nodes tied in distance with the k-th exact neighbor may be swapped for it, which
+    /// counts as a miss. Catapults are learned by the approximate search as in
+    /// `beam_search`.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `k` - Number of nearest neighbors to return
+    /// * `beam_width` - Maximum beam size during search (must be ≥ k)
+    /// * `stats` - Statistics tracker, updated by both searches
+    ///
+    /// # Returns
+    /// The approximate results, the exact neighbors and the recall of the former against
+    /// the latter
+    pub fn beam_search_verified(
+        &self,
+        query: &[AlignedBlock],
+        k: usize,
+        beam_width: usize,
+        stats: &mut Stats,
+    ) -> VerifiedResults {
+        let results = self.beam_search(query, k, beam_width, stats);
+        let exact = self.exact_search(query, k, stats);
+        let ids = |entries: &[CandidateEntry]| -> Vec<usize> {
+            entries.iter().map(|entry| entry.index.internal).collect()
+        };
+        let recall = query_recall_at_k(&ids(&results), &ids(&exact), k);
+        VerifiedResults {
+            results,
+            exact,
+            recall,
+        }
+    }
+
+    /// Replaces the approximate results of the queries that missed a true neighbor by
+    /// their exact neighbors.
+    ///
+    /// A query missed when its recall@k against `ground_truth` (as computed by
+    /// [`query_recall_at_k`]) is below 1. Only those queries go through
+    /// [`exact_search`](Self::exact_search); the results of the other queries are returned
+    /// unchanged, so the cost of exact search is only paid where it matters.
+    ///
+    /// # Arguments
+    /// * `queries` - Query vectors as aligned blocks
+    /// * `approximate_results` - Neighbor ids already found for each query, closest first
+    /// * `ground_truth` - True neighbor ids for each query, closest first
+    /// * `k` - Number of neighbors to check and to search for on a miss
+    /// * `stats` - Statistics tracker to update with the distances computed by rescues
+    ///
+    /// # Returns
+    /// One result list per query: the exact top `k` for queries that missed, and the
+    /// approximate results otherwise
+    ///
+    /// # Panics
+    /// Panics if `queries`, `approximate_results` and `ground_truth` differ in length
+    pub fn exact_rescue(
+        &self,
+        queries: &[Vec<AlignedBlock>],
+        approximate_results: &[Vec<usize>],
+        ground_truth: &[Vec<usize>],
+        k: usize,
+        stats: &mut Stats,
+    ) -> Vec<Vec<usize>> {
+        assert!(
+            queries.len() == approximate_results.len() && queries.len() == ground_truth.len(),
+            "queries, results and ground truth must cover the same queries"
+        );
+        queries
+            .iter()
+            .zip(approximate_results)
+            .zip(ground_truth)
+            .map(|((query, found), truth)| {
+                if query_recall_at_k(found, truth, k) < 1.0 {
+                    self.exact_search(query, k, stats)
+                        .iter()
+                        .map(|e| e.index.internal)
+                        .collect()
+                } else {
+                    found.clone()
+                }
+            })
+            .collect()
+    }
+
+    /// Performs [`exact_search`](Self::exact_search) with the nodes split into contiguous
+    /// chunks scanned by `num_threads` scoped threads.
+    ///
+    /// Each thread keeps the k nearest nodes of its chunk, and the per-chunk winners are
+    /// merged at the end, so results are identical to `exact_search`. Meant for generating
+    /// ground truth over large graphs, where a single-threaded scan per query is slow.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `k` - Number of nearest neighbors to return
+    /// * `stats` - Statistics tracker to update with distance computations
+    /// * `num_threads` - Number of threads to scan with (at least 1)
+    ///
+    /// # Returns
+    /// A vector of the k nearest candidate entries (fewer if the graph holds fewer than
+    /// `k` nodes), sorted by ascending distance
+    ///
+    /// # Panics
+    /// Panics if `num_threads` is 0
+    pub fn exact_search_parallel(
+        &self,
+        query: &[AlignedBlock],
+        k: usize,
+        stats: &mut Stats,
+        num_threads: usize,
+    ) -> Vec<CandidateEntry>
+    where
+        P: Sync,
+    {
+        assert!(num_threads > 0, "exact search needs at least one thread");
+        let query = &*self.prepare_query(query);
+        stats.bump_computed_dists(self.adjacency.len());
+
+        let chunk_len = self.adjacency.len().div_ceil(num_threads).max(1);
+        let tombstones = self.live_filter();
+        let weights = self.weights.as_deref();
+        let chunk_winners: Vec<CandidateEntry> = std::thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .adjacency
+                .chunks(chunk_len)
+                .enumerate()
+                .map(|(i, chunk)| {
+                    scope.spawn(move || {
+                        Self::exact_top_k(chunk, i * chunk_len, tombstones, weights, query, k)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("exact search thread panicked"))
+                .collect()
+        });
+
+        let mut results = chunk_winners;
+        results.sort();
+        results.truncate(k);
+        results
+    }
+
+    /// Returns the deleted nodes to skip in exact searches, if any.
+    fn live_filter(&self) -> Option<&CompressedBitset> {
+        (self.num_deleted > 0).then_some(&self.tombstones)
+    }
+
+    /// Returns the k nodes of `nodes` nearest to an already rotated `query`, sorted by
+    /// ascending distance, skipping those set in `tombstones` and weighting distances by
+    /// `weights` if given. `nodes[0]` is node `first_index` of the graph.
+    fn exact_top_k(
+        nodes: &[Node<P>],
+        first_index: usize,
+        tombstones: Option<&CompressedBitset>,
+        weights: Option<&[AlignedBlock]>,
+        query: &[AlignedBlock],
+        k: usize,
+    ) -> Vec<CandidateEntry> {
+        if k == 0 {
+            return Vec::new();
+        }
+        let mut best = SmallestKCandidates::new(k);
+        for (i, node) in nodes.iter().enumerate() {
+            let index = NodeId {
+                internal: first_index + i,
+            };
+            if tombstones.is_some_and(|t| t.get(index.internal)) {
+                continue;
+            }
+            let distance = match weights {
+                Some(weights) => node.payload.l2_squared_weighted_to(query, weights),
+                None => node.payload.l2_squared_to(query),
+            };
+            best.insert_batch(&[CandidateEntry {
+                distance: distance.into(),
+                index,
+                has_catapult_ancestor: false,
+            }]);
+        }
+        best.into_iter().collect()
+    }
+
+    /// Runs [`beam_search`](Self::beam_search) for every query in order, reusing a single
+    /// candidate beam and visited set across all of them.
+    ///
+    /// Results are identical to calling `beam_search` once per query (including the
+    /// catapults learned along the way), but the per-query allocations are avoided. This is
+    /// meant to be called once per worker thread on a batch of queries.
+    ///
+    /// # Arguments
+    /// * `queries` - Query vectors as aligned blocks
+    /// * `k` - Number of nearest neighbors to return per query
+    /// * `beam_width` - Maximum beam size during search (must be ≥ k)
+    /// * `stats` - Statistics tracker accumulating over the whole batch
+    ///
+    /// # Returns
+    /// One vector of k nearest candidate entries per query, in query order
+    pub fn beam_search_batch(
+        &self,
+        queries: &[Vec<AlignedBlock>],
+        k: usize,
+        beam_width: usize,
+        stats: &mut Stats,
+    ) -> Vec<Vec<CandidateEntry>> {
+        self.beam_search_batch_with::<CompressedBitset>(queries, k, beam_width, stats)
+    }
+
+    /// Same as [`beam_search_batch`](Self::beam_search_batch), with the visited set type
+    /// chosen by the caller.
+    ///
+    /// The default [`CompressedBitset`] is a good fit for most graphs. On very large graphs
+    /// where each search touches a few thousand widely scattered nodes,
+    /// [`RoaringSet`](crate::sets::visited::RoaringSet) keeps the per-search footprint much
+    /// smaller.
+    ///
+    /// # Type Parameters
+    /// * `Visited` - The visited set implementation reused across the batch
+    pub fn beam_search_batch_with<Visited: VisitorSet + Default>(
+        &self,
+        queries: &[Vec<AlignedBlock>],
+        k: usize,
+        beam_width: usize,
+        stats: &mut Stats,
+    ) -> Vec<Vec<CandidateEntry>> {
+        let mut scratch = SearchScratch::<Visited>::new(beam_width);
+        queries
+            .iter()
+            .map(|query| {
+                self.beam_search_with_scratch(
+                    query,
+                    k,
+                    beam_width,
+                    stats,
+                    &mut scratch,
+                    &SearchOptions::learning(),
+                )
+                .results
+            })
+            .collect()
+    }
+
+    /// Performs [`beam_search`](Self::beam_search) on the buffers of `ctx`.
+    ///
+    /// Results and learned catapults are identical to `beam_search`; only the candidate beam
+    /// and visited set come from `ctx` instead of a fresh allocation.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `k` - Number of nearest neighbors to return
+    /// * `beam_width` - Maximum beam size during search (must be ≥ k)
+    /// * `stats` - Statistics tracker for performance monitoring
+    /// * `ctx` - Buffers of the calling thread, reused across its searches
+    ///
+    /// # Returns
+    /// The k nearest candidate entries, sorted by ascending distance
+    pub fn beam_search_with_ctx<Visited: VisitorSet + Default>(
+        &self,
+        query: &[AlignedBlock],
+        k: usize,
+        beam_width: usize,
+        stats: &mut Stats,
+        ctx: &mut SearchContext<Visited>,
+    ) -> Vec<CandidateEntry> {
+        self.beam_search_with_scratch(
+            query,
+            k,
+            beam_width,
+            stats,
+            ctx.scratch_for(beam_width),
+            &SearchOptions::learning(),
+        )
+        .results
+    }
+
+    /// Greedily descends the upper levels towards `query`, from their entry point down to
+    /// level 1.
+    ///
+    /// # Returns
+    /// The node reached on level 1, to start the level-0 search from
+    fn descend(
+        &self,
+        levels: &UpperLevels,
+        query: &[AlignedBlock],
+        stats: &mut Stats,
+        options: &SearchOptions<'_>,
+    ) -> NodeId {
+        let mut current = self.distances_from_indices(
+            std::iter::once(levels.entry_point()),
+            query,
+            false,
+            stats,
+            options,
+        )[0];
+        for level in (1..=levels.max_level()).rev() {
+            while let Some(closest) = self
+                .distances_from_indices(
+                    levels.to_level(current.index, level).iter(),
+                    query,
+                    false,
+                    stats,
+                    options,
+                )
+                .into_iter()
+                .min()
+                .filter(|closest| closest.distance < current.distance)
+            {
+                current = closest;
+            }
+        }
+        current.index
+    }
+
+    /// Selects and scores the points a search of `query` starts from.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector, already prepared
+    /// * `k` - Number of nearest neighbors the search returns
+    /// * `stats` - Statistics tracker for performance monitoring
+    /// * `options` - Per-search options affecting distances
+    ///
+    /// # Returns
+    /// The starting points of the query's bucket, and the scored starting candidates:
+    /// catapults first, then the starting node and entry points that are not catapults
+    fn starting_candidates(
+        &self,
+        query: &[AlignedBlock],
+        k: usize,
+        stats: &mut Stats,
+        options: &SearchOptions<'_>,
+    ) -> (StartingPoints, Vec<CandidateEntry>) {
+        let hash_search = if let SearchStrategy::LshApg(lsh_apg) = &self.strategy {
+            let mut lshapg_candidates = Vec::new();
+            for candidate_set in lsh_apg
+                .iter()
+                .map(|zorder| zorder.query_k_closest(query, 4 * k))
+            {
+                lshapg_candidates.extend(candidate_set.iter());
+            }
+            lshapg_candidates.sort();
+            lshapg_candidates.dedup();
+            StartingPoints {
+                signature: 0,
+                catapults: lshapg_candidates,
+                starting_node: self.starter.starting_node(),
+                entry_points: self.starter.entry_points().to_vec(),
+            }
+        } else {
+            self.starter.select_starting_points(query)
+        };
+        let hash_search = match &self.upper_levels {
+            Some(levels) => StartingPoints {
+                starting_node: self.descend(levels, query, stats, options),
+                ..hash_search
+            },
+            None => hash_search,
+        };
+
+        // Convert catapults to candidate entries (marked as having catapult ancestry)
+        let mut distances = self.distances_from_indices(
+            hash_search.catapults.iter().copied(),
+            query,
+            true,
+            stats,
+            options,
+        );
+        distances.sort();
+        distances.shrink_to(k);
+
+        // Add the starting node and entry points (not catapults, so marked as false),
+        // unless they are ones
+        let fixed_entries: Vec<NodeId> = std::iter::once(hash_search.starting_node)
+            .chain(hash_search.entry_points.iter().copied())
+            .filter(|node| !hash_search.catapults.contains(node))
+            .collect();
+        stats.bump_skipped_dists(1 + hash_search.entry_points.len() - fixed_entries.len());
+        let fixed_entries =
+            self.distances_from_indices(fixed_entries.into_iter(), query, false, stats, options);
+        distances.extend(fixed_entries);
+        (hash_search, distances)
+    }
+
+    /// Shared implementation of [`beam_search`](Self::beam_search) running on caller-provided buffers.
+    ///
+    /// When `options.learn_catapults` is false, existing catapults are still used as starting
+    /// points but the search result is not written back into the LSH bucket.
+    fn beam_search_with_scratch<Visited: VisitorSet>(
+        &self,
+        query: &[AlignedBlock],
+        k: usize,
+        beam_width: usize,
+        stats: &mut Stats,
+        scratch: &mut SearchScratch<Visited>,
+        options: &SearchOptions<'_>,
+    ) -> SearchOutcome {
+        let query = &*self.prepare_query(query);
+        let (hash_search, distances) = self.starting_candidates(query, k, stats, options);
+
+        let outcome =
+            self.beam_search_raw(query, &distances, k, beam_width, stats, scratch, options);
+        let search_results = &outcome.results;
+
+        if self.strategy.uses_catapults() {
+            if options.learn_catapults
+                && self.strategy.learns_catapults()
+                && self.catapult_writes_enabled()
+            {
+                self.learn_from_results(
+                    query,
+                    hash_search.signature,
+                    &hash_search.catapults,
+                    search_results,
+                    stats,
+                );
+            }
+            if search_results.iter().any(|e| e.has_catapult_ancestor) {
+                stats.bump_searches_with_catapults();
+            }
+        }
+        outcome
+    }
+
+    /// Touches the `catapults` that made it into `results` and learns the best result as
+    /// a catapult of bucket `signature`. Does nothing if `results` is empty.
+    fn learn_from_results(
+        &self,
+        query: &[AlignedBlock],
+        signature: usize,
+        catapults: &[NodeId],
+        results: &[CandidateEntry],
+        stats: &mut Stats,
+    ) {
+        // results may be unsorted, so the best one is not necessarily first, and every
+        // candidate may have been deleted
+        let Some(best_result) = results.iter().min() else {
+            return;
+        };
+        // catapults that made it into the final result earned their place
+        let useful: Vec<NodeId> = catapults
+            .iter()
+            .copied()
+            .filter(|c| results.iter().any(|e| e.index == *c))
+            .collect();
+        // touching takes the bucket's write lock only when a catapult helped
+        stats.bump_catapult_write_locks(1 + usize::from(!useful.is_empty()));
+        self.starter.touch_catapults(signature, &useful);
+        self.starter
+            .learn_catapult(query, signature, best_result.index);
+    }
+
+    /// Populates catapults by searching a batch of representative queries, e.g. at startup.
+    ///
+    /// Each query is searched once and learns its best result as a catapult of its LSH
+    /// bucket, exactly like a regular search, so that the first production queries do not
+    /// start from cold buckets. Statistics of these searches are discarded and never reach
+    /// the caller's counters.
+    ///
+    /// This mutates the catapult state shared by all searches of the graph. It should run
+    /// before concurrent serving begins: interleaved with production searches, it would
+    /// compete with them for bucket slots and locks. It does nothing on graphs that do
+    /// not learn catapults.
+    ///
+    /// # Arguments
+    /// * `queries` - Representative queries, as aligned blocks
+    /// * `k` - Number of nearest neighbors to search for
+    /// * `beam_width` - Beam width of the warm-up searches (must be ≥ k)
+    pub fn warm_up(&self, queries: &[Vec<AlignedBlock>], k: usize, beam_width: usize) {
+        self.beam_search_batch(queries, k, beam_width, &mut Stats::new());
+    }
+
+    /// Warms up catapults in rounds until recall on a held-out sample reaches `target`.
+    ///
+    /// Warmup queries are searched `round_size` at a time, learning catapults exactly like
+    /// regular searches. Before the first round and after each one, the held-out
+    /// `sample_queries` are searched *without* learning catapults and their recall@k is
+    /// measured against `ground_truth`. Warmup stops as soon as the measured recall reaches
+    /// `target`, or when the warmup queries run out. Statistics of both warmup and
+    /// evaluation searches are discarded.
+    ///
+    /// # Arguments
+    /// * `warmup_queries` - Representative queries used to populate catapults
+    /// * `sample_queries` - Held-out queries used to measure recall
+    /// * `ground_truth` - True nearest neighbor ids for each sample query
+    /// * `k` - Number of neighbors to search for and to measure recall on
+    /// * `beam_width` - Beam width for both warmup and evaluation searches
+    /// * `round_size` - Number of warmup queries between two recall measurements
+    /// * `target` - Recall in `[0, 1]` at which warmup stops
+    ///
+    /// # Returns
+    /// The number of warmup queries that were run
+    #[allow(clippy::too_many_arguments)]
+    pub fn warmup_until_recall(
+        &self,
+        warmup_queries: &[Vec<AlignedBlock>],
+        sample_queries: &[Vec<AlignedBlock>],
+        ground_truth: &[Vec<usize>],
+        k: usize,
+        beam_width: usize,
+        round_size: usize,
+        target: f64,
+    ) -> usize {
+        assert!(round_size > 0);
+        let mut scratch = SearchScratch::<CompressedBitset>::new(beam_width);
+        let mut stats = Stats::new();
+
+        let mut measure_recall = |scratch: &mut SearchScratch| {
+            let results: Vec<Vec<usize>> = sample_queries
+                .iter()
+                .map(|query| {
+                    self.beam_search_with_scratch(
+                        query,
+                        k,
+                        beam_width,
+                        &mut stats,
+                        scratch,
+                        &SearchOptions::default(),
+                    )
+                    .results
+                    .iter()
+                    .map(|e| e.index.internal)
+                    .collect()
+                })
+                .collect();
+            recall_at_k(&results, ground_truth, k)
+        };
+
+        let mut used = 0;
+        if measure_recall(&mut scratch) >= target {
+            return used;
+        }
+
+        for round in warmup_queries.chunks(round_size) {
+            let mut warmup_stats = Stats::new();
+            for query in round {
+                self.beam_search_with_scratch(
+                    query,
+                    k,
+                    beam_width,
+                    &mut warmup_stats,
+                    &mut scratch,
+                    &SearchOptions::learning(),
+                );
+            }
+            used += round.len();
+
+            if measure_recall(&mut scratch) >= target {
+                break;
+            }
+        }
+
+        used
+    }
+
+    /// Clears all cached catapults from all LSH buckets.
+    ///
+    /// This is useful for benchmarking to measure performance without the benefit
+    /// of cached starting points, or to reset state between different workloads.
+    pub fn clear_all_catapults(&self) {
+        self.starter.clear_all_catapults();
+    }
+
+    /// Sets whether searches write their best result back as a catapult.
+    ///
+    /// A learning search ends by taking the write lock of its LSH bucket, to record its
+    /// best result and touch the catapults that helped it. Queries around a popular
+    /// region all hash to the same few buckets, so under heavy concurrency these writes
+    /// serialize on a handful of locks. Disabling them keeps reading the catapults already
+    /// learned, and the [`catapult write locks`](Stats::get_catapult_write_locks) counted
+    /// by searches show the locking that was saved.
+    ///
+    /// Unlike [`SearchStrategy::CatapultReadOnly`], which is fixed when the graph is built,
+    /// this takes effect on the next searches of a graph already shared between threads.
+    /// It has no effect on strategies that do not learn catapults.
+    ///
+    /// # Arguments
+    /// * `enabled` - Whether searches learn catapults, the default
+    pub fn set_catapult_writes(&self, enabled: bool) {
+        self.catapult_writes.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Returns whether searches write their best result back as a catapult, see
+    /// [`set_catapult_writes`](Self::set_catapult_writes).
+    pub fn catapult_writes_enabled(&self) -> bool {
+        self.catapult_writes.load(Ordering::Relaxed)
+    }
+
+    /// Clears the catapults of a range of LSH buckets, leaving the others untouched.
+    ///
+    /// See [`EngineStarter::clear_bucket_range`].
+    ///
+    /// # Panics
+    /// Panics if the range is reversed or reaches past the number of buckets, `2^num_hash`
+    pub fn clear_catapult_buckets(&self, signatures: Range<usize>) {
+        self.starter.clear_bucket_range(signatures);
+    }
+
+    /// Ages all learned catapults by one decay cycle, dropping those unused for too long.
+    ///
+    /// Only eviction policies tracking last use, such as
+    /// [`DecaySet`](crate::sets::catapults::DecaySet), drop anything; with the others this
+    /// only takes and releases every bucket's lock. Meant to be called periodically, e.g.
+    /// every few thousand queries, concurrently with searches.
+    pub fn decay_catapults(&self) {
+        self.starter.decay_catapults();
+    }
+
+    /// Lazily iterates over all learned catapults as `(bucket signature, catapult)` pairs.
+    ///
+    /// Catapults belong to LSH buckets rather than to graph nodes, so the source of each
+    /// pair is the signature of the bucket holding it. See
+    /// [`EngineStarter::iter_catapults`] for the locking behavior.
+    pub fn iter_catapults(&self) -> impl Iterator<Item = (usize, NodeId)> + '_ {
+        self.starter.iter_catapults()
+    }
+
+    /// Collects all learned catapults as `(bucket signature, catapult)` pairs.
+    pub fn export_catapult_edges(&self) -> Vec<(usize, NodeId)> {
+        self.starter.export_catapult_edges()
+    }
+
+    /// Returns the catapults currently learned for one LSH bucket.
+    ///
+    /// The bucket is read-locked while its catapults are copied out. See
+    /// [`EngineStarter::bucket_catapults`].
+    ///
+    /// # Arguments
+    /// * `signature` - The LSH signature (bucket index) of the queries the catapults serve
+    ///
+    /// # Panics
+    /// Panics if `signature` is not below the number of buckets, `2^num_hash`
+    pub fn bucket_catapults(&self, signature: usize) -> Vec<NodeId> {
+        self.starter.bucket_catapults(signature)
+    }
+
+    /// Returns the number of catapults learned across all LSH buckets.
+    ///
+    /// This reads a counter maintained on every insertion and eviction, without taking
+    /// any bucket lock.
+    pub fn total_catapults(&self) -> usize {
+        self.starter.catapult_count()
+    }
+
+    /// Writes every learned catapult to `path`.
+    ///
+    /// See [`EngineStarter::save_catapults`] for the file format.
+    ///
+    /// # Errors
+    /// Returns any I/O error raised while creating or writing the file
+    pub fn save_catapults(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.starter.save_catapults(path)
+    }
+
+    /// Replaces all catapults with the ones stored at `path` by
+    /// [`save_catapults`](Self::save_catapults).
+    ///
+    /// See [`EngineStarter::load_catapults`]. The file must also have been saved for a
+    /// graph with at least as many nodes, as every catapult it references must be a node
+    /// of this graph.
+    ///
+    /// # Errors
+    /// Returns an `InvalidData` error if the hasher configuration differs, a catapult is
+    /// not below [`len`](Self::len) or the file is malformed, or any I/O error raised
+    /// while reading. On error, the catapults of the graph are left untouched.
+    pub fn load_catapults(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.starter.load_catapults_below(path, self.len())
+    }
+
+    /// Returns the number of nodes in the graph.
+    ///
+    /// # Returns
+    /// The total number of nodes stored in the adjacency list
+    #[allow(clippy::len_without_is_empty)] // checking the size of the graph is fine, but it is never ever empty
+    pub fn len(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    /// Returns the vector stored for a node, e.g. one returned by a search.
+    ///
+    /// With the default payload, this is the node's aligned blocks and derefs to
+    /// `&[AlignedBlock]`. Transformed or rotated graphs return the vector as stored, in
+    /// the space queries are mapped to before searching. Deleted nodes keep their payload
+    /// until [`compaction`](Self::compaction).
+    ///
+    /// # Arguments
+    /// * `id` - The node whose payload to return
+    ///
+    /// # Returns
+    /// The payload of the node
+    ///
+    /// # Panics
+    /// Panics if `id` is not a node of the graph, i.e. `id.internal >= self.len()`
+    pub fn payload(&self, id: NodeId) -> &P {
+        &self.adjacency[id.internal].payload
+    }
+
+    /// Returns the logical dimension of the stored vectors in f32 elements.
+    ///
+    /// Payloads and queries are zero-padded up to the next multiple of `SIMD_LANECOUNT`,
+    /// which leaves L2 distances and dot products unchanged; this is the dimension before
+    /// padding, as found in the payload file and used by the LSH hasher.
+    pub fn dim(&self) -> usize {
+        self.starter.plane_dim()
+    }
+
+    /// Returns the total number of directed edges in the graph (sum of all neighbor list lengths). Does not include catapult edges.
+    pub fn total_edge_count(&self) -> usize {
+        match &self.csr {
+            Some(csr) => csr.num_edges(),
+            None => self.adjacency.iter().map(|n| n.neighbors.len()).sum(),
+        }
+    }
+
+    /// Returns the mean L2 length of the graph's edges. Does not include catapult edges.
+    ///
+    /// Lengths are weighted by the [dimension weights](Self::with_dimension_weights), if
+    /// any. The value is computed over every directed edge on the first call, which costs
+    /// one distance computation per edge, and cached afterwards.
+    ///
+    /// # Returns
+    /// The mean Euclidean distance between the endpoints of an edge, or 0.0 if the graph
+    /// has no edges
+    pub fn mean_edge_length(&self) -> f32 {
+        *self.mean_edge_length.get_or_init(|| {
+            let mut total = 0.0f64;
+            let mut count = 0usize;
+            for (i, node) in self.adjacency.iter().enumerate() {
+                let from = node.payload.to_blocks();
+                for neighbor in self.neighbors_of(i) {
+                    let neighbor = &self.adjacency[neighbor.internal].payload;
+                    total += self.distance_to_blocks(neighbor, &from).sqrt() as f64;
+                    count += 1;
+                }
+            }
+            if count == 0 {
+                0.0
+            } else {
+                (total / count as f64) as f32
+            }
+        })
+    }
+
+    /// Measures how far the stored payloads are from their full-precision originals.
+    ///
+    /// Computes the mean, over all nodes, of the squared L2 distance between the payload
+    /// as stored in the graph (reconstructed to full precision) and `original[i]`. This is meant to validate a quantization
+    /// configuration before serving: a graph storing exact f32 payloads has an error of
+    /// zero, and lossy storage shows up as a positive value on the same scale as the
+    /// distances returned by search.
+    ///
+    /// # Arguments
+    /// * `original` - Full-precision vector of every node, in node order
+    ///
+    /// # Returns
+    /// The mean squared reconstruction error per vector
+    ///
+    /// # Panics
+    /// Panics if `original` does not hold exactly one vector per node, or if a vector's
+    /// dimension differs from the stored payload's
+    pub fn quantization_error(&self, original: &[Vec<AlignedBlock>]) -> f32 {
+        assert_eq!(
+            original.len(),
+            self.adjacency.len(),
+            "expected one original vector per node"
+        );
+
+        let total: f64 = self
+            .adjacency
+            .iter()
+            .zip(original)
+            .map(|(node, orig)| node.payload.to_blocks().l2_squared(orig) as f64)
+            .sum();
+        (total / self.adjacency.len() as f64) as f32
+    }
+
+    /// Re-ranks search results by their exact distance to full-precision vectors.
+    ///
+    /// Searches on a [`quantize`](AdjacencyGraph::quantize)d graph rank nodes by distances
+    /// to lossy payloads, which can swap nodes whose distances differ by less than the
+    /// quantization error. Searching for more candidates than needed and re-ranking them
+    /// against the original vectors recovers the exact order among those candidates.
+    /// Distances are weighted like the search's if the graph has dimension weights.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks, as passed to the search
+    /// * `candidates` - Results of a search on this graph
+    /// * `full_precision` - Full-precision vector of every node, in node order and in the
+    ///   space the payloads are stored in, as for
+    ///   [`quantization_error`](Self::quantization_error)
+    /// * `k` - Number of candidates to keep
+    ///
+    /// # Returns
+    /// The `k` candidates closest to the query by exact distance, sorted by ascending
+    /// distance, with their distances replaced by the exact ones
+    ///
+    /// # Panics
+    /// Panics if `full_precision` does not hold exactly one vector per node
+    pub fn rerank(
+        &self,
+        query: &[AlignedBlock],
+        candidates: Vec<CandidateEntry>,
+        full_precision: &[Vec<AlignedBlock>],
+        k: usize,
+    ) -> Vec<CandidateEntry> {
+        assert_eq!(
+            full_precision.len(),
+            self.adjacency.len(),
+            "expected one full-precision vector per node"
+        );
+
+        let query = &*self.prepare_query(query);
+        let mut reranked: Vec<CandidateEntry> = candidates
+            .into_iter()
+            .map(|candidate| {
+                let vector = &full_precision[candidate.index.internal];
+                let distance = match &self.weights {
+                    Some(weights) => vector.l2_squared_weighted(query, weights),
+                    None => vector.l2_squared(query),
+                };
+                CandidateEntry {
+                    distance: distance.into(),
+                    ..candidate
+                }
+            })
+            .collect();
+        reranked.sort();
+        reranked.truncate(k);
+        reranked
+    }
+
+    /// Performs [`beam_search`](Self::beam_search) for `ef` candidates and
+    /// [`rerank`](Self::rerank)s them down to the `k` best by exact distance.
+    ///
+    /// Catapults are learned from the search's own ranking, before re-ranking.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `k` - Number of nearest neighbors to return
+    /// * `ef` - Number of candidates to re-rank (must be ≥ k)
+    /// * `beam_width` - Maximum beam size during search (must be ≥ ef)
+    /// * `full_precision` - Full-precision vector of every node, see `rerank`
+    /// * `stats` - Statistics tracker for performance monitoring
+    ///
+    /// # Returns
+    /// The k nearest candidate entries by exact distance, sorted by ascending distance
+    ///
+    /// # Panics
+    /// Panics if `ef < k`, or if `full_precision` does not hold exactly one vector per node
+    pub fn beam_search_reranked(
+        &self,
+        query: &[AlignedBlock],
+        k: usize,
+        ef: usize,
+        beam_width: usize,
+        full_precision: &[Vec<AlignedBlock>],
+        stats: &mut Stats,
+    ) -> Vec<CandidateEntry> {
+        assert!(ef >= k, "cannot re-rank {ef} candidates down to {k}");
+        let candidates = self.beam_search(query, ef, beam_width, stats);
+        stats.bump_computed_dists(candidates.len());
+        self.rerank(query, candidates, full_precision, k)
+    }
+}
+
+/// Hashes the exact bits of a payload, so that equal vectors always get equal hashes.
+fn hash_payload<P: Payload>(payload: &P) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for block in payload.to_blocks() {
+        for x in block.data {
+            x.to_bits().hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        numerics::SIMD_LANECOUNT,
+        search::{
+            GraphBuilder, SearchStrategy,
+            hash_start::{EngineStarter, EngineStarterParams, zorder_index::ZOrderIndex},
+        },
+        sets::{catapults::LruSet, fixed::FlatFixedSet},
+    };
+
+    pub type TestEngineStarter = EngineStarter<LruSet>;
+
+    use super::*;
+
+    // A simple graph:
+    // Nodes: 0 (pos 0), 1 (pos 10), 2 (pos 20), 3 (pos 30), 4 (pos 40)
+    // Edges: 0 -> 1 -> 2 -> 3 -> 4
+    // Query: 11.0
+    fn setup_simple_graph(catapults_enabled: bool) -> AdjacencyGraph<LruSet> {
+        let nodes = vec![
+            // 0: Pos 0.0, Neighbors: [1]
+            Node {
+                payload: vec![AlignedBlock::new([0.0; SIMD_LANECOUNT])].into_boxed_slice(),
+                neighbors: FlatFixedSet::new(vec![1]),
+            },
+            // 1: Pos 10.0, Neighbors: [2]
+            Node {
+                payload: vec![AlignedBlock::new([10.0; SIMD_LANECOUNT])].into_boxed_slice(),
+                neighbors: FlatFixedSet::new(vec![2]),
+            },
+            // 2: Pos 20.0, Neighbors: [1, 3]
+            Node {
+                payload: vec![AlignedBlock::new([20.0; SIMD_LANECOUNT])].into_boxed_slice(),
+                neighbors: FlatFixedSet::new(vec![1, 3]),
+            },
+            // 3: Pos 30.0, Neighbors: [4]
+            Node {
+                payload: vec![AlignedBlock::new([30.0; SIMD_LANECOUNT])].into_boxed_slice(),
+                neighbors: FlatFixedSet::new(vec![4]),
+            },
+            // 4: Pos 40.0, Neighbors: [1]
+            Node {
+                payload: vec![AlignedBlock::new([40.0; SIMD_LANECOUNT])].into_boxed_slice(),
+                neighbors: FlatFixedSet::new(vec![1]),
+            },
+        ];
+        let strategy = if catapults_enabled {
+            SearchStrategy::Catapult
+        } else {
+            SearchStrategy::Vanilla
+        };
+        // Start from node 0
+        let params = EngineStarterParams::new(
+            4,
+            40,
+            SIMD_LANECOUNT,
+            NodeId { internal: 0 },
+            42,
+            catapults_enabled,
+        );
+        AdjacencyGraph::new_flat(nodes, EngineStarter::new(params), strategy)
+    }
+
+    #[test]
+    fn lshapg() {
+        let nodes = vec![
+            // 0: Pos 0.0, Neighbors: [1]
+            Node {
+                payload: vec![AlignedBlock::new([0.0; SIMD_LANECOUNT])].into_boxed_slice(),
+                neighbors: FlatFixedSet::new(vec![1]),
+            },
+            // 1: Pos 10.0, Neighbors: [2]
+            Node {
+                payload: vec![AlignedBlock::new([10.0; SIMD_LANECOUNT])].into_boxed_slice(),
+                neighbors: FlatFixedSet::new(vec![2]),
+            },
+            // 2: Pos 20.0, Neighbors: [1, 3]
+            Node {
+                payload: vec![AlignedBlock::new([20.0; SIMD_LANECOUNT])].into_boxed_slice(),
+                neighbors: FlatFixedSet::new(vec![1, 3]),
+            },
+            // 3: Pos 30.0, Neighbors: [4]
+            Node {
+                payload: vec![AlignedBlock::new([30.0; SIMD_LANECOUNT])].into_boxed_slice(),
+                neighbors: FlatFixedSet::new(vec![4]),
+            },
+            // 4: Pos 40.0, Neighbors: [1]
+            Node {
+                payload: vec![AlignedBlock::new([40.0; SIMD_LANECOUNT])].into_boxed_slice(),
+                neighbors: FlatFixedSet::new(vec![1]),
+            },
+        ];
+        let strategy = SearchStrategy::LshApg([ZOrderIndex::new(2, 16, 0, 1.0)]);
+        // Start from node 0
+        let params =
+            EngineStarterParams::new(4, 40, SIMD_LANECOUNT, NodeId { internal: 0 }, 42, false);
+        let lshapg: AdjacencyGraph<LruSet> =
+            AdjacencyGraph::new_flat(nodes, EngineStarter::new(params), strategy);
+        let mut stats = Stats::new();
+
+        assert_eq!(
+            lshapg.beam_search(
+                &[AlignedBlock::new([40.0; SIMD_LANECOUNT])],
+                5,
+                5,
+                &mut stats
+            )[0]
+            .index,
+            NodeId { internal: 4 }
+        );
+    }
+
+    #[test]
+    fn test_basic_search_path() {
+        let graph = setup_simple_graph(true);
+        let query = vec![AlignedBlock::new([11.0; SIMD_LANECOUNT])];
+        let k = 2;
+        let beam_width = 3;
+
+        // Distances: 0(121), 1(1), 2(81), 3(361), 4(841)
+        let mut stats = crate::statistics::Stats::new();
+        stats.enable_adv_tracking();
+
+        let results1 = graph.beam_search(&query, k, beam_width, &mut stats);
+        let results2 = graph.beam_search(&query, k, beam_width, &mut stats);
+
+        assert_eq!(stats.get_searches_with_catapults(), 1);
+
+        assert_eq!(
+            results1.iter().map(|e| e.index).collect::<Vec<_>>(),
+            results2.iter().map(|e| e.index).collect::<Vec<_>>()
+        );
+
+        // Final candidates (in order of distance): 1 (1), 2 (81), 0 (121)
+        // Top K=2 results: [1, 2]
+        assert_eq!(results1.len(), k);
+        assert_eq!(results1[0].index.internal, 1);
+        assert_eq!(results1[1].index.internal, 2);
+
+        // Verify the graph structure - count total edges
+        let total_edges: usize = graph
+            .adjacency
+            .iter()
+            .map(|n| n.neighbors.to_slice().len())
+            .sum();
+        assert_eq!(total_edges, graph.total_edge_count());
+    }
+
+    #[test]
+    fn test_batch_search_matches_individual_searches() {
+        let batched_graph = setup_simple_graph(true);
+        let single_graph = setup_simple_graph(true);
+
+        let queries: Vec<Vec<AlignedBlock>> = [11.0, 39.0, 11.0, 2.0, 25.0]
+            .iter()
+            .map(|&v| vec![AlignedBlock::new([v; SIMD_LANECOUNT])])
+            .collect();
+
+        let mut batched_stats = Stats::new();
+        let batched = batched_graph.beam_search_batch(&queries, 2, 3, &mut batched_stats);
+
+        let mut single_stats = Stats::new();
+        let singles: Vec<Vec<CandidateEntry>> = queries
+            .iter()
+            .map(|q| single_graph.beam_search(q, 2, 3, &mut single_stats))
+            .collect();
+
+        assert_eq!(batched, singles);
+        assert_eq!(
+            batched_stats.get_computed_dists(),
+            single_stats.get_computed_dists()
+        );
+        assert_eq!(
+            batched_stats.get_searches_with_catapults(),
+            single_stats.get_searches_with_catapults()
+        );
+    }
+
+    #[test]
+    fn test_search_context_matches_fresh_buffers() {
+        let ctx_graph = setup_simple_graph(true);
+        let fresh_graph = setup_simple_graph(true);
+        let mut ctx = SearchContext::<CompressedBitset>::new();
+
+        // the beam width changes midway, which resizes the context's buffers
+        for (value, beam_width) in [(11.0, 3), (39.0, 3), (11.0, 2), (2.0, 4), (25.0, 2)] {
+            let query = vec![AlignedBlock::new([value; SIMD_LANECOUNT])];
+            let (mut ctx_stats, mut fresh_stats) = (Stats::new(), Stats::new());
+            assert_eq!(
+                ctx_graph.beam_search_with_ctx(&query, 2, beam_width, &mut ctx_stats, &mut ctx),
+                fresh_graph.beam_search(&query, 2, beam_width, &mut fresh_stats)
+            );
+            assert_eq!(
+                ctx_stats.get_nodes_visited(),
+                fresh_stats.get_nodes_visited()
+            );
+        }
+    }
+
+    #[test]
+    fn test_beam_search_l2_takes_square_roots_of_results() {
+        let graph = setup_simple_graph(false);
+        let query = vec![AlignedBlock::new([11.0; SIMD_LANECOUNT])];
+        let mut stats = Stats::new();
+
+        // 16 lanes at 1.0 and 9.0 from the query
+        assert_eq!(
+            graph.beam_search_l2(&query, 2, 3, &mut stats),
+            vec![
+                (NodeId { internal: 1 }, Distance::L2(4.0)),
+                (NodeId { internal: 2 }, Distance::L2(36.0))
+            ]
+        );
+        let squared = graph.beam_search(&query, 2, 3, &mut stats);
+        assert_eq!(squared[0].squared_l2_distance(), Distance::SquaredL2(16.0));
+    }
+
+    fn graph_with_payloads(payloads: Vec<Vec<AlignedBlock>>) -> AdjacencyGraph<LruSet> {
+        let nodes = payloads
+            .into_iter()
+            .map(|payload| Node {
+                payload: payload.into_boxed_slice(),
+                neighbors: FlatFixedSet::new(vec![]),
+            })
+            .collect();
+        let params =
+            EngineStarterParams::new(2, 4, SIMD_LANECOUNT, NodeId { internal: 0 }, 42, false);
+        AdjacencyGraph::new_flat(nodes, EngineStarter::new(params), SearchStrategy::Vanilla)
+    }
+
+    fn random_payloads(n: usize, seed: u64) -> Vec<Vec<AlignedBlock>> {
+        use rand::prelude::*;
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..n)
+            .map(|_| {
+                let mut data = [0.0; SIMD_LANECOUNT];
+                for v in data.iter_mut() {
+                    *v = rng.random_range(-10.0..10.0);
+                }
+                vec![AlignedBlock::new(data)]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_quantization_error_is_zero_for_exact_payloads() {
+        let original = random_payloads(100, 1);
+        let graph = graph_with_payloads(original.clone());
+        assert_eq!(graph.quantization_error(&original), 0.0);
+    }
+
+    #[test]
+    fn test_quantization_error_of_uniform_rounding() {
+        // rounding to a grid of step s adds an error uniform in [-s/2, s/2] to every
+        // coordinate, whose mean square is s^2 / 12
+        let step = 0.5;
+        let original = random_payloads(4000, 2);
+        let rounded = original
+            .iter()
+            .map(|v| {
+                v.iter()
+                    .map(|b| AlignedBlock::new(b.data.map(|x| (x / step).round() * step)))
+                    .collect()
+            })
+            .collect();
+        let graph = graph_with_payloads(rounded);
+
+        let expected = SIMD_LANECOUNT as f32 * step * step / 12.0;
+        let error = graph.quantization_error(&original);
+        assert!(
+            (error - expected).abs() < 0.05 * expected,
+            "error={error} expected={expected}"
+        );
+    }
+
+    #[test]
+    fn test_quantization_error_of_quantized_graph() {
+        let original = random_payloads(200, 4);
+        let graph = graph_with_payloads(original.clone()).quantize();
+
+        // values span [-10, 10], so each coordinate is off by at most 10 / 127 / 2
+        let max_error = SIMD_LANECOUNT as f32 * (10.0f32 / 127.0 / 2.0).powi(2);
+        let error = graph.quantization_error(&original);
+        assert!(error > 0.0 && error <= max_error, "error={error}");
+    }
+
+    #[test]
+    fn test_quantized_graph_search_matches_full_precision() {
+        let full = setup_simple_graph(false);
+        let quantized = setup_simple_graph(false).quantize();
+
+        for q in [0.0, 9.0, 22.0, 31.0, 40.0] {
+            let query = vec![AlignedBlock::new([q; SIMD_LANECOUNT])];
+            let mut stats = Stats::new();
+            let indices =
+                |r: Vec<CandidateEntry>| r.iter().map(|e| e.index.internal).collect::<Vec<_>>();
+            assert_eq!(
+                indices(quantized.beam_search(&query, 2, 3, &mut stats)),
+                indices(full.beam_search(&query, 2, 3, &mut stats))
+            );
+        }
+    }
+
+    #[test]
+    fn test_binarized_graph_finds_hamming_neighbors() {
+        use rand::prelude::*;
+
+        let mut rng = StdRng::seed_from_u64(8);
+        let mut random_code = || {
+            AlignedBlock::allocate_padded((0..40).map(|_| rng.random_range(0..2) as f32).collect())
+        };
+        let codes: Vec<Vec<AlignedBlock>> = (0..300).map(|_| random_code()).collect();
+        let params = EngineStarterParams::new(4, 4, 40, NodeId { internal: 0 }, 42, false);
+        let graph = AdjacencyGraph::<LruSet>::new_flat(
+            crate::search::GraphBuilder::new(12).build(&codes),
+            EngineStarter::new(params),
+            SearchStrategy::Vanilla,
+        )
+        .binarize();
+        assert_eq!(graph.payload(NodeId { internal: 7 }).to_blocks(), codes[7]);
+
+        for _ in 0..10 {
+            let query = random_code();
+            let hamming = |code: &[AlignedBlock]| code.l2_squared(&query);
+            let best = codes
+                .iter()
+                .map(|c| hamming(c))
+                .fold(f32::INFINITY, f32::min);
+            let results = graph.beam_search(&query, 1, 64, &mut Stats::new());
+            assert_eq!(results[0].distance, best.into());
+            assert_eq!(hamming(&codes[results[0].index.internal]), best);
+        }
+    }
+
+    #[test]
+    fn test_beam_search_with_params_matches_explicit_arguments() {
+        let graph = setup_simple_graph(false);
+        let query = vec![AlignedBlock::new([31.0; SIMD_LANECOUNT])];
+        let params = SearchParams::new().with_k(2).with_beam_width(3);
+        let outcome = graph.beam_search_with(&query, &params, &mut Stats::new());
+        assert!(outcome.converged());
+        assert_eq!(
+            outcome.results,
+            graph.beam_search(&query, 2, 3, &mut Stats::new())
+        );
+
+        let capped = graph.beam_search_with(
+            &query,
+            &params.with_max_iterations(1).with_distance_budget(100),
+            &mut Stats::new(),
+        );
+        assert_eq!(capped.termination, Termination::IterationCap);
+    }
+
+    #[test]
+    fn test_beam_search_with_params_can_skip_learning() {
+        let graph = setup_simple_graph(true);
+        let query = vec![AlignedBlock::new([40.0; SIMD_LANECOUNT])];
+        let params = SearchParams::new().with_k(1).with_beam_width(2);
+        graph.beam_search_with(
+            &query,
+            &params.clone().with_catapult_learning(false),
+            &mut Stats::new(),
+        );
+        assert_eq!(graph.export_catapult_edges(), vec![]);
+
+        graph.beam_search_with(&query, &params, &mut Stats::new());
+        assert_eq!(graph.export_catapult_edges().len(), 1);
+    }
+
+    #[test]
+    fn test_payload_of_a_search_result_is_at_distance_zero() {
+        let graph = setup_simple_graph(false);
+        let query = vec![AlignedBlock::new([21.0; SIMD_LANECOUNT])];
+        let best = graph.beam_search(&query, 1, 2, &mut Stats::new())[0].index;
+        let payload: &[AlignedBlock] = graph.payload(best);
+        assert_eq!(payload, [AlignedBlock::new([20.0; SIMD_LANECOUNT])]);
+        assert_eq!(payload.l2_squared(payload), 0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_payload_panics_past_the_last_node() {
+        setup_simple_graph(false).payload(NodeId { internal: 5 });
+    }
+
+    #[test]
+    fn test_triangle_pruning_skips_distances_without_changing_results() {
+        use rand::prelude::*;
+
+        let mut rng = StdRng::seed_from_u64(21);
+        let points: Vec<Vec<AlignedBlock>> = (0..400)
+            .map(|_| point_2d(rng.random_range(0.0..100.0), rng.random_range(0.0..100.0)))
+            .collect();
+        let plain = built_graph(&points);
+        let mut pruned = built_graph(&points);
+        pruned.use_triangle_pruning();
+        assert!(pruned.uses_triangle_pruning());
+
+        let (mut plain_stats, mut pruned_stats) = (Stats::new(), Stats::new());
+        for _ in 0..30 {
+            let query = point_2d(rng.random_range(0.0..100.0), rng.random_range(0.0..100.0));
+            assert_eq!(
+                pruned.beam_search(&query, 5, 16, &mut pruned_stats),
+                plain.beam_search(&query, 5, 16, &mut plain_stats)
+            );
+        }
+        assert_eq!(plain_stats.get_pruned_dists(), 0);
+        assert!(pruned_stats.get_pruned_dists() > 0);
+        assert!(pruned_stats.get_computed_dists() < plain_stats.get_computed_dists());
+    }
+
+    #[test]
+    fn test_triangle_pruning_follows_insertions() {
+        let mut graph = setup_simple_graph(false);
+        graph.use_triangle_pruning();
+        let new = graph.insert(
+            vec![AlignedBlock::new([50.0; SIMD_LANECOUNT])].into_boxed_slice(),
+            vec![4],
+            Some(4),
+        );
+        let query = vec![AlignedBlock::new([49.0; SIMD_LANECOUNT])];
+        let results = graph.beam_search(&query, 1, 1, &mut Stats::new());
+        assert_eq!(results[0].index, new);
+    }
+
+    #[test]
+    fn test_verified_search_flags_neighbors_the_graph_cannot_reach() {
+        // 0 → 1 ⇄ 3, so the search from node 0 never sees node 2, the true best for 20
+        let edges = [vec![1], vec![3], vec![1], vec![1]];
+        let nodes = edges
+            .into_iter()
+            .enumerate()
+            .map(|(i, neighbors)| Node {
+                payload: vec![AlignedBlock::new([10.0 * i as f32; SIMD_LANECOUNT])]
+                    .into_boxed_slice(),
+                neighbors: FlatFixedSet::new(neighbors),
+            })
+            .collect();
+        let params =
+            EngineStarterParams::new(4, 8, SIMD_LANECOUNT, NodeId { internal: 0 }, 42, false);
+        let graph: AdjacencyGraph<LruSet> =
+            AdjacencyGraph::new_flat(nodes, EngineStarter::new(params), SearchStrategy::Vanilla);
+
+        let query = vec![AlignedBlock::new([20.0; SIMD_LANECOUNT])];
+        let verified = graph.beam_search_verified(&query, 2, 4, &mut Stats::new());
+        assert_eq!(verified.recall, 0.5);
+        assert!(!verified.is_exact());
+        assert_eq!(verified.missed(), vec![NodeId { internal: 2 }]);
+
+        let query = vec![AlignedBlock::new([0.0; SIMD_LANECOUNT])];
+        assert!(
+            graph
+                .beam_search_verified(&query, 2, 4, &mut Stats::new())
+                .is_exact()
+        );
+    }
+
+    #[test]
+    fn test_reranking_recovers_recall_lost_to_quantization() {
+        use rand::prelude::*;
+
+        // two fixed outlier lanes stretch every vector's range, so quantization error
+        // swamps the small differences that decide the nearest neighbors
+        let mut rng = StdRng::seed_from_u64(12);
+        let mut clustered = || {
+            let mut data = [0.0; SIMD_LANECOUNT];
+            for v in data.iter_mut() {
+                *v = rng.random_range(-0.05..0.05);
+            }
+            data[0] = 10.0;
+            data[1] = -10.0;
+            vec![AlignedBlock::new(data)]
+        };
+        let points: Vec<Vec<AlignedBlock>> = (0..300).map(|_| clustered()).collect();
+        let queries: Vec<Vec<AlignedBlock>> = (0..20).map(|_| clustered()).collect();
+        let graph = built_graph(&points).quantize();
+
+        let k = 10;
+        let (mut raw_hits, mut reranked_hits) = (0, 0);
+        for query in &queries {
+            let mut exact: Vec<usize> = (0..points.len()).collect();
+            exact.sort_by(|&a, &b| {
+                points[a]
+                    .l2_squared(query)
+                    .total_cmp(&points[b].l2_squared(query))
+            });
+            let truth = &exact[..k];
+            let hits = |results: &[CandidateEntry]| {
+                results
+                    .iter()
+                    .filter(|r| truth.contains(&r.index.internal))
+                    .count()
+            };
+
+            let raw = graph.beam_search(query, k, 64, &mut Stats::new());
+            let reranked = graph.beam_search_reranked(query, k, 64, 64, &points, &mut Stats::new());
+            assert!(reranked.is_sorted());
+            assert_eq!(
+                reranked[0].distance,
+                points[reranked[0].index.internal].l2_squared(query).into()
+            );
+            raw_hits += hits(&raw);
+            reranked_hits += hits(&reranked);
+        }
+        assert!(
+            reranked_hits > raw_hits,
+            "reranked={reranked_hits} raw={raw_hits}"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "expected one original vector per node")]
+    fn test_quantization_error_rejects_wrong_count() {
+        let original = random_payloads(3, 3);
+        let graph = graph_with_payloads(original.clone());
+        graph.quantization_error(&original[..2]);
+    }
+
+    #[test]
+    fn test_iter_catapults_matches_export_on_warmed_graph() {
+        let graph = setup_simple_graph(true);
+        let mut stats = Stats::new();
+        for q in [1.0, 12.0, 24.0, 33.0, 39.0, -50.0] {
+            let query = vec![AlignedBlock::new([q; SIMD_LANECOUNT])];
+            graph.beam_search(&query, 1, 2, &mut stats);
+        }
+
+        let streamed: Vec<(usize, NodeId)> = graph.iter_catapults().collect();
+        assert!(!streamed.is_empty());
+        assert_eq!(streamed, graph.export_catapult_edges());
+        assert_eq!(streamed.len(), graph.starter.catapult_count());
+    }
+
+    #[test]
+    fn test_bucket_catapults_hold_the_landing_node_of_a_search() {
+        let graph = setup_simple_graph(true);
+        assert_eq!(graph.total_catapults(), 0);
+
+        let query = vec![AlignedBlock::new([39.0; SIMD_LANECOUNT])];
+        let signature = graph.starter.signature(&query);
+        let mut stats = Stats::new();
+        let best = graph.beam_search(&query, 1, 2, &mut stats)[0].index;
+
+        assert_eq!(graph.bucket_catapults(signature), vec![best]);
+        assert_eq!(graph.total_catapults(), 1);
+        let other = vec![AlignedBlock::new([-39.0; SIMD_LANECOUNT])];
+        graph.beam_search(&other, 1, 2, &mut stats);
+        assert_eq!(graph.total_catapults(), graph.export_catapult_edges().len());
+    }
+
+    #[test]
+    fn test_penalty_flips_top_result() {
+        let graph = setup_simple_graph(false);
+        let query = vec![AlignedBlock::new([38.0; SIMD_LANECOUNT])];
+        let mut stats = Stats::new();
+
+        let plain = graph.beam_search(&query, 2, 3, &mut stats);
+        assert_eq!(plain[0].index.internal, 4);
+
+        // node 4 is out of stock: push it far away
+        let out_of_stock = |i: usize| if i == 4 { 1e6 } else { 0.0 };
+        let penalized = graph.beam_search_penalized(&query, 2, 3, &mut stats, out_of_stock);
+        assert_eq!(penalized[0].index.internal, 3);
+        assert_eq!(penalized[1].index.internal, 2);
+
+        // a zero penalty changes nothing
+        let neutral = graph.beam_search_penalized(&query, 2, 3, &mut stats, |_| 0.0);
+        assert_eq!(neutral, plain);
+    }
+
+    #[test]
+    fn test_penalty_is_included_in_distances() {
+        let graph = setup_simple_graph(false);
+        let query = vec![AlignedBlock::new([10.0; SIMD_LANECOUNT])];
+        let mut stats = Stats::new();
+
+        let results = graph.beam_search_penalized(&query, 1, 3, &mut stats, |i| i as f32);
+        assert_eq!(results[0].index.internal, 1);
+        assert_eq!(results[0].distance.0, 1.0);
+    }
+
+    #[test]
+    fn test_concurrent_batches_share_catapults_safely() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<AdjacencyGraph<LruSet>>();
+
+        let points = random_payloads(300, 41);
+        let params =
+            EngineStarterParams::new(3, 4, SIMD_LANECOUNT, NodeId { internal: 0 }, 42, true);
+        let graph = AdjacencyGraph::<LruSet>::new_flat(
+            GraphBuilder::new(8).build(&points),
+            EngineStarter::new(params),
+            SearchStrategy::Catapult,
+        );
+        let queries = random_payloads(400, 42);
+
+        let results: Vec<Vec<Vec<CandidateEntry>>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = queries
+                .chunks(100)
+                .map(|chunk| {
+                    let graph = &graph;
+                    scope.spawn(move || graph.beam_search_batch(chunk, 5, 16, &mut Stats::new()))
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        let mut stats = Stats::new();
+        for (query, found) in queries.iter().zip(results.concat()) {
+            assert_eq!(found.len(), 5);
+            assert!(found.is_sorted());
+            // distances are real: no result can beat the exact nearest neighbor
+            let exact = graph.exact_search(query, 1, &mut stats);
+            assert!(found[0].distance >= exact[0].distance);
+        }
+        assert!(graph.starter.catapult_count() <= 4 * (1 << 3));
+        assert!(
+            graph
+                .export_catapult_edges()
+                .iter()
+                .all(|(_, node)| node.internal < graph.len())
+        );
+    }
+
+    #[test]
+    fn test_disabled_catapult_writes_keep_concurrent_searches_working() {
+        let points = random_payloads(300, 43);
+        let params =
+            EngineStarterParams::new(3, 4, SIMD_LANECOUNT, NodeId { internal: 0 }, 42, true);
+        let graph = AdjacencyGraph::<LruSet>::new_flat(
+            GraphBuilder::new(8).build(&points),
+            EngineStarter::new(params),
+            SearchStrategy::Catapult,
+        );
+        let queries = random_payloads(400, 44);
+        let search_all = |graph: &AdjacencyGraph<LruSet>| {
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = queries
+                    .chunks(100)
+                    .map(|chunk| {
+                        scope.spawn(move || {
+                            let mut stats = Stats::new();
+                            let found = graph.beam_search_batch(chunk, 5, 16, &mut stats);
+                            (found, stats)
+                        })
+                    })
+                    .collect();
+                handles.into_iter().map(|h| h.join().unwrap()).fold(
+                    (Vec::new(), Stats::new()),
+                    |(mut all, stats), (found, more)| {
+                        all.extend(found);
+                        (all, stats.merge(&more))
+                    },
+                )
+            })
+        };
+
+        // the first pass learns catapults, the second only reads them
+        let (_, learning) = search_all(&graph);
+        assert!(learning.get_catapult_write_locks() >= queries.len());
+        let learned = graph.export_catapult_edges();
+
+        assert!(graph.catapult_writes_enabled());
+        graph.set_catapult_writes(false);
+        assert!(!graph.catapult_writes_enabled());
+        let (results, reading) = search_all(&graph);
+
+        assert_eq!(reading.get_catapult_write_locks(), 0);
+        assert!(reading.get_searches_with_catapults() > 0);
+        assert_eq!(graph.export_catapult_edges(), learned);
+        let mut stats = Stats::new();
+        for (query, found) in queries.iter().zip(results) {
+            assert_eq!(found.len(), 5);
+            assert!(found[0].distance >= graph.exact_search(query, 1, &mut stats)[0].distance);
+        }
+    }
+
+    #[test]
+    fn test_search_iter_yields_the_sorted_beam() {
+        let points = random_payloads(300, 51);
+        let build = || {
+            let params =
+                EngineStarterParams::new(4, 4, SIMD_LANECOUNT, NodeId { internal: 0 }, 42, true);
+            AdjacencyGraph::<LruSet>::new_flat(
+                GraphBuilder::new(8).build(&points),
+                EngineStarter::new(params),
+                SearchStrategy::Catapult,
+            )
+        };
+        let (vec_graph, iter_graph, take_graph) = (build(), build(), build());
+        let mut stats = Stats::new();
+
+        for query in random_payloads(10, 52) {
+            let full = vec_graph.beam_search(&query, 16, 16, &mut stats);
+            let collected: Vec<_> = iter_graph
+                .beam_search_iter(&query, 16, &mut stats)
+                .collect();
+            assert_eq!(collected, full);
+
+            let top: Vec<_> = take_graph
+                .beam_search_iter(&query, 16, &mut stats)
+                .take(4)
+                .collect();
+            assert_eq!(top, full[..4]);
+        }
+
+        let query = &points[3];
+        let threshold = 1.0.into();
+        let close: Vec<_> = iter_graph
+            .beam_search_iter(query, 16, &mut stats)
+            .take_while(|c| c.distance < threshold)
+            .collect();
+        assert_eq!(close.len(), 1);
+        assert_eq!(close[0].index, NodeId { internal: 3 });
+    }
+
+    #[test]
+    fn test_unsorted_search_returns_the_same_set() {
+        let points = random_payloads(300, 21);
+        let nodes = GraphBuilder::new(8).build(&points);
+        let params =
+            EngineStarterParams::new(4, 4, SIMD_LANECOUNT, NodeId { internal: 0 }, 42, true);
+        let sorted_graph = AdjacencyGraph::<LruSet>::new_flat(
+            nodes,
+            EngineStarter::new(params),
+            SearchStrategy::Catapult,
+        );
+        let unsorted_graph = AdjacencyGraph::<LruSet>::new_flat(
+            GraphBuilder::new(8).build(&points),
+            EngineStarter::new(params),
+            SearchStrategy::Catapult,
+        );
+
+        for query in random_payloads(20, 22) {
+            let (mut sorted_stats, mut unsorted_stats) = (Stats::new(), Stats::new());
+            let sorted = sorted_graph.beam_search(&query, 5, 16, &mut sorted_stats);
+            let mut unsorted =
+                unsorted_graph.beam_search_unsorted(&query, 5, 16, &mut unsorted_stats);
+            unsorted.sort();
+            assert_eq!(unsorted, sorted);
+            assert_eq!(
+                unsorted_stats.get_computed_dists(),
+                sorted_stats.get_computed_dists()
+            );
+        }
+        assert_eq!(
+            unsorted_graph.export_catapult_edges(),
+            sorted_graph.export_catapult_edges()
+        );
+    }
+
+    #[test]
+    fn test_approx_count_within() {
+        let graph = setup_simple_graph(false);
+        // halfway between nodes 1 and 2, both at L2 distance 20 (4 * 5)
+        let query = vec![AlignedBlock::new([15.0; SIMD_LANECOUNT])];
+
+        assert_eq!(graph.approx_count_within(&query, 25.0, 2), 2);
+        assert_eq!(graph.approx_count_within(&query, 10.0, 2), 0);
+        // no edge leads to node 0: it is only counted if it is still in the beam
+        assert_eq!(graph.approx_count_within(&query, 1000.0, 1), 4);
+        assert_eq!(graph.approx_count_within(&query, 1000.0, 5), 5);
+    }
+
+    #[test]
+    fn test_approx_count_skips_deleted_nodes_inside_the_radius() {
+        let mut graph = setup_graph_with_unreachable_node();
+        // nodes 0, 1 and 2 lie at L2 distances 0, 40 and 80 from node 0
+        let query = vec![AlignedBlock::new([0.0; SIMD_LANECOUNT])];
+        assert_eq!(graph.approx_count_within(&query, 85.0, 1), 3);
+
+        // node 2 is still reached through the deleted node 1
+        graph.delete(NodeId { internal: 1 });
+        assert_eq!(graph.approx_count_within(&query, 85.0, 1), 2);
+    }
+
+    #[test]
+    fn test_approx_count_matches_exact_count_on_built_graph() {
+        let points = random_payloads(400, 31);
+        let graph = AdjacencyGraph::<LruSet>::new_flat(
+            GraphBuilder::new(12).build(&points),
+            EngineStarter::new(EngineStarterParams::new(
+                4,
+                4,
+                SIMD_LANECOUNT,
+                NodeId { internal: 0 },
+                42,
+                false,
+            )),
+            SearchStrategy::Vanilla,
+        );
+        let query = &points[7];
+        let radius = 25.0;
+        let exact = points
+            .iter()
+            .filter(|p| p.l2_squared(query) <= radius * radius)
+            .count();
+
+        let approx = graph.approx_count_within(query, radius, 16);
+        assert!(approx <= exact);
+        assert!(approx as f64 >= 0.9 * exact as f64, "{approx} vs {exact}");
+    }
+
+    #[test]
+    fn test_confidence_of_clear_and_ambiguous_queries() {
+        let graph = setup_simple_graph(false);
+        let mut stats = Stats::new();
+
+        let clear = vec![AlignedBlock::new([40.0; SIMD_LANECOUNT])];
+        let scored = graph.beam_search_with_confidence(&clear, 2, 5, &mut stats);
+        assert_eq!(scored.results, graph.beam_search(&clear, 2, 5, &mut stats));
+        assert_eq!(scored.confidence.len(), 2);
+        assert_eq!(scored.confidence[0], 1.0);
+        // node 3 at distance 40, node 2 right behind at 80
+        assert!((scored.confidence[1] - 0.5).abs() < 1e-6);
+
+        // halfway between nodes 1 and 2
+        let ambiguous = vec![AlignedBlock::new([15.0; SIMD_LANECOUNT])];
+        let scored = graph.beam_search_with_confidence(&ambiguous, 1, 5, &mut stats);
+        assert_eq!(scored.results.len(), 1);
+        assert_eq!(scored.confidence, vec![0.0]);
+    }
+
+    // Nodes 0..=4 on a line 10 apart, linked as a chain, plus node 5 at 100 that no edge
+    // points to. A beam search starting from node 0 can never reach node 5.
+    fn setup_graph_with_unreachable_node() -> AdjacencyGraph<LruSet> {
+        let positions = [0.0, 10.0, 20.0, 30.0, 40.0, 100.0];
+        let neighbors = [
+            vec![1],
+            vec![0, 2],
+            vec![1, 3],
+            vec![2, 4],
+            vec![3],
+            vec![4],
+        ];
+        let nodes = positions
+            .iter()
+            .zip(neighbors)
+            .map(|(&x, neighs)| Node {
+                payload: vec![AlignedBlock::new([x; SIMD_LANECOUNT])].into_boxed_slice(),
+                neighbors: FlatFixedSet::new(neighs),
+            })
+            .collect();
+        let params =
+            EngineStarterParams::new(2, 4, SIMD_LANECOUNT, NodeId { internal: 0 }, 42, false);
+        AdjacencyGraph::new_flat(nodes, EngineStarter::new(params), SearchStrategy::Vanilla)
+    }
+
+    #[test]
+    fn test_mean_edge_length() {
+        let graph = setup_graph_with_unreachable_node();
+        // 8 edges of length 10 * 4 (one per lane), and 5 -> 4 of length 60 * 4
+        let expected = (8.0 * 40.0 + 240.0) / 9.0;
+        assert!((graph.mean_edge_length() - expected).abs() < 1e-4);
+
+        assert_eq!(
+            graph_with_payloads(random_payloads(3, 1)).mean_edge_length(),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_collapsed_beam_holds_distinct_payloads() {
+        // nodes 1, 2 and 3 are copies of the same vector
+        let positions = [0.0, 10.0, 10.0, 10.0, 20.0, 30.0];
+        let mut graph = graph_with_payloads(
+            positions
+                .iter()
+                .map(|&x| vec![AlignedBlock::new([x; SIMD_LANECOUNT])])
+                .collect(),
+        );
+        for (node, neighbors) in [
+            (0, vec![1, 2, 3]),
+            (1, vec![4]),
+            (2, vec![4]),
+            (3, vec![4]),
+            (4, vec![5]),
+        ] {
+            graph.adjacency[node].neighbors = FlatFixedSet::new(neighbors);
+        }
+        let query = vec![AlignedBlock::new([9.0; SIMD_LANECOUNT])];
+        let mut stats = Stats::new();
+
+        let ids = |results: Vec<CandidateEntry>| -> Vec<usize> {
+            results.iter().map(|c| c.index.internal).collect()
+        };
+        let mut plain = ids(graph.beam_search(&query, 3, 3, &mut stats));
+        plain.sort_unstable();
+        assert_eq!(plain, vec![1, 2, 3]);
+
+        graph.collapse_duplicate_payloads();
+        assert!(graph.collapses_duplicate_payloads());
+        let collapsed = ids(graph.beam_search(&query, 3, 3, &mut stats));
+        assert_eq!(collapsed.len(), 3);
+        assert!([1, 2, 3].contains(&collapsed[0]));
+        assert_eq!(collapsed[1..], [0, 4]);
+    }
+
+    #[test]
+    fn test_results_report_catapult_provenance() {
+        let query = vec![AlignedBlock::new([41.0; SIMD_LANECOUNT])];
+        let mut stats = Stats::new();
+
+        // the first search walks from node 0 and stores node 4 as a catapult
+        let graph = setup_simple_graph(true);
+        let first = graph.beam_search(&query, 2, 2, &mut stats);
+        assert_eq!(first[0].index.internal, 4);
+        assert!(first.iter().all(|e| !e.has_catapult_ancestor));
+
+        // the second one starts from the catapult, and reaches node 3 through it
+        let second = graph.beam_search(&query, 2, 2, &mut stats);
+        assert_eq!(second[0].index.internal, 4);
+        assert!(second.iter().all(|e| e.has_catapult_ancestor), "{second:?}");
+        assert_eq!(stats.get_searches_with_catapults(), 1);
+
+        let vanilla = setup_simple_graph(false);
+        vanilla.beam_search(&query, 2, 2, &mut stats);
+        let results = vanilla.beam_search(&query, 2, 2, &mut stats);
+        assert!(results.iter().all(|e| !e.has_catapult_ancestor));
+    }
+
+    #[test]
+    fn test_narrow_beam_reports_saturation() {
+        let points = random_payloads(300, 43);
+        let params =
+            EngineStarterParams::new(3, 4, SIMD_LANECOUNT, NodeId { internal: 0 }, 42, false);
+        let graph = AdjacencyGraph::<LruSet>::new_flat(
+            GraphBuilder::new(8).build(&points),
+            EngineStarter::new(params),
+            SearchStrategy::Vanilla,
+        );
+
+        let saturation = |beam_width| {
+            let mut stats = Stats::new();
+            for query in random_payloads(20, 44) {
+                graph.beam_search(&query, 1, beam_width, &mut stats);
+            }
+            stats.beam_saturation()
+        };
+        let (narrow, generous) = (saturation(1), saturation(300));
+        assert!(narrow > 0.1, "narrow beam saturation {narrow}");
+        assert_eq!(generous, 0.0);
+    }
+
+    #[test]
+    fn test_known_candidates_are_not_recomputed() {
+        let graph = setup_simple_graph(true);
+        let query = vec![AlignedBlock::new([1.0; SIMD_LANECOUNT])];
+
+        // the whole graph fits in the beam: expanding 2 and 4 finds 1 already there
+        let mut stats = Stats::new();
+        graph.beam_search(&query, 1, 5, &mut stats);
+        assert_eq!(stats.get_computed_dists(), 5);
+        assert_eq!(stats.get_skipped_dists(), 2);
+
+        // node 0 is now both the starting node and the bucket's catapult
+        let mut stats = Stats::new();
+        let results = graph.beam_search(&query, 1, 5, &mut stats);
+        assert_eq!(results[0].index.internal, 0);
+        assert_eq!(stats.get_computed_dists(), 5);
+        assert_eq!(stats.get_skipped_dists(), 3);
+    }
+
+    #[test]
+    fn test_per_query_recording_sees_each_search() {
+        let graph = setup_graph_with_unreachable_node();
+        let mut stats = Stats::new();
+        stats.enable_per_query_recording();
+        for x in [0.0, 40.0] {
+            let query = vec![AlignedBlock::new([x; SIMD_LANECOUNT])];
+            graph.beam_search(&query, 1, 2, &mut stats);
+        }
+
+        let histogram = stats.nodes_per_query().unwrap();
+        assert_eq!(histogram.len(), 2);
+        let (fastest, slowest) = (
+            stats.percentile(0.0).unwrap(),
+            stats.percentile(100.0).unwrap(),
+        );
+        assert!(fastest < slowest);
+        assert_eq!(fastest + slowest, stats.get_nodes_visited());
+    }
+
+    #[test]
+    fn test_exact_rescue_only_researches_missed_queries() {
+        let graph = setup_graph_with_unreachable_node();
+        let queries: Vec<Vec<AlignedBlock>> = [0.0, 95.0, 40.0]
+            .iter()
+            .map(|&x| vec![AlignedBlock::new([x; SIMD_LANECOUNT])])
+            .collect();
+        // the second query's approximate search could not reach node 5
+        let approximate = vec![vec![0, 1], vec![4, 3], vec![4, 3]];
+        let truth = vec![vec![0, 1], vec![5, 4], vec![4, 3]];
+        let mut stats = Stats::new();
+
+        let rescued = graph.exact_rescue(&queries, &approximate, &truth, 2, &mut stats);
+        assert_eq!(rescued, truth);
+        assert_eq!(stats.get_computed_dists(), graph.len());
+    }
+
+    #[test]
+    fn test_exact_search_returns_true_neighbors() {
+        let graph = setup_graph_with_unreachable_node();
+        let query = vec![AlignedBlock::new([95.0; SIMD_LANECOUNT])];
+        let mut stats = Stats::new();
+
+        let results = graph.exact_search(&query, 2, &mut stats);
+        let ids: Vec<usize> = results.iter().map(|c| c.index.internal).collect();
+        assert_eq!(ids, vec![5, 4]);
+        assert_eq!(stats.get_computed_dists(), 6);
+        assert_eq!(graph.exact_search(&query, 10, &mut stats).len(), 6);
+    }
+
+    #[test]
+    fn test_parallel_exact_search_matches_exact_search() {
+        let graph = graph_with_payloads(random_payloads(500, 11));
+        let query = random_payloads(1, 12).remove(0);
+        let mut stats = Stats::new();
+
+        let expected = graph.exact_search(&query, 10, &mut stats);
+        for num_threads in [1, 3, 8, 1000] {
+            let mut parallel_stats = Stats::new();
+            let parallel =
+                graph.exact_search_parallel(&query, 10, &mut parallel_stats, num_threads);
+            assert_eq!(parallel, expected);
+            assert_eq!(parallel_stats.get_computed_dists(), 500);
+        }
+
+        // a hand-checked case: nodes at 0, 10, .., 40 and 100, query at 35
+        let graph = setup_graph_with_unreachable_node();
+        let query = vec![AlignedBlock::new([35.0; SIMD_LANECOUNT])];
+        let results = graph.exact_search_parallel(&query, 3, &mut stats, 4);
+        let ids: Vec<usize> = results.iter().map(|c| c.index.internal).collect();
+        let distances: Vec<f32> = results.iter().map(|c| c.distance.0).collect();
+        assert_eq!(ids, vec![3, 4, 2]);
+        assert_eq!(distances, vec![400.0, 400.0, 3600.0]);
+    }
+
+    #[test]
+    fn test_guaranteed_search_falls_back_when_beam_misses() {
+        let graph = setup_graph_with_unreachable_node();
+        let query = vec![AlignedBlock::new([95.0; SIMD_LANECOUNT])];
+        let mut stats = Stats::new();
+
+        let beam = graph.beam_search(&query, 1, 2, &mut stats);
+        assert_eq!(beam[0].index.internal, 4);
+
+        let guaranteed = graph.beam_search_guaranteed(&query, 1, 2, &mut stats, 1.0);
+        assert_eq!(guaranteed[0].index.internal, 5);
+    }
+
+    #[test]
+    fn test_guaranteed_search_with_k_zero_returns_nothing() {
+        let graph = setup_graph_with_unreachable_node();
+        let query = vec![AlignedBlock::new([95.0; SIMD_LANECOUNT])];
+        let mut stats = Stats::new();
+        assert!(
+            graph
+                .beam_search_guaranteed(&query, 0, 2, &mut stats, 1.0)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_guaranteed_search_keeps_confident_beam_result() {
+        let graph = setup_graph_with_unreachable_node();
+        let query = vec![AlignedBlock::new([21.0; SIMD_LANECOUNT])];
+
+        let mut beam_stats = Stats::new();
+        let beam = graph.beam_search(&query, 2, 2, &mut beam_stats);
+        let mut stats = Stats::new();
+        let guaranteed = graph.beam_search_guaranteed(&query, 2, 2, &mut stats, 1.0);
+
+        assert_eq!(guaranteed, beam);
+        assert_eq!(stats.get_computed_dists(), beam_stats.get_computed_dists());
+    }
+
+    #[test]
+    fn test_inserted_node_is_found_through_back_links() {
+        let mut graph = setup_simple_graph(false);
+        let query = vec![AlignedBlock::new([45.0; SIMD_LANECOUNT])];
+        let mut stats = Stats::new();
+
+        let id = graph.insert(
+            vec![AlignedBlock::new([46.0; SIMD_LANECOUNT])].into_boxed_slice(),
+            vec![4],
+            Some(4),
+        );
+
+        assert_eq!(id, NodeId { internal: 5 });
+        assert_eq!(graph.len(), 6);
+        assert_eq!(graph.neighbors_of(5), &[NodeId { internal: 4 }]);
+        assert!(graph.neighbors_of(4).contains(&id));
+        assert_eq!(graph.beam_search(&query, 1, 2, &mut stats)[0].index, id);
+    }
+
+    #[test]
+    fn test_insert_back_links_respect_degree() {
+        let mut graph = setup_simple_graph(false);
+        let degrees: Vec<usize> = (0..5).map(|i| graph.neighbors_of(i).len()).collect();
+
+        let id = graph.insert(
+            vec![AlignedBlock::new([5.0; SIMD_LANECOUNT])].into_boxed_slice(),
+            (0..5).collect(),
+            Some(2),
+        );
+
+        for (i, &degree) in degrees.iter().enumerate() {
+            let linked = graph.neighbors_of(i).contains(&id);
+            assert_eq!(linked, degree < 2, "node {i}");
+        }
+
+        let before = graph.total_edge_count();
+        graph.insert(
+            vec![AlignedBlock::new([6.0; SIMD_LANECOUNT])].into_boxed_slice(),
+            vec![0, 1],
+            None,
+        );
+        assert_eq!(graph.total_edge_count(), before + 2);
+    }
+
+    #[test]
+    fn test_insert_unpacks_csr_layout() {
+        let mut graph = setup_simple_graph(false);
+        graph.use_csr_layout();
+        let edges = graph.total_edge_count();
+        let length = graph.mean_edge_length();
+
+        graph.insert(
+            vec![AlignedBlock::new([50.0; SIMD_LANECOUNT])].into_boxed_slice(),
+            vec![4],
+            Some(8),
+        );
+
+        assert!(!graph.has_csr_layout());
+        assert_eq!(graph.total_edge_count(), edges + 2);
+        assert_eq!(graph.neighbors_of(1), &[NodeId { internal: 2 }]);
+        assert!(graph.mean_edge_length() != length);
+    }
+
+    #[test]
+    #[should_panic(expected = "neighbor index out of bounds")]
+    fn test_insert_rejects_unknown_neighbor() {
+        let mut graph = setup_simple_graph(false);
+        graph.insert(
+            vec![AlignedBlock::new([1.0; SIMD_LANECOUNT])].into_boxed_slice(),
+            vec![5],
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "payload dimension differs")]
+    fn test_insert_rejects_wrong_dimension() {
+        let mut graph = setup_simple_graph(false);
+        graph.insert(Vec::new().into_boxed_slice(), vec![0], None);
+    }
+
+    #[test]
+    fn test_entry_points_reach_disconnected_cluster() {
+        // two chains with no edge between them, searched from node 0 of the first one
+        let nodes: Vec<Node> = (0..10)
+            .map(|i| {
+                let position = if i < 5 { i as f32 } else { 100.0 + i as f32 };
+                let next = if i % 5 == 4 { i - 4 } else { i + 1 };
+                Node {
+                    payload: vec![AlignedBlock::new([position; SIMD_LANECOUNT])].into_boxed_slice(),
+                    neighbors: FlatFixedSet::new(vec![next]),
+                }
+            })
+            .collect();
+        let params =
+            EngineStarterParams::new(4, 8, SIMD_LANECOUNT, NodeId { internal: 0 }, 42, false);
+        let mut graph = AdjacencyGraph::<LruSet>::new_flat(
+            nodes,
+            EngineStarter::new(params),
+            SearchStrategy::Vanilla,
+        );
+        let query = vec![AlignedBlock::new([106.0; SIMD_LANECOUNT])];
+        let search = |graph: &AdjacencyGraph<LruSet>| {
+            graph.beam_search(&query, 1, 2, &mut Stats::new())[0].index
+        };
+        assert_eq!(search(&graph), NodeId { internal: 4 });
+
+        assert_eq!(graph.use_diverse_entry_points(2), &[NodeId { internal: 9 }]);
+        assert_eq!(search(&graph), NodeId { internal: 6 });
+    }
+
+    #[test]
+    fn test_checked_search_reports_out_of_bounds_neighbor() {
+        let mut graph = setup_simple_graph(false);
+        let query = vec![AlignedBlock::new([40.0; SIMD_LANECOUNT])];
+        let mut stats = Stats::new();
+        let results = graph.beam_search_checked(&query, 1, 2, &mut stats).unwrap();
+        assert_eq!(results[0].index, NodeId { internal: 4 });
+
+        graph.set_neighbors(3, vec![4, 7]);
+        let error = graph
+            .beam_search_checked(&query, 1, 2, &mut stats)
+            .unwrap_err();
+        assert_eq!(
+            error,
+            SearchError::NeighborOutOfBounds {
+                node: NodeId { internal: 3 },
+                neighbor: 7,
+                num_nodes: 5,
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "node 3 links to neighbor 7, but the graph holds 5 nodes"
+        );
+
+        graph.set_neighbors(3, vec![4]);
+        graph.starter.set_entry_points(vec![NodeId { internal: 5 }]);
+        assert!(matches!(
+            graph.check_bounds(),
+            Err(SearchError::EntryOutOfBounds { .. })
+        ));
+    }
+
+    #[test]
+    fn test_medoid_replaces_starting_node() {
+        let mut graph = setup_simple_graph(false);
+        assert_eq!(graph.use_medoid_as_starting_node(), NodeId { internal: 2 });
+        assert_eq!(graph.starting_node(), NodeId { internal: 2 });
+
+        // a search towards node 4 now expands nodes 2, 3 and 4 only
+        let query = vec![AlignedBlock::new([40.0; SIMD_LANECOUNT])];
+        let mut stats = Stats::new();
+        assert_eq!(
+            graph.beam_search(&query, 1, 1, &mut stats)[0].index,
+            NodeId { internal: 4 }
+        );
+        assert_eq!(stats.get_nodes_visited(), 3);
+    }
+
+    #[test]
+    fn test_deleted_best_node_is_excluded_from_results() {
+        let mut graph = setup_simple_graph(false);
+        let query = vec![AlignedBlock::new([40.0; SIMD_LANECOUNT])];
+        let mut stats = Stats::new();
+        assert_eq!(
+            graph.beam_search(&query, 1, 2, &mut stats)[0].index,
+            NodeId { internal: 4 }
+        );
+
+        assert!(graph.delete(NodeId { internal: 4 }));
+        assert!(!graph.delete(NodeId { internal: 4 }));
+        assert_eq!(graph.num_deleted(), 1);
+        let results = graph.beam_search(&query, 2, 3, &mut stats);
+        let ids: Vec<usize> = results.iter().map(|c| c.index.internal).collect();
+        assert_eq!(ids, vec![3, 2]);
+        assert_eq!(
+            graph.exact_search(&query, 1, &mut stats)[0].index,
+            NodeId { internal: 3 }
+        );
+    }
+
+    #[test]
+    fn test_search_traverses_deleted_nodes() {
+        let mut graph = setup_simple_graph(false);
+        // node 2 is the only way from the starting node to nodes 3 and 4
+        graph.delete(NodeId { internal: 2 });
+        let query = vec![AlignedBlock::new([40.0; SIMD_LANECOUNT])];
+        let results = graph.beam_search(&query, 2, 5, &mut Stats::new());
+        let ids: Vec<usize> = results.iter().map(|c| c.index.internal).collect();
+        assert_eq!(ids, vec![4, 3]);
+    }
+
+    #[test]
+    fn test_compaction_drops_deleted_nodes_and_renumbers() {
+        let mut graph = setup_simple_graph(true);
+        graph.use_csr_layout();
+        graph.delete(NodeId { internal: 1 });
+        let remap = graph.compaction();
+        let remap: Vec<Option<usize>> = remap.iter().map(|id| id.map(|n| n.internal)).collect();
+        assert_eq!(remap, vec![Some(0), None, Some(1), Some(2), Some(3)]);
+        assert_eq!(graph.len(), 4);
+        assert_eq!(graph.num_deleted(), 0);
+        assert!(graph.has_csr_layout());
+        // node 2 (now 1) lost its edge to node 1, node 4 (now 3) its only edge
+        assert_eq!(graph.neighbors_of(1).to_vec(), vec![NodeId { internal: 2 }]);
+        assert!(graph.neighbors_of(3).is_empty());
+        assert_eq!(graph.starting_node(), NodeId { internal: 0 });
+    }
+
+    #[test]
+    #[should_panic(expected = "the starting node cannot be deleted")]
+    fn test_starting_node_cannot_be_deleted() {
+        setup_simple_graph(false).delete(NodeId { internal: 0 });
+    }
+
+    #[test]
+    fn test_iteration_cap_reports_unconverged_search() {
+        let graph = setup_simple_graph(false);
+        let query = vec![AlignedBlock::new([40.0; SIMD_LANECOUNT])];
+        let mut stats = Stats::new();
+
+        let capped = graph.beam_search_with_status(&query, 1, 2, &mut stats, Some(1));
+        assert!(!capped.converged());
+        assert_eq!(capped.termination, Termination::IterationCap);
+        assert_ne!(capped.results[0].index, NodeId { internal: 4 });
+
+        let free = graph.beam_search_with_status(&query, 1, 2, &mut stats, None);
+        assert!(free.converged());
+        assert_eq!(free.results[0].index, NodeId { internal: 4 });
+        assert_eq!(free.results, graph.beam_search(&query, 1, 2, &mut stats));
+    }
+
+    #[test]
+    fn test_generous_iteration_cap_still_converges() {
+        let graph = setup_simple_graph(false);
+        let query = vec![AlignedBlock::new([40.0; SIMD_LANECOUNT])];
+        let mut stats = Stats::new();
+
+        let outcome = graph.beam_search_with_status(&query, 1, 2, &mut stats, Some(100));
+        assert!(outcome.converged());
+        assert_eq!(outcome.results[0].index, NodeId { internal: 4 });
+    }
+
+    fn built_graph(points: &[Vec<AlignedBlock>]) -> AdjacencyGraph<LruSet> {
+        let params =
+            EngineStarterParams::new(4, 4, SIMD_LANECOUNT, NodeId { internal: 0 }, 42, true);
+        AdjacencyGraph::new_flat(
+            crate::search::GraphBuilder::new(8).build(points),
+            EngineStarter::new(params),
+            SearchStrategy::Catapult,
+        )
+    }
+
+    #[test]
+    fn test_load_catapults_rejects_nodes_missing_from_the_graph() {
+        let points = random_payloads(30, 3);
+        let large = built_graph(&points);
+        large.starter.new_catapult(2, NodeId { internal: 25 });
+        let path = crate::fs::temp_path("graph-catapults");
+        large.save_catapults(&path).unwrap();
+
+        let reloaded = built_graph(&points);
+        reloaded.load_catapults(&path).unwrap();
+        let small = built_graph(&points[..20]);
+        small.starter.new_catapult(1, NodeId { internal: 4 });
+        let err = small.load_catapults(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(reloaded.bucket_catapults(2), vec![NodeId { internal: 25 }]);
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("node 25"));
+        assert_eq!(small.bucket_catapults(1), vec![NodeId { internal: 4 }]);
+        assert_eq!(small.total_catapults(), 1);
+    }
+
+    #[test]
+    fn test_ranked_results_look_up_rank_and_distance() {
+        let graph = setup_simple_graph(false);
+        let query = vec![AlignedBlock::new([11.0; SIMD_LANECOUNT])];
+        let ranked = graph.beam_search_ranked(&query, 3, 3, &mut Stats::new());
+
+        assert_eq!(ranked.len(), 3);
+        assert_eq!(ranked.rank_of(NodeId { internal: 1 }), Some(0));
+        assert_eq!(
+            ranked.get(NodeId { internal: 2 }),
+            Some((1, Distance::SquaredL2(16.0 * 81.0)))
+        );
+        assert!(!ranked.contains(NodeId { internal: 4 }));
+        let indices: Vec<usize> = ranked.results().iter().map(|c| c.index.internal).collect();
+        assert_eq!(indices, vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn test_dimension_weights_change_the_ranking() {
+        let points = vec![point_2d(0.0, 0.0), point_2d(1.0, 10.0), point_2d(3.0, 0.0)];
+        let query = point_2d(1.0, 0.0);
+        let nearest =
+            |graph: &AdjacencyGraph<LruSet>| graph.beam_search(&query, 1, 3, &mut Stats::new())[0];
+        assert_eq!(nearest(&built_graph(&points)).index, NodeId { internal: 0 });
+
+        // the second dimension is ignored and the first one counts twice
+        let mut weights = vec![1.0; SIMD_LANECOUNT];
+        weights[0] = 2.0;
+        weights[1] = 0.0;
+        let weighted = built_graph(&points).with_dimension_weights(weights);
+        let best = nearest(&weighted);
+        assert_eq!(best.index, NodeId { internal: 1 });
+        assert_eq!(best.distance.0, 0.0);
+        assert_eq!(weighted.dimension_weights().unwrap()[0].data[0], 2.0);
+    }
+
+    #[test]
+    fn test_dimension_weights_apply_to_exact_search_and_radius_counts() {
+        let points = vec![point_2d(0.0, 0.0), point_2d(1.0, 10.0), point_2d(3.0, 0.0)];
+        let query = point_2d(1.0, 0.0);
+        let unweighted = built_graph(&points);
+        let mut weights = vec![1.0; SIMD_LANECOUNT];
+        weights[0] = 2.0;
+        weights[1] = 0.0;
+        let weighted = built_graph(&points).with_dimension_weights(weights);
+
+        let exact = weighted.exact_search(&query, 1, &mut Stats::new());
+        assert_eq!(exact[0].index, NodeId { internal: 1 });
+        assert_eq!(exact[0].distance.0, 0.0);
+        let parallel = weighted.exact_search_parallel(&query, 1, &mut Stats::new(), 2);
+        assert_eq!(parallel, exact);
+
+        // only node 1 is within 0.5 once the second dimension is ignored
+        assert_eq!(unweighted.approx_count_within(&query, 0.5, 3), 0);
+        assert_eq!(weighted.approx_count_within(&query, 0.5, 3), 1);
+
+        assert!(weighted.mean_edge_length() < unweighted.mean_edge_length());
+    }
+
+    #[test]
+    #[should_panic(expected = "weights must be finite and non-negative")]
+    fn test_dimension_weights_must_be_non_negative() {
+        let points = vec![point_2d(0.0, 0.0), point_2d(1.0, 10.0)];
+        built_graph(&points).with_dimension_weights(vec![-1.0; SIMD_LANECOUNT]);
+    }
+
+    #[test]
+    fn test_search_iter_expands_lazily_and_covers_the_beam() {
+        let points = random_payloads(300, 31);
+        let queries = random_payloads(5, 32);
+        let graph = built_graph(&points);
+
+        for query in &queries {
+            let mut stats = Stats::new();
+            let mut streamed: Vec<CandidateEntry> =
+                graph.search_iter(query, 16, &mut stats).collect();
+            let mut eager_stats = Stats::new();
+            // the iterator learned nothing, so this search starts from the same points
+            let eager = graph.beam_search(query, 5, 16, &mut eager_stats);
+            graph.clear_all_catapults();
+
+            assert_eq!(stats.get_nodes_visited(), eager_stats.get_nodes_visited());
+            assert_eq!(streamed.len(), stats.get_nodes_visited());
+            // every node of the final beam was expanded, so the best ones were yielded
+            streamed.sort();
+            assert_eq!(&streamed[..5], &eager[..]);
+
+            let mut partial = Stats::new();
+            assert_eq!(
+                graph.search_iter(query, 16, &mut partial).take(3).count(),
+                3
+            );
+            assert_eq!(partial.get_nodes_visited(), 3);
+        }
+    }
+
+    #[test]
+    fn test_adaptive_beam_settles_on_stable_results() {
+        let points = random_payloads(500, 23);
+        let queries = random_payloads(10, 24);
+        let (adaptive, fixed) = (built_graph(&points), built_graph(&points));
+
+        for query in &queries {
+            let mut stats = Stats::new();
+            let (results, width) =
+                adaptive.beam_search_adaptive(query, 5, 128, 1, &mut stats, None);
+            assert!([5, 10, 20, 40, 80, 128].contains(&width), "{width}");
+            assert_eq!(results.len(), 5);
+            // the settled round is a plain search at that width
+            let plain = fixed.beam_search_with_status(query, 5, width, &mut Stats::new(), None);
+            let ids = |r: &[CandidateEntry]| r.iter().map(|e| e.index).collect::<Vec<_>>();
+            assert_eq!(ids(&results), ids(&plain.results));
+            assert!(stats.get_beam_calls() >= 2 || width == 128);
+        }
+
+        // a budget ends the first round early, and tiny graphs settle in one round
+        let mut stats = Stats::new();
+        let (results, width) =
+            adaptive.beam_search_adaptive(&queries[0], 5, 128, 3, &mut stats, Some(10));
+        assert_eq!((results.len(), width, stats.get_beam_calls()), (5, 5, 1));
+
+        let tiny = setup_simple_graph(false);
+        let query = vec![AlignedBlock::new([21.0; SIMD_LANECOUNT])];
+        let mut stats = Stats::new();
+        let (results, width) = tiny.beam_search_adaptive(&query, 5, 64, 4, &mut stats, None);
+        assert_eq!((results.len(), width, stats.get_beam_calls()), (5, 5, 1));
+    }
+
+    #[test]
+    fn test_tiny_distance_budget_still_returns_k_results() {
+        let points = random_payloads(500, 21);
+        let query = &random_payloads(1, 22)[0];
+        let (budgeted, free) = (built_graph(&points), built_graph(&points));
+
+        let mut budget_stats = Stats::new();
+        let outcome = budgeted.beam_search_with_budget(query, 5, 20, &mut budget_stats, Some(10));
+        assert_eq!(outcome.termination, Termination::DistanceBudget);
+        assert!(!outcome.converged());
+        assert_eq!(outcome.results.len(), 5);
+        assert!(outcome.results.is_sorted());
+
+        let mut free_stats = Stats::new();
+        let unbounded = free.beam_search_with_status(query, 5, 20, &mut free_stats, None);
+        assert!(unbounded.converged());
+        // one expansion past the budget scores at most 8 more neighbors
+        assert!(budget_stats.get_computed_dists() < 10 + 8);
+        assert!(budget_stats.get_computed_dists() < free_stats.get_computed_dists());
+    }
+
+    #[test]
+    fn test_wider_result_pool_never_lowers_recall() {
+        let points = random_payloads(1000, 31);
+        let queries = random_payloads(30, 32);
+        let params =
+            EngineStarterParams::new(4, 4, SIMD_LANECOUNT, NodeId { internal: 0 }, 42, false);
+        // no catapults, so every pool size walks the graph the same way
+        let graph = AdjacencyGraph::<LruSet>::new_flat(
+            crate::search::GraphBuilder::new(8).build(&points),
+            EngineStarter::new(params),
+            SearchStrategy::Vanilla,
+        );
+        let ids = |results: &[CandidateEntry]| -> Vec<usize> {
+            results.iter().map(|c| c.index.internal).collect()
         };
 
-        // Convert catapults to candidate entries (marked as having catapult ancestry)
-        let mut distances = self.distances_from_indices(&hash_search.catapults, query, true, stats);
-        distances.sort();
-        distances.shrink_to(k);
+        let mut stats = Stats::new();
+        let truth: Vec<Vec<usize>> = queries
+            .iter()
+            .map(|q| ids(&graph.exact_search(q, 10, &mut stats)))
+            .collect();
+        let recalls: Vec<f64> = [10, 20, 40, 80]
+            .into_iter()
+            .map(|ef| {
+                let results: Vec<Vec<usize>> = queries
+                    .iter()
+                    .map(|q| ids(&graph.beam_search_with_ef_results(q, 10, 10, ef, &mut stats)))
+                    .collect();
+                recall_at_k(&results, &truth, 10)
+            })
+            .collect();
+        assert!(recalls.is_sorted(), "{recalls:?}");
 
-        // Add the starting node (not a catapult, so marked as false)
-        let starting_node_entry =
-            self.distances_from_indices(&[hash_search.starting_node], query, false, stats);
-        distances.extend(starting_node_entry);
+        // a beam of 4 alone could never return more than 4 of the 10 true neighbors
+        let pooled: Vec<Vec<usize>> = queries
+            .iter()
+            .map(|q| ids(&graph.beam_search_with_ef_results(q, 10, 4, 10, &mut stats)))
+            .collect();
+        assert!(pooled.iter().all(|found| found.len() == 10));
+        assert!(recall_at_k(&pooled, &truth, 10) > 0.4);
 
-        let search_results = self.beam_search_raw(query, &distances, k, beam_width, stats);
-        let best_result = search_results[0].index;
+        let plain: Vec<Vec<usize>> = queries
+            .iter()
+            .map(|q| ids(&graph.beam_search(q, 10, 10, &mut stats)))
+            .collect();
+        assert_eq!(recall_at_k(&plain, &truth, 10), recalls[0]);
 
-        if matches!(self.strategy, SearchStrategy::Catapult) {
-            self.starter
-                .new_catapult(hash_search.signature, best_result);
-            if search_results.iter().any(|e| e.has_catapult_ancestor) {
-                stats.bump_searches_with_catapults();
-            }
+        // spare pool entries replace deleted results
+        let mut graph = graph;
+        for &node in &plain[0][..5] {
+            graph.delete(NodeId { internal: node });
         }
-        search_results
+        assert!(graph.beam_search(&queries[0], 10, 10, &mut stats).len() < 10);
+        let refilled = graph.beam_search_with_ef_results(&queries[0], 10, 10, 20, &mut stats);
+        assert_eq!(refilled.len(), 10);
     }
 
-    /// Clears all cached catapults from all LSH buckets.
-    ///
-    /// This is useful for benchmarking to measure performance without the benefit
-    /// of cached starting points, or to reset state between different workloads.
-    pub fn clear_all_catapults(&self) {
-        self.starter.clear_all_catapults();
-    }
+    #[test]
+    fn test_centering_preserves_l2_ordering() {
+        // integer coordinates, so that centering on an integer mean is exact
+        let round = |vectors: Vec<Vec<AlignedBlock>>| -> Vec<Vec<AlignedBlock>> {
+            vectors
+                .into_iter()
+                .map(|v| {
+                    v.iter()
+                        .map(|b| AlignedBlock::new(b.data.map(f32::round)))
+                        .collect()
+                })
+                .collect()
+        };
+        let points = round(random_payloads(300, 41));
+        let queries = round(random_payloads(10, 42));
+        let plain = built_graph(&points);
+        let centering = AffineTransform::new((0..SIMD_LANECOUNT).map(|i| i as f32 - 5.0).collect());
+        let centered = built_graph(&points).with_affine_transform(centering.clone());
+        assert_eq!(centered.affine_transform(), Some(&centering));
 
-    /// Returns the number of nodes in the graph.
-    ///
-    /// # Returns
-    /// The total number of nodes stored in the adjacency list
-    #[allow(clippy::len_without_is_empty)] // checking the size of the graph is fine, but it is never ever empty
-    pub fn len(&self) -> usize {
-        self.adjacency.len()
+        let mut stats = Stats::new();
+        for q in &queries {
+            let ids = |results: Vec<CandidateEntry>| -> Vec<NodeId> {
+                results.iter().map(|c| c.index).collect()
+            };
+            assert_eq!(
+                ids(plain.exact_search(q, 10, &mut stats)),
+                ids(centered.exact_search(q, 10, &mut stats))
+            );
+            // searches take the untransformed query
+            assert_eq!(centered.beam_search(q, 10, 20, &mut stats).len(), 10);
+        }
     }
 
-    /// Returns the total number of directed edges in the graph (sum of all neighbor list lengths). Does not include catapult edges.
-    pub fn total_edge_count(&self) -> usize {
-        self.adjacency
-            .iter()
-            .map(|n| n.neighbors.to_slice().len())
-            .sum()
+    #[test]
+    fn test_projection_rebuilds_hashing_for_new_dimension() {
+        let points = random_payloads(200, 43);
+        let query = &random_payloads(1, 44)[0];
+        // keep the first 4 coordinates only
+        let rows = (0..4)
+            .map(|i| (0..SIMD_LANECOUNT).map(|j| (i == j) as u8 as f32).collect())
+            .collect();
+        let transform = AffineTransform::new(vec![0.0; SIMD_LANECOUNT]).with_projection(rows);
+        let graph = built_graph(&points).with_affine_transform(transform.clone());
+        assert_eq!(graph.dim(), 4);
+
+        let mut stats = Stats::new();
+        let results = graph.beam_search(query, 5, 10, &mut stats);
+        assert_eq!(results.len(), 5);
+        let truth = graph.exact_search(query, 1, &mut stats);
+        assert_eq!(results[0].index, truth[0].index);
+
+        // the same graph built from payloads that were transformed beforehand
+        let mut nodes = crate::search::GraphBuilder::new(8).build(&points);
+        for node in &mut nodes {
+            node.payload = transform.apply(&node.payload).into_boxed_slice();
+        }
+        let params = EngineStarterParams::new(4, 4, 4, NodeId { internal: 0 }, 42, true);
+        let reattached = AdjacencyGraph::<LruSet>::new_flat(
+            nodes,
+            EngineStarter::new(params),
+            SearchStrategy::Catapult,
+        )
+        .assume_affine_transform(transform);
+        assert_eq!(
+            reattached.beam_search(query, 5, 10, &mut stats)[0].index,
+            results[0].index
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::{
-        numerics::SIMD_LANECOUNT,
-        search::{
-            SearchStrategy,
-            hash_start::{EngineStarter, EngineStarterParams, zorder_index::ZOrderIndex},
-        },
-        sets::{catapults::LruSet, fixed::FlatFixedSet},
-    };
+    #[test]
+    fn test_expired_deadline_still_returns_k_results() {
+        let points = random_payloads(500, 23);
+        let query = &random_payloads(1, 24)[0];
+        let graph = built_graph(&points);
 
-    pub type TestEngineStarter = EngineStarter<LruSet>;
+        let mut stats = Stats::new();
+        let outcome = graph.beam_search_with_deadline(query, 5, 20, &mut stats, Duration::ZERO);
+        assert_eq!(outcome.termination, Termination::Deadline);
+        assert_eq!(outcome.results.len(), 5);
+        assert!(outcome.results.is_sorted());
+        assert_eq!(stats.get_nodes_visited(), DEADLINE_CHECK_INTERVAL);
+        assert_eq!(stats.get_deadline_hits(), 1);
 
-    use super::*;
+        let relaxed =
+            graph.beam_search_with_deadline(query, 5, 20, &mut stats, Duration::from_secs(60));
+        assert!(relaxed.converged());
+        assert_eq!(stats.get_deadline_hits(), 1);
+    }
 
-    // A simple graph:
-    // Nodes: 0 (pos 0), 1 (pos 10), 2 (pos 20), 3 (pos 30), 4 (pos 40)
-    // Edges: 0 -> 1 -> 2 -> 3 -> 4
-    // Query: 11.0
-    fn setup_simple_graph(catapults_enabled: bool) -> AdjacencyGraph<LruSet> {
-        let nodes = vec![
-            // 0: Pos 0.0, Neighbors: [1]
-            Node {
-                payload: vec![AlignedBlock::new([0.0; SIMD_LANECOUNT])].into_boxed_slice(),
-                neighbors: FlatFixedSet::new(vec![1]),
-            },
-            // 1: Pos 10.0, Neighbors: [2]
-            Node {
-                payload: vec![AlignedBlock::new([10.0; SIMD_LANECOUNT])].into_boxed_slice(),
-                neighbors: FlatFixedSet::new(vec![2]),
-            },
-            // 2: Pos 20.0, Neighbors: [1, 3]
-            Node {
-                payload: vec![AlignedBlock::new([20.0; SIMD_LANECOUNT])].into_boxed_slice(),
-                neighbors: FlatFixedSet::new(vec![1, 3]),
-            },
-            // 3: Pos 30.0, Neighbors: [4]
-            Node {
-                payload: vec![AlignedBlock::new([30.0; SIMD_LANECOUNT])].into_boxed_slice(),
-                neighbors: FlatFixedSet::new(vec![4]),
-            },
-            // 4: Pos 40.0, Neighbors: [1]
-            Node {
-                payload: vec![AlignedBlock::new([40.0; SIMD_LANECOUNT])].into_boxed_slice(),
-                neighbors: FlatFixedSet::new(vec![1]),
-            },
-        ];
-        let strategy = if catapults_enabled {
-            SearchStrategy::Catapult
-        } else {
-            SearchStrategy::Vanilla
-        };
-        // Start from node 0
-        let params = EngineStarterParams::new(
-            4,
-            40,
-            SIMD_LANECOUNT,
-            NodeId { internal: 0 },
-            42,
-            catapults_enabled,
+    #[test]
+    fn test_identity_rotation_leaves_results_unchanged() {
+        let points = random_payloads(200, 7);
+        let queries = random_payloads(20, 8);
+        let plain = built_graph(&points);
+        let rotated = built_graph(&points).with_rotation(Rotation::identity(SIMD_LANECOUNT));
+
+        let mut stats = Stats::new();
+        for q in &queries {
+            assert_eq!(
+                plain.beam_search(q, 5, 10, &mut stats),
+                rotated.beam_search(q, 5, 10, &mut stats)
+            );
+        }
+        assert_eq!(
+            plain.export_catapult_edges(),
+            rotated.export_catapult_edges()
         );
-        AdjacencyGraph::new_flat(nodes, EngineStarter::new(params), strategy)
     }
 
     #[test]
-    fn lshapg() {
-        let nodes = vec![
-            // 0: Pos 0.0, Neighbors: [1]
-            Node {
-                payload: vec![AlignedBlock::new([0.0; SIMD_LANECOUNT])].into_boxed_slice(),
-                neighbors: FlatFixedSet::new(vec![1]),
-            },
-            // 1: Pos 10.0, Neighbors: [2]
-            Node {
-                payload: vec![AlignedBlock::new([10.0; SIMD_LANECOUNT])].into_boxed_slice(),
-                neighbors: FlatFixedSet::new(vec![2]),
-            },
-            // 2: Pos 20.0, Neighbors: [1, 3]
-            Node {
-                payload: vec![AlignedBlock::new([20.0; SIMD_LANECOUNT])].into_boxed_slice(),
-                neighbors: FlatFixedSet::new(vec![1, 3]),
-            },
-            // 3: Pos 30.0, Neighbors: [4]
-            Node {
-                payload: vec![AlignedBlock::new([30.0; SIMD_LANECOUNT])].into_boxed_slice(),
-                neighbors: FlatFixedSet::new(vec![4]),
-            },
-            // 4: Pos 40.0, Neighbors: [1]
-            Node {
-                payload: vec![AlignedBlock::new([40.0; SIMD_LANECOUNT])].into_boxed_slice(),
-                neighbors: FlatFixedSet::new(vec![1]),
-            },
-        ];
-        let strategy = SearchStrategy::LshApg([ZOrderIndex::new(2, 16, 0, 1.0)]);
-        // Start from node 0
-        let params =
-            EngineStarterParams::new(4, 40, SIMD_LANECOUNT, NodeId { internal: 0 }, 42, false);
-        let lshapg: AdjacencyGraph<LruSet> =
-            AdjacencyGraph::new_flat(nodes, EngineStarter::new(params), strategy);
+    fn test_permutation_rotation_relabels_dimensions_consistently() {
+        let points = random_payloads(200, 9);
+        let perm: Vec<usize> = (0..SIMD_LANECOUNT).map(|i| (i * 5 + 3) % 16).collect();
+        let rotation = Rotation::permutation(&perm);
+        let plain = built_graph(&points);
+        let rotated = built_graph(&points).with_rotation(rotation.clone());
+
+        // payloads are stored relabeled
+        for (i, p) in points.iter().enumerate() {
+            let stored: Vec<f32> = rotated
+                .payload(NodeId { internal: i })
+                .iter()
+                .flat_map(|b| b.data)
+                .collect();
+            let expected: Vec<f32> = perm.iter().map(|&src| p[0].data[src]).collect();
+            assert_eq!(stored, expected);
+        }
+
+        // and queries are relabeled the same way, so exact neighbors do not change
+        let mut stats = Stats::new();
+        for q in &random_payloads(20, 10) {
+            let ids = |r: Vec<CandidateEntry>| r.iter().map(|c| c.index).collect::<Vec<_>>();
+            assert_eq!(
+                ids(plain.exact_search(q, 5, &mut stats)),
+                ids(rotated.exact_search(q, 5, &mut stats))
+            );
+            let best = rotated.beam_search(q, 1, 10, &mut stats)[0];
+            let expected = rotation.apply(q).l2_squared(rotated.payload(best.index));
+            assert_eq!(best.distance.0, expected);
+        }
+    }
+
+    #[test]
+    fn test_catapults_in_results_are_touched() {
+        let graph = setup_simple_graph(true);
+        let query = vec![AlignedBlock::new([39.0; SIMD_LANECOUNT])];
+        let signature = graph.starter.signature(&query);
+
+        // node 3 ends up in the top-2 of this query, node 0 does not
+        graph
+            .starter
+            .new_catapult(signature, NodeId { internal: 3 });
+        graph
+            .starter
+            .new_catapult(signature, NodeId { internal: 0 });
+
         let mut stats = Stats::new();
+        let results = graph.beam_search(&query, 2, 3, &mut stats);
+        assert_eq!(
+            results.iter().map(|e| e.index.internal).collect::<Vec<_>>(),
+            vec![4, 3]
+        );
 
+        // 3 was touched past 0, then the best result 4 was inserted as newest
         assert_eq!(
-            lshapg.beam_search(
-                &[AlignedBlock::new([40.0; SIMD_LANECOUNT])],
-                5,
-                5,
-                &mut stats
-            )[0]
-            .index,
-            NodeId { internal: 4 }
+            graph.starter.bucket_catapults(signature),
+            vec![
+                NodeId { internal: 0 },
+                NodeId { internal: 3 },
+                NodeId { internal: 4 }
+            ]
         );
     }
 
     #[test]
-    fn test_basic_search_path() {
+    fn test_adaptive_search_touches_catapults_in_results() {
         let graph = setup_simple_graph(true);
-        let query = vec![AlignedBlock::new([11.0; SIMD_LANECOUNT])];
-        let k = 2;
-        let beam_width = 3;
+        let query = vec![AlignedBlock::new([39.0; SIMD_LANECOUNT])];
+        let signature = graph.starter.signature(&query);
+        graph
+            .starter
+            .new_catapult(signature, NodeId { internal: 3 });
+        graph
+            .starter
+            .new_catapult(signature, NodeId { internal: 0 });
 
-        // Distances: 0(121), 1(1), 2(81), 3(361), 4(841)
-        let mut stats = crate::statistics::Stats::new();
-        stats.enable_adv_tracking();
+        let mut stats = Stats::new();
+        let (results, _) = graph.beam_search_adaptive(&query, 2, 3, 1, &mut stats, None);
+        assert_eq!(
+            results.iter().map(|e| e.index.internal).collect::<Vec<_>>(),
+            vec![4, 3]
+        );
 
-        let results1 = graph.beam_search(&query, k, beam_width, &mut stats);
-        let results2 = graph.beam_search(&query, k, beam_width, &mut stats);
+        // learned once, touching 3 as a regular search does
+        assert_eq!(stats.get_catapult_write_locks(), 2);
+        assert_eq!(
+            graph.starter.bucket_catapults(signature),
+            vec![
+                NodeId { internal: 0 },
+                NodeId { internal: 3 },
+                NodeId { internal: 4 }
+            ]
+        );
+    }
 
-        assert_eq!(stats.get_searches_with_catapults(), 1);
+    #[test]
+    fn test_csr_layout_preserves_neighbors_and_results() {
+        let mut csr_graph = setup_simple_graph(false);
+        let list_graph = setup_simple_graph(false);
+
+        let expected: Vec<Vec<NodeId>> = (0..list_graph.len())
+            .map(|i| list_graph.neighbors_of(i).to_vec())
+            .collect();
 
+        csr_graph.use_csr_layout();
+        assert!(csr_graph.has_csr_layout());
+        assert!(!list_graph.has_csr_layout());
+        for (i, neighbors) in expected.iter().enumerate() {
+            assert_eq!(csr_graph.neighbors_of(i), neighbors.as_slice());
+            assert!(csr_graph.adjacency[i].neighbors.as_slice().is_empty());
+        }
+        assert_eq!(csr_graph.total_edge_count(), list_graph.total_edge_count());
+
+        for q in [3.0, 17.0, 38.0] {
+            let query = vec![AlignedBlock::new([q; SIMD_LANECOUNT])];
+            let mut csr_stats = Stats::new();
+            let mut list_stats = Stats::new();
+            assert_eq!(
+                csr_graph.beam_search(&query, 2, 3, &mut csr_stats),
+                list_graph.beam_search(&query, 2, 3, &mut list_stats)
+            );
+            assert_eq!(
+                csr_stats.get_computed_dists(),
+                list_stats.get_computed_dists()
+            );
+        }
+    }
+
+    #[test]
+    fn test_batch_search_with_roaring_visitor_matches_default() {
+        use crate::sets::visited::RoaringSet;
+
+        let default_graph = setup_simple_graph(true);
+        let roaring_graph = setup_simple_graph(true);
+
+        let queries: Vec<Vec<AlignedBlock>> = [11.0, 39.0, 2.0, 25.0]
+            .iter()
+            .map(|&v| vec![AlignedBlock::new([v; SIMD_LANECOUNT])])
+            .collect();
+
+        let mut default_stats = Stats::new();
+        let default_results = default_graph.beam_search_batch(&queries, 2, 3, &mut default_stats);
+
+        let mut roaring_stats = Stats::new();
+        let roaring_results =
+            roaring_graph.beam_search_batch_with::<RoaringSet>(&queries, 2, 3, &mut roaring_stats);
+
+        assert_eq!(default_results, roaring_results);
         assert_eq!(
-            results1.iter().map(|e| e.index).collect::<Vec<_>>(),
-            results2.iter().map(|e| e.index).collect::<Vec<_>>()
+            default_stats.get_nodes_visited(),
+            roaring_stats.get_nodes_visited()
         );
+    }
 
-        // Final candidates (in order of distance): 1 (1), 2 (81), 0 (121)
-        // Top K=2 results: [1, 2]
-        assert_eq!(results1.len(), k);
-        assert_eq!(results1[0].index.internal, 1);
-        assert_eq!(results1[1].index.internal, 2);
+    /// A 2-D point embedded in one block; the remaining lanes hold a large constant so that
+    /// every point hashes to the same LSH bucket without affecting distances.
+    fn point_2d(x: f32, y: f32) -> Vec<AlignedBlock> {
+        let mut data = [100.0; SIMD_LANECOUNT];
+        data[0] = x;
+        data[1] = y;
+        vec![AlignedBlock::new(data)]
+    }
 
-        // Verify the graph structure - count total edges
-        let total_edges: usize = graph
-            .adjacency
+    // Nodes: 0 (0,0) -> [1, 2]; 1 (5,-5) -> [3]; 2 (3,3) -> [0]; 3 (10,0) -> [4]; 4 (10,10) -> [3]
+    // With beam_width 1, a query at (10,10) greedily walks 0 -> 2 and gets stuck, while a query
+    // at (10,-2) walks 0 -> 1 -> 3 and leaves node 3 behind as a catapult.
+    fn setup_detour_graph() -> AdjacencyGraph<LruSet> {
+        let positions = [
+            (0.0, 0.0),
+            (5.0, -5.0),
+            (3.0, 3.0),
+            (10.0, 0.0),
+            (10.0, 10.0),
+        ];
+        let neighbors = [vec![1, 2], vec![3], vec![0], vec![4], vec![3]];
+        let nodes = positions
             .iter()
-            .map(|n| n.neighbors.to_slice().len())
-            .sum();
-        assert_eq!(total_edges, graph.total_edge_count());
+            .zip(neighbors)
+            .map(|(&(x, y), neighs)| Node {
+                payload: point_2d(x, y).into_boxed_slice(),
+                neighbors: FlatFixedSet::new(neighs),
+            })
+            .collect();
+        let params =
+            EngineStarterParams::new(4, 40, SIMD_LANECOUNT, NodeId { internal: 0 }, 42, true);
+        AdjacencyGraph::new_flat(
+            nodes,
+            TestEngineStarter::new(params),
+            SearchStrategy::Catapult,
+        )
+    }
+
+    #[test]
+    fn test_warm_up_learns_the_best_result_of_each_query() {
+        let graph = setup_simple_graph(true);
+        assert!(graph.export_catapult_edges().is_empty());
+
+        let query = vec![AlignedBlock::new([39.0; SIMD_LANECOUNT])];
+        graph.warm_up(std::slice::from_ref(&query), 1, 2);
+        let learned: Vec<NodeId> = graph
+            .export_catapult_edges()
+            .into_iter()
+            .map(|(_, catapult)| catapult)
+            .collect();
+        assert_eq!(learned, vec![NodeId { internal: 4 }]);
+
+        // the next search of the bucket starts from the learned catapult
+        let mut stats = Stats::new();
+        let results = graph.beam_search(&query, 1, 2, &mut stats);
+        assert!(results[0].has_catapult_ancestor);
+        assert_eq!(stats.get_searches_with_catapults(), 1);
+    }
+
+    #[test]
+    fn test_read_only_searches_leave_buckets_unchanged() {
+        let mut graph = setup_simple_graph(true);
+        graph.strategy = SearchStrategy::CatapultReadOnly;
+        let query = vec![AlignedBlock::new([39.0; SIMD_LANECOUNT])];
+        let signature = graph.starter.select_starting_points(&query).signature;
+        graph
+            .starter
+            .learn_catapult(&query, signature, NodeId { internal: 3 });
+        let buckets = graph.export_catapult_edges();
+
+        let mut stats = Stats::new();
+        let first = graph.beam_search(&query, 1, 2, &mut stats);
+        let second = graph.beam_search(&query, 1, 2, &mut stats);
+
+        // node 4 would have been learned by a regular search
+        assert_eq!(first, second);
+        assert_eq!(first[0].index, NodeId { internal: 4 });
+        assert!(first[0].has_catapult_ancestor);
+        assert_eq!(stats.get_searches_with_catapults(), 2);
+        assert_eq!(graph.export_catapult_edges(), buckets);
+    }
+
+    #[test]
+    fn test_warmup_until_recall_stops_once_target_met() {
+        let graph = setup_detour_graph();
+        let sample = vec![point_2d(10.0, 10.0), point_2d(3.0, 3.0)];
+        let truth = vec![vec![4], vec![2]];
+        let warmup = vec![point_2d(0.0, 0.0), point_2d(10.0, -2.0), point_2d(1.0, 1.0)];
+
+        let signature = graph.starter.select_starting_points(&sample[0]).signature;
+        for q in sample.iter().chain(warmup.iter()) {
+            assert_eq!(graph.starter.select_starting_points(q).signature, signature);
+        }
+
+        let measure = |graph: &AdjacencyGraph<LruSet>| {
+            let mut stats = Stats::new();
+            let mut scratch = SearchScratch::<CompressedBitset>::new(1);
+            let results: Vec<Vec<usize>> = sample
+                .iter()
+                .map(|q| {
+                    graph
+                        .beam_search_with_scratch(
+                            q,
+                            1,
+                            1,
+                            &mut stats,
+                            &mut scratch,
+                            &SearchOptions::default(),
+                        )
+                        .results
+                        .iter()
+                        .map(|e| e.index.internal)
+                        .collect()
+                })
+                .collect();
+            recall_at_k(&results, &truth, 1)
+        };
+
+        assert_eq!(measure(&graph), 0.5);
+        // measuring recall must not have taught the graph anything
+        assert!(
+            graph
+                .starter
+                .select_starting_points(&sample[0])
+                .catapults
+                .is_empty()
+        );
+
+        let used = graph.warmup_until_recall(&warmup, &sample, &truth, 1, 1, 1, 1.0);
+        assert_eq!(used, 2);
+        assert_eq!(measure(&graph), 1.0);
+
+        // a target that is already met needs no warmup at all
+        assert_eq!(
+            graph.warmup_until_recall(&warmup, &sample, &truth, 1, 1, 1, 1.0),
+            0
+        );
     }
 
     #[test]
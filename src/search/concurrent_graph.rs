@@ -0,0 +1,514 @@
+use std::sync::{
+    Mutex, OnceLock, RwLock,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    numerics::{AlignedBlock, Payload, SIMD_LANECOUNT},
+    search::{Node, NodeId, SearchStrategy, hash_start::EngineStarter},
+    sets::{
+        candidates::{CandidateEntry, SmallestKCandidates},
+        catapults::CatapultEvictionPolicy,
+        fixed::FlatFixedSet,
+        visited::{CompressedBitset, VisitorSet},
+    },
+    statistics::Stats,
+};
+
+/// Number of nodes in the first arena chunk; each following chunk is twice as large.
+const FIRST_CHUNK_LEN: usize = 1024;
+
+/// Number of chunk slots, enough for `FIRST_CHUNK_LEN * (2^48 - 1)` nodes.
+const NUM_CHUNKS: usize = 48;
+
+/// A node whose neighbor list can be updated while other threads read it.
+struct ConcurrentNode<P> {
+    payload: P,
+    neighbors: RwLock<FlatFixedSet>,
+}
+
+/// A slice of node slots, each written once when its node is inserted.
+type Chunk<P> = Box<[OnceLock<ConcurrentNode<P>>]>;
+
+/// A proximity graph that accepts insertions while concurrent searches run.
+///
+/// [`AdjacencyGraph`](crate::search::AdjacencyGraph) needs `&mut self` to insert, so
+/// interleaving insertions with searches requires wrapping it in a lock that blocks every
+/// search for the duration of each insertion. This graph instead takes `&self` for both
+/// operations, and can be shared between threads (e.g. in an `Arc` or a thread scope).
+///
+/// # Storage
+/// Nodes live in a chunked arena: chunk `c` holds `1024 * 2^c` node slots and is allocated
+/// when the first node landing in it is inserted. Chunks never move, so a node's index is
+/// stable and reading a node takes no lock. Each neighbor list sits behind its own
+/// `RwLock`; searches copy a node's list under its read lock before scoring it, so they
+/// see a consistent snapshot of every node they expand, while an insertion only blocks the
+/// few nodes it links back from.
+///
+/// # Consistency
+/// Insertions are serialized among themselves. A new node is published, i.e. counted in
+/// [`len`](Self::len), before any existing node links to it, so searches never follow an
+/// edge to a node that is not there yet. A search running during an insertion may or may
+/// not see the new node, depending on whether it expanded the linking neighbors before or
+/// after the back-links were added.
+///
+/// Unlike `AdjacencyGraph`, this graph supports neither the CSR layout, query rotation
+/// or transforms, nor duplicate collapsing, and searches always run to convergence.
+pub struct ConcurrentGraph<EvictPolicy, P = Box<[AlignedBlock]>>
+where
+    EvictPolicy: CatapultEvictionPolicy,
+    P: Payload,
+{
+    chunks: Box<[OnceLock<Chunk<P>>]>,
+    len: AtomicUsize,
+    /// Serializes insertions, which allocate indices and chunks.
+    insertion: Mutex<()>,
+    starter: EngineStarter<EvictPolicy>,
+    learn_catapults: bool,
+}
+
+/// Returns the chunk holding node `index`, and the offset of the node in that chunk.
+fn locate(index: usize) -> (usize, usize) {
+    let position = index / FIRST_CHUNK_LEN + 1;
+    let chunk = (usize::BITS - 1 - position.leading_zeros()) as usize;
+    (chunk, index - FIRST_CHUNK_LEN * ((1 << chunk) - 1))
+}
+
+impl<EvictPolicy, P> ConcurrentGraph<EvictPolicy, P>
+where
+    EvictPolicy: CatapultEvictionPolicy,
+    P: Payload,
+{
+    /// Creates a concurrent graph from an initial set of nodes.
+    ///
+    /// # Arguments
+    /// * `adj` - Vector of nodes representing the graph, where `adj[i]` is node `i`
+    /// * `engine` - LSH-based starting point selector managing catapult buckets
+    /// * `strategy` - [`SearchStrategy::Catapult`] to learn catapults,
+    ///   [`SearchStrategy::CatapultReadOnly`] to only use existing ones, or
+    ///   [`SearchStrategy::Vanilla`] to always start from the engine's starting node
+    ///
+    /// # Returns
+    /// A new `ConcurrentGraph` instance ready for searches and insertions
+    ///
+    /// # Panics
+    /// * Panics if the payloads do not span exactly the blocks needed by the engine's
+    ///   dimension
+    /// * Panics if `strategy` is [`SearchStrategy::LshApg`], whose Z-order indexes are
+    ///   built once and cannot follow insertions
+    pub fn new(
+        adj: Vec<Node<P>>,
+        engine: EngineStarter<EvictPolicy>,
+        strategy: SearchStrategy,
+    ) -> Self {
+        assert!(
+            adj.iter().all(
+                |node| node.payload.num_blocks() == engine.plane_dim().div_ceil(SIMD_LANECOUNT)
+            ),
+            "payload dimension does not match the engine's dimension"
+        );
+        let learn_catapults = match strategy {
+            SearchStrategy::Vanilla | SearchStrategy::CatapultReadOnly => false,
+            SearchStrategy::Catapult => true,
+            SearchStrategy::LshApg(_) => panic!("LSH-APG indexes cannot follow insertions"),
+        };
+
+        let graph = Self {
+            chunks: (0..NUM_CHUNKS).map(|_| OnceLock::new()).collect(),
+            len: AtomicUsize::new(0),
+            insertion: Mutex::new(()),
+            starter: engine,
+            learn_catapults,
+        };
+        for (index, node) in adj.into_iter().enumerate() {
+            graph.publish(
+                index,
+                ConcurrentNode {
+                    payload: node.payload,
+                    neighbors: RwLock::new(node.neighbors),
+                },
+            );
+        }
+        graph
+    }
+
+    /// Writes `node` into slot `index` and makes it visible to searches.
+    ///
+    /// Must be called with increasing indices, by one thread at a time.
+    fn publish(&self, index: usize, node: ConcurrentNode<P>) {
+        let (chunk, offset) = locate(index);
+        let slots = self.chunks[chunk].get_or_init(|| {
+            (0..FIRST_CHUNK_LEN << chunk)
+                .map(|_| OnceLock::new())
+                .collect()
+        });
+        if slots[offset].set(node).is_err() {
+            unreachable!("node slots are written once");
+        }
+        self.len.store(index + 1, Ordering::Release);
+    }
+
+    /// Returns the published node at `index`.
+    fn node(&self, index: usize) -> &ConcurrentNode<P> {
+        let (chunk, offset) = locate(index);
+        self.chunks[chunk]
+            .get()
+            .and_then(|slots| slots[offset].get())
+            .expect("node index out of bounds")
+    }
+
+    /// Returns the number of nodes visible to searches.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    /// Returns whether the graph holds no node.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the engine selecting starting points and storing catapults.
+    pub fn starter(&self) -> &EngineStarter<EvictPolicy> {
+        &self.starter
+    }
+
+    /// Returns a snapshot of the neighbors of `node`.
+    ///
+    /// # Panics
+    /// Panics if `node` is not a node of the graph
+    pub fn neighbors(&self, node: NodeId) -> Vec<NodeId> {
+        self.node(node.internal)
+            .neighbors
+            .read()
+            .unwrap()
+            .as_slice()
+            .to_vec()
+    }
+
+    /// Appends a new node and links it to the given neighbors, while searches may run.
+    ///
+    /// Behaves like [`AdjacencyGraph::insert`](crate::search::AdjacencyGraph::insert): the
+    /// new node gets the next free index and an edge to every node of `neighbors`, and with
+    /// `back_link_degree` set to `Some(max_degree)`, each of those neighbors gets an edge
+    /// back unless it already has `max_degree` neighbors. Each back-link takes the write
+    /// lock of one neighbor list, so searches only wait on the lists being extended.
+    /// Concurrent insertions are serialized.
+    ///
+    /// # Arguments
+    /// * `payload` - Vector of the new node
+    /// * `neighbors` - Indices of existing nodes the new node links to
+    /// * `back_link_degree` - Degree up to which neighbors link back to the new node, or
+    ///   `None` to leave existing nodes untouched
+    ///
+    /// # Returns
+    /// The id of the inserted node
+    ///
+    /// # Panics
+    /// * Panics if a neighbor index does not refer to an existing node
+    /// * Panics if the payload dimension differs from the graph's
+    pub fn insert(
+        &self,
+        payload: P,
+        neighbors: Vec<usize>,
+        back_link_degree: Option<usize>,
+    ) -> NodeId {
+        let _guard = self.insertion.lock().unwrap();
+        let id = NodeId {
+            internal: self.len(),
+        };
+        assert!(
+            neighbors.iter().all(|&n| n < id.internal),
+            "neighbor index out of bounds"
+        );
+        assert_eq!(
+            payload.num_blocks(),
+            self.starter.plane_dim().div_ceil(SIMD_LANECOUNT),
+            "payload dimension differs from the graph's"
+        );
+
+        let back_links = back_link_degree.map(|max_degree| (max_degree, neighbors.clone()));
+        self.publish(
+            id.internal,
+            ConcurrentNode {
+                payload,
+                neighbors: RwLock::new(FlatFixedSet::compact(neighbors, id.internal + 1)),
+            },
+        );
+
+        // only link to the new node once searches can reach it
+        if let Some((max_degree, neighbors)) = back_links {
+            for n in neighbors {
+                let mut set = self.node(n).neighbors.write().unwrap();
+                if set.len() < max_degree && !set.as_slice().contains(&id) {
+                    set.push(id);
+                }
+            }
+        }
+        id
+    }
+
+    /// Scores `indices` against `query`.
+    fn distances_from_indices(
+        &self,
+        indices: &[NodeId],
+        query: &[AlignedBlock],
+        catapult_marker: bool,
+        stats: &mut Stats,
+    ) -> Vec<CandidateEntry> {
+        stats.bump_computed_dists(indices.len());
+        indices
+            .iter()
+            .map(|&index| CandidateEntry {
+                distance: self
+                    .node(index.internal)
+                    .payload
+                    .l2_squared_to(query)
+                    .into(),
+                index,
+                has_catapult_ancestor: catapult_marker,
+            })
+            .collect()
+    }
+
+    /// Performs approximate k-nearest neighbor search, concurrently with insertions.
+    ///
+    /// Follows the same steps as
+    /// [`AdjacencyGraph::beam_search`](crate::search::AdjacencyGraph::beam_search),
+    /// learning catapults with the [`SearchStrategy::Catapult`] strategy. Each expanded
+    /// node's neighbor list is copied under its read lock, so the search sees the list as
+    /// it was either before or after any concurrent back-link, never halfway.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector as aligned blocks
+    /// * `k` - Number of nearest neighbors to return
+    /// * `beam_width` - Maximum beam size during search (must be ≥ k)
+    /// * `stats` - Statistics tracker for performance monitoring
+    ///
+    /// # Returns
+    /// A vector of the k nearest candidate entries, sorted by ascending distance
+    ///
+    /// # Panics
+    /// Panics if `beam_width < k`
+    pub fn beam_search(
+        &self,
+        query: &[AlignedBlock],
+        k: usize,
+        beam_width: usize,
+        stats: &mut Stats,
+    ) -> Vec<CandidateEntry> {
+        assert!(beam_width >= k);
+        stats.bump_beam_calls();
+
+        let starting_points = self.starter.select_starting_points(query);
+        let mut candidates = SmallestKCandidates::new(beam_width);
+        candidates.insert_batch(&self.distances_from_indices(
+            &starting_points.catapults,
+            query,
+            true,
+            stats,
+        ));
+        let fixed_entries: Vec<NodeId> = std::iter::once(starting_points.starting_node)
+            .chain(starting_points.entry_points)
+            .filter(|&node| !candidates.contains(node))
+            .collect();
+        candidates.insert_batch(&self.distances_from_indices(&fixed_entries, query, false, stats));
+
+        let mut visited = CompressedBitset::new();
+        let mut fresh = Vec::new();
+        while let Some(best) = candidates
+            .iter()
+            .filter(|c| !visited.get(c.index.internal))
+            .min()
+            .copied()
+        {
+            {
+                let neighbors = self.node(best.index.internal).neighbors.read().unwrap();
+                fresh.clear();
+                fresh.extend(
+                    neighbors
+                        .as_slice()
+                        .iter()
+                        .filter(|&n| !candidates.contains(n)),
+                );
+            }
+            let scored =
+                self.distances_from_indices(&fresh, query, best.has_catapult_ancestor, stats);
+            candidates.insert_batch(&scored);
+            visited.set(best.index.internal);
+            stats.bump_nodes_visited();
+        }
+
+        let results: Vec<CandidateEntry> = candidates.into_iter().take(k).collect();
+
+        // with k = 0 there is no result to learn
+        if self.learn_catapults
+            && let Some(best) = results.iter().min().map(|e| e.index)
+        {
+            let useful: Vec<NodeId> = starting_points
+                .catapults
+                .iter()
+                .copied()
+                .filter(|c| results.iter().any(|e| e.index == *c))
+                .collect();
+            stats.bump_catapult_write_locks(1 + usize::from(!useful.is_empty()));
+            self.starter
+                .touch_catapults(starting_points.signature, &useful);
+            self.starter
+                .learn_catapult(query, starting_points.signature, best);
+        }
+        if results.iter().any(|e| e.has_catapult_ancestor) {
+            stats.bump_searches_with_catapults();
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        search::{GraphBuilder, hash_start::EngineStarterParams},
+        sets::catapults::LruSet,
+    };
+    use rand::prelude::*;
+    use std::sync::atomic::AtomicBool;
+
+    fn random_points(n: usize, seed: u64) -> Vec<Vec<AlignedBlock>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..n)
+            .map(|_| {
+                let mut data = [0.0; SIMD_LANECOUNT];
+                for v in data.iter_mut() {
+                    *v = rng.random_range(-10.0..10.0);
+                }
+                vec![AlignedBlock::new(data)]
+            })
+            .collect()
+    }
+
+    fn concurrent_graph(points: &[Vec<AlignedBlock>]) -> ConcurrentGraph<LruSet> {
+        let params =
+            EngineStarterParams::new(4, 8, SIMD_LANECOUNT, NodeId { internal: 0 }, 42, true);
+        ConcurrentGraph::new(
+            GraphBuilder::new(8).build(points),
+            EngineStarter::new(params),
+            SearchStrategy::Catapult,
+        )
+    }
+
+    #[test]
+    fn locate_maps_indices_to_growing_chunks() {
+        assert_eq!(locate(0), (0, 0));
+        assert_eq!(locate(FIRST_CHUNK_LEN - 1), (0, FIRST_CHUNK_LEN - 1));
+        assert_eq!(locate(FIRST_CHUNK_LEN), (1, 0));
+        assert_eq!(
+            locate(3 * FIRST_CHUNK_LEN - 1),
+            (1, 2 * FIRST_CHUNK_LEN - 1)
+        );
+        assert_eq!(locate(3 * FIRST_CHUNK_LEN), (2, 0));
+    }
+
+    #[test]
+    fn insert_links_back_and_grows_past_first_chunk() {
+        let points = random_points(FIRST_CHUNK_LEN + 10, 1);
+        let graph = concurrent_graph(&points[..20]);
+        for point in &points[20..] {
+            let id = graph.insert(point.clone().into_boxed_slice(), vec![3], Some(usize::MAX));
+            assert_eq!(id.internal, graph.len() - 1);
+        }
+        assert_eq!(graph.len(), points.len());
+        assert_eq!(
+            graph.neighbors(NodeId {
+                internal: FIRST_CHUNK_LEN + 5
+            }),
+            vec![NodeId { internal: 3 }]
+        );
+        assert!(graph.neighbors(NodeId { internal: 3 }).contains(&NodeId {
+            internal: FIRST_CHUNK_LEN + 5
+        }));
+    }
+
+    #[test]
+    fn search_with_k_zero_learns_nothing() {
+        let graph = concurrent_graph(&random_points(50, 3));
+        let query = &random_points(1, 4)[0];
+        let mut stats = Stats::new();
+        assert!(graph.beam_search(query, 0, 8, &mut stats).is_empty());
+        assert_eq!(graph.starter().catapult_count(), 0);
+        assert_eq!(graph.beam_search(query, 1, 8, &mut stats).len(), 1);
+        assert_eq!(graph.starter().catapult_count(), 1);
+    }
+
+    #[test]
+    fn read_only_searches_count_catapult_use() {
+        let points = random_points(50, 3);
+        let params =
+            EngineStarterParams::new(4, 8, SIMD_LANECOUNT, NodeId { internal: 0 }, 42, true);
+        let graph = ConcurrentGraph::<LruSet>::new(
+            GraphBuilder::new(8).build(&points),
+            EngineStarter::new(params),
+            SearchStrategy::CatapultReadOnly,
+        );
+        let query = &random_points(1, 4)[0];
+        let signature = graph.starter().signature(query);
+        graph
+            .starter()
+            .new_catapult(signature, NodeId { internal: 7 });
+
+        let mut stats = Stats::new();
+        graph.beam_search(query, 5, 50, &mut stats);
+        assert_eq!(stats.get_searches_with_catapults(), 1);
+        assert_eq!(stats.get_catapult_write_locks(), 0);
+        assert_eq!(graph.starter().catapult_count(), 1);
+    }
+
+    #[test]
+    fn searches_run_while_nodes_are_inserted() {
+        let points = random_points(600, 2);
+        let (initial, inserted) = points.split_at(300);
+        let graph = concurrent_graph(initial);
+        let done = AtomicBool::new(false);
+
+        std::thread::scope(|scope| {
+            for reader in 0..4 {
+                let (graph, done) = (&graph, &done);
+                scope.spawn(move || {
+                    let queries = random_points(50, 10 + reader);
+                    let mut stats = Stats::new();
+                    while !done.load(Ordering::Relaxed) {
+                        for query in &queries {
+                            let results = graph.beam_search(query, 5, 16, &mut stats);
+                            assert_eq!(results.len(), 5);
+                            assert!(results.is_sorted());
+                        }
+                    }
+                });
+            }
+
+            let mut stats = Stats::new();
+            for point in inserted {
+                let neighbors = graph
+                    .beam_search(point, 8, 32, &mut stats)
+                    .iter()
+                    .map(|c| c.index.internal)
+                    .collect();
+                graph.insert(point.clone().into_boxed_slice(), neighbors, Some(16));
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+
+        assert_eq!(graph.len(), points.len());
+        let mut stats = Stats::new();
+        let found = inserted
+            .iter()
+            .enumerate()
+            .filter(|(i, point)| {
+                graph.beam_search(point, 1, 32, &mut stats)[0]
+                    .index
+                    .internal
+                    == 300 + i
+            })
+            .count();
+        assert!(found >= inserted.len() * 95 / 100, "found {found}");
+    }
+}
@@ -0,0 +1,229 @@
+use crate::{
+    numerics::Payload,
+    search::{AdjacencyGraph, NodeId},
+    sets::{
+        catapults::CatapultEvictionPolicy,
+        visited::{CompressedBitset, VisitorSet},
+    },
+};
+
+/// The result of checking that every node of a graph can be found by a search.
+///
+/// Beam search only ever reaches nodes connected to its entry points, and expanding an
+/// edge to a node that does not exist panics. Graphs built by [`GraphBuilder`] satisfy both
+/// invariants, but third-party graphs may not.
+///
+/// [`GraphBuilder`]: crate::search::GraphBuilder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectivityReport {
+    /// Number of nodes in the graph
+    pub num_nodes: usize,
+
+    /// Nodes no path leads to from the starting node or the entry points, in ascending
+    /// order
+    pub unreachable: Vec<NodeId>,
+
+    /// Edges pointing past the last node, as (source node, neighbor index) pairs
+    pub out_of_bounds_edges: Vec<(NodeId, usize)>,
+}
+
+impl ConnectivityReport {
+    /// Returns whether every node is reachable and every edge points to a node.
+    pub fn is_valid(&self) -> bool {
+        self.unreachable.is_empty() && self.out_of_bounds_edges.is_empty()
+    }
+}
+
+impl<EvictPolicy, P> AdjacencyGraph<EvictPolicy, P>
+where
+    EvictPolicy: CatapultEvictionPolicy,
+    P: Payload,
+{
+    /// Checks that searches can reach every node without following a dangling edge.
+    ///
+    /// Runs a depth-first traversal from the starting node and the fixed entry points,
+    /// along the directed edges searches follow, and collects the nodes it never reaches.
+    /// Catapults are not entry points here: they only exist once learned, so they cannot
+    /// be relied on to reach a node. Out-of-bounds edges are reported and not followed.
+    /// Deleted nodes are treated like live ones, since searches still expand them.
+    ///
+    /// # Returns
+    /// A report of the unreachable nodes and out-of-bounds edges, which is valid if the
+    /// graph has neither
+    pub fn validate(&self) -> ConnectivityReport {
+        let num_nodes = self.len();
+        let mut out_of_bounds_edges = Vec::new();
+        for node in 0..num_nodes {
+            for neighbor in self.neighbors_of(node) {
+                if neighbor.internal >= num_nodes {
+                    out_of_bounds_edges.push((NodeId { internal: node }, neighbor.internal));
+                }
+            }
+        }
+
+        let roots: Vec<NodeId> = self
+            .fixed_entry_nodes()
+            .into_iter()
+            .filter(|root| root.internal < num_nodes)
+            .collect();
+        let reached = self.reachable_from(&roots, CompressedBitset::new());
+        ConnectivityReport {
+            num_nodes,
+            unreachable: (0..num_nodes)
+                .filter(|&node| !reached.get(node))
+                .map(|internal| NodeId { internal })
+                .collect(),
+            out_of_bounds_edges,
+        }
+    }
+
+    /// Marks every node reachable from `roots` in `reached`, skipping out-of-bounds edges.
+    fn reachable_from(&self, roots: &[NodeId], mut reached: CompressedBitset) -> CompressedBitset {
+        let mut frontier = Vec::new();
+        for root in roots {
+            if !reached.get(root.internal) {
+                reached.set(root.internal);
+                frontier.push(root.internal);
+            }
+        }
+        while let Some(node) = frontier.pop() {
+            for neighbor in self.neighbors_of(node) {
+                if neighbor.internal < self.len() && !reached.get(neighbor.internal) {
+                    reached.set(neighbor.internal);
+                    frontier.push(neighbor.internal);
+                }
+            }
+        }
+        reached
+    }
+}
+
+impl<EvictPolicy> AdjacencyGraph<EvictPolicy>
+where
+    EvictPolicy: CatapultEvictionPolicy,
+{
+    /// Fixes what [`validate`](Self::validate) reports, so searches can reach every node.
+    ///
+    /// Out-of-bounds edges are dropped first. Then, while some node is unreachable, the
+    /// lowest unreachable node gets an incoming edge from its nearest reachable node, which
+    /// makes it and everything it leads to reachable. Each orphan component thus costs one
+    /// extra edge and a scan over the reachable nodes, so nearest-node lookups dominate on
+    /// graphs with many small components. Repaired nodes may exceed the degree of the
+    /// original graph by the number of components attached to them.
+    ///
+    /// # Returns
+    /// The report of the graph before the repair
+    pub fn repair(&mut self) -> ConnectivityReport {
+        let report = self.validate();
+        let num_nodes = report.num_nodes;
+        let mut broken: Vec<usize> = report
+            .out_of_bounds_edges
+            .iter()
+            .map(|(node, _)| node.internal)
+            .collect();
+        broken.dedup();
+        for node in broken {
+            let kept = self
+                .neighbors_of(node)
+                .iter()
+                .map(|n| n.internal)
+                .filter(|&n| n < num_nodes)
+                .collect();
+            self.set_neighbors(node, kept);
+        }
+
+        let mut reached = self.reachable_from(&self.fixed_entry_nodes(), CompressedBitset::new());
+        for orphan in report.unreachable.iter().map(|node| node.internal) {
+            if reached.get(orphan) {
+                continue;
+            }
+            let orphan_payload = self.payload(NodeId { internal: orphan });
+            let anchor = (0..num_nodes)
+                .filter(|&node| reached.get(node))
+                .min_by(|&a, &b| {
+                    let distance = |internal: usize| {
+                        self.payload(NodeId { internal })
+                            .l2_squared_to(orphan_payload)
+                    };
+                    distance(a).total_cmp(&distance(b))
+                })
+                .expect("the starting node is always reachable");
+
+            let mut neighbors: Vec<usize> = self
+                .neighbors_of(anchor)
+                .iter()
+                .map(|n| n.internal)
+                .collect();
+            neighbors.push(orphan);
+            self.set_neighbors(anchor, neighbors);
+            reached = self.reachable_from(&[NodeId { internal: orphan }], reached);
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        numerics::{AlignedBlock, SIMD_LANECOUNT},
+        search::{
+            AdjacencyGraph, Node, NodeId, SearchStrategy,
+            hash_start::{EngineStarter, EngineStarterParams},
+        },
+        sets::{catapults::LruSet, fixed::FlatFixedSet},
+        statistics::Stats,
+    };
+
+    /// Nodes at positions 0..6 where 0 → 1 → 2 is the main component, 3 ⇄ 4 an orphan
+    /// pair and 5 an isolated node with a dangling edge to node 9.
+    fn disconnected_graph() -> AdjacencyGraph<LruSet> {
+        let edges = [vec![1], vec![2], vec![1], vec![4], vec![3], vec![9]];
+        let nodes = edges
+            .into_iter()
+            .enumerate()
+            .map(|(i, neighbors)| Node {
+                payload: vec![AlignedBlock::new([i as f32; SIMD_LANECOUNT])].into_boxed_slice(),
+                neighbors: FlatFixedSet::new(neighbors),
+            })
+            .collect();
+        let params =
+            EngineStarterParams::new(4, 8, SIMD_LANECOUNT, NodeId { internal: 0 }, 42, false);
+        AdjacencyGraph::new_flat(nodes, EngineStarter::new(params), SearchStrategy::Vanilla)
+    }
+
+    #[test]
+    fn validate_reports_orphans_and_dangling_edges() {
+        let report = disconnected_graph().validate();
+        assert_eq!(report.num_nodes, 6);
+        let unreachable: Vec<usize> = report.unreachable.iter().map(|n| n.internal).collect();
+        assert_eq!(unreachable, vec![3, 4, 5]);
+        assert_eq!(
+            report.out_of_bounds_edges,
+            vec![(NodeId { internal: 5 }, 9)]
+        );
+        assert!(!report.is_valid());
+    }
+
+    #[test]
+    fn repair_connects_every_component() {
+        let mut graph = disconnected_graph();
+        graph.use_csr_layout();
+        assert!(!graph.repair().is_valid());
+        assert!(graph.validate().is_valid());
+
+        // orphans hang off their nearest reachable node: 2 for node 3, then 4 for node 5
+        assert_eq!(
+            graph.neighbors_of(2).to_vec(),
+            vec![NodeId { internal: 1 }, NodeId { internal: 3 }]
+        );
+        assert_eq!(
+            graph.neighbors_of(4).to_vec(),
+            vec![NodeId { internal: 3 }, NodeId { internal: 5 }]
+        );
+        assert!(graph.neighbors_of(5).is_empty());
+
+        let query = vec![AlignedBlock::new([5.0; SIMD_LANECOUNT])];
+        let results = graph.beam_search(&query, 1, 1, &mut Stats::new());
+        assert_eq!(results[0].index, NodeId { internal: 5 });
+    }
+}
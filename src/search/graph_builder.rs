@@ -0,0 +1,287 @@
+use std::collections::HashSet;
+
+use crate::{
+    numerics::{AlignedBlock, VectorLike},
+    search::node::Node,
+    sets::fixed::FlatFixedSet,
+};
+
+/// Builds a proximity graph in-process from raw vectors.
+///
+/// Nodes are inserted one at a time in input order, following the incremental Vamana
+/// (DiskANN) construction: each new point runs a greedy beam search over the nodes
+/// inserted so far, starting from node 0, and picks its neighbors among every node seen
+/// during that search with the RobustPrune rule. Each chosen neighbor links back to the
+/// new point and is pruned again if that pushes it over `max_degree`.
+///
+/// This is a single-pass, single-threaded builder meant for small to medium datasets and
+/// tests; it does not match the quality of a multi-pass DiskANN build. Node 0 is a good
+/// starting node for the resulting graph, since every construction search started there.
+///
+/// # Examples
+/// ```
+/// use catapult::{
+///     numerics::{AlignedBlock, SIMD_LANECOUNT},
+///     search::GraphBuilder,
+/// };
+///
+/// let payloads: Vec<Vec<AlignedBlock>> = (0..10)
+///     .map(|i| vec![AlignedBlock::new([i as f32; SIMD_LANECOUNT])])
+///     .collect();
+/// let nodes = GraphBuilder::new(4).build(&payloads);
+/// assert_eq!(nodes.len(), 10);
+/// assert!(nodes.iter().all(|n| n.neighbors.len() <= 4));
+/// ```
+#[derive(Debug, Clone)]
+pub struct GraphBuilder {
+    max_degree: usize,
+    beam_width: usize,
+    alpha: f32,
+}
+
+impl GraphBuilder {
+    /// Creates a builder producing nodes with at most `max_degree` neighbors.
+    ///
+    /// The construction beam width defaults to `2 * max_degree` and the pruning factor
+    /// `alpha` to 1.2.
+    ///
+    /// # Arguments
+    /// * `max_degree` - Maximum number of outgoing edges per node
+    ///
+    /// # Panics
+    /// Panics if `max_degree == 0`
+    pub fn new(max_degree: usize) -> Self {
+        assert!(max_degree > 0, "max_degree must be positive");
+        GraphBuilder {
+            max_degree,
+            beam_width: 2 * max_degree,
+            alpha: 1.2,
+        }
+    }
+
+    /// Sets the beam width of the searches run while inserting points.
+    ///
+    /// Larger values find better neighbor candidates at the cost of a slower build.
+    ///
+    /// # Arguments
+    /// * `beam_width` - Construction beam width, must be positive
+    ///
+    /// # Returns
+    /// The updated builder
+    pub fn with_beam_width(mut self, beam_width: usize) -> Self {
+        assert!(beam_width > 0, "beam_width must be positive");
+        self.beam_width = beam_width;
+        self
+    }
+
+    /// Sets the RobustPrune factor.
+    ///
+    /// A candidate is dropped when an already selected neighbor is `alpha` times closer
+    /// to it than the node being pruned. `alpha = 1.0` gives the sparsest graphs; larger
+    /// values keep more long edges, which shortens search paths.
+    ///
+    /// # Arguments
+    /// * `alpha` - Pruning factor, at least 1.0
+    ///
+    /// # Returns
+    /// The updated builder
+    pub fn with_alpha(mut self, alpha: f32) -> Self {
+        assert!(alpha >= 1.0, "alpha must be at least 1.0");
+        self.alpha = alpha;
+        self
+    }
+
+    /// Builds the neighbor lists of all `payloads`.
+    ///
+    /// # Arguments
+    /// * `payloads` - One vector per node, all of the same dimension
+    ///
+    /// # Returns
+    /// One node per payload, in input order, ready for
+    /// [`AdjacencyGraph::new_flat`](crate::search::AdjacencyGraph::new_flat)
+    pub fn build(&self, payloads: &[Vec<AlignedBlock>]) -> Vec<Node> {
+        let mut neighbors: Vec<Vec<usize>> = vec![Vec::new(); payloads.len()];
+
+        for point in 1..payloads.len() {
+            let visited = self.greedy_search(payloads, &neighbors, point);
+            neighbors[point] = self.robust_prune(payloads, point, visited);
+
+            for i in 0..neighbors[point].len() {
+                let neighbor = neighbors[point][i];
+                neighbors[neighbor].push(point);
+                if neighbors[neighbor].len() > self.max_degree {
+                    let candidates = std::mem::take(&mut neighbors[neighbor]);
+                    neighbors[neighbor] = self.robust_prune(payloads, neighbor, candidates);
+                }
+            }
+        }
+
+        payloads
+            .iter()
+            .zip(neighbors)
+            .map(|(payload, neighs)| Node {
+                payload: payload.clone().into_boxed_slice(),
+                neighbors: FlatFixedSet::compact(neighs, payloads.len()),
+            })
+            .collect()
+    }
+
+    /// Runs a beam search for `payloads[point]` over the nodes inserted before it.
+    ///
+    /// # Returns
+    /// Every node expanded during the search
+    fn greedy_search(
+        &self,
+        payloads: &[Vec<AlignedBlock>],
+        neighbors: &[Vec<usize>],
+        point: usize,
+    ) -> Vec<usize> {
+        let query = &payloads[point];
+        let mut seen = HashSet::from([0]);
+        let mut beam = vec![(payloads[0].l2_squared(query), 0, false)];
+        let mut expanded = Vec::new();
+
+        while let Some(best) = beam.iter().position(|&(_, _, done)| !done) {
+            beam[best].2 = true;
+            let node = beam[best].1;
+            expanded.push(node);
+
+            for &neighbor in &neighbors[node] {
+                if neighbor < point && seen.insert(neighbor) {
+                    beam.push((payloads[neighbor].l2_squared(query), neighbor, false));
+                }
+            }
+            beam.sort_by(|a, b| a.0.total_cmp(&b.0));
+            beam.truncate(self.beam_width);
+        }
+        expanded
+    }
+
+    /// Selects at most `max_degree` diverse neighbors for `point` among `candidates`.
+    ///
+    /// Candidates are considered closest first. Each selected neighbor removes every
+    /// remaining candidate that is at least `alpha` times closer to it than to `point`.
+    fn robust_prune(
+        &self,
+        payloads: &[Vec<AlignedBlock>],
+        point: usize,
+        candidates: Vec<usize>,
+    ) -> Vec<usize> {
+        let mut candidates: Vec<(f32, usize)> = candidates
+            .into_iter()
+            .filter(|&c| c != point)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .map(|c| (payloads[c].l2(&payloads[point]), c))
+            .collect();
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+        let mut selected = Vec::with_capacity(self.max_degree);
+        while !candidates.is_empty() && selected.len() < self.max_degree {
+            let (_, chosen) = candidates.remove(0);
+            selected.push(chosen);
+            candidates.retain(|&(dist_to_point, c)| {
+                self.alpha * payloads[chosen].l2(&payloads[c]) > dist_to_point
+            });
+        }
+        selected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        numerics::SIMD_LANECOUNT,
+        search::{
+            AdjacencyGraph, NodeId, SearchStrategy,
+            hash_start::{EngineStarter, EngineStarterParams},
+        },
+        sets::{candidates::CandidateEntry, catapults::LruSet},
+        statistics::{Stats, recall_at_k},
+    };
+    use rand::prelude::*;
+
+    fn random_points(n: usize, seed: u64) -> Vec<Vec<AlignedBlock>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..n)
+            .map(|_| {
+                let mut data = [0.0; SIMD_LANECOUNT];
+                for v in data.iter_mut() {
+                    *v = rng.random_range(-1.0..1.0);
+                }
+                vec![AlignedBlock::new(data)]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_built_graph_reaches_high_recall() {
+        let points = random_points(1000, 1);
+        let nodes = GraphBuilder::new(16).build(&points);
+        let params =
+            EngineStarterParams::new(4, 4, SIMD_LANECOUNT, NodeId { internal: 0 }, 42, false);
+        let graph = AdjacencyGraph::new_flat(
+            nodes,
+            EngineStarter::<LruSet>::new(params),
+            SearchStrategy::Vanilla,
+        );
+
+        let queries = random_points(50, 2);
+        let mut stats = Stats::new();
+        let ids = |results: Vec<CandidateEntry>| {
+            results.iter().map(|c| c.index.internal).collect::<Vec<_>>()
+        };
+        let found: Vec<Vec<usize>> = queries
+            .iter()
+            .map(|q| ids(graph.beam_search(q, 10, 40, &mut stats)))
+            .collect();
+        let truth: Vec<Vec<usize>> = queries
+            .iter()
+            .map(|q| ids(graph.exact_search(q, 10, &mut stats)))
+            .collect();
+
+        let recall = recall_at_k(&found, &truth, 10);
+        assert!(recall > 0.9, "recall@10 = {recall}");
+    }
+
+    #[test]
+    fn test_neighbor_lists_are_bounded_and_well_formed() {
+        let points = random_points(300, 3);
+        let nodes = GraphBuilder::new(8).with_beam_width(24).build(&points);
+
+        assert_eq!(nodes.len(), 300);
+        for (i, node) in nodes.iter().enumerate() {
+            let neighs = node.neighbors.as_slice();
+            assert!(neighs.len() <= 8, "node {i} has degree {}", neighs.len());
+            assert!(!neighs.is_empty(), "node {i} is isolated");
+            assert!(neighs.iter().all(|n| n.internal != i && n.internal < 300));
+            let unique: HashSet<_> = neighs.iter().collect();
+            assert_eq!(unique.len(), neighs.len());
+            assert_eq!(*node.payload, *points[i]);
+        }
+    }
+
+    #[test]
+    fn test_larger_alpha_keeps_more_edges() {
+        let points = random_points(300, 4);
+        let edges = |alpha| {
+            GraphBuilder::new(16)
+                .with_alpha(alpha)
+                .build(&points)
+                .iter()
+                .map(|n| n.neighbors.len())
+                .sum::<usize>()
+        };
+        assert!(edges(1.5) > edges(1.0));
+    }
+
+    #[test]
+    fn test_trivial_inputs() {
+        assert!(GraphBuilder::new(4).build(&[]).is_empty());
+
+        let single = GraphBuilder::new(4).build(&random_points(1, 5));
+        assert_eq!(single.len(), 1);
+        assert!(single[0].neighbors.is_empty());
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{
+    numerics::Payload,
+    search::AdjacencyGraph,
+    sets::{
+        catapults::CatapultEvictionPolicy,
+        visited::{CompressedBitset, VisitorSet},
+    },
+};
+
+/// Number of breadth-first traversals [`graph_stats`](AdjacencyGraph::graph_stats) runs to
+/// estimate the diameter.
+pub const DIAMETER_SAMPLES: usize = 32;
+
+/// A summary of the structure of a graph: its out-degree distribution and diameter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphStats {
+    /// Number of nodes in the graph
+    pub num_nodes: usize,
+
+    /// Smallest number of outgoing edges of a node
+    pub min_degree: usize,
+
+    /// Largest number of outgoing edges of a node
+    pub max_degree: usize,
+
+    /// Average number of outgoing edges per node
+    pub mean_degree: f64,
+
+    /// Fraction of nodes without outgoing edges, from which a search cannot move on
+    pub dead_end_fraction: f64,
+
+    /// Longest shortest path found from the sampled nodes, in hops. A lower bound on the
+    /// true diameter, which would require a traversal from every node.
+    pub diameter_lower_bound: usize,
+}
+
+impl<EvictPolicy, P> AdjacencyGraph<EvictPolicy, P>
+where
+    EvictPolicy: CatapultEvictionPolicy,
+    P: Payload,
+{
+    /// Summarizes the degree distribution of the graph and estimates its diameter.
+    ///
+    /// Degrees are the lengths of the neighbor lists. The diameter is estimated by running
+    /// a breadth-first traversal from [`DIAMETER_SAMPLES`] nodes spread evenly over the
+    /// graph and keeping the largest distance any of them reaches, so it is exact only when
+    /// every node is sampled. Out-of-bounds edges are not followed, and nodes that cannot
+    /// be reached from a sample do not count towards its distances.
+    ///
+    /// # Returns
+    /// The statistics of the graph, all zero for an empty graph
+    pub fn graph_stats(&self) -> GraphStats {
+        let num_nodes = self.len();
+        let degrees = (0..num_nodes).map(|node| self.neighbors_of(node).len());
+        let total_degree: usize = degrees.clone().sum();
+        let dead_ends = degrees.clone().filter(|&degree| degree == 0).count();
+        let (mean_degree, dead_end_fraction) = if num_nodes == 0 {
+            (0.0, 0.0)
+        } else {
+            (
+                total_degree as f64 / num_nodes as f64,
+                dead_ends as f64 / num_nodes as f64,
+            )
+        };
+
+        let num_samples = DIAMETER_SAMPLES.min(num_nodes);
+        let diameter_lower_bound = (0..num_samples)
+            .map(|sample| self.eccentricity(sample * num_nodes / num_samples))
+            .max()
+            .unwrap_or(0);
+
+        GraphStats {
+            num_nodes,
+            min_degree: degrees.clone().min().unwrap_or(0),
+            max_degree: degrees.max().unwrap_or(0),
+            mean_degree,
+            dead_end_fraction,
+            diameter_lower_bound,
+        }
+    }
+
+    /// Returns the largest number of hops from `source` to a node reachable from it.
+    fn eccentricity(&self, source: usize) -> usize {
+        let mut reached = CompressedBitset::new();
+        reached.set(source);
+        let mut frontier = vec![source];
+        let mut depth = 0;
+        loop {
+            let mut next = Vec::new();
+            for &node in &frontier {
+                for neighbor in self.neighbors_of(node) {
+                    if neighbor.internal < self.len() && !reached.get(neighbor.internal) {
+                        reached.set(neighbor.internal);
+                        next.push(neighbor.internal);
+                    }
+                }
+            }
+            if next.is_empty() {
+                return depth;
+            }
+            frontier = next;
+            depth += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        numerics::{AlignedBlock, SIMD_LANECOUNT},
+        search::{
+            AdjacencyGraph, Node, NodeId, SearchStrategy,
+            hash_start::{EngineStarter, EngineStarterParams},
+        },
+        sets::{catapults::LruSet, fixed::FlatFixedSet},
+    };
+
+    fn graph(edges: Vec<Vec<usize>>) -> AdjacencyGraph<LruSet> {
+        let nodes = edges
+            .into_iter()
+            .enumerate()
+            .map(|(i, neighbors)| Node {
+                payload: vec![AlignedBlock::new([i as f32; SIMD_LANECOUNT])].into_boxed_slice(),
+                neighbors: FlatFixedSet::new(neighbors),
+            })
+            .collect();
+        let params =
+            EngineStarterParams::new(4, 8, SIMD_LANECOUNT, NodeId { internal: 0 }, 42, false);
+        AdjacencyGraph::new_flat(nodes, EngineStarter::new(params), SearchStrategy::Vanilla)
+    }
+
+    #[test]
+    fn graph_stats_reports_degrees_and_diameter() {
+        // node 3 is a dead end, and the longest shortest path is 0 → 2 → 3 (or 1 → 2 → 0)
+        let stats = graph(vec![vec![1, 2], vec![2], vec![0, 1, 3], vec![]]).graph_stats();
+        assert_eq!(stats.num_nodes, 4);
+        assert_eq!(stats.min_degree, 0);
+        assert_eq!(stats.max_degree, 3);
+        assert_eq!(stats.mean_degree, 1.5);
+        assert_eq!(stats.dead_end_fraction, 0.25);
+        // every node is sampled in so small a graph, so the bound is exact
+        assert_eq!(stats.diameter_lower_bound, 2);
+    }
+
+    #[test]
+    fn diameter_of_a_cycle_is_one_less_than_its_length() {
+        let stats = graph((0..5).map(|i| vec![(i + 1) % 5]).collect()).graph_stats();
+        assert_eq!((stats.min_degree, stats.max_degree), (1, 1));
+        assert_eq!(stats.dead_end_fraction, 0.0);
+        assert_eq!(stats.diameter_lower_bound, 4);
+    }
+}
@@ -0,0 +1,352 @@
+use crate::{
+    numerics::AlignedBlock, search::hash_start::EngineStarter,
+    sets::catapults::CatapultEvictionPolicy,
+};
+
+use std::f64::consts::PI;
+
+/// Fraction of the attainable signature entropy below which an LSH configuration is
+/// reported as degenerate.
+const DEGENERATE_ENTROPY_RATIO: f64 = 0.5;
+
+/// How well an LSH configuration spreads a sample of queries over its buckets.
+///
+/// Produced by [`EngineStarter::diagnose`]. A healthy configuration spreads queries over
+/// many buckets so that each bucket gathers catapults for one region of the space. A
+/// degenerate one (too many hash bits for the intrinsic dimension of the data, or
+/// near-duplicate hyperplanes) sends almost every query to a handful of buckets, and
+/// most of the `2^num_hash` buckets are never used.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LshDiagnostics {
+    /// Number of sample queries that were hashed
+    pub num_samples: usize,
+
+    /// Total number of buckets (`2^num_hash`)
+    pub num_buckets: usize,
+
+    /// Number of distinct buckets hit by at least one sample query
+    pub occupied_buckets: usize,
+
+    /// Fraction of all buckets that no sample query hashed to
+    pub empty_bucket_fraction: f64,
+
+    /// Size of the most popular bucket divided by the mean size of occupied buckets
+    /// (1.0 when queries are spread perfectly evenly over the occupied buckets)
+    pub occupancy_skew: f64,
+
+    /// Shannon entropy of the sample's bucket distribution, in bits
+    pub signature_entropy_bits: f64,
+
+    /// Highest entropy this sample could reach: `min(num_hash, log2(num_samples))` bits
+    pub attainable_entropy_bits: f64,
+
+    /// Whether the configuration looks misconfigured, i.e. the signature entropy is below
+    /// half of the attainable entropy
+    pub degenerate: bool,
+}
+
+/// How the stored catapults are spread over the buckets of an [`EngineStarter`].
+///
+/// Produced by [`EngineStarter::bucket_stats`]. Where [`LshDiagnostics`] predicts how a
+/// workload will spread over the buckets, this reports where catapults actually ended
+/// up. Many buckets at capacity suggest raising the capacity or `num_hash`; mostly empty
+/// buckets suggest lowering `num_hash`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BucketStats {
+    /// Total number of buckets (`2^num_hash`)
+    pub num_buckets: usize,
+
+    /// Number of buckets holding at least one catapult
+    pub non_empty_buckets: usize,
+
+    /// Fewest catapults held by any bucket
+    pub min_catapults: usize,
+
+    /// Most catapults held by any bucket
+    pub max_catapults: usize,
+
+    /// Mean number of catapults per bucket, counting empty buckets
+    pub mean_catapults: f64,
+
+    /// Fraction of all buckets holding as many catapults as their capacity allows
+    pub full_bucket_fraction: f64,
+
+    /// Number of buckets holding each number of catapults: `histogram[c]` buckets hold
+    /// exactly `c` catapults, for `c` in `0..=bucket_capacity`
+    pub histogram: Vec<usize>,
+}
+
+/// Returns the probability that two vectors separated by `angle_radians` land in the same
+/// bucket under `num_hash` random hyperplanes.
+///
+/// A single random hyperplane separates the two vectors with probability `θ/π`, so their
+/// full signatures agree with probability `(1 - θ/π)^num_hash`. This allows picking
+/// `num_hash` analytically: queries whose angle to a catapult's query is below the
+/// similarity threshold should still collide often, while unrelated queries should not.
+///
+/// # Arguments
+/// * `angle_radians` - Angle between the two vectors, in `[0, π]`
+/// * `num_hash` - Number of hash bits / hyperplanes
+///
+/// # Returns
+/// The collision probability, between 0 and 1
+///
+/// # Panics
+/// Panics if `angle_radians` is outside `[0, π]`
+///
+/// # Examples
+/// ```
+/// use catapult::search::hash_start::collision_probability;
+///
+/// assert_eq!(collision_probability(0.0, 8), 1.0);
+/// assert_eq!(collision_probability(std::f64::consts::FRAC_PI_2, 1), 0.5);
+/// ```
+pub fn collision_probability(angle_radians: f64, num_hash: usize) -> f64 {
+    assert!(
+        (0.0..=PI).contains(&angle_radians),
+        "angle must be between 0 and π radians"
+    );
+    (1.0 - angle_radians / PI).powi(num_hash as i32)
+}
+
+impl<T: CatapultEvictionPolicy> EngineStarter<T> {
+    /// Hashes a sample of queries and reports how they are distributed over the buckets.
+    ///
+    /// The empty bucket fraction alone is not conclusive, since a sample smaller than the
+    /// number of buckets necessarily leaves most of them empty. The `degenerate` flag
+    /// therefore compares the entropy of the observed signatures with the best entropy a
+    /// sample of that size could reach. Catapults themselves are not read or modified.
+    ///
+    /// # Arguments
+    /// * `sample_queries` - Queries representative of the expected workload
+    ///
+    /// # Returns
+    /// The collected [`LshDiagnostics`]
+    ///
+    /// # Panics
+    /// Panics if `sample_queries` is empty
+    pub fn diagnose(&self, sample_queries: &[Vec<AlignedBlock>]) -> LshDiagnostics {
+        assert!(
+            !sample_queries.is_empty(),
+            "diagnose needs at least one sample query"
+        );
+
+        let mut counts = vec![0usize; self.num_buckets()];
+        for query in sample_queries {
+            counts[self.signature(query)] += 1;
+        }
+
+        let num_samples = sample_queries.len();
+        let occupied_buckets = counts.iter().filter(|&&c| c > 0).count();
+        let max_count = counts.iter().copied().max().unwrap_or(0);
+        let mean_occupied = num_samples as f64 / occupied_buckets as f64;
+
+        let signature_entropy_bits = counts
+            .iter()
+            .filter(|&&c| c > 0)
+            .map(|&c| {
+                let p = c as f64 / num_samples as f64;
+                -p * p.log2()
+            })
+            .sum::<f64>();
+        let attainable_entropy_bits = (self.num_hash() as f64).min((num_samples as f64).log2());
+
+        LshDiagnostics {
+            num_samples,
+            num_buckets: self.num_buckets(),
+            occupied_buckets,
+            empty_bucket_fraction: 1.0 - occupied_buckets as f64 / self.num_buckets() as f64,
+            occupancy_skew: max_count as f64 / mean_occupied,
+            signature_entropy_bits,
+            attainable_entropy_bits,
+            degenerate: signature_entropy_bits < DEGENERATE_ENTROPY_RATIO * attainable_entropy_bits,
+        }
+    }
+}
+
+impl<T: CatapultEvictionPolicy> EngineStarter<T> {
+    /// Reports how many catapults each bucket currently holds.
+    ///
+    /// Each bucket's `RwLock` is read-locked once, one bucket at a time, so the report is
+    /// not an atomic snapshot when searches insert catapults concurrently.
+    ///
+    /// # Returns
+    /// The collected [`BucketStats`]
+    pub fn bucket_stats(&self) -> BucketStats {
+        let capacity = self.bucket_capacity();
+        let mut histogram = vec![0usize; capacity + 1];
+        let (mut min_catapults, mut max_catapults, mut total) = (usize::MAX, 0, 0);
+        for signature in 0..self.num_buckets() {
+            let len = self.bucket_len(signature);
+            histogram[len.min(capacity)] += 1;
+            min_catapults = min_catapults.min(len);
+            max_catapults = max_catapults.max(len);
+            total += len;
+        }
+
+        let num_buckets = self.num_buckets();
+        BucketStats {
+            num_buckets,
+            non_empty_buckets: num_buckets - histogram[0],
+            min_catapults,
+            max_catapults,
+            mean_catapults: total as f64 / num_buckets as f64,
+            full_bucket_fraction: histogram[capacity] as f64 / num_buckets as f64,
+            histogram,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        numerics::SIMD_LANECOUNT,
+        search::{NodeId, hash_start::EngineStarterParams},
+        sets::catapults::LruSet,
+    };
+    use rand::prelude::*;
+    use rand_distr::StandardNormal;
+
+    fn starter(num_hash: usize) -> EngineStarter<LruSet> {
+        EngineStarter::new(EngineStarterParams::new(
+            num_hash,
+            10,
+            SIMD_LANECOUNT,
+            NodeId { internal: 0 },
+            42,
+            true,
+        ))
+    }
+
+    fn gaussian_queries(n: usize, seed: u64) -> Vec<Vec<AlignedBlock>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..n)
+            .map(|_| {
+                let mut data = [0.0; SIMD_LANECOUNT];
+                for v in data.iter_mut() {
+                    *v = rng.sample(StandardNormal);
+                }
+                vec![AlignedBlock::new(data)]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_well_spread_configuration_is_not_degenerate() {
+        let diag = starter(6).diagnose(&gaussian_queries(2000, 1));
+
+        assert_eq!(diag.num_samples, 2000);
+        assert_eq!(diag.num_buckets, 64);
+        assert!(!diag.degenerate, "{diag:?}");
+        assert!(diag.signature_entropy_bits > 4.0, "{diag:?}");
+        assert_eq!(diag.attainable_entropy_bits, 6.0);
+    }
+
+    #[test]
+    fn test_too_many_hashes_for_intrinsic_dimension_is_degenerate() {
+        // every query lies on the same line through the origin, so whatever the number of
+        // hyperplanes, only two signatures can ever be produced
+        let mut rng = StdRng::seed_from_u64(2);
+        let queries: Vec<Vec<AlignedBlock>> = (0..500)
+            .map(|_| {
+                vec![AlignedBlock::new(
+                    [rng.random_range(-10.0..10.0); SIMD_LANECOUNT],
+                )]
+            })
+            .collect();
+
+        let diag = starter(12).diagnose(&queries);
+
+        assert!(diag.degenerate, "{diag:?}");
+        assert!(diag.occupied_buckets <= 2);
+        assert!(diag.empty_bucket_fraction > 0.99);
+        assert!(diag.signature_entropy_bits <= 1.0);
+    }
+
+    #[test]
+    fn test_skew_of_even_spread_is_one() {
+        let diag = starter(4).diagnose(&[vec![AlignedBlock::new([1.0; SIMD_LANECOUNT])]]);
+
+        assert_eq!(diag.occupied_buckets, 1);
+        assert_eq!(diag.occupancy_skew, 1.0);
+        assert_eq!(diag.signature_entropy_bits, 0.0);
+        assert!(!diag.degenerate);
+    }
+
+    #[test]
+    fn test_collision_probability() {
+        assert_eq!(collision_probability(0.0, 12), 1.0);
+        assert_eq!(collision_probability(PI / 2.0, 1), 0.5);
+        assert_eq!(collision_probability(PI / 2.0, 4), 0.0625);
+        assert!((collision_probability(PI / 3.0, 3) - 8.0 / 27.0).abs() < 1e-12);
+        assert_eq!(collision_probability(PI, 1), 0.0);
+    }
+
+    #[test]
+    fn test_collision_probability_matches_hasher() {
+        // two unit vectors at 60 degrees, hashed by a single hyperplane under many seeds
+        let mut a = [0.0; SIMD_LANECOUNT];
+        let mut b = [0.0; SIMD_LANECOUNT];
+        a[0] = 1.0;
+        b[0] = 0.5;
+        b[1] = 0.75f32.sqrt();
+        let (a, b) = (vec![AlignedBlock::new(a)], vec![AlignedBlock::new(b)]);
+
+        let trials = 4000;
+        let collisions = (0..trials)
+            .filter(|&seed| {
+                let starter = EngineStarter::<LruSet>::new(EngineStarterParams::new(
+                    2,
+                    10,
+                    SIMD_LANECOUNT,
+                    NodeId { internal: 0 },
+                    seed,
+                    true,
+                ));
+                starter.signature(&a) == starter.signature(&b)
+            })
+            .count();
+
+        let expected = collision_probability(PI / 3.0, 2);
+        let observed = collisions as f64 / trials as f64;
+        assert!(
+            (observed - expected).abs() < 0.04,
+            "{observed} vs {expected}"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "diagnose needs at least one sample query")]
+    fn test_empty_sample_panics() {
+        starter(4).diagnose(&[]);
+    }
+
+    #[test]
+    fn test_bucket_stats_reports_known_distribution() {
+        // 16 buckets of capacity 3: one full bucket, two holding one catapult, one holding
+        // two, and a full bucket that received more insertions than it can hold
+        let starter = EngineStarter::<LruSet>::new(EngineStarterParams::new(
+            4,
+            3,
+            SIMD_LANECOUNT,
+            NodeId { internal: 0 },
+            42,
+            true,
+        ));
+        for (signature, count) in [(0, 3), (5, 1), (6, 1), (9, 2), (15, 7)] {
+            for id in 0..count {
+                starter.new_catapult(signature, NodeId { internal: id });
+            }
+        }
+
+        let stats = starter.bucket_stats();
+        assert_eq!(stats.num_buckets, 16);
+        assert_eq!(stats.non_empty_buckets, 5);
+        assert_eq!(stats.min_catapults, 0);
+        assert_eq!(stats.max_catapults, 3);
+        assert_eq!(stats.mean_catapults, 10.0 / 16.0);
+        assert_eq!(stats.full_bucket_fraction, 2.0 / 16.0);
+        assert_eq!(stats.histogram, vec![11, 2, 1, 2]);
+    }
+}
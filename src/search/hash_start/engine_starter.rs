@@ -1,6 +1,9 @@
+use std::ops::Range;
 use std::sync::RwLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-use crate::search::NodeId;
+use crate::search::hash_start::zorder_index::ZOrderIndex;
+use crate::search::{Node, NodeId, farthest_point_sample};
 use crate::sets::catapults::CatapultEvictionPolicy;
 use crate::{numerics::AlignedBlock, search::hash_start::hyperplane_hasher::SimilarityHasher};
 
@@ -9,11 +12,52 @@ use crate::{numerics::AlignedBlock, search::hash_start::hyperplane_hasher::Simil
 /// Uses locality-sensitive hashing to map query vectors to buckets of cached starting
 /// points (catapults) from previous successful searches. Each bucket is a thread-safe
 /// evicting structure that stores node indices discovered by similar queries.
+///
+/// # Memory Budget
+/// Each bucket is bounded by its own capacity, but with `2^num_hash` buckets the total
+/// can still grow large. An optional global budget (see
+/// [`EngineStarterParams::with_memory_budget`]) caps the memory used by stored catapult
+/// ids. Whenever an insertion pushes the total over the budget, a global eviction pass
+/// clears whole buckets, least-occupied first, until usage is back under the limit.
+/// Sparsely populated buckets carry the least learned information, so they are the
+/// cheapest to forget; well-populated buckets serving frequent query regions survive.
+/// The bucket that was just written is never cleared by its own insertion, otherwise a
+/// new query region, whose bucket holds a single catapult, would lose it right away.
+///
+/// # LSH Families
+/// By default, queries are bucketed by random hyperplanes, which group queries of similar
+/// direction. With [`LshFamily::PStable`], catapults are instead kept in a Z-order index
+/// keyed by the p-stable code of the query that found them, and grouped by Euclidean
+/// proximity; see [`LshFamily`] for how the two differ.
+///
+/// # Entry Points
+/// Besides the starting node, a starter can hold a few fixed entry points spread over the
+/// whole dataset (see [`with_diverse_entry_points`](Self::with_diverse_entry_points)).
+/// They seed every search, so queries far from the starting node and from any learned
+/// catapult, such as out-of-distribution queries, still start near their region of the
+/// graph. Each entry point costs one extra distance computation per query.
+///
+/// # Lock Striping
+/// Every concurrent search whose query hashes to a bucket takes that bucket's lock, and
+/// the write lock when it learns a catapult, so a popular bucket serializes its writers.
+/// With [`EngineStarterParams::with_lock_stripes`], each bucket is split into several
+/// independently locked stripes, and a catapult always lives in the stripe selected by its
+/// node id. Writers learning different catapults for the same bucket then mostly take
+/// different locks, while readers take every stripe's read lock in turn.
 pub struct EngineStarter<T: CatapultEvictionPolicy> {
     hasher: SimilarityHasher,
+    pstable: Option<RwLock<ZOrderIndex>>,
+    seed: u64,
     starting_node: NodeId,
+    entry_points: Vec<NodeId>,
+    /// The stripes of every bucket, bucket by bucket: bucket `s` owns the `lock_stripes`
+    /// locks starting at `s * lock_stripes`
     catapults: Box<[RwLock<T>]>,
+    lock_stripes: usize,
     enabled_catapults: bool,
+    bucket_capacity: usize,
+    memory_budget_bytes: Option<usize>,
+    catapult_count: AtomicUsize,
 }
 
 /// The result of starting point selection, containing the LSH signature and node indices.
@@ -26,10 +70,38 @@ pub struct StartingPoints {
 
     /// The base starting node that is always included
     pub starting_node: NodeId,
+
+    /// Fixed entry points that are always included besides the starting node
+    pub entry_points: Vec<NodeId>,
+}
+
+/// The locality-sensitive hash family an [`EngineStarter`] maps queries with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LshFamily {
+    /// Random hyperplanes: the signs of `num_hash` projections select one of
+    /// `2^num_hash` catapult buckets. Queries share a bucket when they point in similar
+    /// directions, which suits cosine and inner-product workloads.
+    Hyperplane,
+
+    /// p-stable Euclidean LSH with Z-order bucketing. Each learned catapult is stored in
+    /// a [`ZOrderIndex`] under the p-stable code of the query that found it, and a query
+    /// reads the `bucket_capacity` catapults whose codes share the longest prefix with its
+    /// own, through [`ZOrderIndex::query_k_closest`]. Codes of nearby vectors share long
+    /// prefixes, so queries are matched by L2 proximity rather than by direction, which
+    /// suits L2 workloads whose vectors differ in norm.
+    ///
+    /// The catapult buckets are not used in this mode: bucket-level accessors such as
+    /// [`EngineStarter::bucket_catapults`] and [`EngineStarter::export_catapult_edges`]
+    /// see no catapults. Each code keeps its `bucket_capacity` most recent catapults, and
+    /// exceeding the memory budget evicts the oldest catapults of the whole index.
+    PStable {
+        /// Width of the p-stable quantization cells, see [`ZOrderIndex::new`]
+        w: f32,
+    },
 }
 
 /// Configuration parameters for creating an `EngineStarter`.
-#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+#[derive(Debug, Clone, PartialEq, Copy)]
 pub struct EngineStarterParams {
     /// Number of LSH hash bits (determines 2^num_hash buckets)
     pub num_hash: usize,
@@ -48,6 +120,15 @@ pub struct EngineStarterParams {
 
     /// Whether to enable catapult lookups (if false, only returns starting_node)
     pub enabled_catapults: bool,
+
+    /// Optional global cap on the memory used by stored catapult ids, in bytes
+    pub catapult_memory_budget_bytes: Option<usize>,
+
+    /// Hash family mapping queries to catapults
+    pub lsh_family: LshFamily,
+
+    /// Number of independently locked stripes each bucket is split into
+    pub lock_stripes: usize,
 }
 
 impl EngineStarterParams {
@@ -77,8 +158,55 @@ impl EngineStarterParams {
             starting_node,
             seed,
             enabled_catapults,
+            catapult_memory_budget_bytes: None,
+            lsh_family: LshFamily::Hyperplane,
+            lock_stripes: 1,
         }
     }
+
+    /// Selects p-stable Euclidean LSH with Z-order bucketing instead of hyperplanes.
+    ///
+    /// # Arguments
+    /// * `w` - Width of the p-stable quantization cells
+    ///
+    /// # Returns
+    /// The updated parameters
+    pub fn with_pstable_lsh(mut self, w: f32) -> Self {
+        self.lsh_family = LshFamily::PStable { w };
+        self
+    }
+
+    /// Sets a global memory budget for all catapult buckets combined.
+    ///
+    /// Usage is accounted as `size_of::<NodeId>()` per stored catapult; the fixed
+    /// per-bucket container overhead is not counted.
+    ///
+    /// # Arguments
+    /// * `bytes` - Maximum number of bytes of catapult ids to keep across all buckets
+    ///
+    /// # Returns
+    /// The updated parameters
+    pub fn with_memory_budget(mut self, bytes: usize) -> Self {
+        self.catapult_memory_budget_bytes = Some(bytes);
+        self
+    }
+
+    /// Splits each bucket into `stripes` independently locked stripes.
+    ///
+    /// Each stripe holds up to `ceil(bucket_capacity / stripes)` catapults and evicts on
+    /// its own, so a bucket holds up to `stripes - 1` more catapults than its capacity when
+    /// `stripes` does not divide it, and eviction order is only kept within a stripe. With
+    /// one stripe, the default, every bucket behaves as a single evicting set.
+    ///
+    /// # Arguments
+    /// * `stripes` - Number of locks per bucket, at least 1
+    ///
+    /// # Returns
+    /// The updated parameters
+    pub fn with_lock_stripes(mut self, stripes: usize) -> Self {
+        self.lock_stripes = stripes;
+        self
+    }
 }
 
 impl<T> EngineStarter<T>
@@ -88,14 +216,21 @@ where
     /// Creates a new `EngineStarter` with the specified parameters.
     ///
     /// Initializes the LSH hasher and creates 2^num_hash empty catapult buckets,
-    /// each protected by an RwLock for thread-safe concurrent access.
+    /// each protected by `lock_stripes` RwLocks for thread-safe concurrent access.
     ///
     /// # Arguments
     /// * `params` - Configuration parameters
     ///
     /// # Returns
     /// A new `EngineStarter` instance ready for starting point selection
+    ///
+    /// # Panics
+    /// Panics if `params.lock_stripes` is 0
     pub fn new(params: EngineStarterParams) -> Self {
+        assert!(
+            params.lock_stripes > 0,
+            "a bucket needs at least one stripe"
+        );
         let num_hash = params.num_hash;
         let plane_dim = params.plane_dim;
         let starting_node = params.starting_node;
@@ -103,56 +238,363 @@ where
         let enabled_catapults = params.enabled_catapults;
 
         let hasher = SimilarityHasher::new_seeded(num_hash, plane_dim, seed);
+        let pstable = match params.lsh_family {
+            LshFamily::Hyperplane => None,
+            LshFamily::PStable { w } => {
+                let index = ZOrderIndex::new(num_hash, plane_dim, seed, w);
+                Some(RwLock::new(
+                    index.with_signature_capacity(params.bucket_capacity.max(1)),
+                ))
+            }
+        };
 
-        let amount_of_catapult_sets = 1 << num_hash;
+        let amount_of_catapult_sets = (1 << num_hash) * params.lock_stripes;
+        let stripe_capacity = params.bucket_capacity.div_ceil(params.lock_stripes);
         let mut catapult_vecs = Vec::with_capacity(amount_of_catapult_sets);
         for _ in 0..amount_of_catapult_sets {
-            catapult_vecs.push(RwLock::new(T::new(params.bucket_capacity)));
+            catapult_vecs.push(RwLock::new(T::new(stripe_capacity)));
         }
 
         Self {
             hasher,
+            pstable,
+            seed,
             starting_node,
+            entry_points: Vec::new(),
             catapults: catapult_vecs.into_boxed_slice(),
+            lock_stripes: params.lock_stripes,
             enabled_catapults,
+            bucket_capacity: params.bucket_capacity,
+            memory_budget_bytes: params.catapult_memory_budget_bytes,
+            catapult_count: AtomicUsize::new(0),
         }
     }
 
+    /// Adds fixed entry points spread over the dataset by farthest-point sampling.
+    ///
+    /// The starting node is the first sample, and each of the next `n - 1` is the node
+    /// farthest from all previous ones (see [`farthest_point_sample`]). Selecting them
+    /// costs `n` passes over `nodes`; afterwards, every selection of starting points
+    /// includes them, which costs up to `n - 1` extra distance computations per query.
+    ///
+    /// # Arguments
+    /// * `nodes` - The nodes of the graph this starter serves
+    /// * `n` - Number of diverse seeds, including the starting node
+    ///
+    /// # Returns
+    /// The starter, with its entry points replaced
+    ///
+    /// # Panics
+    /// Panics if the starting node is not a node of `nodes`
+    pub fn with_diverse_entry_points(mut self, nodes: &[Node], n: usize) -> Self {
+        let mut seeds = farthest_point_sample(nodes, self.starting_node, n);
+        seeds.retain(|&seed| seed != self.starting_node);
+        self.entry_points = seeds;
+        self
+    }
+
     /// Selects starting points for a query by hashing it to a catapult bucket.
     ///
     /// Computes the LSH signature for the query and retrieves cached catapults from
     /// the corresponding bucket. The base starting node is always included. If catapults
     /// are disabled, an empty catapults vector is returned.
     ///
+    /// With [`LshFamily::PStable`], the catapults are instead the `bucket_capacity` ones
+    /// whose query codes are closest to the query's in the Z-order index.
+    ///
     /// # Arguments
     /// * `query` - The query vector as aligned blocks
     ///
     /// # Returns
     /// A `StartingPoints` struct containing the signature, catapults, and starting node
     pub fn select_starting_points(&self, query: &[AlignedBlock]) -> StartingPoints {
-        let signature = self.hasher.hash_int(query);
-        let catapults = if self.enabled_catapults {
-            self.catapults[signature].read().unwrap().to_vec()
-        } else {
+        let signature = self.signature(query);
+        let catapults = if !self.enabled_catapults {
             vec![]
+        } else if let Some(index) = &self.pstable {
+            index
+                .read()
+                .unwrap()
+                .query_k_closest(query, self.bucket_capacity)
+        } else {
+            self.bucket_catapults(signature)
         };
         StartingPoints {
             signature,
             catapults,
             starting_node: self.starting_node,
+            entry_points: self.entry_points.clone(),
+        }
+    }
+
+    /// Selects starting points by reading catapults from several buckets near the query.
+    ///
+    /// Queries lying close to a hyperplane often have their best catapults stored in a
+    /// bucket one bit flip away. This probes the query's own bucket plus the `probes - 1`
+    /// closest Hamming perturbations of its signature, flipping the bits whose hyperplanes
+    /// pass nearest to the query first, and returns the union of their catapults. The
+    /// probe order depends only on the hyperplanes and the query, so it is deterministic
+    /// for a given seed; catapults are listed bucket by bucket in probe order, without
+    /// duplicates.
+    ///
+    /// Each probed bucket costs one `RwLock` read acquisition, so this takes up to `probes`
+    /// read locks per query instead of one. The returned signature is still the query's
+    /// own bucket, so learned catapults are only ever written there. With `probes == 1`
+    /// this is equivalent to [`select_starting_points`](Self::select_starting_points).
+    ///
+    /// With [`LshFamily::PStable`], the Z-order lookup already spans neighboring codes, so
+    /// `probes` is ignored and this is equivalent to `select_starting_points`.
+    ///
+    /// # Arguments
+    /// * `query` - The query vector as aligned blocks
+    /// * `probes` - Number of buckets to read, capped at `self.num_buckets()`
+    ///
+    /// # Returns
+    /// A `StartingPoints` struct containing the query's signature, the union of the probed
+    /// buckets' catapults, and the starting node
+    pub fn select_starting_points_multiprobe(
+        &self,
+        query: &[AlignedBlock],
+        probes: usize,
+    ) -> StartingPoints {
+        if self.pstable.is_some() {
+            return self.select_starting_points(query);
+        }
+        let signature = self.signature(query);
+        let mut catapults = Vec::new();
+        if self.enabled_catapults {
+            for probe in self.hasher.probe_signatures(query, probes) {
+                for catapult in self.bucket_catapults(probe) {
+                    if !catapults.contains(&catapult) {
+                        catapults.push(catapult);
+                    }
+                }
+            }
+        }
+        StartingPoints {
+            signature,
+            catapults,
+            starting_node: self.starting_node,
+            entry_points: self.entry_points.clone(),
         }
     }
 
+    /// Computes the LSH signature (bucket index) of a query without touching any bucket.
+    ///
+    /// # Arguments
+    /// * `query` - The query vector as aligned blocks
+    ///
+    /// # Returns
+    /// The bucket index `query` hashes to, in `0..self.num_buckets()`
+    pub fn signature(&self, query: &[AlignedBlock]) -> usize {
+        self.hasher.hash_int(query)
+    }
+
     /// Records a new catapult node for a specific LSH signature bucket.
     ///
     /// This is typically called after a successful search to cache the best result
     /// as a starting point for future queries with the same signature.
     ///
+    /// Only the write lock of the stripe `new_cata` belongs to is taken.
+    ///
     /// # Arguments
     /// * `signature` - The LSH signature (bucket index) to insert into
     /// * `new_cata` - The node index to cache as a catapult
     pub fn new_catapult(&self, signature: usize, new_cata: NodeId) {
-        self.catapults[signature].write().unwrap().insert(new_cata);
+        {
+            let mut bucket = self.stripe_of(signature, new_cata).write().unwrap();
+            let before = bucket.len();
+            bucket.insert(new_cata);
+            let after = bucket.len();
+            if after > before {
+                self.catapult_count
+                    .fetch_add(after - before, Ordering::Relaxed);
+            } else {
+                self.catapult_count
+                    .fetch_sub(before - after, Ordering::Relaxed);
+            }
+        }
+
+        if let Some(budget) = self.memory_budget_bytes
+            && self.catapult_memory_bytes() > budget
+        {
+            self.evict_to_budget(budget, signature);
+        }
+    }
+
+    /// Records a catapult learned by a search for `query`.
+    ///
+    /// With [`LshFamily::Hyperplane`], this is [`new_catapult`](Self::new_catapult) into
+    /// the query's bucket `signature`. With [`LshFamily::PStable`], the catapult is stored
+    /// in the Z-order index under the p-stable code of `query`, unless that code already
+    /// holds it. A code holding `bucket_capacity` catapults drops its oldest one.
+    ///
+    /// # Arguments
+    /// * `query` - The query whose search found the catapult
+    /// * `signature` - The bucket index returned for `query` by starting point selection
+    /// * `new_cata` - The node index to cache as a catapult
+    pub fn learn_catapult(&self, query: &[AlignedBlock], signature: usize, new_cata: NodeId) {
+        let Some(index) = &self.pstable else {
+            self.new_catapult(signature, new_cata);
+            return;
+        };
+        self.insert_pstable(index, |index| index.insert_unique(query, new_cata));
+    }
+
+    /// Returns the catapults of the Z-order index of [`LshFamily::PStable`], each with the
+    /// p-stable code it is stored under, or `None` for hyperplane starters.
+    pub(crate) fn pstable_catapults(&self) -> Option<Vec<(u128, NodeId)>> {
+        let index = self.pstable.as_ref()?.read().unwrap();
+        Some(index.entries().collect())
+    }
+
+    /// Stores a catapult in the Z-order index under an already computed p-stable code,
+    /// as [`learn_catapult`](Self::learn_catapult) does for the code of a query.
+    ///
+    /// # Panics
+    /// Panics if this starter does not use [`LshFamily::PStable`]
+    pub(crate) fn restore_pstable_catapult(&self, code: u128, catapult: NodeId) {
+        let index = self
+            .pstable
+            .as_ref()
+            .expect("only p-stable starters hold a Z-order index");
+        self.insert_pstable(index, |index| {
+            index.insert_unique_by_signature(code, catapult)
+        });
+    }
+
+    /// Inserts into the Z-order index under its write lock, keeps the catapult count in
+    /// step, and enforces the memory budget.
+    ///
+    /// Over budget, the oldest entries are evicted one at a time until usage fits again.
+    /// The entry just inserted is the newest, so it is never evicted by its own insertion.
+    fn insert_pstable(
+        &self,
+        index: &RwLock<ZOrderIndex>,
+        insert: impl FnOnce(&mut ZOrderIndex) -> bool,
+    ) {
+        let mut index = index.write().unwrap();
+        let before = index.len();
+        if !insert(&mut index) {
+            return;
+        }
+        // a full code drops its oldest entry, so the index may not have grown
+        if index.len() > before {
+            self.catapult_count.fetch_add(1, Ordering::Relaxed);
+        }
+
+        if let Some(budget) = self.memory_budget_bytes {
+            while self.catapult_memory_bytes() > budget
+                && index.len() > 1
+                && index.evict_oldest().is_some()
+            {
+                self.catapult_count.fetch_sub(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Lazily iterates over every stored catapult as `(bucket signature, catapult)` pairs.
+    ///
+    /// Buckets are visited in signature order, and each one is read-locked only while
+    /// its contents are copied out, so the iteration never holds more than one bucket's
+    /// catapults in memory and never blocks more than one bucket at a time. Catapults
+    /// inserted concurrently may or may not be observed.
+    pub fn iter_catapults(&self) -> impl Iterator<Item = (usize, NodeId)> + '_ {
+        (0..self.num_buckets()).flat_map(move |signature| {
+            self.bucket_catapults(signature)
+                .into_iter()
+                .map(move |catapult| (signature, catapult))
+        })
+    }
+
+    /// Collects every stored catapult as `(bucket signature, catapult)` pairs.
+    ///
+    /// This is the eager counterpart of [`iter_catapults`](Self::iter_catapults).
+    pub fn export_catapult_edges(&self) -> Vec<(usize, NodeId)> {
+        self.iter_catapults().collect()
+    }
+
+    /// Signals that some catapults of a bucket contributed to a search result.
+    ///
+    /// Each catapult is passed to [`CatapultEvictionPolicy::touch`], letting recency-based
+    /// policies keep it longer. The write lock of each stripe holding a useful catapult is
+    /// taken once for the whole slice, and no lock at all when `useful` is empty.
+    ///
+    /// # Arguments
+    /// * `signature` - The LSH signature (bucket index) the catapults were read from
+    /// * `useful` - The catapults that appeared in the search result
+    pub fn touch_catapults(&self, signature: usize, useful: &[NodeId]) {
+        let stripes = self.bucket(signature);
+        for (index, stripe) in stripes.iter().enumerate() {
+            let mut ids = useful
+                .iter()
+                .filter(|id| id.internal % stripes.len() == index)
+                .peekable();
+            if ids.peek().is_none() {
+                continue;
+            }
+            let mut bucket = stripe.write().unwrap();
+            for &id in ids {
+                bucket.touch(id);
+            }
+        }
+    }
+
+    /// Returns the stripes of one bucket.
+    ///
+    /// # Panics
+    /// Panics if `signature >= self.num_buckets()`
+    fn bucket(&self, signature: usize) -> &[RwLock<T>] {
+        &self.catapults[signature * self.lock_stripes..(signature + 1) * self.lock_stripes]
+    }
+
+    /// Returns the stripe of bucket `signature` that `catapult` is stored in.
+    fn stripe_of(&self, signature: usize, catapult: NodeId) -> &RwLock<T> {
+        &self.bucket(signature)[catapult.internal % self.lock_stripes]
+    }
+
+    /// Clears whole buckets other than `written`, least-occupied first, until usage fits
+    /// in `budget` bytes.
+    fn evict_to_budget(&self, budget: usize, written: usize) {
+        let mut occupancy: Vec<(usize, usize)> = (0..self.num_buckets())
+            .filter(|&signature| signature != written)
+            .map(|signature| (self.bucket_len(signature), signature))
+            .filter(|&(len, _)| len > 0)
+            .collect();
+        occupancy.sort_unstable();
+
+        for (_, signature) in occupancy {
+            if self.catapult_memory_bytes() <= budget {
+                break;
+            }
+            self.clear_bucket(signature);
+        }
+    }
+
+    /// Returns the total number of catapults stored across all buckets.
+    pub fn catapult_count(&self) -> usize {
+        self.catapult_count.load(Ordering::Relaxed)
+    }
+
+    /// Returns the memory used by stored catapult ids, in bytes.
+    ///
+    /// This is the quantity compared against the optional memory budget.
+    pub fn catapult_memory_bytes(&self) -> usize {
+        self.catapult_count() * size_of::<NodeId>()
+    }
+
+    /// Runs one decay cycle on every bucket, see [`CatapultEvictionPolicy::decay`].
+    ///
+    /// Each stripe's write lock is taken in turn, never two at once. Catapults stored in
+    /// the p-stable index of [`LshFamily::PStable`] do not decay.
+    pub fn decay_catapults(&self) {
+        for catapult_set in self.catapults.iter() {
+            let mut bucket = catapult_set.write().unwrap();
+            let before = bucket.len();
+            bucket.decay();
+            self.catapult_count
+                .fetch_sub(before - bucket.len(), Ordering::Relaxed);
+        }
     }
 
     /// Clears all cached catapults from all buckets.
@@ -161,19 +603,183 @@ where
     /// points, or to reset state between different workloads.
     pub fn clear_all_catapults(&self) {
         for catapult_set in self.catapults.iter() {
-            catapult_set.write().unwrap().clear();
+            let mut bucket = catapult_set.write().unwrap();
+            self.catapult_count
+                .fetch_sub(bucket.len(), Ordering::Relaxed);
+            bucket.clear();
+        }
+        if let Some(index) = &self.pstable {
+            let mut index = index.write().unwrap();
+            self.catapult_count
+                .fetch_sub(index.len(), Ordering::Relaxed);
+            index.clear();
+        }
+    }
+
+    /// Clears the catapults of one bucket, leaving every other bucket untouched.
+    ///
+    /// # Arguments
+    /// * `signature` - The LSH signature (bucket index) to clear
+    ///
+    /// # Panics
+    /// Panics if `signature >= self.num_buckets()`
+    pub fn clear_bucket(&self, signature: usize) {
+        // checked before `signature + 1`, which overflows for `usize::MAX`
+        assert!(
+            signature < self.num_buckets(),
+            "signature {signature} would exceed the {} buckets of {} hash bits",
+            self.num_buckets(),
+            self.num_hash()
+        );
+        self.clear_bucket_range(signature..signature + 1);
+    }
+
+    /// Clears the catapults of a range of buckets, e.g. the signatures assigned to one arm
+    /// of an A/B experiment, leaving every other bucket untouched.
+    ///
+    /// Catapults stored in the p-stable index of [`LshFamily::PStable`] belong to no
+    /// bucket and are kept.
+    ///
+    /// # Arguments
+    /// * `signatures` - The LSH signatures (bucket indices) to clear
+    ///
+    /// # Panics
+    /// Panics if the range is reversed or reaches past `self.num_buckets()`
+    pub fn clear_bucket_range(&self, signatures: Range<usize>) {
+        assert!(
+            signatures.start <= signatures.end,
+            "signatures {signatures:?} start after they end"
+        );
+        assert!(
+            signatures.end <= self.num_buckets(),
+            "signatures {signatures:?} exceed the {} buckets of {} hash bits",
+            self.num_buckets(),
+            self.num_hash()
+        );
+        let stripes = signatures.start * self.lock_stripes..signatures.end * self.lock_stripes;
+        for catapult_set in &self.catapults[stripes] {
+            let mut bucket = catapult_set.write().unwrap();
+            self.catapult_count
+                .fetch_sub(bucket.len(), Ordering::Relaxed);
+            bucket.clear();
         }
     }
 
     pub fn starting_node(&self) -> NodeId {
         self.starting_node
     }
+
+    /// Replaces the node included in every selection of starting points.
+    pub fn set_starting_node(&mut self, starting_node: NodeId) {
+        self.starting_node = starting_node;
+    }
+
+    /// Returns the fixed entry points included besides the starting node.
+    pub fn entry_points(&self) -> &[NodeId] {
+        &self.entry_points
+    }
+
+    /// Replaces the fixed entry points included besides the starting node.
+    pub fn set_entry_points(&mut self, entry_points: Vec<NodeId>) {
+        self.entry_points = entry_points;
+    }
+
+    /// Returns the parameters this starter was created with, for its current starting node.
+    pub fn params(&self) -> EngineStarterParams {
+        EngineStarterParams {
+            num_hash: self.num_hash(),
+            bucket_capacity: self.bucket_capacity,
+            plane_dim: self.plane_dim(),
+            starting_node: self.starting_node,
+            seed: self.seed,
+            enabled_catapults: self.enabled_catapults,
+            catapult_memory_budget_bytes: self.memory_budget_bytes,
+            lsh_family: self.lsh_family(),
+            lock_stripes: self.lock_stripes,
+        }
+    }
+
+    /// Returns the hash family mapping queries to catapults.
+    pub fn lsh_family(&self) -> LshFamily {
+        match &self.pstable {
+            None => LshFamily::Hyperplane,
+            Some(index) => LshFamily::PStable {
+                w: index.read().unwrap().w(),
+            },
+        }
+    }
+
+    /// Returns the number of LSH hash bits.
+    pub fn num_hash(&self) -> usize {
+        self.hasher.num_hash()
+    }
+
+    /// Returns the dimension of hashed vectors in f32 elements.
+    pub fn plane_dim(&self) -> usize {
+        self.hasher.dim()
+    }
+
+    /// Returns the seed used to generate the LSH hyperplanes.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the maximum number of catapults each bucket holds.
+    ///
+    /// A striped bucket may hold a few more, see
+    /// [`with_lock_stripes`](EngineStarterParams::with_lock_stripes).
+    pub fn bucket_capacity(&self) -> usize {
+        self.bucket_capacity
+    }
+
+    /// Returns the number of independently locked stripes of each bucket.
+    pub fn lock_stripes(&self) -> usize {
+        self.lock_stripes
+    }
+
+    /// Returns the number of catapult buckets (`2^num_hash`).
+    pub fn num_buckets(&self) -> usize {
+        self.catapults.len() / self.lock_stripes
+    }
+
+    /// Returns the catapults stored in one bucket, stripe by stripe in each stripe's own
+    /// order.
+    ///
+    /// Unlike [`select_starting_points`](Self::select_starting_points), this ignores
+    /// whether catapults are enabled.
+    ///
+    /// # Panics
+    /// Panics if `signature >= self.num_buckets()`
+    pub fn bucket_catapults(&self, signature: usize) -> Vec<NodeId> {
+        let stripes = self.bucket(signature);
+        if let [stripe] = stripes {
+            return stripe.read().unwrap().to_vec();
+        }
+        stripes
+            .iter()
+            .flat_map(|stripe| stripe.read().unwrap().to_vec())
+            .collect()
+    }
+
+    /// Returns the number of catapults stored in one bucket, without copying them.
+    ///
+    /// # Panics
+    /// Panics if `signature >= self.num_buckets()`
+    pub fn bucket_len(&self, signature: usize) -> usize {
+        self.bucket(signature)
+            .iter()
+            .map(|stripe| stripe.read().unwrap().len())
+            .sum()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{numerics::SIMD_LANECOUNT, sets::catapults::LruSet};
+    use crate::{
+        numerics::SIMD_LANECOUNT,
+        sets::catapults::{DecaySet, LruSet},
+    };
 
     type TestEngineStarter = EngineStarter<LruSet>;
 
@@ -472,6 +1078,137 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_iter_catapults_visits_buckets_in_order() {
+        let starter = TestEngineStarter::new(default_params());
+        starter.new_catapult(7, NodeId { internal: 1 });
+        starter.new_catapult(2, NodeId { internal: 5 });
+        starter.new_catapult(7, NodeId { internal: 3 });
+
+        let pairs: Vec<(usize, NodeId)> = starter.iter_catapults().collect();
+        assert_eq!(
+            pairs,
+            vec![
+                (2, NodeId { internal: 5 }),
+                (7, NodeId { internal: 1 }),
+                (7, NodeId { internal: 3 }),
+            ]
+        );
+        assert_eq!(pairs, starter.export_catapult_edges());
+    }
+
+    #[test]
+    fn test_catapult_count_tracks_insertions_and_clears() {
+        let starter = TestEngineStarter::new(default_params());
+        starter.new_catapult(0, NodeId { internal: 1 });
+        starter.new_catapult(0, NodeId { internal: 1 }); // duplicate, no growth
+        starter.new_catapult(1, NodeId { internal: 2 });
+        assert_eq!(starter.catapult_count(), 2);
+        assert_eq!(starter.catapult_memory_bytes(), 2 * size_of::<NodeId>());
+
+        starter.clear_all_catapults();
+        assert_eq!(starter.catapult_count(), 0);
+    }
+
+    #[test]
+    fn test_clear_bucket_leaves_other_buckets_untouched() {
+        let starter = TestEngineStarter::new(default_params());
+        starter.new_catapult(3, NodeId { internal: 1 });
+        starter.new_catapult(3, NodeId { internal: 2 });
+        starter.new_catapult(7, NodeId { internal: 5 });
+
+        starter.clear_bucket(3);
+        assert_eq!(starter.bucket_len(3), 0);
+        assert_eq!(starter.bucket_catapults(7), vec![NodeId { internal: 5 }]);
+        assert_eq!(starter.catapult_count(), 1);
+
+        starter.new_catapult(9, NodeId { internal: 6 });
+        starter.clear_bucket_range(0..8);
+        assert_eq!(starter.bucket_catapults(9), vec![NodeId { internal: 6 }]);
+        assert_eq!(starter.catapult_count(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "exceed the 256 buckets of 8 hash bits")]
+    fn test_clear_bucket_out_of_range_panics() {
+        TestEngineStarter::new(default_params()).clear_bucket(256);
+    }
+
+    #[test]
+    #[should_panic(expected = "signature 18446744073709551615 would exceed the 256 buckets")]
+    fn test_clear_bucket_max_signature_panics() {
+        TestEngineStarter::new(default_params()).clear_bucket(usize::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "signatures 5..2 start after they end")]
+    fn test_clear_reversed_bucket_range_panics() {
+        #[allow(clippy::reversed_empty_ranges)]
+        TestEngineStarter::new(default_params()).clear_bucket_range(5..2);
+    }
+
+    #[test]
+    fn test_decay_drops_stale_catapults_only() {
+        let starter = EngineStarter::<DecaySet<3>>::new(default_params());
+        starter.new_catapult(0, NodeId { internal: 1 });
+        starter.new_catapult(1, NodeId { internal: 2 });
+        for _ in 0..4 {
+            // the catapult of bucket 1 keeps proving useful
+            starter.touch_catapults(1, &[NodeId { internal: 2 }]);
+            starter.decay_catapults();
+        }
+
+        assert!(starter.bucket_catapults(0).is_empty());
+        assert_eq!(starter.bucket_catapults(1), vec![NodeId { internal: 2 }]);
+        assert_eq!(starter.catapult_count(), 1);
+    }
+
+    #[test]
+    fn test_memory_budget_triggers_eviction() {
+        let budget = 10 * size_of::<NodeId>();
+        let starter = TestEngineStarter::new(default_params().with_memory_budget(budget));
+
+        // bucket 0 is well populated, buckets 1.. each get a single catapult
+        for i in 0..8 {
+            starter.new_catapult(0, NodeId { internal: i });
+        }
+        for signature in 1..6 {
+            starter.new_catapult(
+                signature,
+                NodeId {
+                    internal: 100 + signature,
+                },
+            );
+            assert!(starter.catapult_memory_bytes() <= budget);
+        }
+
+        let total: usize = starter
+            .catapults
+            .iter()
+            .map(|b| b.read().unwrap().len())
+            .sum();
+        assert_eq!(total, starter.catapult_count());
+        assert!(starter.catapult_memory_bytes() <= budget);
+
+        // the least-occupied buckets went first, the busy one survived
+        assert_eq!(starter.catapults[0].read().unwrap().len(), 8);
+    }
+
+    #[test]
+    fn test_new_catapult_survives_eviction_at_budget() {
+        let budget = 3 * size_of::<NodeId>();
+        let starter = TestEngineStarter::new(default_params().with_memory_budget(budget));
+        for i in 0..3 {
+            starter.new_catapult(0, NodeId { internal: i });
+        }
+
+        // a new query region pushes usage over the budget, the older bucket makes room
+        starter.new_catapult(7, NodeId { internal: 50 });
+        assert_eq!(starter.bucket_catapults(7), vec![NodeId { internal: 50 }]);
+        assert!(starter.bucket_catapults(0).is_empty());
+        assert!(starter.catapult_memory_bytes() <= budget);
+    }
+
     #[test]
     fn test_empty_catapult_only_returns_starting_node() {
         let custom_starting = 999;
@@ -498,4 +1235,205 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_multiprobe_unions_neighboring_buckets() {
+        let starter = TestEngineStarter::new(default_params());
+        let query = create_test_query(1.0);
+        let probes = starter.hasher.probe_signatures(&query, 3);
+        for (i, &probe) in probes.iter().enumerate() {
+            starter.new_catapult(probe, NodeId { internal: i });
+            starter.new_catapult(probe, NodeId { internal: 7 });
+        }
+
+        let single = starter.select_starting_points_multiprobe(&query, 1);
+        assert_eq!(
+            single.catapults,
+            starter.select_starting_points(&query).catapults
+        );
+
+        let result = starter.select_starting_points_multiprobe(&query, 3);
+        assert_eq!(result.signature, starter.signature(&query));
+        assert_contains_starting_node(&result);
+        let mut catapults: Vec<usize> = result.catapults.iter().map(|c| c.internal).collect();
+        catapults.sort_unstable();
+        assert_eq!(catapults, vec![0, 1, 2, 7]);
+    }
+
+    #[test]
+    fn test_pstable_index_is_bounded_and_keeps_new_catapults_at_budget() {
+        let budget = 4 * size_of::<NodeId>();
+        let starter = TestEngineStarter::new(
+            EngineStarterParams::new(4, 3, SIMD_LANECOUNT, NodeId { internal: 0 }, 3, true)
+                .with_pstable_lsh(1.0)
+                .with_memory_budget(budget),
+        );
+        let query = |value: f32| vec![AlignedBlock::new([value; SIMD_LANECOUNT])];
+
+        // one code keeps its 3 most recent catapults
+        for i in 0..10 {
+            starter.learn_catapult(&query(2.0), 0, NodeId { internal: i });
+        }
+        assert_eq!(starter.catapult_count(), 3);
+        let mut kept = starter.select_starting_points(&query(2.0)).catapults;
+        kept.sort_unstable();
+        assert_eq!(
+            kept,
+            (7..10)
+                .map(|internal| NodeId { internal })
+                .collect::<Vec<_>>()
+        );
+
+        // past the budget, only the oldest entries go, never the one just learned
+        for (i, value) in [40.0, 80.0, 120.0].into_iter().enumerate() {
+            let learned = NodeId { internal: 100 + i };
+            starter.learn_catapult(&query(value), 0, learned);
+            assert!(starter.catapult_memory_bytes() <= budget);
+            let found = starter.select_starting_points(&query(value)).catapults;
+            assert_eq!(found[0], learned);
+        }
+        assert_eq!(starter.catapult_count(), 4);
+    }
+
+    #[test]
+    fn test_pstable_selects_l2_nearest_catapults() {
+        use rand::prelude::*;
+
+        let starter = TestEngineStarter::new(
+            EngineStarterParams::new(
+                4,
+                3,
+                SIMD_LANECOUNT,
+                NodeId {
+                    internal: DEFAULT_STARTING_NODE,
+                },
+                // projects the all-ones direction positively: negative codes all clamp to 0
+                3,
+                true,
+            )
+            .with_pstable_lsh(1.0),
+        );
+        assert_eq!(starter.lsh_family(), LshFamily::PStable { w: 1.0 });
+
+        // queries scattered around one of two centers far apart
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut near = |center: f32| {
+            let mut data = [0.0; SIMD_LANECOUNT];
+            for v in data.iter_mut() {
+                *v = center + rng.random_range(-1.0..1.0);
+            }
+            vec![AlignedBlock::new(data)]
+        };
+        // catapults 0..50 were found by queries near 10, catapults 50..100 near 40
+        for i in 0..100 {
+            let query = near(if i < 50 { 10.0 } else { 40.0 });
+            let signature = starter.signature(&query);
+            starter.learn_catapult(&query, signature, NodeId { internal: i });
+            starter.learn_catapult(&query, signature, NodeId { internal: i });
+        }
+        assert_eq!(starter.catapult_count(), 100);
+        assert!(starter.export_catapult_edges().is_empty());
+
+        for _ in 0..10 {
+            let result = starter.select_starting_points(&near(10.0));
+            assert_eq!(result.catapults.len(), 3);
+            assert!(result.catapults.iter().all(|c| c.internal < 50));
+            let result = starter.select_starting_points_multiprobe(&near(40.0), 4);
+            assert_eq!(result.catapults.len(), 3);
+            assert!(result.catapults.iter().all(|c| c.internal >= 50));
+        }
+
+        starter.clear_all_catapults();
+        assert_eq!(starter.catapult_count(), 0);
+        assert!(
+            starter
+                .select_starting_points(&near(10.0))
+                .catapults
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_striped_bucket_behaves_as_one_bucket() {
+        let starter = TestEngineStarter::new(default_params().with_lock_stripes(4));
+        assert_eq!(starter.num_buckets(), 1 << DEFAULT_NUM_HASH);
+        assert_eq!(starter.params().lock_stripes, 4);
+
+        for i in 0..8 {
+            starter.new_catapult(3, NodeId { internal: i });
+            starter.new_catapult(3, NodeId { internal: i });
+        }
+        let mut stored = starter.bucket_catapults(3);
+        stored.sort_unstable();
+        assert_eq!(
+            stored,
+            (0..8)
+                .map(|internal| NodeId { internal })
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(starter.bucket_len(3), 8);
+        assert_eq!(starter.catapult_count(), 8);
+        assert!(starter.bucket_catapults(2).is_empty());
+
+        // each stripe holds ceil(40 / 4) catapults, so the bucket stays at its capacity
+        for i in 0..200 {
+            starter.new_catapult(5, NodeId { internal: i });
+        }
+        assert_eq!(starter.bucket_len(5), DEFAULT_BUCKET_CAP);
+        assert_eq!(starter.catapult_count(), 8 + DEFAULT_BUCKET_CAP);
+
+        starter.clear_bucket(3);
+        assert_eq!(starter.bucket_len(3), 0);
+        assert_eq!(starter.catapult_count(), DEFAULT_BUCKET_CAP);
+    }
+
+    #[test]
+    fn test_striped_touch_reaches_every_stripe() {
+        let params =
+            EngineStarterParams::new(2, 2, SIMD_LANECOUNT, NodeId { internal: 0 }, 1, true)
+                .with_lock_stripes(2);
+        let starter = TestEngineStarter::new(params);
+        // stripe 0 holds the even ids, stripe 1 the odd ones, one each
+        starter.new_catapult(0, NodeId { internal: 2 });
+        starter.new_catapult(0, NodeId { internal: 3 });
+        starter.touch_catapults(0, &[NodeId { internal: 2 }, NodeId { internal: 3 }]);
+        starter.new_catapult(0, NodeId { internal: 4 });
+        starter.new_catapult(0, NodeId { internal: 5 });
+        let mut stored = starter.bucket_catapults(0);
+        stored.sort_unstable();
+        assert_eq!(stored, vec![NodeId { internal: 4 }, NodeId { internal: 5 }]);
+    }
+
+    /// Times many threads learning catapults for the same bucket, with one lock per bucket
+    /// and with striped buckets. Run with
+    /// `cargo test --release -- --ignored --nocapture striped_bucket_throughput`.
+    #[test]
+    #[ignore]
+    fn striped_bucket_throughput() {
+        use std::time::Instant;
+        const THREADS: usize = 16;
+        const ROUNDS: usize = 200_000;
+        for stripes in [1, 4, 16] {
+            let starter = TestEngineStarter::new(default_params().with_lock_stripes(stripes));
+            let query = create_test_query(1.0);
+            let start = Instant::now();
+            std::thread::scope(|scope| {
+                for thread in 0..THREADS {
+                    let (starter, query) = (&starter, &query);
+                    scope.spawn(move || {
+                        for round in 0..ROUNDS {
+                            let result = starter.select_starting_points(query);
+                            let internal = (round * THREADS + thread) % 1000;
+                            starter.new_catapult(result.signature, NodeId { internal });
+                        }
+                    });
+                }
+            });
+            let elapsed = start.elapsed();
+            println!(
+                "{stripes} stripes: {:.0} searches/s over {THREADS} threads on one bucket",
+                (THREADS * ROUNDS) as f64 / elapsed.as_secs_f64()
+            );
+        }
+    }
 }
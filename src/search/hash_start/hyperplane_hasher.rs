@@ -1,8 +1,11 @@
+use std::cmp::{self, Reverse};
+use std::collections::BinaryHeap;
+
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
 use rand_distr::StandardNormal;
 
-use crate::numerics::{AlignedBlock, SIMD_LANECOUNT, VectorLike};
+use crate::numerics::{AlignedBlock, SIMD_LANECOUNT, project_interleaved};
 
 /// A locality-sensitive hasher using random hyperplane projections.
 ///
@@ -12,9 +15,11 @@ use crate::numerics::{AlignedBlock, SIMD_LANECOUNT, VectorLike};
 /// Similar vectors tend to produce similar signatures, enabling efficient bucketing.
 pub struct SimilarityHasher {
     stored_vectors_dim: usize,
-    /// Random hyperplane normal vectors, each as a sequence of aligned blocks.
-    /// One hyperplane per hash bit.
-    projections: Vec<Vec<AlignedBlock>>,
+    /// Number of hyperplanes, one per hash bit.
+    num_hash: usize,
+    /// Random hyperplane normal vectors, interleaved block by block: block `b` of
+    /// hyperplane `i` is at `b * num_hash + i`, see [`project_interleaved`].
+    projections: Box<[AlignedBlock]>,
 }
 
 impl SimilarityHasher {
@@ -26,29 +31,26 @@ impl SimilarityHasher {
     ///
     /// # Arguments
     /// * `num_hash` - Number of hash bits / hyperplanes to generate
-    /// * `stored_vectors_dim` - Dimension of input vectors in f32 elements (not blocks)
+    /// * `stored_vectors_dim` - Logical dimension of input vectors in f32 elements (not
+    ///   blocks). Need not be a multiple of `SIMD_LANECOUNT`: hashed vectors are then
+    ///   expected zero-padded to whole blocks, and the normals are zero on the padding
+    ///   lanes so that padding never influences a signature.
     /// * `seed` - Random seed for deterministic hyperplane generation
     ///
     /// # Returns
     /// A new `SimilarityHasher` instance
-    ///
-    /// # Panics
-    /// Panics if `stored_vectors_dim` is not a multiple of `SIMD_LANECOUNT`
     pub fn new_seeded(num_hash: usize, stored_vectors_dim: usize, seed: u64) -> Self {
         let rng = StdRng::seed_from_u64(seed);
 
-        assert!(
-            stored_vectors_dim.is_multiple_of(SIMD_LANECOUNT),
-            "dim must be multiple of SIMD_LANECOUNT"
-        );
-
         let mut gaussian_iter = rng.sample_iter(StandardNormal);
         let projections: Vec<Vec<AlignedBlock>> = (0..num_hash)
             .map(|_| {
-                (0..stored_vectors_dim / SIMD_LANECOUNT)
-                    .map(|_| {
+                (0..stored_vectors_dim.div_ceil(SIMD_LANECOUNT))
+                    .map(|block_index| {
+                        let logical_lanes =
+                            (stored_vectors_dim - block_index * SIMD_LANECOUNT).min(SIMD_LANECOUNT);
                         let mut block = [0.0; SIMD_LANECOUNT];
-                        for b in block.iter_mut() {
+                        for b in block[..logical_lanes].iter_mut() {
                             *b = gaussian_iter.next().unwrap();
                         }
                         AlignedBlock::new(block)
@@ -57,12 +59,52 @@ impl SimilarityHasher {
             })
             .collect();
 
+        Self::from_planes(stored_vectors_dim, projections)
+    }
+
+    /// Creates a hasher from hyperplane normals given one after the other.
+    fn from_planes(stored_vectors_dim: usize, planes: Vec<Vec<AlignedBlock>>) -> Self {
+        let num_blocks = stored_vectors_dim.div_ceil(SIMD_LANECOUNT);
+        let projections = (0..num_blocks)
+            .flat_map(|block| planes.iter().map(move |plane| plane[block]))
+            .collect();
         SimilarityHasher {
             stored_vectors_dim,
+            num_hash: planes.len(),
             projections,
         }
     }
 
+    /// Returns the number of hyperplanes, i.e. the number of bits in a signature.
+    pub fn num_hash(&self) -> usize {
+        self.num_hash
+    }
+
+    /// Returns the logical dimension of hashed vectors in f32 elements, excluding padding.
+    pub fn dim(&self) -> usize {
+        self.stored_vectors_dim
+    }
+
+    /// Returns the dot product of `vector` with each hyperplane normal, in hyperplane order.
+    fn project(&self, vector: &[AlignedBlock]) -> Vec<f32> {
+        project_interleaved(vector, &self.projections, self.num_hash)
+    }
+
+    /// Returns whether the padding lanes of `vector`'s last block are all zero.
+    ///
+    /// Padding never changes a signature, since the normals are zero on those lanes, but
+    /// nonzero padding means the vector was packed incorrectly and its distances to the
+    /// zero-padded payloads are wrong.
+    fn padding_is_zero(&self, vector: &[AlignedBlock]) -> bool {
+        let padding =
+            self.stored_vectors_dim.next_multiple_of(SIMD_LANECOUNT) - self.stored_vectors_dim;
+        vector.last().is_none_or(|block| {
+            block.data[SIMD_LANECOUNT - padding..]
+                .iter()
+                .all(|&x| x == 0.0)
+        })
+    }
+
     /// Hashes a vector to a binary signature represented as a vector of booleans.
     ///
     /// Each boolean indicates whether the vector's projection onto the corresponding
@@ -79,15 +121,12 @@ impl SimilarityHasher {
     #[allow(unused)]
     pub fn hash(&self, vector: &[AlignedBlock]) -> Vec<bool> {
         assert!(
-            vector.len() == self.stored_vectors_dim / SIMD_LANECOUNT,
+            vector.len() == self.stored_vectors_dim.div_ceil(SIMD_LANECOUNT),
             "input vector has wrong dimension"
         );
-        self.projections
-            .iter()
-            .map(|proj| {
-                let dot: f32 = vector.dot(proj);
-                dot >= 0.0
-            })
+        self.project(vector)
+            .into_iter()
+            .map(|dot| dot >= 0.0)
             .collect()
     }
 
@@ -105,27 +144,195 @@ impl SimilarityHasher {
     ///
     /// # Panics
     /// Panics if the vector dimension doesn't match the hasher's configured dimension,
-    /// or if `num_hash` exceeds the number of bits in a u64
+    /// or if `num_hash` exceeds the number of bits in a u64. In debug builds, also panics
+    /// if the padding lanes of the last block are not zero, as guaranteed by
+    /// [`AlignedBlock::allocate_padded`]
     pub fn hash_int(&self, vector: &[AlignedBlock]) -> usize {
         assert_eq!(
             vector.len(),
-            self.stored_vectors_dim / SIMD_LANECOUNT,
+            self.stored_vectors_dim.div_ceil(SIMD_LANECOUNT),
             "input vector has wrong dimension"
         );
-        assert!(self.projections.len() <= usize::BITS as usize); // less than 64 planes to fit signature in u64
+        debug_assert!(
+            self.padding_is_zero(vector),
+            "input vector has nonzero padding lanes"
+        );
+        assert!(self.num_hash <= usize::BITS as usize); // less than 64 planes to fit signature in u64
 
         let mut projected = 0usize;
-        for plane in self.projections.iter() {
-            projected = projected << 1 | ((plane.dot(vector) >= 0.0) as usize);
+        for dot in self.project(vector) {
+            projected = projected << 1 | ((dot >= 0.0) as usize);
         }
 
         projected
     }
+
+    /// Hashes a vector to a signature of up to 128 bits, left-aligned in a u128.
+    ///
+    /// Hyperplane `i` sets bit `127 - i`, so the first hyperplane is the most significant
+    /// bit and unused low bits are zero. Two signatures then share a prefix of length `l`
+    /// exactly when the vectors lie on the same side of the first `l` hyperplanes, which
+    /// keeps the longest common prefix of a [`ZOrderIndex`](super::zorder_index::ZOrderIndex)
+    /// meaningful. For `num_hash <= 64`, shifting the result right by `128 - num_hash` gives
+    /// [`hash_int`](Self::hash_int).
+    ///
+    /// # Arguments
+    /// * `vector` - The input vector as aligned blocks
+    ///
+    /// # Returns
+    /// The packed signature, hyperplane 0 in the most significant bit
+    ///
+    /// # Panics
+    /// Panics if the vector dimension doesn't match the hasher's configured dimension, or
+    /// if `num_hash` exceeds 128. In debug builds, also panics if the padding lanes of the
+    /// last block are not zero
+    pub fn hash_u128(&self, vector: &[AlignedBlock]) -> u128 {
+        assert_eq!(
+            vector.len(),
+            self.stored_vectors_dim.div_ceil(SIMD_LANECOUNT),
+            "input vector has wrong dimension"
+        );
+        debug_assert!(
+            self.padding_is_zero(vector),
+            "input vector has nonzero padding lanes"
+        );
+        assert!(
+            self.num_hash <= u128::BITS as usize,
+            "at most 128 hyperplanes fit in a u128 signature"
+        );
+
+        let mut projected = 0u128;
+        for (i, dot) in self.project(vector).into_iter().enumerate() {
+            if dot >= 0.0 {
+                projected |= 1 << (u128::BITS as usize - 1 - i);
+            }
+        }
+        projected
+    }
+
+    /// Lists the `probes` signatures most likely to hold `vector`'s neighbors, best first.
+    ///
+    /// The first signature is always [`hash_int`](Self::hash_int)'s. The following ones are
+    /// its Hamming perturbations ordered by the summed projection magnitude of the flipped
+    /// bits, so bits whose hyperplane passes closest to `vector` are flipped first. Sets of
+    /// flipped bits are enumerated with a min-heap (shift/expand), which visits each set
+    /// once in non-decreasing score order. Ties are broken by bit index, so the sequence is
+    /// fully determined by the hyperplanes and the vector.
+    ///
+    /// # Arguments
+    /// * `vector` - The input vector as aligned blocks
+    /// * `probes` - Number of signatures to return, capped at `2^num_hash`
+    ///
+    /// # Returns
+    /// Up to `probes` distinct signatures, the vector's own signature first
+    ///
+    /// # Panics
+    /// Same conditions as [`hash_int`](Self::hash_int)
+    pub fn probe_signatures(&self, vector: &[AlignedBlock], probes: usize) -> Vec<usize> {
+        let base = self.hash_int(vector);
+        let num_hash = self.num_hash;
+        let probes = probes.min(1usize.checked_shl(num_hash as u32).unwrap_or(usize::MAX));
+        if probes == 0 {
+            return vec![];
+        }
+
+        // bit masks sorted by how close the vector lies to the matching hyperplane
+        let mut margins: Vec<(f32, usize)> = self
+            .project(vector)
+            .into_iter()
+            .enumerate()
+            .map(|(plane, dot)| (dot.abs(), 1 << (num_hash - 1 - plane)))
+            .collect();
+        margins.sort_by(|a, b| a.0.total_cmp(&b.0).then(b.1.cmp(&a.1)));
+
+        let mut signatures = Vec::with_capacity(probes);
+        signatures.push(base);
+
+        // each heap entry is a set of positions into `margins`, identified by its score,
+        // its largest position and the xor mask of all its positions
+        let mut heap = BinaryHeap::new();
+        if let Some(&(score, mask)) = margins.first() {
+            heap.push(Reverse(Perturbation {
+                score,
+                last: 0,
+                mask,
+            }));
+        }
+        while signatures.len() < probes {
+            let Some(Reverse(set)) = heap.pop() else {
+                break;
+            };
+            signatures.push(base ^ set.mask);
+
+            if let Some(&(next_score, next_mask)) = margins.get(set.last + 1) {
+                let (last_score, last_mask) = margins[set.last];
+                // shift: replace the largest position by the next one
+                heap.push(Reverse(Perturbation {
+                    score: set.score - last_score + next_score,
+                    last: set.last + 1,
+                    mask: set.mask ^ last_mask ^ next_mask,
+                }));
+                // expand: add the next position
+                heap.push(Reverse(Perturbation {
+                    score: set.score + next_score,
+                    last: set.last + 1,
+                    mask: set.mask ^ next_mask,
+                }));
+            }
+        }
+
+        signatures
+    }
+}
+
+/// A set of flipped signature bits, ordered by score for multi-probe enumeration.
+struct Perturbation {
+    score: f32,
+    last: usize,
+    mask: usize,
+}
+
+impl PartialEq for Perturbation {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == cmp::Ordering::Equal
+    }
+}
+
+impl Eq for Perturbation {}
+
+impl PartialOrd for Perturbation {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Perturbation {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then(self.mask.cmp(&other.mask))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::numerics::VectorLike;
+
+    /// Returns the hyperplane normals one after the other, undoing the interleaving.
+    fn planes(hasher: &SimilarityHasher) -> Vec<Vec<AlignedBlock>> {
+        (0..hasher.num_hash)
+            .map(|plane| {
+                hasher
+                    .projections
+                    .iter()
+                    .skip(plane)
+                    .step_by(hasher.num_hash)
+                    .copied()
+                    .collect()
+            })
+            .collect()
+    }
 
     #[test]
     fn test_new_seeded_determinism() {
@@ -135,6 +342,30 @@ mod tests {
         assert_eq!(h1.projections, h2.projections);
     }
 
+    #[test]
+    fn test_interleaved_projections_match_per_plane_dot_products() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for (num_hash, dim) in [(1, SIMD_LANECOUNT), (16, 100), (64, 4 * SIMD_LANECOUNT)] {
+            let hasher = SimilarityHasher::new_seeded(num_hash, dim, 17);
+            let planes = planes(&hasher);
+            for _ in 0..20 {
+                let values = (0..dim).map(|_| rng.random_range(-1.0..1.0)).collect();
+                let vector = AlignedBlock::allocate_padded(values);
+                let expected = planes.iter().fold(0, |acc, plane| {
+                    acc << 1 | (plane.dot(&vector) >= 0.0) as usize
+                });
+                assert_eq!(hasher.hash_int(&vector), expected);
+                let dots: Vec<u32> = planes.iter().map(|p| p.dot(&vector).to_bits()).collect();
+                let projected: Vec<u32> = hasher
+                    .project(&vector)
+                    .iter()
+                    .map(|d| d.to_bits())
+                    .collect();
+                assert_eq!(projected, dots);
+            }
+        }
+    }
+
     #[test]
     fn test_hash_consistency_same_input() {
         let hasher = SimilarityHasher::new_seeded(16, SIMD_LANECOUNT, 123);
@@ -162,9 +393,9 @@ mod tests {
     #[test]
     fn test_hash_dot_sign_behavior() {
         // Manually define a simple hasher with known projection
-        let hasher = SimilarityHasher {
-            stored_vectors_dim: SIMD_LANECOUNT,
-            projections: vec![
+        let hasher = SimilarityHasher::from_planes(
+            SIMD_LANECOUNT,
+            vec![
                 vec![AlignedBlock::new([
                     1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
                 ])],
@@ -172,7 +403,7 @@ mod tests {
                     0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
                 ])],
             ], // x-axis and y-axis projections
-        };
+        );
 
         let input = vec![AlignedBlock::new([
             2.0, -3.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
@@ -185,10 +416,22 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "dim must be multiple of SIMD_LANECOUNT")]
-    fn test_panics_on_non_multiple_dimension() {
-        // Line 29: dim not multiple of SIMD_LANECOUNT
-        let _ = SimilarityHasher::new_seeded(8, SIMD_LANECOUNT + 1, 42);
+    fn test_non_multiple_dimension_is_padded() {
+        let hasher = SimilarityHasher::new_seeded(32, 100, 42);
+        assert_eq!(hasher.dim(), 100);
+        for proj in &planes(&hasher) {
+            assert_eq!(proj.len(), 7);
+            assert!(proj[6].data[4..].iter().all(|&x| x == 0.0));
+        }
+
+        let values: Vec<f32> = (0..100).map(|i| (i as f32 * 0.37).sin()).collect();
+        let padded = AlignedBlock::allocate_padded(values.clone());
+        let expected = planes(&hasher).iter().fold(0, |acc, proj| {
+            let flat = proj.iter().flat_map(|b| b.data);
+            let dot: f32 = flat.zip(&values).map(|(p, v)| p * v).sum();
+            acc << 1 | (dot >= 0.0) as usize
+        });
+        assert_eq!(hasher.hash_int(&padded), expected);
     }
 
     #[test]
@@ -208,4 +451,94 @@ mod tests {
         let wrong_vec = vec![AlignedBlock::new([0.0; SIMD_LANECOUNT])]; // Only 1 block instead of 2
         let _ = hasher.hash_int(&wrong_vec);
     }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "input vector has nonzero padding lanes")]
+    fn test_hash_int_catches_nonzero_padding() {
+        let hasher = SimilarityHasher::new_seeded(8, 100, 42);
+        let mut values = vec![1.0; 112];
+        values[105] = 0.5;
+        let _ = hasher.hash_int(&AlignedBlock::allocate_padded(values));
+    }
+
+    #[test]
+    fn test_packed_queries_pass_padding_check() {
+        let hasher = SimilarityHasher::new_seeded(8, 100, 42);
+        let packed = AlignedBlock::allocate_padded(vec![1.0; 100]);
+        assert!(hasher.padding_is_zero(&packed));
+        let _ = hasher.hash_int(&packed);
+
+        let aligned = SimilarityHasher::new_seeded(8, 2 * SIMD_LANECOUNT, 42);
+        assert!(aligned.padding_is_zero(&[AlignedBlock::new([3.0; SIMD_LANECOUNT]); 2]));
+    }
+
+    #[test]
+    fn test_hash_u128_left_aligns_hash_int() {
+        let hasher = SimilarityHasher::new_seeded(10, SIMD_LANECOUNT, 7);
+        for value in [-3.0, 0.5, 2.0] {
+            let mut data = [value; SIMD_LANECOUNT];
+            data[0] = 1.0;
+            let vector = vec![AlignedBlock::new(data)];
+            let wide = hasher.hash_u128(&vector);
+            assert_eq!((wide >> (128 - 10)) as usize, hasher.hash_int(&vector));
+            assert_eq!(wide << 10, 0);
+        }
+
+        // more planes than a usize holds
+        let wide = SimilarityHasher::new_seeded(128, SIMD_LANECOUNT, 7);
+        let vector = vec![AlignedBlock::new([1.0; SIMD_LANECOUNT])];
+        let signature = wide.hash_u128(&vector);
+        for (i, bit) in wide.hash(&vector).into_iter().enumerate() {
+            assert_eq!(signature >> (127 - i) & 1 == 1, bit);
+        }
+    }
+
+    #[test]
+    fn test_probe_signatures_flip_smallest_margins_first() {
+        // the vector lies far from plane 0 (MSB), closest to plane 2 (LSB), between for 1
+        let hasher = SimilarityHasher::from_planes(
+            SIMD_LANECOUNT,
+            (0..3)
+                .map(|plane| {
+                    let mut block = [0.0; SIMD_LANECOUNT];
+                    block[plane] = 1.0;
+                    vec![AlignedBlock::new(block)]
+                })
+                .collect(),
+        );
+        let mut input = [0.0; SIMD_LANECOUNT];
+        input[..3].copy_from_slice(&[5.0, -2.0, 1.0]);
+        let input = vec![AlignedBlock::new(input)];
+
+        let base = hasher.hash_int(&input);
+        assert_eq!(base, 0b101);
+        // flipped sets in score order: {2}=1, {1}=2, {1,2}=3, {0}=5, {0,2}=6, ...
+        assert_eq!(
+            hasher.probe_signatures(&input, 6),
+            vec![
+                base,
+                base ^ 0b001,
+                base ^ 0b010,
+                base ^ 0b011,
+                base ^ 0b100,
+                base ^ 0b101
+            ]
+        );
+    }
+
+    #[test]
+    fn test_probe_signatures_enumerates_every_bucket_once() {
+        let hasher = SimilarityHasher::new_seeded(6, 100, 3);
+        let input = AlignedBlock::allocate_padded((0..100).map(|i| (i as f32).cos()).collect());
+        let probes = hasher.probe_signatures(&input, 1000);
+        assert_eq!(probes.len(), 64);
+        assert_eq!(probes[0], hasher.hash_int(&input));
+
+        let mut sorted = probes.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..64).collect::<Vec<_>>());
+        assert_eq!(hasher.probe_signatures(&input, 10), probes[..10]);
+        assert!(hasher.probe_signatures(&input, 0).is_empty());
+    }
 }
@@ -3,9 +3,13 @@
 //! This module provides LSH-based infrastructure for mapping query vectors to cached
 //! catapult starting points, enabling fast warm starts for similar queries.
 
+mod diagnostics;
 mod engine_starter;
 mod hyperplane_hasher;
 mod pstable_hasher;
 pub mod zorder_index;
 
+pub use diagnostics::*;
 pub use engine_starter::*;
+pub use hyperplane_hasher::SimilarityHasher;
+pub use pstable_hasher::PStableHashingBlock;
@@ -3,7 +3,21 @@ use rand_distr::{StandardNormal, Uniform};
 
 use crate::numerics::{AlignedBlock, SIMD_LANECOUNT, VectorLike};
 
-#[allow(dead_code)]
+/// A Euclidean locality-sensitive hasher built from p-stable (Gaussian) projections.
+///
+/// Each of the `num_hash` hash functions is `h(q) = floor((a · q + b) / w)`, where `a` is
+/// a vector of independent standard normal entries and `b` is drawn uniformly from
+/// `[0, w)`. Vectors close in L2 distance fall into the same quantization cell of each
+/// projection with high probability. [`hash`](Self::hash) interleaves the cell indices of
+/// all projections into a single Z-order (Morton) code.
+///
+/// # Choosing `w`
+/// The cell width sets the granularity of the buckets. A large `w` puts far-apart vectors
+/// in the same cell, until every vector shares one code and the hash carries no
+/// information; a small `w` splits even close neighbors across cells, until every vector
+/// gets a code of its own. A good `w` is comparable to the distance under which vectors
+/// should be considered neighbors; [`estimate_w`](Self::estimate_w) derives one from the
+/// spread of a data sample.
 pub struct PStableHashingBlock {
     a_vectors: Vec<Vec<AlignedBlock>>,
     bs: Vec<f32>,
@@ -11,25 +25,63 @@ pub struct PStableHashingBlock {
 }
 
 impl PStableHashingBlock {
-    #[allow(dead_code)]
+    /// Creates a hasher from explicit projections and offsets.
+    ///
+    /// # Arguments
+    /// * `a_vectors` - One projection vector per hash function, as aligned blocks
+    /// * `bs` - One offset per hash function, normally in `[0, w)`
+    /// * `w` - Width of the quantization cells
+    ///
+    /// # Returns
+    /// A new `PStableHashingBlock` instance
+    ///
+    /// # Panics
+    /// * Panics if `a_vectors` and `bs` have different lengths
+    /// * Panics if `w` is not positive and finite
+    pub fn new(a_vectors: Vec<Vec<AlignedBlock>>, bs: Vec<f32>, w: f32) -> Self {
+        assert!(Self::valid_w(w), "w must be positive and finite, got {w}");
+        assert_eq!(
+            a_vectors.len(),
+            bs.len(),
+            "one offset is needed per projection"
+        );
+        Self { a_vectors, bs, w }
+    }
+
+    /// Creates a deterministic hasher following the standard p-stable construction.
+    ///
+    /// Projection entries are drawn from the standard normal distribution and offsets
+    /// uniformly from `[0, w)`, each from its own generator seeded from `seed`, so the same
+    /// seed always produces the same hasher.
+    ///
+    /// # Arguments
+    /// * `num_hash` - Number of hash functions
+    /// * `stored_vectors_dim` - Logical dimension of input vectors in f32 elements; the
+    ///   projections are zero on the padding lanes of the last block
+    /// * `seed` - Random seed for deterministic generation
+    /// * `w` - Width of the quantization cells
+    ///
+    /// # Returns
+    /// A new `PStableHashingBlock` instance
+    ///
+    /// # Panics
+    /// Panics if `w` is not positive and finite
     pub fn new_seeded(num_hash: usize, stored_vectors_dim: usize, seed: u64, w: f32) -> Self {
+        assert!(Self::valid_w(w), "w must be positive and finite, got {w}");
         let rng1 = StdRng::seed_from_u64(seed);
         let rng2 = StdRng::seed_from_u64(u64::MAX ^ seed);
 
-        assert!(
-            stored_vectors_dim.is_multiple_of(SIMD_LANECOUNT),
-            "dim must be multiple of SIMD_LANECOUNT"
-        );
-
         let mut gaussian_iter = rng1.sample_iter(StandardNormal);
         let mut uniform_iter = rng2.sample_iter(Uniform::new(0.0, w).unwrap());
 
         let projections: Vec<Vec<AlignedBlock>> = (0..num_hash)
             .map(|_| {
-                (0..stored_vectors_dim / SIMD_LANECOUNT)
-                    .map(|_| {
+                (0..stored_vectors_dim.div_ceil(SIMD_LANECOUNT))
+                    .map(|block_index| {
+                        let logical_lanes =
+                            (stored_vectors_dim - block_index * SIMD_LANECOUNT).min(SIMD_LANECOUNT);
                         let mut block = [0.0; SIMD_LANECOUNT];
-                        for b in block.iter_mut() {
+                        for b in block[..logical_lanes].iter_mut() {
                             *b = gaussian_iter.next().unwrap();
                         }
                         AlignedBlock::new(block)
@@ -42,11 +94,46 @@ impl PStableHashingBlock {
             .map(|_| uniform_iter.next().unwrap())
             .collect();
 
-        Self {
-            a_vectors: projections,
-            bs,
-            w,
-        }
+        Self::new(projections, bs, w)
+    }
+
+    /// Returns whether `w` can be used as a cell width.
+    fn valid_w(w: f32) -> bool {
+        w.is_finite() && w > 0.0
+    }
+
+    /// Estimates a cell width from a sample of the vectors to hash.
+    ///
+    /// The projection of a vector of norm `r` onto a standard normal vector is normally
+    /// distributed with standard deviation `r`, so the projections of the sample spread
+    /// over about four times the root mean square of its norms. The estimate divides that
+    /// spread into `cells` cells per projection: more cells give finer buckets. The sample
+    /// should be centered like the hashed data, since the spread is measured from the
+    /// origin.
+    ///
+    /// # Arguments
+    /// * `sample` - Vectors representative of the data, as aligned blocks
+    /// * `cells` - Number of cells the sample's projections should span
+    ///
+    /// # Returns
+    /// A cell width to pass to [`new_seeded`](Self::new_seeded)
+    ///
+    /// # Panics
+    /// Panics if `cells` is 0 or the sample holds no nonzero vector
+    pub fn estimate_w(sample: &[Vec<AlignedBlock>], cells: usize) -> f32 {
+        assert!(cells > 0, "cells must be at least 1");
+        let mean_squared_norm = sample.iter().map(|v| v.dot(v)).sum::<f32>() / sample.len() as f32;
+        let w = 4.0 * mean_squared_norm.sqrt() / cells as f32;
+        assert!(
+            Self::valid_w(w),
+            "cannot estimate w from a sample without nonzero vectors"
+        );
+        w
+    }
+
+    /// Returns the width of the quantization cells.
+    pub fn w(&self) -> f32 {
+        self.w
     }
 
     fn hash_one(&self, q: &[AlignedBlock], index: usize) -> f32 {
@@ -87,7 +174,11 @@ impl PStableHashingBlock {
         }
     }
 
-    #[allow(dead_code)]
+    /// Hashes a vector to the Z-order code of its quantization cells.
+    ///
+    /// The 128 bits are split evenly between the hash functions, and the bits of their
+    /// cell indices are interleaved from the most significant down, so codes sharing a
+    /// long prefix come from vectors sharing high-order cell bits on every projection.
     pub fn hash(&self, q: &[AlignedBlock]) -> u128 {
         let hh = self.big_h(q);
         let dims_per_k = u128::BITS as usize / hh.len();
@@ -135,6 +226,89 @@ mod tests {
         assert_eq!(PStableHashingBlock::irelu(f32::NEG_INFINITY), 0);
     }
 
+    #[test]
+    fn test_new_seeded_determinism() {
+        let h1 = PStableHashingBlock::new_seeded(8, SIMD_LANECOUNT * 2, 12345, 4.0);
+        let h2 = PStableHashingBlock::new_seeded(8, SIMD_LANECOUNT * 2, 12345, 4.0);
+        // Same seed → identical projections and offsets
+        assert_eq!(h1.a_vectors, h2.a_vectors);
+        assert_eq!(h1.bs, h2.bs);
+        assert!(h1.bs.iter().all(|&b| (0.0..4.0).contains(&b)));
+    }
+
+    #[test]
+    fn test_new_uses_given_projections() {
+        // a single projection onto the first axis, offset by half a cell
+        let mut axis = [0.0; SIMD_LANECOUNT];
+        axis[0] = 1.0;
+        let hasher = PStableHashingBlock::new(vec![vec![AlignedBlock::new(axis)]], vec![1.0], 2.0);
+
+        let mut q = [0.0; SIMD_LANECOUNT];
+        q[0] = 4.5;
+        assert_eq!(hasher.big_h(&[AlignedBlock::new(q)]), vec![2.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "one offset is needed per projection")]
+    fn test_new_rejects_mismatched_offsets() {
+        PStableHashingBlock::new(
+            vec![vec![AlignedBlock::new([1.0; SIMD_LANECOUNT])]],
+            vec![],
+            1.0,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "w must be positive and finite")]
+    fn test_zero_w_is_rejected() {
+        PStableHashingBlock::new_seeded(2, SIMD_LANECOUNT, 42, 0.0);
+    }
+
+    #[test]
+    fn test_estimate_w_spreads_sample_over_cells() {
+        // every vector has norm 2: projections spread over about 8
+        let sample: Vec<Vec<AlignedBlock>> = (0..SIMD_LANECOUNT)
+            .map(|axis| {
+                let mut data = [0.0; SIMD_LANECOUNT];
+                data[axis] = if axis % 2 == 0 { 2.0 } else { -2.0 };
+                vec![AlignedBlock::new(data)]
+            })
+            .collect();
+        assert_eq!(PStableHashingBlock::estimate_w(&sample, 8), 1.0);
+        assert_eq!(PStableHashingBlock::estimate_w(&sample, 1), 8.0);
+    }
+
+    #[test]
+    fn test_w_trades_collisions_for_scattering() {
+        use rand::prelude::*;
+        let mut rng = StdRng::seed_from_u64(5);
+        let sample: Vec<Vec<AlignedBlock>> = (0..100)
+            .map(|_| {
+                let mut data = [0.0; SIMD_LANECOUNT];
+                for v in data.iter_mut() {
+                    *v = rng.random_range(-10.0..10.0);
+                }
+                vec![AlignedBlock::new(data)]
+            })
+            .collect();
+        let distinct_codes = |w: f32| {
+            let hasher = PStableHashingBlock::new_seeded(4, SIMD_LANECOUNT, 42, w);
+            let mut codes: Vec<u128> = sample.iter().map(|v| hasher.hash(v)).collect();
+            codes.sort_unstable();
+            codes.dedup();
+            codes.len()
+        };
+
+        // far wider than the data: everything shares one cell
+        assert_eq!(distinct_codes(1e6), 1);
+        // far narrower than the gaps between vectors: every vector gets its own code,
+        // except the few whose projections are all negative and clamp to 0
+        let scattered = distinct_codes(1e-3);
+        assert!(scattered > 90, "{scattered}");
+        let estimated = distinct_codes(PStableHashingBlock::estimate_w(&sample, 8));
+        assert!(1 < estimated && estimated < scattered, "{estimated}");
+    }
+
     #[test]
     fn test_hash_deterministic() {
         // Same input should always produce same hash
@@ -1,9 +1,34 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, VecDeque};
 use std::ops::Bound;
 
 use crate::numerics::AlignedBlock;
 use crate::search::NodeId;
-use crate::search::hash_start::pstable_hasher::PStableHashingBlock;
+use crate::search::hash_start::{
+    hyperplane_hasher::SimilarityHasher, pstable_hasher::PStableHashingBlock,
+};
+
+/// A locality-sensitive hash producing u128 signatures that a [`ZOrderIndex`] can order.
+///
+/// Implementations must place their most informative bits first: the index treats keys
+/// sharing a longer bit-prefix as closer.
+pub trait ZOrderHasher {
+    /// Hashes `vector` to its u128 signature.
+    fn hash_u128(&self, vector: &[AlignedBlock]) -> u128;
+}
+
+impl ZOrderHasher for PStableHashingBlock {
+    /// The Z-order code of the p-stable cells, see [`PStableHashingBlock::hash`].
+    fn hash_u128(&self, vector: &[AlignedBlock]) -> u128 {
+        self.hash(vector)
+    }
+}
+
+impl ZOrderHasher for SimilarityHasher {
+    /// The hyperplane signs, first hyperplane first, see [`SimilarityHasher::hash_u128`].
+    fn hash_u128(&self, vector: &[AlignedBlock]) -> u128 {
+        SimilarityHasher::hash_u128(self, vector)
+    }
+}
 
 /// An ordered index of Z-order (Morton-coded) u128 hash signatures mapping to node IDs.
 ///
@@ -18,30 +43,167 @@ use crate::search::hash_start::pstable_hasher::PStableHashingBlock;
 /// the target's successor in sorted order visits keys in roughly LLCP-descending order
 /// because a longer shared prefix implies a smaller absolute difference and vice versa —
 /// not perfectly, but it is the standard approximation used in LSHAPG-style indices.
-pub struct ZOrderIndex {
-    tree: BTreeMap<u128, Vec<NodeId>>,
-    hasher: PStableHashingBlock,
+///
+/// # Hashers
+/// Signatures come from a [`ZOrderHasher`]: p-stable Euclidean LSH by default, or random
+/// hyperplanes (cosine LSH) through [`ZOrderIndex::new_hyperplane`], whose signatures
+/// share a prefix of length `l` when the vectors agree on the first `l` hyperplanes.
+///
+/// # Capacity
+/// Each entry is stamped with its insertion age. An index built with
+/// [`with_signature_capacity`](ZOrderIndex::with_signature_capacity) keeps at most that
+/// many nodes per signature, dropping the oldest of a signature first, and
+/// [`evict_oldest`](ZOrderIndex::evict_oldest) removes the oldest entry of the whole index.
+pub struct ZOrderIndex<H: ZOrderHasher = PStableHashingBlock> {
+    /// Nodes of each signature with their insertion age, oldest first
+    tree: BTreeMap<u128, VecDeque<(u64, NodeId)>>,
+    /// Signature of every entry, keyed by insertion age
+    ages: BTreeMap<u64, u128>,
+    next_age: u64,
+    signature_capacity: usize,
+    hasher: H,
 }
 
 pub const LSH_APG_REDUNDANCY: usize = 1;
 
 impl ZOrderIndex {
     pub fn new(num_hash: usize, stored_vectors_dim: usize, seed: u64, w: f32) -> Self {
+        Self::with_hasher(PStableHashingBlock::new_seeded(
+            num_hash,
+            stored_vectors_dim,
+            seed,
+            w,
+        ))
+    }
+
+    /// Return the width of the p-stable quantization cells.
+    pub fn w(&self) -> f32 {
+        self.hasher.w()
+    }
+
+    /// Return the LLCP (number of shared leading bits) between two signatures.
+    pub fn llcp(a: u128, b: u128) -> u32 {
+        (a ^ b).leading_zeros()
+    }
+}
+
+impl ZOrderIndex<SimilarityHasher> {
+    /// Create an empty index over the signs of `num_hash` random hyperplanes.
+    ///
+    /// # Panics
+    /// Panics on insertion or query if `num_hash` exceeds 128.
+    pub fn new_hyperplane(num_hash: usize, stored_vectors_dim: usize, seed: u64) -> Self {
+        Self::with_hasher(SimilarityHasher::new_seeded(
+            num_hash,
+            stored_vectors_dim,
+            seed,
+        ))
+    }
+}
+
+impl<H: ZOrderHasher> ZOrderIndex<H> {
+    /// Create an empty index keyed by the signatures of `hasher`.
+    pub fn with_hasher(hasher: H) -> Self {
         Self {
             tree: BTreeMap::new(),
-            hasher: PStableHashingBlock::new_seeded(num_hash, stored_vectors_dim, seed, w),
+            ages: BTreeMap::new(),
+            next_age: 0,
+            signature_capacity: usize::MAX,
+            hasher,
         }
     }
 
+    /// Keep at most `capacity` nodes per signature, dropping the oldest one of a signature
+    /// when an insertion exceeds it. Unbounded by default.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is 0
+    pub fn with_signature_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "a signature must hold at least one node");
+        self.signature_capacity = capacity;
+        self
+    }
+
     /// Insert `node` under `signature`. Multiple nodes may share the same signature.
     pub fn insert(&mut self, key: &[AlignedBlock], node: NodeId) {
-        let target_signature = self.hasher.hash(key);
-        self.tree.entry(target_signature).or_default().push(node);
+        let target_signature = self.hasher.hash_u128(key);
+        self.push(target_signature, node);
     }
 
-    /// Return the LLCP (number of shared leading bits) between two signatures.
-    pub fn llcp(a: u128, b: u128) -> u32 {
-        (a ^ b).leading_zeros()
+    /// Append `node` as the newest entry of `signature`, enforcing the signature capacity.
+    fn push(&mut self, signature: u128, node: NodeId) {
+        let age = self.next_age;
+        self.next_age += 1;
+        self.ages.insert(age, signature);
+        let nodes = self.tree.entry(signature).or_default();
+        nodes.push_back((age, node));
+        if nodes.len() > self.signature_capacity
+            && let Some((oldest, _)) = nodes.pop_front()
+        {
+            self.ages.remove(&oldest);
+        }
+    }
+
+    /// Insert `node` under the signature of `key`, unless that signature already holds it.
+    ///
+    /// Returns whether `node` was inserted.
+    pub fn insert_unique(&mut self, key: &[AlignedBlock], node: NodeId) -> bool {
+        self.insert_unique_by_signature(self.hasher.hash_u128(key), node)
+    }
+
+    /// Insert `node` under `signature`, unless that signature already holds it.
+    ///
+    /// Returns whether `node` was inserted.
+    pub fn insert_unique_by_signature(&mut self, signature: u128, node: NodeId) -> bool {
+        if self
+            .tree
+            .get(&signature)
+            .is_some_and(|nodes| nodes.iter().any(|&(_, n)| n == node))
+        {
+            return false;
+        }
+        self.push(signature, node);
+        true
+    }
+
+    /// Remove the entry inserted first among those still stored.
+    ///
+    /// Returns the removed node, or `None` if the index is empty.
+    pub fn evict_oldest(&mut self) -> Option<NodeId> {
+        let (age, signature) = self.ages.pop_first()?;
+        let nodes = self.tree.get_mut(&signature)?;
+        // entries of a signature are stored in age order, so the oldest one is in front
+        let (front_age, node) = nodes.pop_front()?;
+        debug_assert_eq!(front_age, age);
+        if nodes.is_empty() {
+            self.tree.remove(&signature);
+        }
+        Some(node)
+    }
+
+    /// Return the number of stored nodes, counting each signature a node is stored under.
+    pub fn len(&self) -> usize {
+        self.ages.len()
+    }
+
+    /// Return whether the index stores no node.
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Return every stored node with the signature it is stored under, oldest first.
+    pub fn entries(&self) -> impl Iterator<Item = (u128, NodeId)> + '_ {
+        self.ages.iter().map(|(age, &signature)| {
+            let nodes = &self.tree[&signature];
+            let at = nodes.partition_point(|&(a, _)| a < *age);
+            (signature, nodes[at].1)
+        })
+    }
+
+    /// Remove every stored node.
+    pub fn clear(&mut self) {
+        self.tree.clear();
+        self.ages.clear();
     }
 
     /// Return up to `k` [`NodeId`]s whose signatures have the greatest LLCP with `target_signature`.
@@ -76,10 +238,10 @@ impl ZOrderIndex {
 
             let right_llcp = right
                 .peek()
-                .map(|&(&sig, _)| Self::llcp(target_signature, sig));
+                .map(|&(&sig, _)| ZOrderIndex::llcp(target_signature, sig));
             let left_llcp = left
                 .peek()
-                .map(|&(&sig, _)| Self::llcp(target_signature, sig));
+                .map(|&(&sig, _)| ZOrderIndex::llcp(target_signature, sig));
 
             // Pick whichever side has the longer common prefix; prefer right on tie.
             let take_right = match (right_llcp, left_llcp) {
@@ -89,13 +251,13 @@ impl ZOrderIndex {
                 (Some(r), Some(l)) => r >= l,
             };
 
-            let (_, nodes): (&u128, &Vec<NodeId>) = if take_right {
+            let (_, nodes): (&u128, &VecDeque<(u64, NodeId)>) = if take_right {
                 right.next().unwrap()
             } else {
                 left.next().unwrap()
             };
 
-            for &node in nodes {
+            for &(_, node) in nodes {
                 result.push(node);
                 if result.len() >= k {
                     break;
@@ -109,7 +271,7 @@ impl ZOrderIndex {
     ///
     /// Hashes `target_vec` and delegates to [`Self::query_k_closest_by_signature`].
     pub fn query_k_closest(&self, target_vec: &[AlignedBlock], k: usize) -> Vec<NodeId> {
-        let target_signature = self.hasher.hash(target_vec);
+        let target_signature = self.hasher.hash_u128(target_vec);
         self.query_k_closest_by_signature(target_signature, k)
     }
 }
@@ -223,6 +385,51 @@ mod tests {
         assert_eq!(result.len(), 2);
     }
 
+    #[test]
+    fn test_hyperplane_index_returns_same_direction_first() {
+        let mut idx = ZOrderIndex::new_hyperplane(64, 16, 42);
+        let axis = |lane: usize, scale: f32| {
+            let mut data = [0.0; 16];
+            data[lane] = scale;
+            vec![AlignedBlock::new(data)]
+        };
+        for lane in 0..16 {
+            idx.insert(&axis(lane, 1.0), node(lane));
+        }
+        // hyperplane signs ignore the norm: a longer vector along lane 5 matches node 5
+        assert_eq!(idx.query_k_closest(&axis(5, 30.0), 1), vec![node(5)]);
+        assert_eq!(idx.query_k_closest(&axis(11, 0.1), 1), vec![node(11)]);
+    }
+
+    #[test]
+    fn test_signature_capacity_drops_oldest_of_the_signature() {
+        let mut idx = new_for_test().with_signature_capacity(2);
+        let (v, w) = (vec_of(1.0), vec_of(-50.0));
+        idx.insert(&v, node(1));
+        idx.insert(&w, node(9));
+        idx.insert(&v, node(2));
+        idx.insert(&v, node(3));
+        assert_eq!(idx.len(), 3);
+        let result = idx.query_k_closest(&v, 2);
+        assert!(result.contains(&node(2)) && result.contains(&node(3)));
+        assert!(!idx.entries().any(|(_, n)| n == node(1)));
+    }
+
+    #[test]
+    fn test_evict_oldest_follows_insertion_order() {
+        let mut idx = new_for_test();
+        for (i, value) in [3.0, -3.0, 3.0, 0.5].into_iter().enumerate() {
+            idx.insert(&vec_of(value), node(i));
+        }
+        let order: Vec<NodeId> = idx.entries().map(|(_, n)| n).collect();
+        assert_eq!(order, vec![node(0), node(1), node(2), node(3)]);
+        assert_eq!(idx.evict_oldest(), Some(node(0)));
+        assert_eq!(idx.evict_oldest(), Some(node(1)));
+        assert_eq!(idx.len(), 2);
+        idx.clear();
+        assert_eq!(idx.evict_oldest(), None);
+    }
+
     #[test]
     pub fn test_1000_index() {
         let mut idx = ZOrderIndex::new(10, 16, 42, 1.0);
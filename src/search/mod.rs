@@ -1,9 +1,29 @@
 pub mod hash_start;
 
 mod adjacency_graph;
+mod concurrent_graph;
+mod connectivity;
+mod graph_builder;
+mod graph_stats;
 mod node;
+mod search_error;
+mod search_outcome;
+mod search_params;
+mod search_pool;
 mod search_strategy;
+mod synthetic_queries;
+mod upper_levels;
 
 pub use adjacency_graph::*;
+pub use concurrent_graph::*;
+pub use connectivity::*;
+pub use graph_builder::*;
+pub use graph_stats::*;
 pub use node::*;
+pub use search_error::*;
+pub use search_outcome::*;
+pub use search_params::*;
+pub use search_pool::*;
 pub use search_strategy::*;
+pub use synthetic_queries::*;
+pub use upper_levels::*;
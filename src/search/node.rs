@@ -1,6 +1,14 @@
 use std::fmt::Debug;
 
-use crate::{numerics::AlignedBlock, sets::fixed::FlatFixedSet};
+use rand::{SeedableRng, rngs::StdRng, seq::index::sample};
+
+use crate::{
+    numerics::{AlignedBlock, VectorLike},
+    sets::fixed::FlatFixedSet,
+};
+
+/// Number of nodes above which [`compute_medoid`] works on random samples.
+pub const MEDOID_SAMPLE_SIZE: usize = 1024;
 
 /// A type-safe wrapper for node indices in the proximity graph.
 ///
@@ -21,14 +29,102 @@ pub struct NodeId {
 /// A node in the proximity graph, containing its vector data and neighbor connections.
 ///
 /// Each node stores both its connectivity information (neighbors) and the actual
-/// vector embedding (payload) as SIMD-aligned blocks for efficient distance computation.
-pub struct Node {
-    /// The immutable set of neighbor node indices.
+/// vector embedding (payload). By default the payload is a full-precision vector of
+/// SIMD-aligned blocks; any [`Payload`](crate::numerics::Payload) can be used instead.
+pub struct Node<P = Box<[AlignedBlock]>> {
+    /// The set of neighbor node indices, only grown by runtime insertions.
     pub neighbors: FlatFixedSet,
 
-    /// The vector embedding for this node, stored as SIMD-aligned blocks of f32 values
-    /// for efficient parallel distance computations.
-    pub payload: Box<[AlignedBlock]>,
+    /// The vector embedding for this node, by default stored as SIMD-aligned blocks of
+    /// f32 values for efficient parallel distance computations.
+    pub payload: P,
+}
+
+/// Finds the node minimizing the total L2 distance to all other nodes.
+///
+/// A medoid is the most central node of the graph, which makes it a good default starting
+/// node: every query starts roughly as close to its answer as any other query. Graphs of
+/// at most [`MEDOID_SAMPLE_SIZE`] nodes are searched exhaustively, in quadratic time. On
+/// larger graphs, `MEDOID_SAMPLE_SIZE` candidates are scored by their total distance to
+/// `MEDOID_SAMPLE_SIZE` other random nodes, so the result is a node close to the center
+/// rather than the exact medoid. The samples are drawn with a fixed seed, so the result is
+/// deterministic.
+///
+/// # Arguments
+/// * `nodes` - The nodes of the graph, where `nodes[i]` is node `i`
+///
+/// # Returns
+/// The id of the (approximate) medoid, ties going to the smallest index
+///
+/// # Panics
+/// Panics if `nodes` is empty
+pub fn compute_medoid(nodes: &[Node]) -> NodeId {
+    assert!(!nodes.is_empty(), "cannot compute the medoid of no nodes");
+    let (candidates, references): (Vec<usize>, Vec<usize>) = if nodes.len() <= MEDOID_SAMPLE_SIZE {
+        ((0..nodes.len()).collect(), (0..nodes.len()).collect())
+    } else {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut candidates = sample(&mut rng, nodes.len(), MEDOID_SAMPLE_SIZE).into_vec();
+        let references = sample(&mut rng, nodes.len(), MEDOID_SAMPLE_SIZE).into_vec();
+        candidates.sort_unstable();
+        (candidates, references)
+    };
+
+    let total_distance = |candidate: usize| -> f64 {
+        references
+            .iter()
+            .map(|&other| nodes[candidate].payload.l2(&nodes[other].payload) as f64)
+            .sum()
+    };
+    let medoid = candidates
+        .into_iter()
+        .map(|candidate| (total_distance(candidate), candidate))
+        .min_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)))
+        .map(|(_, candidate)| candidate)
+        .expect("there is at least one candidate");
+    NodeId { internal: medoid }
+}
+
+/// Greedily picks up to `n` well-spread nodes by farthest-point sampling.
+///
+/// Starting from `first`, each step adds the node whose distance to the closest node
+/// picked so far is the largest, so every pick lands in the region worst covered by the
+/// previous ones. This takes `n` passes over `nodes`, one distance computation per node
+/// and pass.
+///
+/// # Arguments
+/// * `nodes` - The nodes of the graph, where `nodes[i]` is node `i`
+/// * `first` - The first node picked, usually the graph's starting node
+/// * `n` - Number of nodes to pick, including `first`
+///
+/// # Returns
+/// The picked nodes in selection order, starting with `first`. Fewer than `n` nodes are
+/// returned if the graph holds fewer than `n` distinct payloads, and none if `n == 0`.
+///
+/// # Panics
+/// Panics if `first` is not a node of `nodes`
+pub fn farthest_point_sample(nodes: &[Node], first: NodeId, n: usize) -> Vec<NodeId> {
+    assert!(first.internal < nodes.len(), "first node is out of bounds");
+    let mut picked = Vec::with_capacity(n);
+    let mut closest = vec![f32::INFINITY; nodes.len()];
+    let mut next = first.internal;
+    while picked.len() < n {
+        picked.push(NodeId { internal: next });
+        let newest = &nodes[next].payload;
+        for (distance, node) in closest.iter_mut().zip(nodes) {
+            *distance = distance.min(node.payload.l2(newest));
+        }
+        let (farthest, &distance) = closest
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1).then(b.0.cmp(&a.0)))
+            .expect("there is at least one node");
+        if distance <= 0.0 {
+            break;
+        }
+        next = farthest;
+    }
+    picked
 }
 
 impl Debug for NodeId {
@@ -37,7 +133,7 @@ impl Debug for NodeId {
     }
 }
 
-impl Debug for Node {
+impl<P: Debug> Debug for Node<P> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Node")
             .field("neighbors", &self.neighbors)
@@ -52,6 +148,54 @@ mod tests {
     use crate::numerics::SIMD_LANECOUNT;
     use crate::sets::fixed::FlatFixedSet;
 
+    fn node_at(x: f32, y: f32) -> Node {
+        let mut data = [0.0; SIMD_LANECOUNT];
+        data[0] = x;
+        data[1] = y;
+        Node {
+            payload: vec![AlignedBlock::new(data)].into_boxed_slice(),
+            neighbors: FlatFixedSet::new(vec![]),
+        }
+    }
+
+    #[test]
+    fn test_medoid_is_center_of_cluster() {
+        // a 3x3 grid listed row by row, plus a far outlier pulling the mean away
+        let mut nodes: Vec<Node> = (0..9)
+            .map(|i| node_at((i % 3) as f32, (i / 3) as f32))
+            .collect();
+        nodes.push(node_at(40.0, 40.0));
+        assert_eq!(compute_medoid(&nodes), NodeId { internal: 4 });
+        assert_eq!(compute_medoid(&nodes[..1]), NodeId { internal: 0 });
+    }
+
+    #[test]
+    fn test_sampled_medoid_is_near_center() {
+        let n = 3 * MEDOID_SAMPLE_SIZE;
+        let nodes: Vec<Node> = (0..n).map(|i| node_at(i as f32, 0.0)).collect();
+        let medoid = compute_medoid(&nodes).internal;
+        assert!(medoid.abs_diff(n / 2) < n / 10, "{medoid}");
+    }
+
+    #[test]
+    fn test_farthest_point_sample_spreads_picks() {
+        // three clusters along a line, the middle one holding the first pick
+        let nodes: Vec<Node> = [10.0, 10.5, 0.0, 0.5, 20.0, 20.5, 20.0]
+            .into_iter()
+            .map(|x| node_at(x, 0.0))
+            .collect();
+        let picks = farthest_point_sample(&nodes, NodeId { internal: 0 }, 3);
+        let picks: Vec<usize> = picks.iter().map(|id| id.internal).collect();
+        assert_eq!(picks, vec![0, 5, 2]);
+
+        // node 6 duplicates node 4, so only six nodes can be picked
+        assert_eq!(
+            farthest_point_sample(&nodes, NodeId { internal: 0 }, 10).len(),
+            6
+        );
+        assert!(farthest_point_sample(&nodes, NodeId { internal: 0 }, 0).is_empty());
+    }
+
     #[test]
     fn test_node_debug_format_basic() {
         let node = Node {
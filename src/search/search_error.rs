@@ -0,0 +1,53 @@
+use std::fmt::{self, Display};
+
+use crate::search::NodeId;
+
+/// Why a checked search refused to run on a graph.
+///
+/// Returned by [`AdjacencyGraph::beam_search_checked`] instead of the index-out-of-bounds
+/// panic an unchecked search raises when it meets a corrupt graph.
+///
+/// [`AdjacencyGraph::beam_search_checked`]: crate::search::AdjacencyGraph::beam_search_checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchError {
+    /// A neighbor list holds an index past the last node of the graph.
+    NeighborOutOfBounds {
+        /// The node whose neighbor list is corrupt
+        node: NodeId,
+        /// The offending neighbor index
+        neighbor: usize,
+        /// Number of nodes in the graph
+        num_nodes: usize,
+    },
+
+    /// The starting node or one of the entry points is not a node of the graph.
+    EntryOutOfBounds {
+        /// The offending entry node
+        node: NodeId,
+        /// Number of nodes in the graph
+        num_nodes: usize,
+    },
+}
+
+impl Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchError::NeighborOutOfBounds {
+                node,
+                neighbor,
+                num_nodes,
+            } => write!(
+                f,
+                "node {} links to neighbor {neighbor}, but the graph holds {num_nodes} nodes",
+                node.internal
+            ),
+            SearchError::EntryOutOfBounds { node, num_nodes } => write!(
+                f,
+                "entry node {} is not one of the {num_nodes} nodes of the graph",
+                node.internal
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SearchError {}
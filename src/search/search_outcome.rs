@@ -0,0 +1,185 @@
+use crate::{
+    search::NodeId,
+    sets::candidates::{CandidateEntry, Distance},
+};
+
+/// Why a beam search stopped expanding nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    /// Every candidate left in the beam had been expanded. This is how an unconstrained
+    /// search always ends, and the results are as good as the beam width allows.
+    Exhausted,
+
+    /// The search stopped after expanding its maximum number of nodes while unexpanded
+    /// candidates remained, so better neighbors may have been missed.
+    IterationCap,
+
+    /// The search stopped after computing its budget of distances while unexpanded
+    /// candidates remained, so better neighbors may have been missed.
+    DistanceBudget,
+
+    /// The search stopped once its wall-clock deadline had passed while unexpanded
+    /// candidates remained, so better neighbors may have been missed.
+    Deadline,
+}
+
+/// The results of a beam search together with the reason it stopped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchOutcome {
+    /// The nearest candidates found, sorted by ascending distance
+    pub results: Vec<CandidateEntry>,
+
+    /// Why the search stopped
+    pub termination: Termination,
+}
+
+impl SearchOutcome {
+    /// Returns whether the search ran until its frontier was exhausted.
+    ///
+    /// A converged search returned the best results it could find; a search that was cut
+    /// short by a cap returned approximate results that a longer search might improve.
+    pub fn converged(&self) -> bool {
+        self.termination == Termination::Exhausted
+    }
+}
+
+/// Search results paired with a confidence score for each of them.
+///
+/// The confidence of a result measures how clearly it stands out from the candidate ranked
+/// right after it: `1 - d / d_next`, where `d` and `d_next` are the L2 distances of the
+/// result and of the next candidate of the beam. It lies in `[0, 1]`. A score close to 1
+/// means the next candidate is much farther away, so the ranking is unambiguous; a score
+/// close to 0 means several candidates are nearly equidistant, so the ranking could easily
+/// flip. The last candidate of a beam that holds no other node scores 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoredResults {
+    /// The nearest candidates found, sorted by ascending distance
+    pub results: Vec<CandidateEntry>,
+
+    /// Confidence of each result, in the same order as `results`
+    pub confidence: Vec<f32>,
+}
+
+impl ScoredResults {
+    /// Scores the first `k` entries of a sorted beam against their successors.
+    ///
+    /// # Arguments
+    /// * `beam` - Candidates sorted by ascending squared distance
+    /// * `k` - Number of results to keep
+    pub(crate) fn from_beam(mut beam: Vec<CandidateEntry>, k: usize) -> Self {
+        let distances: Vec<f32> = beam.iter().map(|c| c.distance.0.sqrt()).collect();
+        let confidence = (0..k.min(beam.len()))
+            .map(|i| match distances.get(i + 1) {
+                Some(&next) if next > 0.0 => 1.0 - distances[i] / next,
+                Some(_) => 0.0,
+                None => 1.0,
+            })
+            .collect();
+        beam.truncate(k);
+        ScoredResults {
+            results: beam,
+            confidence,
+        }
+    }
+}
+
+/// Search results indexed by node, answering "is node X a result, at which rank" in
+/// O(log k).
+///
+/// The results keep their ascending distance order, ranks starting at 0 for the nearest,
+/// and a copy of their ids sorted by id serves the lookups. Meant for re-ranking stages
+/// that look up many nodes in the same result list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankedResults {
+    /// The nearest candidates found, sorted by ascending distance
+    results: Vec<CandidateEntry>,
+    /// Id and rank of every result, sorted by id
+    by_node: Box<[(NodeId, usize)]>,
+}
+
+impl RankedResults {
+    /// Indexes results by node.
+    ///
+    /// # Arguments
+    /// * `results` - Candidates sorted by ascending distance, each node at most once
+    pub fn new(results: Vec<CandidateEntry>) -> Self {
+        let mut by_node: Box<[(NodeId, usize)]> = results
+            .iter()
+            .enumerate()
+            .map(|(rank, entry)| (entry.index, rank))
+            .collect();
+        by_node.sort_unstable();
+        RankedResults { results, by_node }
+    }
+
+    /// Returns the rank of `node` among the results, 0 being the nearest.
+    pub fn rank_of(&self, node: NodeId) -> Option<usize> {
+        self.by_node
+            .binary_search_by_key(&node, |&(id, _)| id)
+            .ok()
+            .map(|i| self.by_node[i].1)
+    }
+
+    /// Returns the rank of `node` and its squared distance to the query, if it is a result.
+    pub fn get(&self, node: NodeId) -> Option<(usize, Distance)> {
+        self.rank_of(node)
+            .map(|rank| (rank, self.results[rank].squared_l2_distance()))
+    }
+
+    /// Returns whether `node` is among the results.
+    pub fn contains(&self, node: NodeId) -> bool {
+        self.rank_of(node).is_some()
+    }
+
+    /// Returns the results, sorted by ascending distance.
+    pub fn results(&self) -> &[CandidateEntry] {
+        &self.results
+    }
+
+    /// Returns the number of results.
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Returns whether there are no results.
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Consumes the index, returning the results sorted by ascending distance.
+    pub fn into_results(self) -> Vec<CandidateEntry> {
+        self.results
+    }
+}
+
+/// Approximate search results checked against the exact nearest neighbors.
+///
+/// Built by [`beam_search_verified`](crate::search::AdjacencyGraph::beam_search_verified)
+/// for tests and offline diagnosis of recall regressions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifiedResults {
+    /// The nearest candidates found by the approximate search, sorted by ascending distance
+    pub results: Vec<CandidateEntry>,
+
+    /// The exact nearest neighbors, sorted by ascending distance
+    pub exact: Vec<CandidateEntry>,
+
+    /// Fraction of the exact neighbors found by the approximate search, in `[0, 1]`
+    pub recall: f64,
+}
+
+impl VerifiedResults {
+    /// Returns whether the approximate search found every exact neighbor.
+    pub fn is_exact(&self) -> bool {
+        self.recall == 1.0
+    }
+
+    /// Returns the exact neighbors the approximate search missed, closest first.
+    pub fn missed(&self) -> Vec<NodeId> {
+        self.exact
+            .iter()
+            .map(|entry| entry.index)
+            .filter(|node| !self.results.iter().any(|found| found.index == *node))
+            .collect()
+    }
+}
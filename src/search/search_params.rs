@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+/// Per-search settings for [`AdjacencyGraph::beam_search_with`].
+///
+/// Gathers the knobs the `beam_search_*` variants take one at a time, so a search can
+/// combine them and new ones can be added without changing any signature. Settings are
+/// chosen with the `with_*` methods, starting from the defaults of [`SearchParams::new`].
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use catapult::search::SearchParams;
+///
+/// let params = SearchParams::new()
+///     .with_k(5)
+///     .with_beam_width(32)
+///     .with_timeout(Duration::from_millis(2));
+/// assert_eq!(params.k(), 5);
+/// assert_eq!(params.max_distance_computations(), None);
+/// ```
+///
+/// [`AdjacencyGraph::beam_search_with`]: crate::search::AdjacencyGraph::beam_search_with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchParams {
+    k: usize,
+    beam_width: usize,
+    max_iterations: Option<usize>,
+    max_distance_computations: Option<usize>,
+    timeout: Option<Duration>,
+    learn_catapults: bool,
+}
+
+impl SearchParams {
+    /// Creates the default settings: the 10 nearest neighbors with a beam of width 64, no
+    /// iteration cap, distance budget or deadline, and catapults learned as the graph's
+    /// strategy allows.
+    pub fn new() -> Self {
+        SearchParams {
+            k: 10,
+            beam_width: 64,
+            max_iterations: None,
+            max_distance_computations: None,
+            timeout: None,
+            learn_catapults: true,
+        }
+    }
+
+    /// Sets the number of nearest neighbors to return.
+    ///
+    /// # Arguments
+    /// * `k` - Number of results, must be positive
+    ///
+    /// # Returns
+    /// The updated settings
+    ///
+    /// # Panics
+    /// Panics if `k == 0`
+    pub fn with_k(mut self, k: usize) -> Self {
+        assert!(k > 0, "k must be positive");
+        self.k = k;
+        self
+    }
+
+    /// Sets the maximum beam size during search.
+    ///
+    /// # Arguments
+    /// * `beam_width` - Beam width, which must be at least `k` when searching
+    ///
+    /// # Returns
+    /// The updated settings
+    pub fn with_beam_width(mut self, beam_width: usize) -> Self {
+        self.beam_width = beam_width;
+        self
+    }
+
+    /// Caps the number of nodes expanded, see
+    /// [`beam_search_with_status`](crate::search::AdjacencyGraph::beam_search_with_status).
+    ///
+    /// # Arguments
+    /// * `max_iterations` - Maximum number of nodes to expand
+    ///
+    /// # Returns
+    /// The updated settings
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = Some(max_iterations);
+        self
+    }
+
+    /// Caps the number of distances computed, see
+    /// [`beam_search_with_budget`](crate::search::AdjacencyGraph::beam_search_with_budget).
+    ///
+    /// # Arguments
+    /// * `max_distance_computations` - Maximum number of distances to compute
+    ///
+    /// # Returns
+    /// The updated settings
+    pub fn with_distance_budget(mut self, max_distance_computations: usize) -> Self {
+        self.max_distance_computations = Some(max_distance_computations);
+        self
+    }
+
+    /// Bounds the wall-clock time of each search, see
+    /// [`beam_search_with_deadline`](crate::search::AdjacencyGraph::beam_search_with_deadline).
+    ///
+    /// # Arguments
+    /// * `timeout` - Time a search may take, counted from its start
+    ///
+    /// # Returns
+    /// The updated settings
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets whether searches write their best result back as a catapult.
+    ///
+    /// Disabling learning leaves the catapult buckets untouched, as with
+    /// [`SearchStrategy::CatapultReadOnly`], for this search only. Enabling it has no
+    /// effect on a graph whose strategy does not learn catapults.
+    ///
+    /// # Arguments
+    /// * `learn_catapults` - Whether to learn catapults
+    ///
+    /// # Returns
+    /// The updated settings
+    ///
+    /// [`SearchStrategy::CatapultReadOnly`]: crate::search::SearchStrategy::CatapultReadOnly
+    pub fn with_catapult_learning(mut self, learn_catapults: bool) -> Self {
+        self.learn_catapults = learn_catapults;
+        self
+    }
+
+    /// Returns the number of nearest neighbors to return.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Returns the maximum beam size during search.
+    pub fn beam_width(&self) -> usize {
+        self.beam_width
+    }
+
+    /// Returns the maximum number of nodes expanded, if capped.
+    pub fn max_iterations(&self) -> Option<usize> {
+        self.max_iterations
+    }
+
+    /// Returns the maximum number of distances computed, if capped.
+    pub fn max_distance_computations(&self) -> Option<usize> {
+        self.max_distance_computations
+    }
+
+    /// Returns the time a search may take, if bounded.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Returns whether searches learn catapults.
+    pub fn learns_catapults(&self) -> bool {
+        self.learn_catapults
+    }
+}
+
+impl Default for SearchParams {
+    fn default() -> Self {
+        Self::new()
+    }
+}
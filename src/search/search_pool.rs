@@ -0,0 +1,258 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Receiver, Sender},
+    },
+    thread::{self, JoinHandle},
+};
+
+use crate::{
+    numerics::{AlignedBlock, Payload},
+    search::AdjacencyGraph,
+    sets::{candidates::CandidateEntry, catapults::CatapultEvictionPolicy},
+    statistics::Stats,
+};
+
+/// A search waiting for a worker of a [`SearchPool`].
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A fixed set of worker threads running searches on any number of graphs.
+///
+/// Serving several independent graphs, e.g. one per tenant, with one thread pool each
+/// leaves most threads idle while the others are busy. A single pool instead takes search
+/// jobs for any graph, each naming its graph through an `Arc`, and runs them on the first
+/// free worker, in submission order. Searches only need `&self`, so jobs on the same graph
+/// run concurrently as well, learning catapults as [`AdjacencyGraph::beam_search`] does.
+///
+/// Dropping the pool lets the workers finish the queued jobs, then joins them.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+///
+/// use catapult::{
+///     numerics::{AlignedBlock, SIMD_LANECOUNT},
+///     search::{
+///         AdjacencyGraph, GraphBuilder, NodeId, SearchPool, SearchStrategy,
+///         hash_start::{EngineStarter, EngineStarterParams},
+///     },
+///     sets::catapults::LruSet,
+/// };
+///
+/// let points: Vec<Vec<AlignedBlock>> = (0..20)
+///     .map(|i| vec![AlignedBlock::new([i as f32; SIMD_LANECOUNT])])
+///     .collect();
+/// let params = EngineStarterParams::new(4, 8, SIMD_LANECOUNT, NodeId { internal: 0 }, 42, false);
+/// let graph = Arc::new(AdjacencyGraph::<LruSet>::new_flat(
+///     GraphBuilder::new(4).build(&points),
+///     EngineStarter::new(params),
+///     SearchStrategy::Vanilla,
+/// ));
+///
+/// let pool = SearchPool::new(2);
+/// let pending = pool.submit(Arc::clone(&graph), points[7].clone(), 1, 8);
+/// let (results, _stats) = pending.wait();
+/// assert_eq!(results[0].index, NodeId { internal: 7 });
+/// ```
+pub struct SearchPool {
+    /// Queue of jobs shared by the workers, `None` once the pool shuts down
+    jobs: Option<Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl SearchPool {
+    /// Starts a pool of `num_threads` workers.
+    ///
+    /// # Arguments
+    /// * `num_threads` - Number of searches that can run at the same time
+    ///
+    /// # Panics
+    /// Panics if `num_threads == 0`
+    pub fn new(num_threads: usize) -> Self {
+        assert!(num_threads > 0, "a search pool needs at least one thread");
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let queue = Arc::new(Mutex::new(queue));
+        let workers = (0..num_threads)
+            .map(|i| {
+                let queue = Arc::clone(&queue);
+                thread::Builder::new()
+                    .name(format!("search-pool-{i}"))
+                    .spawn(move || {
+                        loop {
+                            // the lock is released as soon as a job is taken
+                            let job = queue.lock().expect("search pool queue poisoned").recv();
+                            match job {
+                                // a panicking search only fails its own job
+                                Ok(job) => _ = panic::catch_unwind(AssertUnwindSafe(job)),
+                                Err(_) => break,
+                            }
+                        }
+                    })
+                    .expect("failed to spawn a search pool worker")
+            })
+            .collect();
+        SearchPool {
+            jobs: Some(jobs),
+            workers,
+        }
+    }
+
+    /// Returns the number of worker threads.
+    pub fn num_threads(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Queues a [`beam_search`](AdjacencyGraph::beam_search) of `query` on `graph`.
+    ///
+    /// # Arguments
+    /// * `graph` - Graph to search, shared with the caller
+    /// * `query` - Query vector as aligned blocks
+    /// * `k` - Number of nearest neighbors to return
+    /// * `beam_width` - Maximum beam size during search (must be ≥ k)
+    ///
+    /// # Returns
+    /// A handle to wait on for the results of the search
+    pub fn submit<EvictPolicy, P>(
+        &self,
+        graph: Arc<AdjacencyGraph<EvictPolicy, P>>,
+        query: Vec<AlignedBlock>,
+        k: usize,
+        beam_width: usize,
+    ) -> PendingSearch
+    where
+        EvictPolicy: CatapultEvictionPolicy + Send + Sync + 'static,
+        P: Payload + Send + Sync + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        let job: Job = Box::new(move || {
+            let mut stats = Stats::new();
+            let results = graph.beam_search(&query, k, beam_width, &mut stats);
+            // the caller may have dropped its handle, which is fine
+            _ = sender.send((results, stats));
+        });
+        self.jobs
+            .as_ref()
+            .expect("the pool only shuts down when dropped")
+            .send(job)
+            .expect("search pool workers stopped");
+        PendingSearch { receiver }
+    }
+}
+
+impl Drop for SearchPool {
+    fn drop(&mut self) {
+        // closing the queue stops each worker once the queue is drained
+        self.jobs = None;
+        for worker in self.workers.drain(..) {
+            _ = worker.join();
+        }
+    }
+}
+
+/// The eventual results of a search submitted to a [`SearchPool`].
+pub struct PendingSearch {
+    receiver: Receiver<(Vec<CandidateEntry>, Stats)>,
+}
+
+impl PendingSearch {
+    /// Blocks until the search has run.
+    ///
+    /// # Returns
+    /// The k nearest candidate entries, sorted by ascending distance, and the statistics
+    /// of this search alone
+    ///
+    /// # Panics
+    /// Panics if the search panicked
+    pub fn wait(self) -> (Vec<CandidateEntry>, Stats) {
+        self.receiver.recv().expect("the search panicked")
+    }
+
+    /// Returns the results if the search has already run, without blocking.
+    ///
+    /// # Returns
+    /// The results and statistics of the search, or the handle back if it has not run yet
+    ///
+    /// # Panics
+    /// Panics if the search panicked
+    pub fn try_wait(self) -> Result<(Vec<CandidateEntry>, Stats), Self> {
+        match self.receiver.try_recv() {
+            Ok(outcome) => Ok(outcome),
+            Err(mpsc::TryRecvError::Empty) => Err(self),
+            Err(mpsc::TryRecvError::Disconnected) => panic!("the search panicked"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        numerics::SIMD_LANECOUNT,
+        search::{
+            GraphBuilder, NodeId, SearchStrategy,
+            hash_start::{EngineStarter, EngineStarterParams},
+        },
+        sets::catapults::LruSet,
+    };
+
+    fn graph_of(points: &[Vec<AlignedBlock>]) -> Arc<AdjacencyGraph<LruSet>> {
+        let params =
+            EngineStarterParams::new(4, 8, SIMD_LANECOUNT, NodeId { internal: 0 }, 42, true);
+        Arc::new(AdjacencyGraph::new_flat(
+            GraphBuilder::new(6).build(points),
+            EngineStarter::new(params),
+            SearchStrategy::Catapult,
+        ))
+    }
+
+    fn line(n: usize, offset: f32) -> Vec<Vec<AlignedBlock>> {
+        (0..n)
+            .map(|i| vec![AlignedBlock::new([offset + i as f32; SIMD_LANECOUNT])])
+            .collect()
+    }
+
+    #[test]
+    fn one_pool_serves_two_graphs() {
+        // the same query has a different nearest node in each graph
+        let first = graph_of(&line(50, 0.0));
+        let second = graph_of(&line(30, 100.0));
+        let pool = SearchPool::new(3);
+        assert_eq!(pool.num_threads(), 3);
+
+        let pending: Vec<_> = (0..40)
+            .map(|i| {
+                let query = vec![AlignedBlock::new([i as f32 * 4.0; SIMD_LANECOUNT])];
+                let on_first = pool.submit(Arc::clone(&first), query.clone(), 2, 8);
+                let on_second = pool.submit(Arc::clone(&second), query.clone(), 2, 8);
+                (query, on_first, on_second)
+            })
+            .collect();
+
+        for (query, on_first, on_second) in pending {
+            let (results, stats) = on_first.wait();
+            assert_eq!(results, first.exact_search(&query, 2, &mut Stats::new()));
+            assert_eq!(stats.get_beam_calls(), 1);
+            let (results, _) = on_second.wait();
+            assert_eq!(results, second.exact_search(&query, 2, &mut Stats::new()));
+        }
+    }
+
+    #[test]
+    fn dropping_the_pool_finishes_queued_searches() {
+        let graph = graph_of(&line(20, 0.0));
+        let pool = SearchPool::new(1);
+        let query = vec![AlignedBlock::new([3.0; SIMD_LANECOUNT])];
+        let pending: Vec<_> = (0..10)
+            .map(|_| pool.submit(Arc::clone(&graph), query.clone(), 1, 4))
+            .collect();
+        drop(pool);
+        for search in pending {
+            let search = search
+                .try_wait()
+                .ok()
+                .expect("queued searches ran before the drop");
+            assert_eq!(search.0[0].index, NodeId { internal: 3 });
+        }
+    }
+}
@@ -3,6 +3,10 @@ use crate::search::hash_start::zorder_index::{LSH_APG_REDUNDANCY, ZOrderIndex};
 pub enum SearchStrategy {
     Vanilla,
     Catapult,
+    /// Starts searches from existing catapults but never learns nor refreshes any, so that
+    /// repeated runs over the same queries leave the buckets untouched, e.g. when
+    /// benchmarking a warmed-up graph.
+    CatapultReadOnly,
     LshApg([ZOrderIndex; LSH_APG_REDUNDANCY]),
 }
 
@@ -19,6 +23,8 @@ impl SearchStrategy {
             SearchStrategy::Vanilla
         } else if s == "catapult" && args.is_none() {
             SearchStrategy::Catapult
+        } else if s == "catapult-readonly" && args.is_none() {
+            SearchStrategy::CatapultReadOnly
         } else if s == "lshapg"
             && let Some(args) = args
         {
@@ -36,6 +42,19 @@ impl SearchStrategy {
             )
         }
     }
+
+    /// Returns whether searches start from the catapults of the query's bucket.
+    pub fn uses_catapults(&self) -> bool {
+        matches!(
+            self,
+            SearchStrategy::Catapult | SearchStrategy::CatapultReadOnly
+        )
+    }
+
+    /// Returns whether searches write their results back into the catapult buckets.
+    pub fn learns_catapults(&self) -> bool {
+        matches!(self, SearchStrategy::Catapult)
+    }
 }
 
 #[cfg(test)]
@@ -59,6 +78,13 @@ mod tests {
         ))
     }
 
+    #[test]
+    pub fn load_catapult_readonly() {
+        let strategy = SearchStrategy::from_string("catapult-readonly", None);
+        assert!(strategy.uses_catapults());
+        assert!(!strategy.learns_catapults());
+    }
+
     #[test]
     #[should_panic]
     pub fn load_panic() {
@@ -0,0 +1,119 @@
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use rand_distr::StandardNormal;
+
+use crate::{
+    numerics::{AlignedBlock, Payload},
+    search::{AdjacencyGraph, NodeId},
+    sets::catapults::CatapultEvictionPolicy,
+};
+
+/// Generates queries near the stored vectors of `graph`, for benchmarks without a query
+/// file.
+///
+/// Each query is the payload of a node drawn uniformly at random, with independent
+/// Gaussian noise of standard deviation `noise_std` added to each of its
+/// [`dim`](AdjacencyGraph::dim) logical entries; padding lanes stay zero. For small
+/// noise, the nearest neighbor of a query is most likely the node it was drawn from, which
+/// makes recall measurable without computing a ground truth. The same seed always yields
+/// the same queries.
+///
+/// Queries are built from the payloads as stored, so on a graph with a transform or
+/// rotation they live in the stored space rather than in the space queries are given in.
+///
+/// # Arguments
+/// * `graph` - Graph whose payloads the queries are drawn around
+/// * `count` - Number of queries to generate
+/// * `noise_std` - Standard deviation of the noise added to each entry, at least 0
+/// * `seed` - Random seed for the node choice and the noise
+///
+/// # Returns
+/// `count` queries as aligned blocks, of the graph's dimension
+///
+/// # Panics
+/// Panics if the graph is empty while `count > 0`, or if `noise_std` is negative or not
+/// finite
+pub fn generate_queries<EvictPolicy, P>(
+    graph: &AdjacencyGraph<EvictPolicy, P>,
+    count: usize,
+    noise_std: f32,
+    seed: u64,
+) -> Vec<Vec<AlignedBlock>>
+where
+    EvictPolicy: CatapultEvictionPolicy,
+    P: Payload,
+{
+    assert!(
+        noise_std.is_finite() && noise_std >= 0.0,
+        "noise_std must be a finite, non-negative number"
+    );
+    assert!(
+        count == 0 || graph.len() > 0,
+        "cannot draw queries from an empty graph"
+    );
+    let dim = graph.dim();
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| {
+            let source = NodeId {
+                internal: rng.random_range(0..graph.len()),
+            };
+            let mut query = graph.payload(source).to_blocks();
+            for value in query.iter_mut().flat_map(|b| b.data.iter_mut()).take(dim) {
+                *value += noise_std * rng.sample::<f32, _>(StandardNormal);
+            }
+            query
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        numerics::SIMD_LANECOUNT,
+        search::{
+            GraphBuilder, SearchStrategy,
+            hash_start::{EngineStarter, EngineStarterParams},
+        },
+        sets::catapults::LruSet,
+        statistics::Stats,
+    };
+
+    fn graph(dim: usize) -> AdjacencyGraph<LruSet> {
+        let mut rng = StdRng::seed_from_u64(3);
+        let points: Vec<Vec<AlignedBlock>> = (0..300)
+            .map(|_| AlignedBlock::allocate_padded((0..dim).map(|_| rng.random()).collect()))
+            .collect();
+        let params = EngineStarterParams::new(4, 8, dim, NodeId { internal: 0 }, 42, false);
+        AdjacencyGraph::new_flat(
+            GraphBuilder::new(8).build(&points),
+            EngineStarter::new(params),
+            SearchStrategy::Vanilla,
+        )
+    }
+
+    #[test]
+    fn queries_are_reproducible_and_keep_padding_zero() {
+        let graph = graph(20);
+        let queries = generate_queries(&graph, 50, 0.05, 9);
+        assert_eq!(queries, generate_queries(&graph, 50, 0.05, 9));
+        assert_ne!(queries, generate_queries(&graph, 50, 0.05, 10));
+        for query in &queries {
+            assert_eq!(query.len(), 20usize.div_ceil(SIMD_LANECOUNT));
+            assert!(
+                query[1].data[20 - SIMD_LANECOUNT..]
+                    .iter()
+                    .all(|&x| x == 0.0)
+            );
+        }
+    }
+
+    #[test]
+    fn noiseless_queries_are_stored_vectors() {
+        let graph = graph(16);
+        for query in generate_queries(&graph, 20, 0.0, 1) {
+            let found = graph.exact_search(&query, 1, &mut Stats::new());
+            assert_eq!(found[0].distance.0, 0.0);
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use crate::{
+    search::NodeId,
+    sets::fixed::{FlatFixedSet, HierarchicalFixedSet, NeighborSlice},
+};
+
+/// The levels of a hierarchical (HNSW) graph above level 0.
+///
+/// Level 0 is the flat adjacency of an [`AdjacencyGraph`](crate::search::AdjacencyGraph); this structure only holds the
+/// sparse levels above it, for the few nodes that live there. A graph with upper levels
+/// starts each search with the greedy descent of HNSW: from the entry point on the top
+/// level, it repeatedly moves to the closest neighbor until none is closer, then carries
+/// on one level below, and starts the beam search of level 0 from the node reached on
+/// level 1 instead of a fixed starting node. Catapults and entry points are used as
+/// usual.
+#[derive(Debug)]
+pub struct UpperLevels {
+    /// Nodes living above level 0, whose level-0 lists are left empty
+    links: HashMap<NodeId, HierarchicalFixedSet>,
+    /// The node every descent starts from
+    entry_point: NodeId,
+    /// Highest level of the graph
+    max_level: usize,
+}
+
+impl UpperLevels {
+    /// Splits the per-node lists of a hierarchical graph into level 0 and the levels above.
+    ///
+    /// The entry point is the lowest node living on the highest level. Canonical HNSW
+    /// graphs hold a single node there, which loaders should check before splitting.
+    ///
+    /// # Arguments
+    /// * `links` - Lists of every level of every node, indexed by node id
+    ///
+    /// # Returns
+    /// The level-0 neighbor list of every node, and the levels above, or `None` for them if
+    /// no node lives above level 0
+    pub fn split(links: Vec<HierarchicalFixedSet>) -> (Vec<FlatFixedSet>, Option<Self>) {
+        let max_level = links.iter().map(HierarchicalFixedSet::max_level).max();
+        let entry_point = links
+            .iter()
+            .position(|node| Some(node.max_level()) == max_level);
+
+        let mut level0 = Vec::with_capacity(links.len());
+        let mut upper = HashMap::new();
+        for (internal, node) in links.into_iter().enumerate() {
+            let mut levels = node.into_levels();
+            level0.push(std::mem::replace(&mut levels[0], FlatFixedSet::new(vec![])));
+            if levels.len() > 1 {
+                upper.insert(
+                    NodeId { internal },
+                    HierarchicalFixedSet::from_levels(levels),
+                );
+            }
+        }
+
+        let upper = match (max_level, entry_point) {
+            (Some(max_level), Some(entry)) if max_level > 0 => Some(UpperLevels {
+                links: upper,
+                entry_point: NodeId { internal: entry },
+                max_level,
+            }),
+            _ => None,
+        };
+        (level0, upper)
+    }
+
+    /// Returns the node every descent starts from.
+    pub fn entry_point(&self) -> NodeId {
+        self.entry_point
+    }
+
+    /// Returns the highest level of the graph.
+    pub fn max_level(&self) -> usize {
+        self.max_level
+    }
+
+    /// Returns the number of nodes living above level 0.
+    pub fn len(&self) -> usize {
+        self.links.len()
+    }
+
+    /// Returns whether no node lives above level 0, which never holds for a split graph.
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+
+    /// Returns the highest level `node` lives on, 0 for nodes absent from upper levels.
+    pub fn level_of(&self, node: NodeId) -> usize {
+        self.links
+            .get(&node)
+            .map_or(0, HierarchicalFixedSet::max_level)
+    }
+
+    /// Borrows the neighbors of `node` on `level`.
+    ///
+    /// # Arguments
+    /// * `node` - Node whose neighbors to read
+    /// * `level` - Level to read, at least 1
+    ///
+    /// # Returns
+    /// The neighbor list, empty if the node does not live on that level
+    ///
+    /// # Panics
+    /// Panics if `level` is 0, whose lists live in the graph's adjacency
+    pub fn to_level(&self, node: NodeId, level: usize) -> NeighborSlice<'_> {
+        assert!(level > 0, "level-0 neighbors live in the graph's adjacency");
+        match self.links.get(&node) {
+            Some(levels) => levels.to_level(level),
+            None => NeighborSlice::Wide(&[]),
+        }
+    }
+
+    /// Renumbers the nodes after a compaction, dropping deleted ones from every level.
+    ///
+    /// # Returns
+    /// The renumbered levels, or `None` if the entry point was deleted
+    pub(crate) fn remap(&self, remap: &[Option<NodeId>]) -> Option<Self> {
+        let entry_point = remap[self.entry_point.internal]?;
+        let num_nodes = remap.iter().flatten().count();
+        let links = self
+            .links
+            .iter()
+            .filter_map(|(node, levels)| {
+                let new_id = remap[node.internal]?;
+                let lists = (0..=levels.max_level())
+                    .map(|level| {
+                        levels
+                            .to_level(level)
+                            .iter()
+                            .filter_map(|n| remap[n.internal])
+                            .map(|n| n.internal)
+                            .collect()
+                    })
+                    .collect();
+                Some((new_id, HierarchicalFixedSet::compact(lists, num_nodes)))
+            })
+            .collect();
+        Some(UpperLevels {
+            links,
+            entry_point,
+            max_level: self.max_level,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_keeps_only_upper_nodes() {
+        let links = vec![
+            HierarchicalFixedSet::new(vec![vec![1, 2], vec![2], vec![]]),
+            HierarchicalFixedSet::new(vec![vec![0]]),
+            HierarchicalFixedSet::new(vec![vec![0, 1], vec![0]]),
+        ];
+        let (level0, upper) = UpperLevels::split(links);
+        let upper = upper.unwrap();
+
+        assert_eq!(level0.len(), 3);
+        assert_eq!(
+            level0[2].as_slice().to_vec(),
+            vec![NodeId { internal: 0 }, NodeId { internal: 1 }]
+        );
+        assert_eq!(
+            (upper.entry_point(), upper.max_level()),
+            (NodeId { internal: 0 }, 2)
+        );
+        assert_eq!(upper.len(), 2);
+        assert_eq!(upper.level_of(NodeId { internal: 1 }), 0);
+        assert_eq!(
+            upper.to_level(NodeId { internal: 2 }, 1).to_vec(),
+            vec![NodeId { internal: 0 }]
+        );
+        assert!(upper.to_level(NodeId { internal: 2 }, 2).is_empty());
+
+        // node 2 is deleted: node 0 loses its level-1 link
+        let remapped = upper
+            .remap(&[
+                Some(NodeId { internal: 0 }),
+                Some(NodeId { internal: 1 }),
+                None,
+            ])
+            .unwrap();
+        assert_eq!(remapped.len(), 1);
+        assert!(remapped.to_level(NodeId { internal: 0 }, 1).is_empty());
+    }
+
+    #[test]
+    fn flat_graphs_have_no_upper_levels() {
+        assert!(UpperLevels::split(Vec::new()).1.is_none());
+        let links = vec![
+            HierarchicalFixedSet::new(vec![vec![1]]),
+            HierarchicalFixedSet::new(vec![vec![0]]),
+        ];
+        assert!(UpperLevels::split(links).1.is_none());
+    }
+}
@@ -1,14 +1,21 @@
-use crate::{search::NodeId, sets::candidates::TotalF32};
+use crate::{
+    search::NodeId,
+    sets::candidates::{Distance, TotalF32},
+};
 
 /// A candidate node in a graph search, storing its distance from the query point
 /// and metadata about how it was discovered.
 ///
-/// Candidates are ordered primarily by distance (ascending) for use in priority queues
-/// during beam search algorithms. Two candidates with the same distance and index are
-/// considered equal regardless of their catapult ancestry.
-#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+/// Candidates are totally ordered by distance (ascending), then by node index (ascending)
+/// when distances tie, for use in priority queues during beam search algorithms. Two
+/// candidates with the same distance and index are considered equal regardless of their
+/// catapult ancestry. Because ties are broken by index rather than by arrival order, a
+/// bounded beam keeps the same members and a search returns the same results whatever
+/// order equidistant candidates were inserted in.
+#[derive(Copy, Clone, Debug)]
 pub struct CandidateEntry {
-    /// Distance from the query point to this candidate node.
+    /// Squared L2 distance from the query point to this candidate node, see
+    /// [`l2_distance`](Self::l2_distance) for the actual distance.
     pub distance: TotalF32,
 
     /// Index of the candidate node in the graph.
@@ -16,9 +23,35 @@ pub struct CandidateEntry {
 
     /// Whether this candidate was discovered via a catapult (long-range cached connection)
     /// rather than through standard neighbor traversal.
+    ///
+    /// During a search, the flag is inherited from the expanded node whose neighbor list
+    /// produced the candidate, so it is set on a catapult and on everything reached from
+    /// one. When a node is reachable both ways, the route that first put it in the beam
+    /// decides.
     pub has_catapult_ancestor: bool,
 }
 
+impl CandidateEntry {
+    /// Returns the distance of the candidate, tagged as a squared L2 distance.
+    pub fn squared_l2_distance(&self) -> Distance {
+        Distance::SquaredL2(self.distance.0)
+    }
+
+    /// Returns the L2 distance of the candidate, taking the square root of
+    /// [`distance`](Self::distance).
+    pub fn l2_distance(&self) -> Distance {
+        self.squared_l2_distance().into_l2()
+    }
+}
+
+impl PartialEq for CandidateEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for CandidateEntry {}
+
 impl PartialOrd for CandidateEntry {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
@@ -27,6 +60,28 @@ impl PartialOrd for CandidateEntry {
 
 impl Ord for CandidateEntry {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.distance.cmp(&other.distance)
+        self.distance
+            .cmp(&other.distance)
+            .then(self.index.cmp(&other.index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(distance: f32, index: usize, has_catapult_ancestor: bool) -> CandidateEntry {
+        CandidateEntry {
+            distance: distance.into(),
+            index: NodeId { internal: index },
+            has_catapult_ancestor,
+        }
+    }
+
+    #[test]
+    fn ties_are_broken_by_index() {
+        assert!(entry(1.0, 7, false) < entry(2.0, 3, false));
+        assert!(entry(1.0, 3, false) < entry(1.0, 7, false));
+        assert_eq!(entry(1.0, 3, false), entry(1.0, 3, true));
     }
 }
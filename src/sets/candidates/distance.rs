@@ -0,0 +1,59 @@
+/// A distance between a query and a node, tagged with its unit.
+///
+/// Searches rank candidates by squared L2 distance, which orders them like the L2 distance
+/// without a square root per candidate, and [`CandidateEntry::distance`] holds that squared
+/// value. Comparing it against an absolute threshold expressed in L2 units silently gives
+/// wrong answers, so results handed to callers carry their unit in this type instead.
+/// Converting to L2 takes one square root, which is only worth paying for the final
+/// results.
+///
+/// Searches with per-node penalties add the penalty to the squared distance, so the L2
+/// value of such a result includes the penalty too.
+///
+/// [`CandidateEntry::distance`]: crate::sets::candidates::CandidateEntry::distance
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Distance {
+    /// The squared Euclidean distance, as computed during search
+    SquaredL2(f32),
+
+    /// The Euclidean distance
+    L2(f32),
+}
+
+impl Distance {
+    /// Returns the distance in L2 units, taking a square root if needed.
+    pub fn to_l2(self) -> f32 {
+        match self {
+            Distance::SquaredL2(squared) => squared.sqrt(),
+            Distance::L2(distance) => distance,
+        }
+    }
+
+    /// Returns the distance in squared L2 units, squaring it if needed.
+    pub fn to_squared_l2(self) -> f32 {
+        match self {
+            Distance::SquaredL2(squared) => squared,
+            Distance::L2(distance) => distance * distance,
+        }
+    }
+
+    /// Converts the distance to L2 units.
+    pub fn into_l2(self) -> Self {
+        Distance::L2(self.to_l2())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversions_respect_units() {
+        let squared = Distance::SquaredL2(25.0);
+        assert_eq!(squared.to_l2(), 5.0);
+        assert_eq!(squared.to_squared_l2(), 25.0);
+        assert_eq!(squared.into_l2(), Distance::L2(5.0));
+        assert_eq!(Distance::L2(5.0).into_l2(), Distance::L2(5.0));
+        assert_eq!(Distance::L2(3.0).to_squared_l2(), 9.0);
+    }
+}
@@ -2,12 +2,15 @@
 //!
 //! This module provides data structures for tracking and managing candidate nodes
 //! during nearest neighbor search, including specialized floating-point ordering
-//! and bounded k-smallest tracking with deduplication.
+//! and bounded k-smallest tracking with deduplication, as well as a unit-tagged
+//! [`Distance`] for the results handed to callers.
 
 mod candidate_entry;
+mod distance;
 mod ordered_float;
 mod smallest_k;
 
 pub use candidate_entry::*;
+pub use distance::*;
 pub use ordered_float::*;
 pub use smallest_k::*;
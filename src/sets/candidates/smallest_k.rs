@@ -1,26 +1,47 @@
-use std::vec::IntoIter;
-
-use crate::sets::candidates::CandidateEntry;
+use std::{
+    collections::{BinaryHeap, binary_heap, hash_map::Entry},
+    vec::IntoIter,
+};
+
+use crate::{
+    search::NodeId,
+    sets::{
+        candidates::CandidateEntry,
+        visited::{IntegerMap, IntegerSet},
+    },
+};
 
 /// A bounded priority queue that maintains the k smallest unique candidate entries.
 ///
 /// This structure keeps track of the k smallest `CandidateEntry` elements seen so far,
-/// automatically deduplicating by (distance, index) and evicting larger elements when
-/// capacity is exceeded. Elements are maintained in sorted order for efficient access
-/// to the best candidates.
+/// automatically deduplicating by index and evicting larger elements when capacity is
+/// exceeded. Members are kept in a max-heap keyed on distance, so the worst member is
+/// always at hand for eviction, alongside a set of member indices for the duplicate
+/// check.
 ///
 /// # Insertion Semantics
-/// - Duplicate entries (same distance and index) are ignored
-/// - If not full, new unique entries are inserted in sorted order
+/// - Duplicate entries (same index as a member) are ignored
+/// - If not full, new unique entries are added
 /// - If full and the new entry is smaller than the current maximum, the maximum is evicted
 /// - If full and the new entry is larger than or equal to the maximum, it is ignored
 ///
+/// Comparisons use the total order of [`CandidateEntry`], which breaks distance ties by
+/// index, so the retained set only depends on which entries were inserted, not on their
+/// order.
+///
 /// # Time Complexity
 /// - `insert_batch`: O(n log k) where n is batch size and k is capacity
-/// - Deduplication check: O(k) worst case when many entries share the same distance
+/// - Deduplication check: O(1) expected
+/// - `iter`: O(k), in no particular order; `into_iter` sorts in O(k log k)
 pub struct SmallestKCandidates {
-    sorted_members: Vec<CandidateEntry>,
+    members: BinaryHeap<CandidateEntry>,
+    /// Indices of the members.
+    keys: IntegerSet,
+    /// Number of members with each payload key, when collapsing.
+    payload_keys: IntegerMap<usize>,
     capacity: usize,
+    /// Number of members evicted by a better candidate since the last clear.
+    evictions: usize,
 }
 
 impl SmallestKCandidates {
@@ -36,16 +57,21 @@ impl SmallestKCandidates {
     /// Panics if `capacity == 0`
     pub fn new(capacity: usize) -> Self {
         assert!(capacity > 0);
+        let mut keys = IntegerSet::default();
+        keys.reserve(capacity);
         SmallestKCandidates {
-            sorted_members: Vec::with_capacity(capacity),
+            members: BinaryHeap::with_capacity(capacity),
+            keys,
+            payload_keys: IntegerMap::default(),
             capacity,
+            evictions: 0,
         }
     }
 
     /// Inserts a batch of candidate entries, maintaining the k smallest unique elements.
     ///
-    /// For each item in the batch, attempts to insert it while maintaining sorted order
-    /// and the capacity constraint. Duplicates (same distance and index) are ignored.
+    /// For each item in the batch, attempts to insert it while maintaining the capacity
+    /// constraint. Duplicates (same index as a member) are ignored.
     ///
     /// # Arguments
     /// * `items` - Slice of candidate entries to insert
@@ -53,53 +79,156 @@ impl SmallestKCandidates {
     /// # Returns
     /// The number of items actually added (excluding duplicates and rejected entries)
     pub fn insert_batch(&mut self, items: &[CandidateEntry]) -> usize {
+        self.insert_batch_with(items, None::<fn(NodeId) -> u64>)
+    }
+
+    /// Inserts a batch of candidate entries, treating candidates with identical payloads
+    /// as one.
+    ///
+    /// Behaves like [`insert_batch`](Self::insert_batch), except that an item is also
+    /// ignored when a member has the same `payload_key` and the same distance. Distinct
+    /// payloads with colliding keys are only merged if they are also at the same distance
+    /// from the query. Between two calls to [`clear`](Self::clear), use either this method
+    /// or `insert_batch`, since the duplicate check of one does not know the payload keys
+    /// of the other.
+    ///
+    /// # Arguments
+    /// * `items` - Slice of candidate entries to insert
+    /// * `payload_key` - Hash of the payload of a node, equal for equal payloads
+    ///
+    /// # Returns
+    /// The number of items actually added (excluding duplicates and rejected entries)
+    pub fn insert_batch_collapsing(
+        &mut self,
+        items: &[CandidateEntry],
+        payload_key: impl Fn(NodeId) -> u64,
+    ) -> usize {
+        self.insert_batch_with(items, Some(payload_key))
+    }
+
+    /// Inserts a batch of candidate entries, skipping members and, if `payload_key` is
+    /// given, items with the same payload key and distance as a member.
+    fn insert_batch_with(
+        &mut self,
+        items: &[CandidateEntry],
+        payload_key: Option<impl Fn(NodeId) -> u64>,
+    ) -> usize {
         let mut added_count = 0;
 
         for item in items {
-            // 1. find the insertion point (O(log K))
-            let idx = self.sorted_members.partition_point(|m| m < item);
-
-            // 2. duplicate Check - when distances are equal, we need to check all entries with the same distance
-            // Check if this index already exists anywhere in the array with the same distance
-            let mut is_duplicate = false;
-            let mut check_idx = idx;
-            while check_idx < self.sorted_members.len()
-                && self.sorted_members[check_idx].distance == item.distance
-            {
-                if self.sorted_members[check_idx].index == item.index {
-                    is_duplicate = true;
-                    break;
-                }
-                check_idx += 1;
+            // Full: only an item smaller than the current max can get in, replacing it.
+            // Check the distance first, which rejects most items without hashing.
+            let full = self.members.len() >= self.capacity;
+            if full && *item >= *self.members.peek().expect("capacity is positive") {
+                continue;
+            }
+            if self.keys.contains(&item.index.internal) {
+                continue;
             }
-            if is_duplicate {
+            let item_key = payload_key.as_ref().map(|key| key(item.index));
+            if let (Some(key), Some(item_key)) = (&payload_key, item_key)
+                && self.has_payload_at(item_key, item, key)
+            {
                 continue;
             }
 
-            // 3. size Management
-            if self.sorted_members.len() < self.capacity {
-                // Not full yet: maintain sort order by inserting at idx
-                self.sorted_members.insert(idx, *item);
-                added_count += 1;
-            } else if idx < self.capacity {
-                // Full, but new item is smaller than our current max (last element)
-                // Remove the largest element and insert the new one
-                self.sorted_members.pop();
-                self.sorted_members.insert(idx, *item);
+            self.keys.insert(item.index.internal);
+            if let Some(item_key) = item_key {
+                *self.payload_keys.entry(item_key as usize).or_default() += 1;
+            }
+            if !full {
+                self.members.push(*item);
                 added_count += 1;
+                continue;
+            }
+
+            let mut worst = self.members.peek_mut().expect("capacity is positive");
+            self.keys.remove(&worst.index.internal);
+            if let Some(key) = &payload_key
+                && let Entry::Occupied(mut count) =
+                    self.payload_keys.entry(key(worst.index) as usize)
+            {
+                *count.get_mut() -= 1;
+                if *count.get() == 0 {
+                    count.remove();
+                }
             }
-            // If idx == self.capacity, item is >= all current members; ignore it.
+            // sifts the new item down to its place when `worst` is dropped, in O(log k)
+            *worst = *item;
+            added_count += 1;
+            self.evictions += 1;
         }
 
         added_count
     }
 
-    /// Returns an iterator over the candidate entries in sorted order (smallest to largest).
+    /// Returns whether a member has payload key `item_key` and the distance of `item`.
+    ///
+    /// Members are only scanned when the key is known, i.e. for duplicates and collisions.
+    fn has_payload_at(
+        &self,
+        item_key: u64,
+        item: &CandidateEntry,
+        payload_key: impl Fn(NodeId) -> u64,
+    ) -> bool {
+        self.payload_keys.contains_key(&(item_key as usize))
+            && self
+                .members
+                .iter()
+                .any(|m| m.distance == item.distance && payload_key(m.index) == item_key)
+    }
+
+    /// Returns an iterator over the candidate entries, in no particular order.
     ///
     /// # Returns
-    /// An iterator that yields references to `CandidateEntry` in ascending distance order
-    pub fn iter(&self) -> std::slice::Iter<'_, CandidateEntry> {
-        self.sorted_members.iter()
+    /// An iterator that yields references to every `CandidateEntry` currently held
+    pub fn iter(&self) -> binary_heap::Iter<'_, CandidateEntry> {
+        self.members.iter()
+    }
+
+    /// Returns `true` if a member has index `index`.
+    pub fn contains(&self, index: NodeId) -> bool {
+        self.keys.contains(&index.internal)
+    }
+
+    /// Returns the number of members evicted by a better candidate since the last
+    /// [`clear`](Self::clear).
+    ///
+    /// Every eviction means the structure was full when a better candidate arrived, so a
+    /// beam that evicts often is saturated and may be dropping useful candidates.
+    pub fn evictions(&self) -> usize {
+        self.evictions
+    }
+
+    /// Returns the largest member if the structure is full, i.e. the entry a new candidate
+    /// has to beat to get in.
+    pub fn worst_if_full(&self) -> Option<&CandidateEntry> {
+        if self.members.len() < self.capacity {
+            None
+        } else {
+            self.members.peek()
+        }
+    }
+
+    /// Returns the number of candidates currently held.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Returns `true` if no candidate is held.
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Removes all candidates while keeping the allocated capacity.
+    ///
+    /// This allows a single instance to be reused across many searches with the same
+    /// beam width without reallocating its backing buffer.
+    pub fn clear(&mut self) {
+        self.members.clear();
+        self.keys.clear();
+        self.payload_keys.clear();
+        self.evictions = 0;
     }
 }
 
@@ -107,8 +236,9 @@ impl IntoIterator for SmallestKCandidates {
     type Item = CandidateEntry;
     type IntoIter = IntoIter<CandidateEntry>;
 
+    /// Consumes the candidates, yielding them in ascending distance order.
     fn into_iter(self) -> IntoIter<CandidateEntry> {
-        self.sorted_members.into_iter()
+        self.members.into_sorted_vec().into_iter()
     }
 }
 
@@ -122,7 +252,7 @@ mod tests {
 
     fn contents_sorted(sk: &SmallestKCandidates) -> Vec<CandidateEntry> {
         // Access via clone; sorts ascending.
-        let mut ret = sk.sorted_members.to_vec();
+        let mut ret: Vec<CandidateEntry> = sk.iter().copied().collect();
         ret.sort();
         ret
     }
@@ -137,7 +267,7 @@ mod tests {
                 has_catapult_ancestor: false,
             }]);
         }
-        assert_eq!(sk.sorted_members.len(), 3);
+        assert_eq!(sk.len(), 3);
         assert_eq!(
             contents_sorted(&sk)
                 .iter()
@@ -173,8 +303,8 @@ mod tests {
         sk.insert_batch(&batch_1);
 
         // State should be [5, 10]
-        assert_eq!(sk.sorted_members.len(), 2);
-        assert_eq!(sk.sorted_members[0].index.internal, 5);
+        assert_eq!(sk.len(), 2);
+        assert_eq!(contents_sorted(&sk)[0].index.internal, 5);
 
         // Batch 2: Larger than capacity, contains smaller values,
         // and contains a duplicate of an existing member (5)
@@ -204,9 +334,12 @@ mod tests {
 
         // Final state should be the 3 smallest unique indices: [1, 2, 5]
         // 7 and 10 should have been displaced/ignored.
-        assert_eq!(sk.sorted_members.len(), 3);
+        assert_eq!(sk.len(), 3);
 
-        let results: Vec<usize> = sk.sorted_members.iter().map(|c| c.index.internal).collect();
+        let results: Vec<usize> = contents_sorted(&sk)
+            .iter()
+            .map(|c| c.index.internal)
+            .collect();
         assert_eq!(results, vec![1, 2, 5]);
     }
 
@@ -232,7 +365,7 @@ mod tests {
         for i in (1..=10).rev() {
             sk.insert_batch(&[entry(i as f32, i)]);
         }
-        assert_eq!(sk.sorted_members.len(), 3);
+        assert_eq!(sk.len(), 3);
         let mut results: Vec<_> = sk.into_iter().map(|c| c.index.internal).collect();
         results.sort();
         assert_eq!(results, vec![1, 2, 3]);
@@ -249,7 +382,36 @@ mod tests {
             entry(1.0, 200),
         ]);
 
-        assert_eq!(sk.sorted_members.len(), 2);
+        assert_eq!(sk.len(), 2);
+    }
+
+    #[test]
+    fn test_equal_distances_keep_smallest_indices_in_any_order() {
+        use rand::prelude::*;
+        let mut entries: Vec<CandidateEntry> = (0..100).map(|i| entry(1.0, i)).collect();
+        let mut rng = StdRng::seed_from_u64(7);
+
+        for _ in 0..5 {
+            entries.shuffle(&mut rng);
+            let mut sk = SmallestKCandidates::new(10);
+            for chunk in entries.chunks(7) {
+                sk.insert_batch(chunk);
+            }
+            let ids: Vec<usize> = sk.into_iter().map(|c| c.index.internal).collect();
+            assert_eq!(ids, (0..10).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_contains_tracks_evictions() {
+        let mut sk = SmallestKCandidates::new(2);
+        sk.insert_batch(&[entry(10.0, 1), entry(20.0, 2)]);
+        assert!(sk.contains(NodeId { internal: 2 }));
+
+        sk.insert_batch(&[entry(15.0, 3), entry(30.0, 4)]);
+        assert!(!sk.contains(NodeId { internal: 2 }));
+        assert_eq!(sk.evictions(), 1);
+        assert!(sk.contains(NodeId { internal: 1 }) && sk.contains(NodeId { internal: 3 }));
     }
 
     #[test]
@@ -259,7 +421,7 @@ mod tests {
         // Fill it: [10.0, 20.0]
         sk.insert_batch(&[entry(10.0, 1), entry(20.0, 2), entry(30.0, 3)]);
 
-        assert_eq!(sk.sorted_members.len(), 2);
+        assert_eq!(sk.len(), 2);
 
         // This should replace 20.0 (15.0 < 20.0)
         sk.insert_batch(&[entry(15.0, 4)]);
@@ -275,10 +437,24 @@ mod tests {
         let mut sk = SmallestKCandidates::new(1);
         sk.insert_batch(&[entry(50.0, 1), entry(10.0, 2), entry(100.0, 3)]); // Ignore
 
-        assert_eq!(sk.sorted_members.len(), 1);
+        assert_eq!(sk.len(), 1);
         assert_eq!(sk.iter().next().unwrap().index.internal, 2);
     }
 
+    #[test]
+    fn test_clear_allows_reuse() {
+        let mut sk = SmallestKCandidates::new(2);
+        sk.insert_batch(&[entry(1.0, 1), entry(2.0, 2)]);
+        sk.clear();
+
+        assert_eq!(sk.iter().count(), 0);
+        assert!(sk.members.capacity() >= 2);
+
+        sk.insert_batch(&[entry(5.0, 5), entry(3.0, 3), entry(4.0, 4)]);
+        let results: Vec<usize> = sk.into_iter().map(|c| c.index.internal).collect();
+        assert_eq!(results, vec![3, 4]);
+    }
+
     #[test]
     fn test_randomized_consistency() {
         use rand::prelude::*;
@@ -316,4 +492,51 @@ mod tests {
             assert_eq!(actual[i].index, expected[i].index);
         }
     }
+
+    #[test]
+    fn collapsing_keeps_one_candidate_per_payload() {
+        let entry = |distance: f32, index: usize| CandidateEntry {
+            distance: distance.into(),
+            index: NodeId { internal: index },
+            has_catapult_ancestor: false,
+        };
+        // nodes 1 and 2 share a payload, node 3 is at the same distance with another one
+        let key = |id: NodeId| {
+            if id.internal == 2 {
+                1
+            } else {
+                id.internal as u64
+            }
+        };
+        let mut sk = SmallestKCandidates::new(3);
+        let added = sk.insert_batch_collapsing(
+            &[entry(1.0, 1), entry(1.0, 2), entry(1.0, 3), entry(2.0, 4)],
+            key,
+        );
+
+        assert_eq!(added, 3);
+        let mut ids: Vec<usize> = sk.iter().map(|c| c.index.internal).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn collapsing_keeps_colliding_keys_at_other_distances() {
+        let entry = |distance: f32, index: usize| CandidateEntry {
+            distance: distance.into(),
+            index: NodeId { internal: index },
+            has_catapult_ancestor: false,
+        };
+        // every payload key collides, but only nodes 1 and 2 are at the same distance
+        let mut sk = SmallestKCandidates::new(3);
+        let first = [entry(2.0, 1), entry(2.0, 2), entry(3.0, 3), entry(4.0, 8)];
+        assert_eq!(sk.insert_batch_collapsing(&first, |_| 7), 3);
+
+        // evicting node 8 leaves the key of nodes 1 and 3, so node 5 still collapses
+        let added = sk.insert_batch_collapsing(&[entry(1.0, 4), entry(2.0, 5)], |_| 7);
+        assert_eq!(added, 1);
+        let mut ids: Vec<usize> = sk.iter().map(|c| c.index.internal).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![1, 3, 4]);
+    }
 }
@@ -30,6 +30,33 @@ pub trait CatapultEvictionPolicy {
     /// A vector containing all stored node indices
     fn to_vec(&self) -> Vec<NodeId>;
 
+    /// Returns the number of catapults currently stored.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if no catapults are stored.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Removes all stored catapults, resetting the structure to empty.
     fn clear(&mut self);
+
+    /// Signals that a stored catapult contributed to a search result.
+    ///
+    /// Recency-based policies use this to keep useful catapults alive longer. Touching a
+    /// node that is not stored has no effect. The default implementation ignores the
+    /// signal, which suits insertion-order policies.
+    ///
+    /// # Arguments
+    /// * `neighbor` - The node index of the catapult that was useful
+    fn touch(&mut self, neighbor: NodeId) {
+        let _ = neighbor;
+    }
+
+    /// Ages every stored catapult by one decay cycle.
+    ///
+    /// Time-based policies drop the catapults that went unused for too many cycles. The
+    /// default implementation keeps everything, which suits policies evicting by volume
+    /// only.
+    fn decay(&mut self) {}
 }
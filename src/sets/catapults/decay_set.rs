@@ -0,0 +1,143 @@
+use std::collections::VecDeque;
+
+use crate::{search::NodeId, sets::catapults::CatapultEvictionPolicy};
+
+/// Default number of decay cycles a catapult survives without being used.
+pub const DEFAULT_DECAY_WINDOW: u32 = 8;
+
+/// An LRU catapult storage structure whose entries also expire with time.
+///
+/// Entries are kept and evicted exactly like in [`LruSet`](crate::sets::catapults::LruSet):
+/// inserting an entry, anew or again, and [`touch`](CatapultEvictionPolicy::touch)ing it
+/// make it the most recent, and the least recent entry is evicted when capacity is
+/// exceeded. In addition, each entry carries the number of
+/// [`decay`](CatapultEvictionPolicy::decay) cycles since it was last inserted or touched,
+/// and is dropped once that age reaches `WINDOW`, however much room is left. Under a
+/// drifting query distribution, catapults learned from old queries are thus forgotten even
+/// when too few new ones arrive to push them out by volume.
+///
+/// Nothing decays on its own: callers pick the cycle length by how often they call
+/// [`AdjacencyGraph::decay_catapults`](crate::search::AdjacencyGraph::decay_catapults),
+/// e.g. once every few thousand queries.
+///
+/// # Type Parameters
+/// * `WINDOW` - Number of decay cycles an unused entry survives, greater than 0
+pub struct DecaySet<const WINDOW: u32 = DEFAULT_DECAY_WINDOW> {
+    capacity: usize,
+    /// Entries from least to most recently used, with their age in decay cycles
+    queue: VecDeque<(NodeId, u32)>,
+}
+
+impl<const WINDOW: u32> DecaySet<WINDOW> {
+    /// Returns the position of `key` in the queue, if stored.
+    fn position(&self, key: NodeId) -> Option<usize> {
+        self.queue.iter().position(|&(node, _)| node == key)
+    }
+}
+
+impl<const WINDOW: u32> CatapultEvictionPolicy for DecaySet<WINDOW> {
+    fn insert(&mut self, key: NodeId) {
+        if let Some(pos) = self.position(key) {
+            self.queue.remove(pos);
+        }
+        if self.queue.len() == self.capacity {
+            self.queue.pop_front();
+        }
+        self.queue.push_back((key, 0));
+    }
+
+    /// Creates an empty set.
+    ///
+    /// # Panics
+    /// Panics if `capacity` or `WINDOW` is 0
+    fn new(capacity: usize) -> Self {
+        assert!(capacity > 0);
+        assert!(WINDOW > 0, "entries must survive at least one decay cycle");
+        DecaySet {
+            capacity,
+            queue: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    fn to_vec(&self) -> Vec<NodeId> {
+        self.queue.iter().map(|&(node, _)| node).collect()
+    }
+
+    fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    fn touch(&mut self, key: NodeId) {
+        if let Some(pos) = self.position(key) {
+            self.queue.remove(pos);
+            self.queue.push_back((key, 0));
+        }
+    }
+
+    fn decay(&mut self) {
+        self.queue.retain_mut(|(_, age)| {
+            *age += 1;
+            *age < WINDOW
+        });
+    }
+}
+
+impl<const WINDOW: u32> std::fmt::Debug for DecaySet<WINDOW> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DecaySet")
+            .field("capacity", &self.capacity)
+            .field("window", &WINDOW)
+            .field("queue", &self.queue)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(set: &DecaySet<3>) -> Vec<usize> {
+        set.to_vec().iter().map(|n| n.internal).collect()
+    }
+
+    #[test]
+    fn untouched_entries_expire_while_used_ones_survive() {
+        let mut set = DecaySet::<3>::new(4);
+        set.insert(NodeId { internal: 1 });
+        set.insert(NodeId { internal: 2 });
+
+        for _ in 0..5 {
+            set.touch(NodeId { internal: 2 });
+            set.decay();
+        }
+        assert_eq!(ids(&set), vec![2]);
+
+        // an entry lives for WINDOW - 1 cycles after its last use
+        set.insert(NodeId { internal: 3 });
+        set.decay();
+        set.decay();
+        assert_eq!(ids(&set), vec![3]);
+        set.decay();
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn capacity_evicts_the_least_recently_used() {
+        let mut set = DecaySet::<3>::new(2);
+        set.insert(NodeId { internal: 1 });
+        set.insert(NodeId { internal: 2 });
+        set.touch(NodeId { internal: 1 });
+        set.insert(NodeId { internal: 3 });
+        assert_eq!(ids(&set), vec![1, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "entries must survive at least one decay cycle")]
+    fn zero_window_panics() {
+        let _ = DecaySet::<0>::new(2);
+    }
+}
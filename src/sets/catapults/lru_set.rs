@@ -4,9 +4,10 @@ use crate::{search::NodeId, sets::catapults::CatapultEvictionPolicy};
 
 /// An LRU (Least Recently Used) catapult storage structure with deduplication.
 ///
-/// Maintains up to `CAPACITY` unique node indices, evicting the oldest entry when
-/// capacity is exceeded. Reinserting an existing element removes its old position
-/// and adds it as the newest entry, maintaining set semantics.
+/// Maintains up to `CAPACITY` unique node indices, evicting the least recently used entry
+/// when capacity is exceeded. Reinserting an existing element removes its old position
+/// and adds it as the newest entry, maintaining set semantics. [`touch`](CatapultEvictionPolicy::touch)
+/// does the same for an entry that proved useful, without inserting anything new.
 ///
 /// # Type Parameters
 /// * `CAPACITY` - Maximum number of catapult entries to store, must be greater than 0
@@ -40,6 +41,10 @@ impl CatapultEvictionPolicy for LruSet {
         self.queue.iter().copied().collect()
     }
 
+    fn len(&self) -> usize {
+        self.queue.len()
+    }
+
     fn insert(&mut self, key: NodeId) {
         // Remove any existing occurrence of the key to maintain set behavior
         if let Some(pos) = self.queue.iter().position(|&x| x == key) {
@@ -65,6 +70,13 @@ impl CatapultEvictionPolicy for LruSet {
     fn clear(&mut self) {
         self.queue.clear();
     }
+
+    fn touch(&mut self, key: NodeId) {
+        if let Some(pos) = self.queue.iter().position(|&x| x == key) {
+            self.queue.remove(pos);
+            self.queue.push_back(key);
+        }
+    }
 }
 
 impl std::fmt::Debug for LruSet {
@@ -86,6 +98,17 @@ mod tests {
         assert_eq!(fifo.queue.len(), 0);
     }
 
+    #[test]
+    fn len_tracks_queue() {
+        let mut fifo = LruSet::new(2);
+        assert!(fifo.is_empty());
+        fifo.insert(NodeId { internal: 1 });
+        fifo.insert(NodeId { internal: 2 });
+        fifo.insert(NodeId { internal: 3 });
+        assert_eq!(fifo.len(), 2);
+        assert!(!fifo.is_empty());
+    }
+
     #[test]
     fn insert_single_element() {
         let mut fifo = LruSet::new(3);
@@ -270,6 +293,53 @@ mod tests {
         assert_eq!(fifo.queue[3].internal, 5);
     }
 
+    #[test]
+    fn touch_moves_entry_to_most_recent() {
+        let mut lru = LruSet::new(3);
+        lru.insert(NodeId { internal: 1 });
+        lru.insert(NodeId { internal: 2 });
+        lru.insert(NodeId { internal: 3 });
+        // Queue: [1, 2, 3]
+
+        lru.touch(NodeId { internal: 1 });
+        // Queue: [2, 3, 1]
+
+        assert_eq!(lru.queue.len(), 3);
+        assert_eq!(lru.queue[0].internal, 2);
+        assert_eq!(lru.queue[1].internal, 3);
+        assert_eq!(lru.queue[2].internal, 1);
+    }
+
+    #[test]
+    fn touched_entry_survives_eviction() {
+        let mut lru = LruSet::new(3);
+        lru.insert(NodeId { internal: 1 });
+        lru.insert(NodeId { internal: 2 });
+        lru.insert(NodeId { internal: 3 });
+
+        lru.touch(NodeId { internal: 1 });
+        lru.insert(NodeId { internal: 4 }); // Evicts 2, not the touched 1
+        lru.insert(NodeId { internal: 5 }); // Evicts 3
+
+        assert_eq!(lru.queue.len(), 3);
+        assert_eq!(lru.queue[0].internal, 1);
+        assert_eq!(lru.queue[1].internal, 4);
+        assert_eq!(lru.queue[2].internal, 5);
+    }
+
+    #[test]
+    fn touch_missing_entry_is_noop() {
+        let mut lru = LruSet::new(2);
+        lru.insert(NodeId { internal: 1 });
+        lru.insert(NodeId { internal: 2 });
+
+        lru.touch(NodeId { internal: 9 });
+
+        assert_eq!(lru.queue.len(), 2);
+        assert_eq!(lru.queue[0].internal, 1);
+        assert_eq!(lru.queue[1].internal, 2);
+    }
+
     #[test]
     fn set_behavior_no_duplicates_in_final_state() {
         let mut fifo = LruSet::new(5);
@@ -5,7 +5,9 @@
 //! eviction-based data structures to store and manage these catapults efficiently.
 
 mod catapult_neighbor_set;
+mod decay_set;
 mod lru_set;
 
 pub use catapult_neighbor_set::*;
+pub use decay_set::*;
 pub use lru_set::*;
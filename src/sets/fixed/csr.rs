@@ -0,0 +1,131 @@
+use std::fmt::Debug;
+
+use crate::{search::NodeId, sets::fixed::NeighborSlice};
+
+/// All neighbor lists of a graph packed in compressed-sparse-row (CSR) form.
+///
+/// Edges of every node are stored back to back in a single buffer, and `offsets[i]..offsets[i + 1]`
+/// delimits the neighbors of node `i`. Compared to one [`FlatFixedSet`](super::FlatFixedSet) per
+/// node, this removes one heap allocation per node and keeps neighbor lists of consecutive
+/// nodes adjacent in memory, at the cost of being immutable once built.
+///
+/// # Examples
+/// ```
+/// use catapult::{search::NodeId, sets::fixed::CsrNeighbors};
+///
+/// let lists = [vec![NodeId { internal: 1 }], vec![], vec![NodeId { internal: 0 }, NodeId { internal: 1 }]];
+/// let csr = CsrNeighbors::from_lists(lists.iter().map(|l| l.as_slice()));
+/// assert_eq!(csr.num_nodes(), 3);
+/// assert_eq!(csr.neighbors(2), &[NodeId { internal: 0 }, NodeId { internal: 1 }]);
+/// assert!(csr.neighbors(1).is_empty());
+/// ```
+pub struct CsrNeighbors {
+    /// `num_nodes + 1` monotonically increasing offsets into `edges`, starting at 0.
+    offsets: Box<[usize]>,
+    edges: Box<[NodeId]>,
+}
+
+impl CsrNeighbors {
+    /// Packs the given neighbor lists, where the `i`-th list holds the neighbors of node `i`.
+    ///
+    /// # Arguments
+    /// * `lists` - Neighbor lists in node order
+    ///
+    /// # Returns
+    /// A new `CsrNeighbors` holding a copy of every list
+    pub fn from_lists<'a, L: Into<NeighborSlice<'a>>>(lists: impl IntoIterator<Item = L>) -> Self {
+        let mut offsets = vec![0];
+        let mut edges = Vec::new();
+        for list in lists {
+            edges.extend(list.into());
+            offsets.push(edges.len());
+        }
+        CsrNeighbors {
+            offsets: offsets.into_boxed_slice(),
+            edges: edges.into_boxed_slice(),
+        }
+    }
+
+    /// Returns the neighbors of `node` as a contiguous slice.
+    ///
+    /// # Panics
+    /// Panics if `node >= self.num_nodes()`
+    #[inline]
+    pub fn neighbors(&self, node: usize) -> &[NodeId] {
+        &self.edges[self.offsets[node]..self.offsets[node + 1]]
+    }
+
+    /// Returns the number of nodes whose neighbor lists are stored.
+    pub fn num_nodes(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// Returns the total number of stored edges across all nodes.
+    pub fn num_edges(&self) -> usize {
+        self.edges.len()
+    }
+}
+
+impl Debug for CsrNeighbors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CsrNeighbors")
+            .field("num_nodes", &self.num_nodes())
+            .field("num_edges", &self.num_edges())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sets::fixed::FlatFixedSet;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_empty_graph() {
+        let csr = CsrNeighbors::from_lists(std::iter::empty::<&[NodeId]>());
+        assert_eq!(csr.num_nodes(), 0);
+        assert_eq!(csr.num_edges(), 0);
+    }
+
+    #[test]
+    fn test_empty_lists_in_between() {
+        let sets = [
+            FlatFixedSet::new(vec![]),
+            FlatFixedSet::new(vec![3, 4]),
+            FlatFixedSet::new(vec![]),
+        ];
+        let csr = CsrNeighbors::from_lists(sets.iter().map(FlatFixedSet::as_slice));
+
+        assert_eq!(csr.num_nodes(), 3);
+        assert_eq!(csr.num_edges(), 2);
+        assert!(csr.neighbors(0).is_empty());
+        assert!(csr.neighbors(2).is_empty());
+    }
+
+    #[test]
+    fn test_matches_per_node_sets() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let sets: Vec<FlatFixedSet> = (0..500)
+            .map(|_| {
+                let degree = rng.random_range(0..40);
+                FlatFixedSet::new((0..degree).map(|_| rng.random_range(0..500)).collect())
+            })
+            .collect();
+
+        let csr = CsrNeighbors::from_lists(sets.iter().map(FlatFixedSet::as_slice));
+
+        assert_eq!(csr.num_nodes(), sets.len());
+        assert_eq!(csr.num_edges(), sets.iter().map(|s| s.len()).sum::<usize>());
+        for (i, set) in sets.iter().enumerate() {
+            assert_eq!(csr.neighbors(i), set.as_slice(), "mismatch at node {i}");
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_out_of_bounds_node_panics() {
+        let csr = CsrNeighbors::from_lists([[NodeId { internal: 0 }].as_slice()]);
+        let _ = csr.neighbors(1);
+    }
+}
@@ -2,18 +2,34 @@ use std::fmt::Debug;
 
 use crate::search::NodeId;
 
-/// An immutable set of neighbor indices for a flat proximity graph node.
+/// Graphs with at most this many nodes store neighbor indices as u32.
+const NARROW_MAX_NODES: usize = u32::MAX as usize + 1;
+
+/// Backing storage of a [`FlatFixedSet`].
+#[derive(Clone)]
+enum Storage {
+    Wide(Box<[NodeId]>),
+    Narrow(Box<[u32]>),
+}
+
+/// A set of neighbor indices for a flat proximity graph node.
 ///
 /// Stores a single fixed list of neighbor indices that does not vary by level.
 /// This is suitable for single-layer proximity graphs like DiskANN-style structures.
+/// The list is stored without spare capacity, since it is only grown when a node is
+/// inserted at runtime.
+///
+/// Indices are stored either as `usize` or, for graphs with fewer than 2^32 nodes, as
+/// `u32`, which halves adjacency memory. Narrow indices are widened on access, so both
+/// representations behave identically through [`as_slice`](Self::as_slice).
 pub struct FlatFixedSet {
-    neighbors: Box<[NodeId]>,
+    neighbors: Storage,
 }
 
 impl FlatFixedSet {
     /// Creates a new flat fixed set from a vector of neighbor indices.
     ///
-    /// The provided indices are converted to an immutable boxed slice.
+    /// The provided indices are converted to an immutable boxed slice of `usize` indices.
     ///
     /// # Arguments
     /// * `initial_values` - Vector of node indices representing the neighbors
@@ -22,26 +38,253 @@ impl FlatFixedSet {
     /// A new `FlatFixedSet` containing the provided neighbor indices
     pub fn new(initial_values: Vec<usize>) -> Self {
         FlatFixedSet {
-            neighbors: initial_values
-                .iter()
-                .map(|&x| NodeId { internal: x })
-                .collect(),
+            neighbors: Storage::Wide(
+                initial_values
+                    .iter()
+                    .map(|&x| NodeId { internal: x })
+                    .collect(),
+            ),
         }
     }
 
+    /// Creates a flat fixed set using the smallest index width that fits the graph.
+    ///
+    /// Indices are stored as `u32` if the graph has at most 2^32 nodes, and as `usize`
+    /// otherwise.
+    ///
+    /// # Arguments
+    /// * `initial_values` - Vector of node indices representing the neighbors
+    /// * `num_nodes` - Number of nodes in the graph the set belongs to
+    ///
+    /// # Returns
+    /// A new `FlatFixedSet` containing the provided neighbor indices
+    ///
+    /// # Panics
+    /// Panics if an index is not below `num_nodes` while narrow storage is selected
+    pub fn compact(initial_values: Vec<usize>, num_nodes: usize) -> Self {
+        if num_nodes > NARROW_MAX_NODES {
+            return Self::new(initial_values);
+        }
+        FlatFixedSet {
+            neighbors: Storage::Narrow(
+                initial_values
+                    .iter()
+                    .map(|&x| u32::try_from(x).expect("neighbor index out of range"))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Copies the neighbor indices into a new boxed slice.
     pub fn to_slice(&self) -> Box<[NodeId]> {
-        self.neighbors.clone()
+        self.as_slice().iter().collect()
+    }
+
+    /// Borrows the neighbor indices without copying them.
+    pub fn as_slice(&self) -> NeighborSlice<'_> {
+        match &self.neighbors {
+            Storage::Wide(ids) => NeighborSlice::Wide(ids),
+            Storage::Narrow(ids) => NeighborSlice::Narrow(ids),
+        }
+    }
+
+    /// Returns the number of neighbors.
+    pub fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    /// Returns whether the node has no neighbors.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns whether indices are stored as `u32`.
+    pub fn is_narrow(&self) -> bool {
+        matches!(self.neighbors, Storage::Narrow(_))
+    }
+
+    /// Returns the number of bytes used by the stored indices.
+    pub fn memory_bytes(&self) -> usize {
+        match &self.neighbors {
+            Storage::Wide(ids) => size_of_val::<[NodeId]>(ids),
+            Storage::Narrow(ids) => size_of_val::<[u32]>(ids),
+        }
+    }
+
+    /// Appends a neighbor at the end of the list.
+    ///
+    /// This reallocates the list, which is fine for occasional runtime insertions but
+    /// should not be used to build a graph edge by edge. Narrow storage is widened if
+    /// `neighbor` does not fit in a u32.
+    ///
+    /// # Arguments
+    /// * `neighbor` - Index of the node to add
+    pub fn push(&mut self, neighbor: NodeId) {
+        let storage = std::mem::replace(&mut self.neighbors, Storage::Narrow(Box::new([])));
+        self.neighbors = match (storage, u32::try_from(neighbor.internal)) {
+            (Storage::Narrow(ids), Ok(narrow)) => {
+                let mut ids = ids.into_vec();
+                ids.push(narrow);
+                Storage::Narrow(ids.into_boxed_slice())
+            }
+            (storage, _) => {
+                let mut ids = match storage {
+                    Storage::Wide(ids) => ids.into_vec(),
+                    Storage::Narrow(ids) => ids
+                        .iter()
+                        .map(|&x| NodeId {
+                            internal: x as usize,
+                        })
+                        .collect(),
+                };
+                ids.push(neighbor);
+                Storage::Wide(ids.into_boxed_slice())
+            }
+        };
     }
 }
 
 impl Debug for FlatFixedSet {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("FixedSet")
-            .field("neighbors", &self.neighbors)
+            .field("neighbors", &self.as_slice())
             .finish()
     }
 }
 
+/// A borrowed list of neighbor indices, stored either as `usize` or as `u32`.
+///
+/// Returned by [`FlatFixedSet::as_slice`]. Indices are widened to [`NodeId`] when read.
+#[derive(Clone, Copy)]
+pub enum NeighborSlice<'a> {
+    /// Indices stored as full-width node ids
+    Wide(&'a [NodeId]),
+
+    /// Indices stored as u32
+    Narrow(&'a [u32]),
+}
+
+impl<'a> NeighborSlice<'a> {
+    /// Returns the number of neighbors.
+    #[inline]
+    pub fn len(&self) -> usize {
+        match self {
+            NeighborSlice::Wide(ids) => ids.len(),
+            NeighborSlice::Narrow(ids) => ids.len(),
+        }
+    }
+
+    /// Returns whether there are no neighbors.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates over the neighbors in order.
+    #[inline]
+    pub fn iter(&self) -> NeighborIter<'a> {
+        match *self {
+            NeighborSlice::Wide(ids) => NeighborIter::Wide(ids.iter()),
+            NeighborSlice::Narrow(ids) => NeighborIter::Narrow(ids.iter()),
+        }
+    }
+
+    /// Returns whether `id` is one of the neighbors.
+    pub fn contains(&self, id: &NodeId) -> bool {
+        self.iter().any(|n| n == *id)
+    }
+
+    /// Copies the neighbors into a vector.
+    pub fn to_vec(&self) -> Vec<NodeId> {
+        self.iter().collect()
+    }
+}
+
+impl<'a> From<&'a [NodeId]> for NeighborSlice<'a> {
+    fn from(ids: &'a [NodeId]) -> Self {
+        NeighborSlice::Wide(ids)
+    }
+}
+
+impl<'a> IntoIterator for NeighborSlice<'a> {
+    type Item = NodeId;
+    type IntoIter = NeighborIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Debug for NeighborSlice<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl PartialEq for NeighborSlice<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl PartialEq<[NodeId]> for NeighborSlice<'_> {
+    fn eq(&self, other: &[NodeId]) -> bool {
+        self.iter().eq(other.iter().copied())
+    }
+}
+
+impl PartialEq<&[NodeId]> for NeighborSlice<'_> {
+    fn eq(&self, other: &&[NodeId]) -> bool {
+        *self == **other
+    }
+}
+
+impl<const N: usize> PartialEq<&[NodeId; N]> for NeighborSlice<'_> {
+    fn eq(&self, other: &&[NodeId; N]) -> bool {
+        *self == other.as_slice()
+    }
+}
+
+impl PartialEq<NeighborSlice<'_>> for &[NodeId] {
+    fn eq(&self, other: &NeighborSlice<'_>) -> bool {
+        other == self
+    }
+}
+
+/// Iterator over the neighbors of a [`NeighborSlice`].
+#[derive(Clone)]
+pub enum NeighborIter<'a> {
+    /// Iterating over full-width node ids
+    Wide(std::slice::Iter<'a, NodeId>),
+
+    /// Iterating over u32 indices
+    Narrow(std::slice::Iter<'a, u32>),
+}
+
+impl Iterator for NeighborIter<'_> {
+    type Item = NodeId;
+
+    #[inline]
+    fn next(&mut self) -> Option<NodeId> {
+        match self {
+            NeighborIter::Wide(iter) => iter.next().copied(),
+            NeighborIter::Narrow(iter) => iter.next().map(|&x| NodeId {
+                internal: x as usize,
+            }),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            NeighborIter::Wide(iter) => iter.size_hint(),
+            NeighborIter::Narrow(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl ExactSizeIterator for NeighborIter<'_> {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -51,10 +294,10 @@ mod tests {
         let values = vec![1, 2, 3, 4, 5];
         let fixed_set: FlatFixedSet = FlatFixedSet::new(values.clone());
 
-        assert_eq!(fixed_set.neighbors.len(), 5);
+        assert_eq!(fixed_set.len(), 5);
         assert_eq!(
             &*fixed_set
-                .neighbors
+                .as_slice()
                 .iter()
                 .map(|x| x.internal)
                 .collect::<Vec<_>>(),
@@ -67,7 +310,7 @@ mod tests {
         let values: Vec<usize> = vec![];
         let fixed_set: FlatFixedSet = FlatFixedSet::new(values);
 
-        assert_eq!(fixed_set.neighbors.len(), 0);
+        assert_eq!(fixed_set.len(), 0);
     }
 
     #[test]
@@ -75,8 +318,8 @@ mod tests {
         let values = vec![42];
         let fixed_set: FlatFixedSet = FlatFixedSet::new(values);
 
-        assert_eq!(fixed_set.neighbors.len(), 1);
-        assert_eq!(fixed_set.neighbors[0], NodeId { internal: 42 });
+        assert_eq!(fixed_set.len(), 1);
+        assert_eq!(fixed_set.to_slice()[0], NodeId { internal: 42 });
     }
 
     #[test]
@@ -94,6 +337,92 @@ mod tests {
         assert_ne!(result1.as_ptr(), result2.as_ptr());
     }
 
+    #[test]
+    fn test_as_slice_borrows_storage() {
+        let fixed_set = FlatFixedSet::new(vec![4, 2]);
+        let borrowed = fixed_set.as_slice();
+
+        assert_eq!(borrowed, &[NodeId { internal: 4 }, NodeId { internal: 2 }]);
+        match (borrowed, &fixed_set.neighbors) {
+            (NeighborSlice::Wide(view), Storage::Wide(ids)) => {
+                assert_eq!(view.as_ptr(), ids.as_ptr())
+            }
+            _ => panic!("expected wide storage"),
+        }
+    }
+
+    #[test]
+    fn test_narrow_set_matches_wide_set() {
+        let values = vec![0, 17, 4_000_000_000, 9, 9];
+        let wide = FlatFixedSet::new(values.clone());
+        let narrow = FlatFixedSet::compact(values, 4_000_000_001);
+
+        assert!(narrow.is_narrow());
+        assert!(!wide.is_narrow());
+        assert_eq!(narrow.as_slice(), wide.as_slice());
+        assert_eq!(narrow.to_slice(), wide.to_slice());
+        assert_eq!(narrow.len(), 5);
+        assert!(narrow.as_slice().contains(&NodeId {
+            internal: 4_000_000_000
+        }));
+        assert_eq!(format!("{narrow:?}"), format!("{wide:?}"));
+    }
+
+    #[test]
+    fn test_narrow_set_halves_memory() {
+        let values: Vec<usize> = (0..1000).collect();
+        let wide = FlatFixedSet::new(values.clone());
+        let narrow = FlatFixedSet::compact(values, 1000);
+
+        assert_eq!(wide.memory_bytes(), 1000 * size_of::<usize>());
+        assert_eq!(narrow.memory_bytes() * 2, wide.memory_bytes());
+    }
+
+    #[test]
+    fn test_huge_graphs_keep_wide_storage() {
+        let set = FlatFixedSet::compact(vec![1, 2], usize::MAX);
+        assert!(!set.is_narrow());
+    }
+
+    #[test]
+    fn test_push_widens_narrow_storage_when_needed() {
+        let mut set = FlatFixedSet::compact(vec![1], 10);
+        set.push(NodeId { internal: 2 });
+        assert!(set.is_narrow());
+
+        set.push(NodeId {
+            internal: usize::MAX,
+        });
+        assert!(!set.is_narrow());
+        assert_eq!(
+            set.as_slice(),
+            &[
+                NodeId { internal: 1 },
+                NodeId { internal: 2 },
+                NodeId {
+                    internal: usize::MAX
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_push_appends_without_spare_capacity() {
+        let mut fixed_set = FlatFixedSet::new(vec![3]);
+        fixed_set.push(NodeId { internal: 8 });
+
+        assert_eq!(fixed_set.len(), 2);
+        assert_eq!(
+            fixed_set.as_slice(),
+            &[NodeId { internal: 3 }, NodeId { internal: 8 }]
+        );
+
+        let mut empty = FlatFixedSet::new(vec![]);
+        assert!(empty.is_empty());
+        empty.push(NodeId { internal: 0 });
+        assert_eq!(empty.len(), 1);
+    }
+
     #[test]
     fn test_debug_formatting() {
         let values = vec![7, 8, 9];
@@ -113,9 +442,9 @@ mod tests {
         let values: Vec<usize> = (0..10000).collect();
         let fixed_set: FlatFixedSet = FlatFixedSet::new(values.clone());
 
-        assert_eq!(fixed_set.neighbors.len(), 10000);
-        assert_eq!(fixed_set.neighbors[0], NodeId { internal: 0 });
-        assert_eq!(fixed_set.neighbors[9999], NodeId { internal: 9999 });
+        assert_eq!(fixed_set.len(), 10000);
+        assert_eq!(fixed_set.to_slice()[0], NodeId { internal: 0 });
+        assert_eq!(fixed_set.to_slice()[9999], NodeId { internal: 9999 });
     }
 
     #[test]
@@ -123,10 +452,10 @@ mod tests {
         let values = vec![5, 5, 5, 10, 10];
         let fixed_set: FlatFixedSet = FlatFixedSet::new(values);
 
-        assert_eq!(fixed_set.neighbors.len(), 5);
+        assert_eq!(fixed_set.len(), 5);
         assert_eq!(
             &*fixed_set
-                .neighbors
+                .as_slice()
                 .iter()
                 .map(|x| x.internal)
                 .collect::<Vec<_>>(),
@@ -139,20 +468,20 @@ mod tests {
         let values = vec![usize::MAX, usize::MAX - 1, 0];
         let fixed_set: FlatFixedSet = FlatFixedSet::new(values);
 
-        assert_eq!(fixed_set.neighbors.len(), 3);
+        assert_eq!(fixed_set.len(), 3);
         assert_eq!(
-            fixed_set.neighbors[0],
+            fixed_set.to_slice()[0],
             NodeId {
                 internal: usize::MAX
             }
         );
         assert_eq!(
-            fixed_set.neighbors[1],
+            fixed_set.to_slice()[1],
             NodeId {
                 internal: usize::MAX - 1
             }
         );
-        assert_eq!(fixed_set.neighbors[2], NodeId { internal: 0 });
+        assert_eq!(fixed_set.to_slice()[2], NodeId { internal: 0 });
     }
 
     #[test]
@@ -162,6 +491,6 @@ mod tests {
         let set1: FlatFixedSet = FlatFixedSet::new(values.clone());
         let set2: FlatFixedSet = FlatFixedSet::new(values);
 
-        assert_eq!(&*set1.neighbors, &*set2.neighbors);
+        assert_eq!(&*set1.to_slice(), &*set2.to_slice());
     }
 }
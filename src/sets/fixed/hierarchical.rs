@@ -0,0 +1,151 @@
+use std::fmt::Debug;
+
+use crate::sets::fixed::{FlatFixedSet, NeighborSlice};
+
+/// The neighbor lists of a hierarchical proximity graph node, one per level.
+///
+/// HNSW-style graphs link every node on level 0 and a shrinking sample of nodes on each
+/// level above it. A node present up to level `l` stores `l + 1` lists, indexed by level,
+/// and has no neighbors on the levels above. Each list is a [`FlatFixedSet`], so indices
+/// are stored as `u32` whenever the graph is small enough.
+pub struct HierarchicalFixedSet {
+    levels: Box<[FlatFixedSet]>,
+}
+
+impl HierarchicalFixedSet {
+    /// Creates a hierarchical set from one vector of neighbor indices per level.
+    ///
+    /// # Arguments
+    /// * `levels` - Neighbor indices of each level, starting at level 0
+    ///
+    /// # Returns
+    /// A new `HierarchicalFixedSet` whose highest level is `levels.len() - 1`
+    ///
+    /// # Panics
+    /// Panics if `levels` is empty, since every node lives on level 0
+    pub fn new(levels: Vec<Vec<usize>>) -> Self {
+        Self::from_levels(levels.into_iter().map(FlatFixedSet::new).collect())
+    }
+
+    /// Creates a hierarchical set using the smallest index width that fits the graph.
+    ///
+    /// # Arguments
+    /// * `levels` - Neighbor indices of each level, starting at level 0
+    /// * `num_nodes` - Number of nodes in the graph the set belongs to
+    ///
+    /// # Returns
+    /// A new `HierarchicalFixedSet` whose highest level is `levels.len() - 1`
+    ///
+    /// # Panics
+    /// Panics if `levels` is empty, or under the conditions of [`FlatFixedSet::compact`]
+    pub fn compact(levels: Vec<Vec<usize>>, num_nodes: usize) -> Self {
+        Self::from_levels(
+            levels
+                .into_iter()
+                .map(|level| FlatFixedSet::compact(level, num_nodes))
+                .collect(),
+        )
+    }
+
+    /// Creates a hierarchical set from already built per-level lists.
+    ///
+    /// # Panics
+    /// Panics if `levels` is empty, since every node lives on level 0
+    pub fn from_levels(levels: Vec<FlatFixedSet>) -> Self {
+        assert!(
+            !levels.is_empty(),
+            "a node must have a level-0 neighbor list"
+        );
+        HierarchicalFixedSet {
+            levels: levels.into_boxed_slice(),
+        }
+    }
+
+    /// Returns the highest level the node lives on.
+    pub fn max_level(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    /// Borrows the neighbors of the node on `level`.
+    ///
+    /// # Arguments
+    /// * `level` - Level to read, 0 being the level every node lives on
+    ///
+    /// # Returns
+    /// The neighbor list of that level, empty if the node does not reach it
+    pub fn to_level(&self, level: usize) -> NeighborSlice<'_> {
+        match self.levels.get(level) {
+            Some(neighbors) => neighbors.as_slice(),
+            None => NeighborSlice::Wide(&[]),
+        }
+    }
+
+    /// Consumes the set, returning its neighbor lists indexed by level.
+    pub fn into_levels(self) -> Vec<FlatFixedSet> {
+        self.levels.into_vec()
+    }
+
+    /// Returns the number of bytes used by the stored indices, over all levels.
+    pub fn memory_bytes(&self) -> usize {
+        self.levels.iter().map(FlatFixedSet::memory_bytes).sum()
+    }
+}
+
+impl Debug for HierarchicalFixedSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HierarchicalFixedSet")
+            .field(
+                "levels",
+                &self
+                    .levels
+                    .iter()
+                    .map(FlatFixedSet::as_slice)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::NodeId;
+
+    fn ids(indices: &[usize]) -> Vec<NodeId> {
+        indices
+            .iter()
+            .map(|&internal| NodeId { internal })
+            .collect()
+    }
+
+    #[test]
+    fn test_levels_keep_their_own_neighbors() {
+        let set = HierarchicalFixedSet::new(vec![vec![1, 2, 3, 4], vec![2, 7], vec![9]]);
+
+        assert_eq!(set.max_level(), 2);
+        assert_eq!(set.to_level(0).to_vec(), ids(&[1, 2, 3, 4]));
+        assert_eq!(set.to_level(1).to_vec(), ids(&[2, 7]));
+        assert_eq!(set.to_level(2).to_vec(), ids(&[9]));
+        assert_ne!(set.to_level(0), set.to_level(2));
+    }
+
+    #[test]
+    fn test_levels_above_the_node_are_empty() {
+        let set = HierarchicalFixedSet::compact(vec![vec![5, 6], vec![6]], 10);
+
+        assert_eq!(set.max_level(), 1);
+        assert!(set.to_level(2).is_empty());
+        assert!(set.to_level(usize::MAX).is_empty());
+        assert_eq!(set.memory_bytes(), 3 * size_of::<u32>());
+
+        let levels = set.into_levels();
+        assert!(levels.iter().all(FlatFixedSet::is_narrow));
+        assert_eq!(levels[1].as_slice().to_vec(), ids(&[6]));
+    }
+
+    #[test]
+    #[should_panic(expected = "a node must have a level-0 neighbor list")]
+    fn test_nodes_live_on_level_zero() {
+        HierarchicalFixedSet::new(vec![]);
+    }
+}
@@ -1,8 +1,14 @@
 //! Fixed neighbor set abstractions for graph nodes.
 //!
-//! This module provides trait definitions and implementations for storing immutable
-//! neighbor relationships in flat proximity graph structures.
+//! This module provides trait definitions and implementations for storing
+//! neighbor relationships in flat proximity graph structures, either one list per node
+//! or packed together in a single CSR buffer, and per-level lists for hierarchical
+//! graphs.
 
+mod csr;
 mod fixed_set;
+mod hierarchical;
 
+pub use csr::*;
 pub use fixed_set::*;
+pub use hierarchical::*;
@@ -0,0 +1,163 @@
+use crate::sets::visited::{IntegerMap, PAGE_SIZE_BITS, Page, VisitorSet};
+
+/// A sparse bitset that only allocates 4096-bit pages for regions that are touched.
+///
+/// Indices are split into a page number (`i / PAGE_SIZE_BITS`) and an offset within that
+/// page. Pages are stored in an identity-hashed [`IntegerMap`], so a search that only
+/// visits a few thousand nodes of a billion-node graph only pays for the pages it touches.
+///
+/// # Examples
+/// ```
+/// use catapult::sets::visited::{CompressedBitset, VisitorSet};
+///
+/// let mut bs = CompressedBitset::new();
+/// bs.set(1_000_000);
+/// assert!(bs.get(1_000_000));
+/// assert!(!bs.get(999_999));
+/// ```
+pub struct CompressedBitset {
+    pages: IntegerMap<Page>,
+    /// Zeroed pages left over from previous uses, handed out before allocating new ones.
+    spare_pages: Vec<Page>,
+}
+
+impl CompressedBitset {
+    /// Creates a new empty bitset with no allocated pages.
+    ///
+    /// # Returns
+    /// A new `CompressedBitset` in which every bit is cleared
+    pub fn new() -> Self {
+        CompressedBitset {
+            pages: IntegerMap::default(),
+            spare_pages: Vec::new(),
+        }
+    }
+
+    /// Returns an estimate of the heap memory used by allocated pages, in bytes.
+    ///
+    /// Spare pages kept for reuse and the hash map bookkeeping are not included.
+    pub fn memory_bytes(&self) -> usize {
+        self.pages.len() * size_of::<Page>()
+    }
+}
+
+impl Default for CompressedBitset {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VisitorSet for CompressedBitset {
+    #[inline]
+    fn get(&self, i: usize) -> bool {
+        self.pages
+            .get(&(i / PAGE_SIZE_BITS))
+            .is_some_and(|page| page.get(i % PAGE_SIZE_BITS))
+    }
+
+    #[inline]
+    fn set(&mut self, i: usize) {
+        let spare_pages = &mut self.spare_pages;
+        self.pages
+            .entry(i / PAGE_SIZE_BITS)
+            .or_insert_with(|| spare_pages.pop().unwrap_or_default())
+            .set(i % PAGE_SIZE_BITS);
+    }
+
+    /// Clears every bit, so the structure can be reused for another search.
+    ///
+    /// Touched pages are zeroed and kept in a spare pool rather than freed, and the page
+    /// map keeps its capacity. The cost is proportional to the number of pages touched
+    /// since the last clear, not to the size of the graph, and a following search that
+    /// touches a similar number of pages performs no allocation at all.
+    fn clear(&mut self) {
+        for (_, mut page) in self.pages.drain() {
+            page.clear();
+            self.spare_pages.push(page);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_bitset_is_empty() {
+        let bs = CompressedBitset::default();
+        assert!(!bs.get(0));
+        assert!(!bs.get(PAGE_SIZE_BITS));
+        assert!(bs.pages.is_empty());
+    }
+
+    #[test]
+    fn set_and_get_across_pages() {
+        let mut bs = CompressedBitset::new();
+        let positions = [
+            0,
+            PAGE_SIZE_BITS - 1,
+            PAGE_SIZE_BITS,
+            10 * PAGE_SIZE_BITS + 7,
+        ];
+        for &p in &positions {
+            bs.set(p);
+        }
+        for &p in &positions {
+            assert!(bs.get(p), "bit {p} should be set");
+        }
+        assert!(!bs.get(1));
+        assert!(!bs.get(PAGE_SIZE_BITS + 1));
+        assert_eq!(bs.pages.len(), 3);
+    }
+
+    #[test]
+    fn clear_resets_all_bits() {
+        let mut bs = CompressedBitset::new();
+        bs.set(5);
+        bs.set(5 * PAGE_SIZE_BITS);
+        bs.clear();
+
+        assert!(!bs.get(5));
+        assert!(!bs.get(5 * PAGE_SIZE_BITS));
+
+        bs.set(6);
+        assert!(bs.get(6));
+        assert!(!bs.get(5));
+    }
+
+    #[test]
+    fn clear_recycles_pages() {
+        let mut bs = CompressedBitset::new();
+        bs.set(0);
+        bs.set(3 * PAGE_SIZE_BITS);
+        bs.clear();
+        assert_eq!(bs.spare_pages.len(), 2);
+
+        bs.set(7 * PAGE_SIZE_BITS + 1);
+        assert_eq!(bs.spare_pages.len(), 1);
+        assert!(bs.get(7 * PAGE_SIZE_BITS + 1));
+        assert!(!bs.get(7 * PAGE_SIZE_BITS));
+    }
+
+    #[test]
+    fn reuse_after_clear_matches_fresh_allocation() {
+        use rand::prelude::*;
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut reused = CompressedBitset::new();
+
+        for _ in 0..20 {
+            let indices: Vec<usize> = (0..200).map(|_| rng.random_range(0..100_000)).collect();
+
+            reused.clear();
+            let mut fresh = CompressedBitset::new();
+            for &i in &indices {
+                reused.set(i);
+                fresh.set(i);
+            }
+
+            for probe in (0..100_000).step_by(97).chain(indices.iter().copied()) {
+                assert_eq!(reused.get(probe), fresh.get(probe), "mismatch at {probe}");
+            }
+        }
+    }
+}
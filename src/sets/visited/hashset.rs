@@ -19,6 +19,10 @@ impl VisitorSet for IntegerSet {
     fn set(&mut self, i: usize) {
         self.insert(i);
     }
+
+    fn clear(&mut self) {
+        IntegerSet::clear(self);
+    }
 }
 
 #[cfg(test)]
@@ -4,13 +4,17 @@
 //! visited during graph search operations, preventing redundant expansions and cycles.
 //! Multiple implementations are available with different space-time tradeoffs.
 
+mod compressed_bitset;
 mod hashset;
 mod integer_map;
 mod page;
+mod roaring_set;
 mod uncompressed_set;
 mod visitor_set;
 
+pub use compressed_bitset::*;
 pub use integer_map::*;
 pub use page::*;
+pub use roaring_set::*;
 pub use uncompressed_set::*;
 pub use visitor_set::*;
@@ -55,6 +55,12 @@ impl Page {
         (self.bits[offset / 64] & (1 << (offset % 64))) != 0
     }
 
+    /// Clears every bit in this page, keeping it allocated for reuse.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.bits = [0; PAGE_SIZE_U64];
+    }
+
     /// Returns the count of set bits in this page.
     ///
     /// # Returns
@@ -177,6 +183,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn clear_resets_all_bits() {
+        let mut page = Page::new();
+        page.set(0);
+        page.set(PAGE_SIZE_BITS - 1);
+        page.clear();
+        assert_eq!(page.len(), 0);
+        assert!(!page.get(0));
+        assert!(!page.get(PAGE_SIZE_BITS - 1));
+    }
+
     #[test]
     fn last_bit_in_page() {
         let mut page = Page::new();
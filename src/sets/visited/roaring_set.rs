@@ -0,0 +1,205 @@
+use crate::sets::visited::{IntegerMap, VisitorSet};
+
+/// Number of low index bits handled by a single container (2^16 indices per container).
+const CONTAINER_BITS: usize = 16;
+
+/// Number of u64 words in a bitmap container (2^16 / 64).
+const BITMAP_WORDS: usize = (1 << CONTAINER_BITS) / 64;
+
+/// Array containers are converted to bitmaps once they hold more than this many entries.
+///
+/// At 4096 entries a sorted `u16` array takes 8 KiB, which is the size of a bitmap container,
+/// so past this point the bitmap is both smaller and faster.
+const ARRAY_MAX_LEN: usize = 4096;
+
+/// Storage for the low 16 bits of all indices sharing the same high bits.
+enum Container {
+    /// Sorted list of low bits, used while the container is sparse.
+    Array(Vec<u16>),
+    /// Plain bitmap over all 2^16 low-bit values, used once the container is dense.
+    Bitmap(Box<[u64; BITMAP_WORDS]>),
+}
+
+impl Container {
+    fn get(&self, low: u16) -> bool {
+        match self {
+            Container::Array(values) => values.binary_search(&low).is_ok(),
+            Container::Bitmap(words) => words[low as usize / 64] & (1 << (low as usize % 64)) != 0,
+        }
+    }
+
+    fn set(&mut self, low: u16) {
+        match self {
+            Container::Array(values) => {
+                if let Err(pos) = values.binary_search(&low) {
+                    values.insert(pos, low);
+                    if values.len() > ARRAY_MAX_LEN {
+                        let mut words = Box::new([0u64; BITMAP_WORDS]);
+                        for &v in values.iter() {
+                            words[v as usize / 64] |= 1 << (v as usize % 64);
+                        }
+                        *self = Container::Bitmap(words);
+                    }
+                }
+            }
+            Container::Bitmap(words) => words[low as usize / 64] |= 1 << (low as usize % 64),
+        }
+    }
+
+    fn memory_bytes(&self) -> usize {
+        match self {
+            Container::Array(values) => values.capacity() * size_of::<u16>(),
+            Container::Bitmap(_) => BITMAP_WORDS * size_of::<u64>(),
+        }
+    }
+}
+
+/// A roaring-style compressed bitset for sparse visitation of very large graphs.
+///
+/// Indices are split into their high bits, which select a container, and their low 16 bits,
+/// which are stored in that container. Sparse containers are sorted `u16` arrays costing two
+/// bytes per set index; once a container holds more than 4096 entries it switches to a plain
+/// 8 KiB bitmap.
+///
+/// Compared to [`CompressedBitset`](crate::sets::visited::CompressedBitset), which allocates a
+/// 512-byte page as soon as any bit of a 4096-index region is touched, this uses far less
+/// memory when a search touches a few thousand widely scattered indices, at the price of a
+/// binary search on lookups. `CompressedBitset` remains the better default for small and
+/// medium graphs.
+///
+/// # Examples
+/// ```
+/// use catapult::sets::visited::{RoaringSet, VisitorSet};
+///
+/// let mut rs = RoaringSet::new();
+/// rs.set(3_000_000_000);
+/// assert!(rs.get(3_000_000_000));
+/// assert!(!rs.get(3_000_000_001));
+/// ```
+pub struct RoaringSet {
+    containers: IntegerMap<Container>,
+}
+
+impl RoaringSet {
+    /// Creates a new empty set with no allocated containers.
+    ///
+    /// # Returns
+    /// A new empty `RoaringSet`
+    pub fn new() -> Self {
+        RoaringSet {
+            containers: IntegerMap::default(),
+        }
+    }
+
+    /// Returns an estimate of the heap memory used by the containers, in bytes.
+    ///
+    /// The hash map bookkeeping itself is not included.
+    pub fn memory_bytes(&self) -> usize {
+        self.containers.values().map(Container::memory_bytes).sum()
+    }
+}
+
+impl Default for RoaringSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VisitorSet for RoaringSet {
+    fn get(&self, i: usize) -> bool {
+        self.containers
+            .get(&(i >> CONTAINER_BITS))
+            .is_some_and(|c| c.get(i as u16))
+    }
+
+    fn set(&mut self, i: usize) {
+        self.containers
+            .entry(i >> CONTAINER_BITS)
+            .or_insert_with(|| Container::Array(Vec::new()))
+            .set(i as u16);
+    }
+
+    fn clear(&mut self) {
+        self.containers.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sets::visited::CompressedBitset;
+    use rand::prelude::*;
+
+    #[test]
+    fn new_set_is_empty() {
+        let rs = RoaringSet::default();
+        assert!(!rs.get(0));
+        assert!(!rs.get(usize::MAX));
+        assert_eq!(rs.memory_bytes(), 0);
+    }
+
+    #[test]
+    fn set_and_get_sparse_and_huge_indices() {
+        let mut rs = RoaringSet::new();
+        let positions = [0, 1, 65_535, 65_536, 1 << 40, usize::MAX];
+        for &p in &positions {
+            rs.set(p);
+        }
+        for &p in &positions {
+            assert!(rs.get(p), "bit {p} should be set");
+        }
+        assert!(!rs.get(2));
+        assert!(!rs.get(65_537));
+        assert!(!rs.get((1 << 40) + 1));
+    }
+
+    #[test]
+    fn array_converts_to_bitmap_when_dense() {
+        let mut rs = RoaringSet::new();
+        for i in (0..2 * ARRAY_MAX_LEN).map(|i| i * 3) {
+            rs.set(i);
+        }
+        assert!(matches!(rs.containers[&0], Container::Bitmap(_)));
+        for i in 0..6 * ARRAY_MAX_LEN {
+            assert_eq!(rs.get(i), i % 3 == 0, "mismatch at {i}");
+        }
+    }
+
+    #[test]
+    fn clear_resets_all_bits() {
+        let mut rs = RoaringSet::new();
+        rs.set(12);
+        rs.set(1 << 30);
+        rs.clear();
+        assert!(!rs.get(12));
+        assert!(!rs.get(1 << 30));
+    }
+
+    #[test]
+    fn matches_compressed_bitset_and_uses_less_memory_when_scattered() {
+        // a few thousand widely scattered indices over a billion-node id space
+        let mut rng = StdRng::seed_from_u64(3);
+        let indices: Vec<usize> = (0..3000)
+            .map(|_| rng.random_range(0..1_000_000_000))
+            .collect();
+
+        let mut roaring = RoaringSet::new();
+        let mut paged = CompressedBitset::new();
+        for &i in &indices {
+            roaring.set(i);
+            paged.set(i);
+        }
+
+        for &i in &indices {
+            assert!(roaring.get(i));
+            assert!(roaring.get(i + 1) == paged.get(i + 1));
+        }
+
+        assert!(
+            roaring.memory_bytes() * 10 < paged.memory_bytes(),
+            "roaring={} paged={}",
+            roaring.memory_bytes(),
+            paged.memory_bytes()
+        );
+    }
+}
@@ -88,6 +88,12 @@ impl VisitorSet for UncompressedSet {
 
         self.buffer[byte_index] & (1u8 << bit_index) != 0
     }
+
+    /// Resets every bit to `0`. This touches the whole buffer, regardless of how many
+    /// bits were set.
+    fn clear(&mut self) {
+        self.buffer.fill(0);
+    }
 }
 
 #[cfg(test)]
@@ -18,4 +18,7 @@ pub trait VisitorSet {
     /// # Arguments
     /// * `i` - The node index to mark as visited
     fn set(&mut self, i: usize);
+
+    /// Unmarks every node, so the set can be reused for another traversal.
+    fn clear(&mut self);
 }
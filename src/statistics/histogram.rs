@@ -0,0 +1,172 @@
+/// Number of linear sub-buckets per power of two, as a power of two.
+const SUB_BUCKET_BITS: u32 = 4;
+
+/// Number of linear sub-buckets per power of two.
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+
+/// A compact histogram of non-negative integer values with bounded relative error.
+///
+/// Values are counted in HdrHistogram-style buckets: values below 16 get a bucket each,
+/// and every larger power-of-two range `[2^e, 2^(e+1))` is split into 16 equal
+/// sub-buckets. Percentiles are therefore exact below 16 and overestimate by less than
+/// 1/16 (6.25%) above, while the histogram never holds more than about a thousand
+/// counters whatever the range of recorded values.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogHistogram {
+    /// Number of recorded values per bucket, grown on demand
+    counts: Vec<u64>,
+
+    /// Total number of recorded values
+    total: u64,
+
+    /// Largest recorded value, used to tighten the top bucket
+    max: usize,
+}
+
+impl LogHistogram {
+    /// Creates an empty histogram.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the index of the bucket holding `value`.
+    fn bucket_of(value: usize) -> usize {
+        if value < SUB_BUCKETS {
+            return value;
+        }
+        let shift = usize::BITS - SUB_BUCKET_BITS - 1 - value.leading_zeros();
+        let sub_bucket = (value >> shift) - SUB_BUCKETS;
+        SUB_BUCKETS + shift as usize * SUB_BUCKETS + sub_bucket
+    }
+
+    /// Returns the largest value that falls in bucket `index`.
+    fn highest_in_bucket(index: usize) -> usize {
+        if index < SUB_BUCKETS {
+            return index;
+        }
+        let shift = (index - SUB_BUCKETS) / SUB_BUCKETS;
+        let mantissa = SUB_BUCKETS + (index - SUB_BUCKETS) % SUB_BUCKETS;
+        // computed as `(mantissa + 1) << shift` minus one without overflowing the top bucket
+        (mantissa << shift) + ((1 << shift) - 1)
+    }
+
+    /// Records one occurrence of `value`.
+    pub fn record(&mut self, value: usize) {
+        let bucket = Self::bucket_of(value);
+        if bucket >= self.counts.len() {
+            self.counts.resize(bucket + 1, 0);
+        }
+        self.counts[bucket] += 1;
+        self.total += 1;
+        self.max = self.max.max(value);
+    }
+
+    /// Returns the number of recorded values.
+    pub fn len(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns `true` if no value was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    /// Returns the value below which `q` percent of the recorded values fall.
+    ///
+    /// The result is the largest value of the bucket holding the requested rank, capped at
+    /// the largest recorded value, so it never underestimates the true percentile.
+    ///
+    /// # Arguments
+    /// * `q` - Percentile in `[0, 100]`, e.g. `99.0` for p99
+    ///
+    /// # Returns
+    /// `None` if no value was recorded
+    ///
+    /// # Panics
+    /// Panics if `q` is outside `[0, 100]`
+    pub fn percentile(&self, q: f64) -> Option<usize> {
+        assert!((0.0..=100.0).contains(&q), "percentile must be in [0, 100]");
+        if self.is_empty() {
+            return None;
+        }
+
+        let rank = ((q / 100.0 * self.total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, &count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Some(Self::highest_in_bucket(index).min(self.max));
+            }
+        }
+        Some(self.max)
+    }
+
+    /// Adds every value recorded in `other` to this histogram.
+    pub fn merge(&mut self, other: &Self) {
+        if other.counts.len() > self.counts.len() {
+            self.counts.resize(other.counts.len(), 0);
+        }
+        for (mine, theirs) in self.counts.iter_mut().zip(&other.counts) {
+            *mine += theirs;
+        }
+        self.total += other.total;
+        self.max = self.max.max(other.max);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_values_are_exact() {
+        let mut histogram = LogHistogram::new();
+        for value in 1..=10 {
+            histogram.record(value);
+        }
+        assert_eq!(histogram.len(), 10);
+        assert_eq!(histogram.percentile(0.0), Some(1));
+        assert_eq!(histogram.percentile(50.0), Some(5));
+        assert_eq!(histogram.percentile(95.0), Some(10));
+        assert_eq!(histogram.percentile(100.0), Some(10));
+    }
+
+    #[test]
+    fn large_values_have_bounded_relative_error() {
+        let mut histogram = LogHistogram::new();
+        for value in 1..=100_000 {
+            histogram.record(value);
+        }
+        for (q, exact) in [(50.0, 50_000.0), (95.0, 95_000.0), (99.0, 99_000.0)] {
+            let reported = histogram.percentile(q).unwrap() as f64;
+            assert!(reported >= exact, "p{q}: {reported} < {exact}");
+            assert!(reported < exact * (1.0 + 1.0 / 16.0), "p{q}: {reported}");
+        }
+        assert_eq!(histogram.percentile(100.0), Some(100_000));
+        assert!(histogram.counts.len() < 300);
+    }
+
+    #[test]
+    fn buckets_cover_every_value_once() {
+        for value in (0..5000).chain([usize::MAX - 1, usize::MAX]) {
+            let bucket = LogHistogram::bucket_of(value);
+            assert!(LogHistogram::highest_in_bucket(bucket) >= value);
+            if bucket > 0 {
+                assert!(LogHistogram::highest_in_bucket(bucket - 1) < value);
+            }
+        }
+    }
+
+    #[test]
+    fn merge_adds_counts() {
+        let (mut a, mut b) = (LogHistogram::new(), LogHistogram::new());
+        a.record(3);
+        b.record(7);
+        b.record(1000);
+        a.merge(&b);
+        assert_eq!(a.len(), 3);
+        assert_eq!(a.percentile(50.0), Some(7));
+        assert_eq!(a.percentile(100.0), Some(1000));
+        assert_eq!(LogHistogram::new().percentile(50.0), None);
+    }
+}
@@ -2,10 +2,15 @@
 //!
 //! This module provides structures for collecting and aggregating metrics about
 //! search performance, including number of searches, nodes visited, distances computed,
-//! and catapult usage.
+//! and catapult usage, optionally with per-query distributions, as well as result quality
+//! metrics such as recall.
 
 mod adversarial;
+mod histogram;
+mod recall;
 mod stats;
 
 pub use adversarial::*;
+pub use histogram::*;
+pub use recall::*;
 pub use stats::*;
@@ -0,0 +1,103 @@
+/// Computes the mean recall@k of approximate results against ground truth.
+///
+/// For each query, recall is the fraction of the first `k` ground-truth neighbors that
+/// appear among the first `k` returned results. The per-query values are averaged. A query
+/// whose ground truth lists fewer than `k` neighbors is scored against the neighbors it
+/// lists, so finding all of them counts as full recall; a query with no ground-truth
+/// neighbor at all has nothing to miss and counts as full recall.
+///
+/// # Arguments
+/// * `results` - Returned neighbor ids per query, closest first
+/// * `ground_truth` - True neighbor ids per query, closest first
+/// * `k` - Number of neighbors to compare
+///
+/// # Returns
+/// The mean recall in `[0, 1]`, or `0.0` when there are no queries
+///
+/// # Panics
+/// Panics if `results` and `ground_truth` have different lengths
+pub fn recall_at_k(results: &[Vec<usize>], ground_truth: &[Vec<usize>], k: usize) -> f64 {
+    assert_eq!(
+        results.len(),
+        ground_truth.len(),
+        "results and ground truth must cover the same queries"
+    );
+    if results.is_empty() || k == 0 {
+        return 0.0;
+    }
+
+    let total: f64 = results
+        .iter()
+        .zip(ground_truth)
+        .map(|(found, truth)| query_recall_at_k(found, truth, k))
+        .sum();
+
+    total / results.len() as f64
+}
+
+/// Computes the recall@k of a single query, as averaged by [`recall_at_k`].
+///
+/// # Arguments
+/// * `found` - Returned neighbor ids, closest first
+/// * `truth` - True neighbor ids, closest first
+/// * `k` - Number of neighbors to compare
+///
+/// # Returns
+/// The fraction of the first `k` true neighbors found among the first `k` results, in
+/// `[0, 1]`; `1.0` when `truth` is empty
+pub fn query_recall_at_k(found: &[usize], truth: &[usize], k: usize) -> f64 {
+    let found = &found[..k.min(found.len())];
+    let expected = k.min(truth.len());
+    if expected == 0 {
+        return 1.0;
+    }
+    let hits = truth.iter().take(k).filter(|id| found.contains(id)).count();
+    hits as f64 / expected as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn perfect_recall() {
+        let results = vec![vec![1, 2, 3], vec![4, 5, 6]];
+        assert_eq!(recall_at_k(&results, &results, 3), 1.0);
+    }
+
+    #[test]
+    fn partial_recall_ignores_order() {
+        let results = vec![vec![2, 1, 9]];
+        let truth = vec![vec![1, 2, 3]];
+        assert!((recall_at_k(&results, &truth, 3) - 2.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn only_first_k_considered() {
+        let results = vec![vec![9, 1]];
+        let truth = vec![vec![1, 2]];
+        assert_eq!(recall_at_k(&results, &truth, 1), 0.0);
+        assert_eq!(recall_at_k(&results, &truth, 2), 0.5);
+    }
+
+    #[test]
+    fn short_ground_truth() {
+        let results = vec![vec![7, 3, 8, 1], vec![2, 4, 6, 0]];
+        let truth = vec![vec![3, 1], vec![]];
+        assert_eq!(recall_at_k(&results, &truth, 4), 1.0);
+
+        let truth = vec![vec![3, 5], vec![]];
+        assert_eq!(recall_at_k(&results, &truth, 4), 0.75);
+    }
+
+    #[test]
+    fn empty_inputs() {
+        assert_eq!(recall_at_k(&[], &[], 10), 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "results and ground truth must cover the same queries")]
+    fn mismatched_lengths_panic() {
+        recall_at_k(&[vec![1]], &[], 1);
+    }
+}
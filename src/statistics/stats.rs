@@ -1,4 +1,6 @@
-use crate::statistics::AdvEdgeTracking;
+use std::fmt::{self, Display};
+
+use crate::statistics::{AdvEdgeTracking, LogHistogram};
 
 /// Performance statistics for tracking beam search operations.
 ///
@@ -7,6 +9,8 @@ use crate::statistics::AdvEdgeTracking;
 /// Statistics can be merged across threads for parallel workloads.
 ///
 /// Adversarial edge tracking (`adv_tracking`) is opt-in and **not** preserved by `merge`.
+/// Per-query recording of nodes visited is opt-in as well, so that the default mode only
+/// sums counters; unlike edge tracking, it is preserved by `merge`.
 pub struct Stats {
     /// Total number of beam search calls performed
     beam_calls: usize,
@@ -20,8 +24,30 @@ pub struct Stats {
     /// Number of searches that benefited from at least one catapult starting point
     searches_with_catapults: usize,
 
+    /// Number of distance computations skipped because the node was already a candidate
+    skipped_dists: usize,
+
+    /// Number of distance computations skipped because a triangle-inequality bound showed
+    /// the node could not enter the beam
+    pruned_dists: usize,
+
+    /// Number of candidates offered to the beam
+    beam_offers: usize,
+
+    /// Number of beam members evicted by a better candidate while the beam was full
+    beam_evictions: usize,
+
+    /// Number of searches stopped by their wall-clock deadline
+    deadline_hits: usize,
+
+    /// Number of catapult write locks taken by searches to learn or touch catapults
+    catapult_write_locks: usize,
+
     /// Optional adversarial edge tracking data. None in normal runs.
     adv_tracking: Option<Box<AdvEdgeTracking>>,
+
+    /// Optional distribution of nodes visited per search. None in normal runs.
+    nodes_per_query: Option<Box<LogHistogram>>,
 }
 
 impl Stats {
@@ -35,10 +61,54 @@ impl Stats {
             nodes_visited: 0,
             dists_computed: 0,
             searches_with_catapults: 0,
+            skipped_dists: 0,
+            pruned_dists: 0,
+            beam_offers: 0,
+            beam_evictions: 0,
+            deadline_hits: 0,
+            catapult_write_locks: 0,
             adv_tracking: None,
+            nodes_per_query: None,
+        }
+    }
+
+    /// Enables recording the number of nodes visited by each search, so that
+    /// [`percentile`](Self::percentile) can report its distribution.
+    pub fn enable_per_query_recording(&mut self) {
+        self.nodes_per_query = Some(Box::default());
+    }
+
+    /// Returns true if per-query recording is active.
+    pub fn has_per_query_recording(&self) -> bool {
+        self.nodes_per_query.is_some()
+    }
+
+    /// Records the number of nodes visited by one complete search.
+    /// No-op if per-query recording is not enabled.
+    pub fn record_query_nodes_visited(&mut self, nodes: usize) {
+        if let Some(histogram) = self.nodes_per_query.as_mut() {
+            histogram.record(nodes);
         }
     }
 
+    /// Returns the `q`-th percentile of the number of nodes visited per search, e.g.
+    /// `percentile(99.0)` for p99. See [`LogHistogram::percentile`] for the precision.
+    ///
+    /// # Returns
+    /// `None` if per-query recording is not enabled or no search was recorded
+    ///
+    /// # Panics
+    /// Panics if `q` is outside `[0, 100]`
+    pub fn percentile(&self, q: f64) -> Option<usize> {
+        self.nodes_per_query.as_ref()?.percentile(q)
+    }
+
+    /// Returns the distribution of nodes visited per search, if per-query recording is
+    /// enabled.
+    pub fn nodes_per_query(&self) -> Option<&LogHistogram> {
+        self.nodes_per_query.as_deref()
+    }
+
     /// Enables adversarial edge tracking. Must be called before any searches.
     /// Incompatible with multi-threaded use via `merge`.
     pub fn enable_adv_tracking(&mut self) {
@@ -138,9 +208,108 @@ impl Stats {
         self.searches_with_catapults
     }
 
+    /// Increments the counter of skipped distance computations.
+    ///
+    /// # Arguments
+    /// * `amt` - The number of nodes whose distance was not recomputed because they were
+    ///   already candidates, e.g. a catapult that is also a neighbor of an expanded node
+    pub fn bump_skipped_dists(&mut self, amt: usize) {
+        self.skipped_dists += amt;
+    }
+
+    /// Returns the number of distance computations skipped as duplicates.
+    ///
+    /// # Returns
+    /// The current skipped distance computation count
+    pub fn get_skipped_dists(&self) -> usize {
+        self.skipped_dists
+    }
+
+    /// Increments the counter of distance computations pruned by triangle-inequality bounds.
+    ///
+    /// # Arguments
+    /// * `amt` - The number of nodes whose distance lower bound already ruled them out
+    pub fn bump_pruned_dists(&mut self, amt: usize) {
+        self.pruned_dists += amt;
+    }
+
+    /// Returns the number of distance computations pruned by triangle-inequality bounds.
+    ///
+    /// # Returns
+    /// The current pruned distance computation count
+    pub fn get_pruned_dists(&self) -> usize {
+        self.pruned_dists
+    }
+
+    /// Increments the counter of catapult write locks taken by searches.
+    ///
+    /// # Arguments
+    /// * `amt` - The number of write locks a search took on catapult storage, to record
+    ///   its best result or touch the catapults that helped it
+    pub fn bump_catapult_write_locks(&mut self, amt: usize) {
+        self.catapult_write_locks += amt;
+    }
+
+    /// Returns the number of catapult write locks taken by searches.
+    ///
+    /// # Returns
+    /// The current catapult write lock count
+    pub fn get_catapult_write_locks(&self) -> usize {
+        self.catapult_write_locks
+    }
+
+    /// Records how a search's beam handled the candidates it was offered.
+    ///
+    /// # Arguments
+    /// * `offers` - The number of candidates offered to the beam
+    /// * `evictions` - The number of members evicted by a better candidate
+    pub fn bump_beam_usage(&mut self, offers: usize, evictions: usize) {
+        self.beam_offers += offers;
+        self.beam_evictions += evictions;
+    }
+
+    /// Returns the number of beam members evicted by a better candidate.
+    ///
+    /// # Returns
+    /// The current beam eviction count
+    pub fn get_beam_evictions(&self) -> usize {
+        self.beam_evictions
+    }
+
+    /// Increments the counter of searches stopped by their wall-clock deadline.
+    pub fn bump_deadline_hits(&mut self) {
+        self.deadline_hits += 1;
+    }
+
+    /// Returns the number of searches stopped by their wall-clock deadline.
+    ///
+    /// # Returns
+    /// The current deadline hit count
+    pub fn get_deadline_hits(&self) -> usize {
+        self.deadline_hits
+    }
+
+    /// Returns the fraction of candidates offered to the beam that evicted a member.
+    ///
+    /// A high saturation means the beam was full most of the time while good candidates
+    /// kept arriving, which suggests a larger beam width; a beam that is wide enough
+    /// hardly ever evicts.
+    ///
+    /// # Returns
+    /// The saturation in `[0, 1]`, or `0.0` if no candidate was offered
+    pub fn beam_saturation(&self) -> f64 {
+        if self.beam_offers == 0 {
+            0.0
+        } else {
+            self.beam_evictions as f64 / self.beam_offers as f64
+        }
+    }
+
     /// Merges two statistics objects by summing their counters.
     ///
     /// This is useful for aggregating statistics from multiple threads or batches.
+    /// Per-query distributions are combined, and recorded in the result if either side
+    /// records them.
     /// **Note:** `adv_tracking` data is not merged and will be `None` in the result.
     ///
     /// # Arguments
@@ -154,9 +323,86 @@ impl Stats {
             nodes_visited: self.nodes_visited + othr.nodes_visited,
             dists_computed: self.dists_computed + othr.dists_computed,
             searches_with_catapults: self.searches_with_catapults + othr.searches_with_catapults,
+            skipped_dists: self.skipped_dists + othr.skipped_dists,
+            pruned_dists: self.pruned_dists + othr.pruned_dists,
+            beam_offers: self.beam_offers + othr.beam_offers,
+            beam_evictions: self.beam_evictions + othr.beam_evictions,
+            deadline_hits: self.deadline_hits + othr.deadline_hits,
+            catapult_write_locks: self.catapult_write_locks + othr.catapult_write_locks,
             adv_tracking: None,
+            nodes_per_query: match (&self.nodes_per_query, &othr.nodes_per_query) {
+                (None, None) => None,
+                (mine, theirs) => {
+                    let mut merged = mine.clone().unwrap_or_default();
+                    if let Some(theirs) = theirs {
+                        merged.merge(theirs);
+                    }
+                    Some(merged)
+                }
+            },
         }
     }
+
+    /// Adds the counters of `othr` to this object, e.g. to fold per-thread statistics
+    /// into one report.
+    ///
+    /// Behaves like [`merge`](Self::merge), except that adversarial edge tracking of this
+    /// object is kept, while the one of `othr` is still ignored.
+    ///
+    /// # Arguments
+    /// * `othr` - The statistics to add
+    pub fn merge_from(&mut self, othr: &Self) {
+        let adv_tracking = self.adv_tracking.take();
+        *self = self.merge(othr);
+        self.adv_tracking = adv_tracking;
+    }
+
+    /// Returns `total` divided by the number of searches, or 0 without searches.
+    fn per_search(&self, total: usize) -> f64 {
+        if self.beam_calls == 0 {
+            0.0
+        } else {
+            total as f64 / self.beam_calls as f64
+        }
+    }
+}
+
+/// Prints a summary of the counters, averaged per search where it makes sense.
+///
+/// # Examples
+/// ```
+/// use catapult::statistics::Stats;
+///
+/// let mut stats = Stats::new();
+/// stats.bump_beam_calls();
+/// stats.bump_computed_dists(12);
+/// assert!(stats.to_string().contains("12.00 dists computed"));
+/// ```
+impl Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} searches, {} used catapults ({:.2}%), {} catapult write locks",
+            self.beam_calls,
+            self.searches_with_catapults,
+            100.0 * self.per_search(self.searches_with_catapults),
+            self.catapult_write_locks
+        )?;
+        writeln!(
+            f,
+            "Avg per search: {:.2} nodes visited, {:.2} dists computed, {:.2} dists skipped, {:.2} dists pruned",
+            self.per_search(self.nodes_visited),
+            self.per_search(self.dists_computed),
+            self.per_search(self.skipped_dists),
+            self.per_search(self.pruned_dists)
+        )?;
+        write!(
+            f,
+            "Beam saturation: {:.2}%, deadline hits: {}",
+            100.0 * self.beam_saturation(),
+            self.deadline_hits
+        )
+    }
 }
 
 impl Default for Stats {
@@ -259,6 +505,8 @@ mod tests {
         stats1.bump_nodes_visited();
         stats1.bump_computed_dists(10);
         stats1.bump_searches_with_catapults();
+        stats1.bump_skipped_dists(2);
+        stats1.bump_beam_usage(10, 1);
 
         let mut stats2 = Stats::new();
         stats2.bump_beam_calls();
@@ -268,6 +516,7 @@ mod tests {
         stats2.bump_computed_dists(25);
         stats2.bump_searches_with_catapults();
         stats2.bump_searches_with_catapults();
+        stats2.bump_catapult_write_locks(2);
 
         let merged = stats1.merge(&stats2);
 
@@ -275,5 +524,66 @@ mod tests {
         assert_eq!(merged.get_nodes_visited(), 4);
         assert_eq!(merged.get_computed_dists(), 35);
         assert_eq!(merged.get_searches_with_catapults(), 3);
+        assert_eq!(merged.get_skipped_dists(), 2);
+        assert_eq!(merged.get_catapult_write_locks(), 2);
+        assert_eq!(merged.get_beam_evictions(), 1);
+        assert_eq!(merged.beam_saturation(), 0.1);
+
+        let mut folded = Stats::new();
+        folded.enable_adv_tracking();
+        folded.merge_from(&stats1);
+        folded.merge_from(&stats2);
+        assert_eq!(folded.get_beam_calls(), 3);
+        assert_eq!(folded.get_nodes_visited(), 4);
+        assert_eq!(folded.get_computed_dists(), 35);
+        assert_eq!(folded.get_searches_with_catapults(), 3);
+        assert_eq!(folded.get_beam_evictions(), 1);
+        assert!(folded.has_adv_tracking());
+    }
+
+    #[test]
+    fn test_display_averages_per_search() {
+        assert!(
+            Stats::new()
+                .to_string()
+                .starts_with("0 searches, 0 used catapults (0.00%)")
+        );
+
+        let mut stats = Stats::new();
+        for _ in 0..4 {
+            stats.bump_beam_calls();
+        }
+        stats.bump_searches_with_catapults();
+        stats.bump_computed_dists(10);
+        stats.bump_deadline_hits();
+        let summary = stats.to_string();
+        assert!(
+            summary.contains("4 searches, 1 used catapults (25.00%)"),
+            "{summary}"
+        );
+        assert!(
+            summary.contains("0.00 nodes visited, 2.50 dists computed"),
+            "{summary}"
+        );
+        assert!(summary.ends_with("deadline hits: 1"), "{summary}");
+    }
+
+    #[test]
+    fn test_per_query_recording_is_opt_in() {
+        let mut stats = Stats::new();
+        stats.record_query_nodes_visited(12);
+        assert!(!stats.has_per_query_recording());
+        assert_eq!(stats.percentile(50.0), None);
+
+        stats.enable_per_query_recording();
+        for nodes in [4, 8, 8, 9, 15] {
+            stats.record_query_nodes_visited(nodes);
+        }
+        assert_eq!(stats.percentile(50.0), Some(8));
+        assert_eq!(stats.percentile(99.0), Some(15));
+
+        let merged = Stats::new().merge(&stats);
+        assert_eq!(merged.nodes_per_query().unwrap().len(), 5);
+        assert_eq!(merged.percentile(0.0), Some(4));
     }
 }